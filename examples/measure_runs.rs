@@ -0,0 +1,25 @@
+extern crate directwrite;
+
+use directwrite::{Factory, TextFormat};
+
+fn main() {
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let text = "Fallback: 😀 日本語 עברית";
+    let utf16: Vec<u16> = text.encode_utf16().collect();
+    for run in factory.analyze_string(text, &format).unwrap() {
+        let start = run.range.start as usize;
+        let end = start + run.range.length as usize;
+        println!(
+            "{:?} in {} is {:.2} DIPs wide",
+            String::from_utf16_lossy(&utf16[start..end]),
+            run.family,
+            run.width
+        );
+    }
+}
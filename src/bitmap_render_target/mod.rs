@@ -0,0 +1,190 @@
+//! BitmapRenderTarget, for rendering glyph runs to an in-memory GDI bitmap.
+
+use crate::descriptions::GlyphRun;
+use crate::enums::MeasuringMode;
+use crate::rendering_params::IRenderingParams;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use math2d::{Color, Matrix3x2f, Recti, Sizeu};
+use winapi::shared::windef::{HDC, RECT, SIZE};
+use winapi::shared::winerror::{E_FAIL, SUCCEEDED};
+use winapi::um::dwrite::IDWriteBitmapRenderTarget;
+use winapi::um::wingdi::{GetCurrentObject, GetObjectW, DIBSECTION, OBJ_BITMAP, RGB};
+use wio::com::ComPtr;
+
+#[repr(transparent)]
+#[derive(Clone, ComWrapper)]
+#[com(send, debug)]
+/// Encapsulates a 32-bit device independent bitmap and device context, which can be used for
+/// rendering glyphs without a GPU. Create one with
+/// [`GdiInterop::create_bitmap_render_target`][1].
+///
+/// [1]: ../struct.GdiInterop.html#method.create_bitmap_render_target
+pub struct BitmapRenderTarget {
+    ptr: ComPtr<IDWriteBitmapRenderTarget>,
+}
+
+impl BitmapRenderTarget {
+    /// Draws a run of glyphs to the bitmap, returning the bounding box of the pixels that were
+    /// touched. The bitmap is not cleared beforehand, and glyphs are blended with whatever is
+    /// already in it.
+    pub fn draw_glyph_run(
+        &mut self,
+        baseline_x: f32,
+        baseline_y: f32,
+        measuring_mode: MeasuringMode,
+        glyph_run: &GlyphRun,
+        params: &dyn IRenderingParams,
+        text_color: Color,
+    ) -> Result<Recti, Error> {
+        let channel = |c: f32| (c.max(0.0).min(1.0) * 255.0).round() as u8;
+        let color = RGB(
+            channel(text_color.r),
+            channel(text_color.g),
+            channel(text_color.b),
+        );
+
+        unsafe {
+            let mut rect: RECT = std::mem::zeroed();
+            let hr = self.ptr.DrawGlyphRun(
+                baseline_x,
+                baseline_y,
+                measuring_mode as u32,
+                &glyph_run.into_raw()?,
+                params.raw_rp() as *const _ as *mut _,
+                color,
+                &mut rect,
+            );
+            if SUCCEEDED(hr) {
+                Ok(Recti {
+                    left: rect.left,
+                    top: rect.top,
+                    right: rect.right,
+                    bottom: rect.bottom,
+                })
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Gets the handle to the memory device context the bitmap is selected into. GDI calls
+    /// can draw to it as well, e.g. to fill the background before drawing glyphs. The handle
+    /// remains owned by this render target and must not be deleted.
+    pub fn memory_dc(&self) -> HDC {
+        unsafe { self.ptr.GetMemoryDC() }
+    }
+
+    /// Gets the size of the bitmap in pixels.
+    pub fn size(&self) -> Result<Sizeu, Error> {
+        unsafe {
+            let mut size = SIZE { cx: 0, cy: 0 };
+            let hr = self.ptr.GetSize(&mut size);
+            if SUCCEEDED(hr) {
+                Ok(Sizeu {
+                    width: size.cx as u32,
+                    height: size.cy as u32,
+                })
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Resizes the bitmap. The contents of the bitmap are lost.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        unsafe {
+            let hr = self.ptr.Resize(width, height);
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Gets the number of bitmap pixels per DIP.
+    pub fn pixels_per_dip(&self) -> f32 {
+        unsafe { self.ptr.GetPixelsPerDip() }
+    }
+
+    /// Sets the number of bitmap pixels per DIP, e.g. `dpi / 96.0`.
+    pub fn set_pixels_per_dip(&mut self, pixels_per_dip: f32) -> Result<(), Error> {
+        unsafe {
+            let hr = self.ptr.SetPixelsPerDip(pixels_per_dip);
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Gets the transform that maps abstract coordinates to DIPs.
+    pub fn current_transform(&self) -> Result<Matrix3x2f, Error> {
+        unsafe {
+            let mut matrix: Matrix3x2f = std::mem::zeroed();
+            let hr = self
+                .ptr
+                .GetCurrentTransform(&mut matrix as *mut Matrix3x2f as *mut _);
+            if SUCCEEDED(hr) {
+                Ok(matrix)
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Sets the transform that maps abstract coordinates to DIPs. This doesn't affect the
+    /// world transform of the memory device context.
+    pub fn set_current_transform(&mut self, transform: &Matrix3x2f) -> Result<(), Error> {
+        unsafe {
+            let hr = self
+                .ptr
+                .SetCurrentTransform(transform as *const Matrix3x2f as *const _);
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Copies the pixels of the bitmap, as rows of 32-bit BGRX pixels from top to bottom. The
+    /// fourth byte of each pixel is not written by glyph drawing and should be ignored.
+    ///
+    /// The pixels are copied because clones of this target share the bitmap, and may draw to it
+    /// from another thread at any time.
+    pub fn bitmap_bits(&self) -> Result<Vec<u8>, Error> {
+        unsafe {
+            let bitmap = GetCurrentObject(self.memory_dc(), OBJ_BITMAP);
+            if bitmap.is_null() {
+                return Err(E_FAIL.into());
+            }
+
+            let mut section: DIBSECTION = std::mem::zeroed();
+            let size = std::mem::size_of::<DIBSECTION>() as i32;
+            if GetObjectW(bitmap, size, &mut section as *mut DIBSECTION as *mut _) != size {
+                return Err(E_FAIL.into());
+            }
+
+            let bits = section.dsBm.bmBits as *const u8;
+            let len = section.dsBm.bmWidthBytes as usize * section.dsBm.bmHeight as usize;
+            if bits.is_null() {
+                return Ok(Vec::new());
+            }
+            Ok(std::slice::from_raw_parts(bits, len).to_vec())
+        }
+    }
+}
+
+pub unsafe trait IBitmapRenderTarget {
+    unsafe fn raw_brt(&self) -> &IDWriteBitmapRenderTarget;
+}
+
+unsafe impl IBitmapRenderTarget for BitmapRenderTarget {
+    unsafe fn raw_brt(&self) -> &IDWriteBitmapRenderTarget {
+        &self.ptr
+    }
+}
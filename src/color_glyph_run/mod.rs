@@ -0,0 +1,131 @@
+//! Types for splitting a glyph run in a color font into its individually colored layers.
+
+use crate::descriptions::{GlyphOffset, GlyphRun};
+use crate::font_face::FontFace;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use math2d::{Color, Point2f};
+use winapi::shared::winerror::{E_POINTER, SUCCEEDED};
+use winapi::um::dwrite_2::{IDWriteColorGlyphRunEnumerator, DWRITE_COLOR_GLYPH_RUN};
+use wio::com::ComPtr;
+
+#[repr(transparent)]
+#[derive(ComWrapper)]
+#[com(send, debug)]
+/// Enumerates the layers of a color glyph run, as returned by
+/// [`Factory::translate_color_glyph_run`][1]. Each layer is a run of monochrome glyphs which
+/// should be drawn in order, on top of each other, with the color of the layer.
+///
+/// [1]: ../struct.Factory.html#method.translate_color_glyph_run
+pub struct ColorGlyphRunEnumerator {
+    ptr: ComPtr<IDWriteColorGlyphRunEnumerator>,
+}
+
+impl Iterator for ColorGlyphRunEnumerator {
+    type Item = Result<ColorGlyphRun, Error>;
+
+    /// Advances to the next layer. A failure to advance or to get the current layer is
+    /// returned as an error, after which the enumeration shouldn't be continued.
+    fn next(&mut self) -> Option<Result<ColorGlyphRun, Error>> {
+        unsafe {
+            let mut has_run = 0;
+            let hr = self.ptr.MoveNext(&mut has_run);
+            if !SUCCEEDED(hr) {
+                return Some(Err(hr.into()));
+            }
+            if has_run == 0 {
+                return None;
+            }
+
+            let mut run = std::ptr::null();
+            let hr = self.ptr.GetCurrentRun(&mut run);
+            if !SUCCEEDED(hr) {
+                return Some(Err(hr.into()));
+            }
+            if run.is_null() {
+                return Some(Err(E_POINTER.into()));
+            }
+
+            Some(Ok(ColorGlyphRun::from_raw(&*run)))
+        }
+    }
+}
+
+#[derive(Clone)]
+/// A single layer of a color glyph run.
+pub struct ColorGlyphRun {
+    /// The physical font face to draw the layer with.
+    pub font_face: FontFace,
+
+    /// The logical size of the font in DIPs.
+    pub font_em_size: f32,
+
+    /// The glyphs which make up this layer.
+    pub glyph_indices: Vec<u16>,
+
+    /// The advance width of each glyph in the layer, or empty if DirectWrite left the advances
+    /// to the font.
+    pub glyph_advances: Vec<f32>,
+
+    /// The offset of each glyph in the layer, or empty if the glyphs have no offsets.
+    pub glyph_offsets: Vec<GlyphOffset>,
+
+    /// Whether the glyphs are rotated 90 degrees to the left for vertical writing.
+    pub is_sideways: bool,
+
+    /// The resolved bidi level of the run.
+    pub bidi_level: u32,
+
+    /// The origin of the baseline for this layer.
+    pub baseline_origin: Point2f,
+
+    /// The color of the layer as RGBA. When `palette_index` is `None` this should be ignored
+    /// and the layer drawn with the current text color instead.
+    pub run_color: Color,
+
+    /// The index of the color in the font's palette, or `None` if the layer uses the text
+    /// foreground color.
+    pub palette_index: Option<u16>,
+}
+
+impl ColorGlyphRun {
+    unsafe fn from_raw(raw: &DWRITE_COLOR_GLYPH_RUN) -> ColorGlyphRun {
+        // Arrays which DirectWrite leaves null come back as empty slices, and are passed on
+        // as null again by `glyph_run`.
+        let run = GlyphRun::from_raw(&raw.glyphRun);
+        ColorGlyphRun {
+            font_face: run.font_face.clone(),
+            font_em_size: run.font_em_size,
+            glyph_indices: run.glyph_indices.to_vec(),
+            glyph_advances: run.glyph_advances.to_vec(),
+            glyph_offsets: run.glyph_offsets.to_vec(),
+            is_sideways: run.is_sideways,
+            bidi_level: run.bidi_level,
+            baseline_origin: Point2f::new(raw.baselineOriginX, raw.baselineOriginY),
+            run_color: Color::new(
+                raw.runColor.r,
+                raw.runColor.g,
+                raw.runColor.b,
+                raw.runColor.a,
+            ),
+            palette_index: match raw.paletteIndex {
+                0xFFFF => None,
+                index => Some(index),
+            },
+        }
+    }
+
+    /// Borrow the layer as a GlyphRun, e.g. to pass it on to a renderer.
+    pub fn glyph_run(&self) -> GlyphRun {
+        GlyphRun {
+            font_face: &self.font_face,
+            font_em_size: self.font_em_size,
+            glyph_indices: &self.glyph_indices,
+            glyph_advances: &self.glyph_advances,
+            glyph_offsets: &self.glyph_offsets,
+            is_sideways: self.is_sideways,
+            bidi_level: self.bidi_level,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::enums::font_feature_tag::FontAxisTag;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// The range one design axis of a variable font can take, as declared by the font's `fvar`
+/// table, together with the value used when none is specified.
+pub struct FontAxisRange {
+    /// The OpenType tag of the axis, e.g. `wght`, `wdth` or `opsz`.
+    pub tag: FontAxisTag,
+
+    /// The smallest value the axis accepts.
+    pub min: f32,
+
+    /// The value of the axis for the font's default instance.
+    pub default: f32,
+
+    /// The largest value the axis accepts.
+    pub max: f32,
+}
+
+impl FontAxisRange {
+    /// Whether the axis can actually vary. Static fonts report their implicit axes, such as
+    /// the weight, with a single value.
+    pub fn is_variable(&self) -> bool {
+        self.min < self.max
+    }
+
+    /// Clamps `value` into the range of the axis.
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.max(self.min).min(self.max)
+    }
+}
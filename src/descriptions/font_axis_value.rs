@@ -0,0 +1,50 @@
+use crate::enums::font_feature_tag::FontAxisTag;
+use crate::sys::DWRITE_FONT_AXIS_VALUE;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// The value of one design axis of a variable font, such as its weight or width.
+pub struct FontAxisValue {
+    /// The OpenType tag of the axis, e.g. `wght`, `wdth` or `opsz`.
+    pub axis_tag: FontAxisTag,
+
+    /// The value of the axis, in the units the font's `fvar` table uses for it.
+    pub value: f32,
+}
+
+impl FontAxisValue {
+    /// Creates a value for the axis with the given tag.
+    pub fn new(axis_tag: impl Into<FontAxisTag>, value: f32) -> FontAxisValue {
+        FontAxisValue {
+            axis_tag: axis_tag.into(),
+            value,
+        }
+    }
+}
+
+#[cfg(test)]
+dcommon::member_compat_test! {
+    font_axis_value_compat:
+    FontAxisValue <=> DWRITE_FONT_AXIS_VALUE {
+        axis_tag <=> axisTag,
+        value <=> value,
+    }
+}
+
+impl From<FontAxisValue> for DWRITE_FONT_AXIS_VALUE {
+    fn from(value: FontAxisValue) -> DWRITE_FONT_AXIS_VALUE {
+        DWRITE_FONT_AXIS_VALUE {
+            axisTag: value.axis_tag.0,
+            value: value.value,
+        }
+    }
+}
+
+impl From<DWRITE_FONT_AXIS_VALUE> for FontAxisValue {
+    fn from(value: DWRITE_FONT_AXIS_VALUE) -> FontAxisValue {
+        FontAxisValue {
+            axis_tag: value.axisTag.into(),
+            value: value.value,
+        }
+    }
+}
@@ -1,10 +1,14 @@
-use crate::font_face::FontFace;
+use crate::font_face::{FontFace, IFontFace};
+use crate::metrics::ScaledGlyphMetrics;
 
 use std::slice::from_raw_parts;
 
 use com_wrapper::ComWrapper;
 use dcommon::helpers::wrap_ref_to_raw_com;
 use dcommon::helpers::{WideCStr, WideStr};
+use dcommon::Error;
+use math2d::{Point2f, Rectf};
+use winapi::shared::winerror::E_INVALIDARG;
 use winapi::um::dwrite::{DWRITE_GLYPH_RUN, DWRITE_GLYPH_RUN_DESCRIPTION};
 
 #[repr(C)]
@@ -36,6 +40,7 @@ dcommon::member_compat_test! {
 
 /// Contains the information needed by renderers to draw glyph runs. All coordinates are in device
 /// independent pixels (DIPs).
+#[derive(Copy, Clone)]
 pub struct GlyphRun<'a> {
     /// The physical font face object to draw with.
     pub font_face: &'a FontFace,
@@ -46,10 +51,12 @@ pub struct GlyphRun<'a> {
     /// An array of indices to render for the glyph run.
     pub glyph_indices: &'a [u16],
 
-    /// An array containing glyph advance widths for the glyph run.
+    /// An array containing glyph advance widths for the glyph run. Either empty, to use the
+    /// default advances of the font, or the same length as `glyph_indices`.
     pub glyph_advances: &'a [f32],
 
-    /// An array containing glyph offsets for the glyph run.
+    /// An array containing glyph offsets for the glyph run. Either empty, for no offsets, or the
+    /// same length as `glyph_indices`.
     pub glyph_offsets: &'a [GlyphOffset],
 
     /// If true, specifies that glyphs are rotated 90 degrees to the left and vertical metrics are
@@ -65,30 +72,150 @@ pub struct GlyphRun<'a> {
 }
 
 impl<'a> GlyphRun<'a> {
+    /// Gets the design metrics of each glyph in the run, scaled to the run's font size. Vertical
+    /// metrics are requested from the font when the run is sideways.
+    pub fn scaled_metrics(&self) -> Result<Vec<ScaledGlyphMetrics>, Error> {
+        let design_units_per_em = self.font_face.metrics().design_units_per_em;
+        let metrics = self
+            .font_face
+            .design_glyph_metrics(self.glyph_indices, self.is_sideways)?;
+        Ok(metrics
+            .into_iter()
+            .map(|m| ScaledGlyphMetrics::new(m, self.font_em_size, design_units_per_em))
+            .collect())
+    }
+
+    /// Computes the ink bounds of the glyphs in this run in DIPs, as if the run were drawn with
+    /// its baseline at `baseline_origin`. The bounds come from the design metrics of each glyph,
+    /// so they do not account for hinting. Returns `None` when no glyph in the run has any ink,
+    /// e.g. a run made entirely of spaces.
+    ///
+    /// Sideways runs are positioned by their vertical origins and the bounds are rotated 90
+    /// degrees clockwise about `baseline_origin`, the way the run is drawn in a vertical line,
+    /// so successive glyphs stack downwards.
+    pub fn bounds(&self, baseline_origin: Point2f) -> Result<Option<Rectf>, Error> {
+        let metrics = self.scaled_metrics()?;
+        let is_rtl = self.bidi_level % 2 == 1;
+
+        let mut bounds: Option<Rectf> = None;
+        let mut pen = 0.0;
+        for (i, m) in metrics.iter().enumerate() {
+            let advance = self.glyph_advances.get(i).cloned().unwrap_or_else(|| {
+                if self.is_sideways {
+                    m.advance_height_dip()
+                } else {
+                    m.advance_width_dip()
+                }
+            });
+            let offset = self.glyph_offsets.get(i).cloned().unwrap_or(GlyphOffset {
+                advance_offset: 0.0,
+                ascender_offset: 0.0,
+            });
+
+            if m.has_ink() {
+                // Position along the run relative to the baseline origin. Right-to-left runs
+                // start at the right edge and place each glyph to the left of the pen.
+                let x = if is_rtl {
+                    -pen - advance - offset.advance_offset
+                } else {
+                    pen + offset.advance_offset
+                };
+                let y = -offset.ascender_offset;
+
+                let ink = if self.is_sideways {
+                    // The glyph is rotated 90 degrees to the left with its vertical origin on
+                    // the baseline, so its top side bearing faces the start of the run.
+                    let center = m.vertical_origin().x;
+                    let run_left = x + m.top_side_bearing_dip();
+                    let run_right = run_left + m.black_box_height_dip();
+                    let run_top = y - (center - m.right_side_bearing_dip());
+                    let run_bottom = y + (center - m.left_side_bearing_dip());
+
+                    // Rotate the run 90 degrees clockwise into its vertical line.
+                    Rectf {
+                        left: baseline_origin.x - run_bottom,
+                        top: baseline_origin.y + run_left,
+                        right: baseline_origin.x - run_top,
+                        bottom: baseline_origin.y + run_right,
+                    }
+                } else {
+                    let left = baseline_origin.x + x + m.left_side_bearing_dip();
+                    let top =
+                        baseline_origin.y + y + m.vertical_origin().y + m.top_side_bearing_dip();
+                    Rectf {
+                        left,
+                        top,
+                        right: left + m.black_box_width_dip(),
+                        bottom: top + m.black_box_height_dip(),
+                    }
+                };
+
+                bounds = Some(match bounds {
+                    Some(b) => Rectf {
+                        left: b.left.min(ink.left),
+                        top: b.top.min(ink.top),
+                        right: b.right.max(ink.right),
+                        bottom: b.bottom.max(ink.bottom),
+                    },
+                    None => ink,
+                });
+            }
+
+            pen += advance;
+        }
+
+        Ok(bounds)
+    }
+
     pub(crate) unsafe fn from_raw(run: &'a DWRITE_GLYPH_RUN) -> GlyphRun<'a> {
         let len = run.glyphCount as usize;
         GlyphRun {
             font_face: wrap_ref_to_raw_com(&run.fontFace),
             font_em_size: run.fontEmSize,
-            glyph_indices: from_raw_parts(run.glyphIndices, len),
-            glyph_advances: from_raw_parts(run.glyphAdvances, len),
-            glyph_offsets: from_raw_parts(run.glyphOffsets as *const GlyphOffset, len),
+            glyph_indices: slice_or_empty(run.glyphIndices, len),
+            glyph_advances: slice_or_empty(run.glyphAdvances, len),
+            glyph_offsets: slice_or_empty(run.glyphOffsets as *const GlyphOffset, len),
             is_sideways: run.isSideways != 0,
             bidi_level: run.bidiLevel,
         }
     }
 
-    pub(crate) unsafe fn into_raw(&self) -> DWRITE_GLYPH_RUN {
-        DWRITE_GLYPH_RUN {
+    /// Fails with `E_INVALIDARG` unless `glyph_advances` and `glyph_offsets` are each either
+    /// empty or exactly as long as `glyph_indices`. Empty ones are passed on as null, which
+    /// lets DirectWrite use the default advances and no offsets.
+    pub(crate) unsafe fn into_raw(&self) -> Result<DWRITE_GLYPH_RUN, Error> {
+        let len = self.glyph_indices.len();
+        let fits = |n: usize| n == 0 || n == len;
+        if !fits(self.glyph_advances.len()) || !fits(self.glyph_offsets.len()) {
+            return Err(E_INVALIDARG.into());
+        }
+
+        Ok(DWRITE_GLYPH_RUN {
             fontFace: self.font_face.get_raw(),
             fontEmSize: self.font_em_size,
-            glyphCount: self.glyph_indices.len() as u32,
-            glyphIndices: self.glyph_indices.as_ptr(),
-            glyphAdvances: self.glyph_advances.as_ptr(),
-            glyphOffsets: self.glyph_offsets.as_ptr() as *const _,
+            glyphCount: len as u32,
+            glyphIndices: ptr_or_null(self.glyph_indices),
+            glyphAdvances: ptr_or_null(self.glyph_advances),
+            glyphOffsets: ptr_or_null(self.glyph_offsets) as *const _,
             isSideways: self.is_sideways as i32,
             bidiLevel: self.bidi_level,
-        }
+        })
+    }
+}
+
+unsafe fn slice_or_empty<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if ptr.is_null() {
+        &[]
+    } else {
+        from_raw_parts(ptr, len)
+    }
+}
+
+fn ptr_or_null<T>(slice: &[T]) -> *const T {
+    if slice.is_empty() {
+        std::ptr::null()
+    } else {
+        slice.as_ptr()
     }
 }
 
@@ -0,0 +1,43 @@
+use crate::enums::BreakCondition;
+
+use checked_enum::UncheckedEnum;
+use winapi::um::dwrite::DWRITE_LINE_BREAKPOINT;
+
+#[derive(Copy, Clone)]
+/// The line breaking properties of a single UTF-16 code unit, as computed by the
+/// [Unicode line breaking algorithm (UAX #14)](https://www.unicode.org/reports/tr14/).
+pub struct LineBreakpoint {
+    /// Whether a break is allowed before this code unit.
+    pub break_condition_before: UncheckedEnum<BreakCondition>,
+
+    /// Whether a break is allowed after this code unit.
+    pub break_condition_after: UncheckedEnum<BreakCondition>,
+
+    /// Indicates that this code unit is whitespace.
+    pub is_whitespace: bool,
+
+    /// Indicates that this code unit is a soft hyphen.
+    pub is_soft_hyphen: bool,
+}
+
+impl Default for LineBreakpoint {
+    fn default() -> Self {
+        LineBreakpoint {
+            break_condition_before: (BreakCondition::Neutral as u32).into(),
+            break_condition_after: (BreakCondition::Neutral as u32).into(),
+            is_whitespace: false,
+            is_soft_hyphen: false,
+        }
+    }
+}
+
+impl From<DWRITE_LINE_BREAKPOINT> for LineBreakpoint {
+    fn from(bp: DWRITE_LINE_BREAKPOINT) -> Self {
+        LineBreakpoint {
+            break_condition_before: (bp.breakConditionBefore() as u32).into(),
+            break_condition_after: (bp.breakConditionAfter() as u32).into(),
+            is_whitespace: bp.isWhitespace() != 0,
+            is_soft_hyphen: bp.isSoftHyphen() != 0,
+        }
+    }
+}
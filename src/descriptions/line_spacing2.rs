@@ -0,0 +1,74 @@
+use crate::enums::{FontLineGapUsage, LineSpacingMethod};
+use crate::text_format::LineSpacing;
+
+use checked_enum::UncheckedEnum;
+use winapi::um::dwrite_3::DWRITE_LINE_SPACING;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+/// The line spacing options available on Windows 10 and later, which add proportional spacing,
+/// leading before the first line, and control over the font's line gap.
+pub struct LineSpacing2 {
+    /// The method used to determine line spacing.
+    pub method: UncheckedEnum<LineSpacingMethod>,
+
+    /// The spacing between lines. For `Uniform` spacing this is the height in DIPs, and for
+    /// `Proportional` spacing it is a scaling factor applied to the computed line height.
+    pub height: f32,
+
+    /// The distance from the top of the line to the baseline. For `Proportional` spacing this is
+    /// a scaling factor applied to the computed baseline.
+    pub baseline: f32,
+
+    /// The proportion of the leading placed above the line, from 0 to 1. Only used for
+    /// `Proportional` spacing.
+    pub leading_before: f32,
+
+    /// Whether the font's line gap is included in the line height.
+    pub font_line_gap_usage: UncheckedEnum<FontLineGapUsage>,
+}
+
+#[cfg(test)]
+dcommon::member_compat_test! {
+    line_spacing2_compat:
+    LineSpacing2 <=> DWRITE_LINE_SPACING {
+        method <=> method,
+        height <=> height,
+        baseline <=> baseline,
+        leading_before <=> leadingBefore,
+        font_line_gap_usage <=> fontLineGapUsage,
+    }
+}
+
+impl LineSpacing2 {
+    /// Checks the values DirectWrite would reject: the proportional factors can't be
+    /// negative.
+    pub fn is_valid(&self) -> bool {
+        if self.method.value != LineSpacingMethod::Proportional as u32 {
+            return true;
+        }
+        self.height >= 0.0 && self.baseline >= 0.0 && self.leading_before >= 0.0
+    }
+
+    pub(crate) fn into_raw(self) -> DWRITE_LINE_SPACING {
+        unsafe { std::mem::transmute(self) }
+    }
+}
+
+impl From<LineSpacing> for LineSpacing2 {
+    fn from(spacing: LineSpacing) -> LineSpacing2 {
+        LineSpacing2 {
+            method: spacing.method,
+            height: spacing.spacing,
+            baseline: spacing.baseline,
+            leading_before: 0.0,
+            font_line_gap_usage: FontLineGapUsage::Default.into(),
+        }
+    }
+}
+
+impl From<DWRITE_LINE_SPACING> for LineSpacing2 {
+    fn from(spacing: DWRITE_LINE_SPACING) -> LineSpacing2 {
+        unsafe { std::mem::transmute(spacing) }
+    }
+}
@@ -3,6 +3,10 @@
 #[doc(inline)]
 pub use self::dbool::DBool;
 #[doc(inline)]
+pub use self::font_axis_range::FontAxisRange;
+#[doc(inline)]
+pub use self::font_axis_value::FontAxisValue;
+#[doc(inline)]
 pub use self::font_feature::FontFeature;
 #[doc(inline)]
 pub use self::glyphs::{GlyphOffset, GlyphRun, GlyphRunDescription};
@@ -10,6 +14,16 @@ pub use self::glyphs::{GlyphOffset, GlyphRun, GlyphRunDescription};
 pub use self::key::FontKey;
 pub(crate) use self::key::KeyPayload;
 #[doc(inline)]
+pub use self::line_breakpoint::LineBreakpoint;
+#[doc(inline)]
+pub use self::line_spacing2::LineSpacing2;
+#[doc(inline)]
+pub use self::panose::Panose;
+#[doc(inline)]
+pub use self::script_analysis::ScriptAnalysis;
+#[doc(inline)]
+pub use self::shaping::{ShapingGlyphProperties, ShapingTextProperties};
+#[doc(inline)]
 pub use self::strikethrough::Strikethrough;
 #[doc(inline)]
 pub use self::text_range::TextRange;
@@ -17,16 +31,32 @@ pub use self::text_range::TextRange;
 pub use self::trimming::Trimming;
 #[doc(inline)]
 pub use self::underline::Underline;
+#[doc(inline)]
+pub use self::unicode_range::UnicodeRange;
 
 #[doc(hidden)]
 pub mod dbool;
 #[doc(hidden)]
+pub mod font_axis_range;
+#[doc(hidden)]
+pub mod font_axis_value;
+#[doc(hidden)]
 pub mod font_feature;
 #[doc(hidden)]
 pub mod glyphs;
 #[doc(hidden)]
 pub mod key;
 #[doc(hidden)]
+pub mod line_breakpoint;
+#[doc(hidden)]
+pub mod line_spacing2;
+#[doc(hidden)]
+pub mod panose;
+#[doc(hidden)]
+pub mod script_analysis;
+#[doc(hidden)]
+pub mod shaping;
+#[doc(hidden)]
 pub mod strikethrough;
 #[doc(hidden)]
 pub mod text_range;
@@ -34,3 +64,5 @@ pub mod text_range;
 pub mod trimming;
 #[doc(hidden)]
 pub mod underline;
+#[doc(hidden)]
+pub mod unicode_range;
@@ -0,0 +1,46 @@
+use crate::enums::PanoseFamily;
+
+use checked_enum::UncheckedEnum;
+use winapi::um::dwrite_1::DWRITE_PANOSE;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+/// The ten digit PANOSE classification of a typeface, which describes its visual
+/// characteristics such as serif style, weight and proportions. Typefaces with similar digits
+/// look alike, which makes the classification useful for finding substitutes.
+///
+/// The meaning of every digit after the first depends on the family kind; the named accessors
+/// describe them for `TextDisplay` faces. A value of 0 means "any" and 1 means "no fit".
+pub struct Panose(pub [u8; 10]);
+
+impl Panose {
+    /// The raw digits, for callers which compute their own distance between classifications.
+    pub fn bytes(&self) -> &[u8; 10] {
+        &self.0
+    }
+
+    /// The kind of typeface, which decides how the other digits are interpreted.
+    pub fn family_kind(&self) -> UncheckedEnum<PanoseFamily> {
+        (self.0[0] as u32).into()
+    }
+
+    /// The serif style of a text face, e.g. 2 for cove, 11 for normal sans and 15 for rounded.
+    pub fn serif_style(&self) -> u8 {
+        self.0[1]
+    }
+
+    /// The weight of a text face, from 2 (very light) to 11 (extra black).
+    pub fn weight(&self) -> u8 {
+        self.0[2]
+    }
+
+    /// The proportion of a text face, e.g. 3 for modern, 4 for even width and 9 for monospaced.
+    pub fn proportion(&self) -> u8 {
+        self.0[3]
+    }
+}
+
+impl From<DWRITE_PANOSE> for Panose {
+    fn from(panose: DWRITE_PANOSE) -> Panose {
+        unsafe { Panose(*panose.values()) }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::enums::ScriptShapes;
+
+use std::fmt;
+
+use winapi::um::dwrite::DWRITE_SCRIPT_ANALYSIS;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+/// Association of text and its writing system script, as well as some display attributes.
+pub struct ScriptAnalysis {
+    /// The zero-based index representation of writing system script.
+    pub script: u16,
+
+    /// Additional shaping requirement of text.
+    pub shapes: ScriptShapes,
+}
+
+#[cfg(test)]
+dcommon::member_compat_test! {
+    script_analysis_compat:
+    ScriptAnalysis <=> DWRITE_SCRIPT_ANALYSIS {
+        script <=> script,
+        shapes <=> shapes,
+    }
+}
+
+impl From<DWRITE_SCRIPT_ANALYSIS> for ScriptAnalysis {
+    fn from(analysis: DWRITE_SCRIPT_ANALYSIS) -> Self {
+        ScriptAnalysis {
+            script: analysis.script,
+            shapes: ScriptShapes(analysis.shapes),
+        }
+    }
+}
+
+impl From<ScriptAnalysis> for DWRITE_SCRIPT_ANALYSIS {
+    fn from(analysis: ScriptAnalysis) -> Self {
+        DWRITE_SCRIPT_ANALYSIS {
+            script: analysis.script,
+            shapes: analysis.shapes.0,
+        }
+    }
+}
+
+impl PartialEq for ScriptAnalysis {
+    fn eq(&self, other: &ScriptAnalysis) -> bool {
+        self.script == other.script && self.shapes.0 == other.shapes.0
+    }
+}
+
+impl Eq for ScriptAnalysis {}
+
+impl fmt::Debug for ScriptAnalysis {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("ScriptAnalysis")
+            .field("script", &self.script)
+            .field("shapes", &self.shapes.0)
+            .finish()
+    }
+}
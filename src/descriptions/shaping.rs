@@ -0,0 +1,76 @@
+use winapi::um::dwrite::{DWRITE_SHAPING_GLYPH_PROPERTIES, DWRITE_SHAPING_TEXT_PROPERTIES};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Shaping output properties of a single UTF-16 code unit.
+pub struct ShapingTextProperties {
+    /// This character can be shaped independently from the others (usually set for the space
+    /// character).
+    pub is_shaped_alone: bool,
+
+    // The reserved bits are private to the shaper, and must be handed back unchanged.
+    bits: u16,
+}
+
+impl From<DWRITE_SHAPING_TEXT_PROPERTIES> for ShapingTextProperties {
+    fn from(props: DWRITE_SHAPING_TEXT_PROPERTIES) -> Self {
+        ShapingTextProperties {
+            is_shaped_alone: props.isShapedAlone() != 0,
+            bits: props.bit_fields,
+        }
+    }
+}
+
+impl From<ShapingTextProperties> for DWRITE_SHAPING_TEXT_PROPERTIES {
+    fn from(props: ShapingTextProperties) -> Self {
+        let mut raw = DWRITE_SHAPING_TEXT_PROPERTIES {
+            bit_fields: props.bits,
+        };
+        raw.set_isShapedAlone(props.is_shaped_alone as u16);
+        raw
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Shaping output properties of a single glyph.
+pub struct ShapingGlyphProperties {
+    /// The justification class of the glyph, which determines whether it can be stretched or
+    /// spaced when justifying text.
+    pub justification: u8,
+
+    /// Indicates that the glyph is the first glyph of its cluster.
+    pub is_cluster_start: bool,
+
+    /// Indicates that the glyph is a diacritic mark.
+    pub is_diacritic: bool,
+
+    /// Indicates that the glyph is a word boundary with no visible space.
+    pub is_zero_width_space: bool,
+
+    // The reserved bits are private to the shaper, and must be handed back unchanged.
+    bits: u16,
+}
+
+impl From<DWRITE_SHAPING_GLYPH_PROPERTIES> for ShapingGlyphProperties {
+    fn from(props: DWRITE_SHAPING_GLYPH_PROPERTIES) -> Self {
+        ShapingGlyphProperties {
+            justification: props.justification() as u8,
+            is_cluster_start: props.isClusterStart() != 0,
+            is_diacritic: props.isDiacritic() != 0,
+            is_zero_width_space: props.isZeroWidthSpace() != 0,
+            bits: props.bit_fields,
+        }
+    }
+}
+
+impl From<ShapingGlyphProperties> for DWRITE_SHAPING_GLYPH_PROPERTIES {
+    fn from(props: ShapingGlyphProperties) -> Self {
+        let mut raw = DWRITE_SHAPING_GLYPH_PROPERTIES {
+            bit_fields: props.bits,
+        };
+        raw.set_justification(props.justification as u16 & 0xF);
+        raw.set_isClusterStart(props.is_cluster_start as u16);
+        raw.set_isDiacritic(props.is_diacritic as u16);
+        raw.set_isZeroWidthSpace(props.is_zero_width_space as u16);
+        raw
+    }
+}
@@ -0,0 +1,50 @@
+use winapi::um::dwrite_1::DWRITE_UNICODE_RANGE;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// An inclusive range of Unicode scalar values, as listed in the character map of a font.
+pub struct UnicodeRange {
+    /// The first code point in the range.
+    pub first: u32,
+    /// The last code point in the range.
+    pub last: u32,
+}
+
+impl UnicodeRange {
+    /// Checks whether `c` falls within the range.
+    pub fn contains(&self, c: char) -> bool {
+        self.first <= c as u32 && c as u32 <= self.last
+    }
+
+    /// The number of code points in the range.
+    pub fn code_point_count(&self) -> u32 {
+        self.last.saturating_sub(self.first) + 1
+    }
+}
+
+#[cfg(test)]
+dcommon::member_compat_test! {
+    unicode_range_compat:
+    UnicodeRange <=> DWRITE_UNICODE_RANGE {
+        first <=> first,
+        last <=> last,
+    }
+}
+
+impl From<DWRITE_UNICODE_RANGE> for UnicodeRange {
+    fn from(range: DWRITE_UNICODE_RANGE) -> Self {
+        UnicodeRange {
+            first: range.first,
+            last: range.last,
+        }
+    }
+}
+
+impl From<UnicodeRange> for DWRITE_UNICODE_RANGE {
+    fn from(range: UnicodeRange) -> Self {
+        DWRITE_UNICODE_RANGE {
+            first: range.first,
+            last: range.last,
+        }
+    }
+}
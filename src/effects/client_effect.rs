@@ -1,3 +1,4 @@
+use crate::effects::typed_effect;
 use crate::effects::DrawingEffect;
 
 use com_wrapper::ComWrapper;
@@ -10,16 +11,31 @@ use wio::com::ComPtr;
 /// Represents a wrapped value that could be any drawing effect type.
 ///
 /// Use the [`DrawingEffect`][1] trait to cast this to a type that could have been passed to
-/// [`TextLayout::set_drawing_effect`][2]. If you need an escape hatch to cast this to another type
+/// [`TextLayout::set_drawing_effect`][2], or [`downcast_ref`](#method.downcast_ref) to borrow
+/// the value of a [`TypedEffect`][4]. If you need an escape hatch to cast this to another type
 /// that doesn't implement [`DrawingEffect`][1], see the [`ComWrapper`][3] trait.
 ///
 /// [1]: trait.DrawingEffect.html
 /// [2]: ../struct.TextLayout.html#method.set_drawing_effect
 /// [3]: https://docs.rs/com-wrapper/*/com_wrapper/trait.ComWrapper.html
+/// [4]: struct.TypedEffect.html
 pub struct ClientEffect {
     ptr: ComPtr<IUnknown>,
 }
 
+impl ClientEffect {
+    /// Borrows the value of the effect if it is a `TypedEffect<T>`. Returns `None` for a
+    /// `TypedEffect` of another type and for effects which aren't `TypedEffect`s at all.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        typed_effect::downcast_ref(self)
+    }
+
+    /// Whether the effect is a `TypedEffect<T>`.
+    pub fn is<T: 'static>(&self) -> bool {
+        self.downcast_ref::<T>().is_some()
+    }
+}
+
 unsafe impl DrawingEffect for ClientEffect {
     fn get_effect_ptr(&self) -> *mut IUnknown {
         self.ptr.as_raw()
@@ -4,8 +4,12 @@
 pub use self::client_effect::ClientEffect;
 #[doc(inline)]
 pub use self::drawing_effect::DrawingEffect;
+#[doc(inline)]
+pub use self::typed_effect::TypedEffect;
 
 #[doc(hidden)]
 pub mod client_effect;
 #[doc(hidden)]
 pub mod drawing_effect;
+#[doc(hidden)]
+pub mod typed_effect;
@@ -0,0 +1,190 @@
+use crate::effects::{ClientEffect, DrawingEffect};
+
+use std::any::TypeId;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use com_wrapper::ComWrapper;
+use winapi::ctypes::c_void;
+use winapi::shared::guiddef::{IsEqualIID, GUID, REFIID};
+use winapi::shared::minwindef::ULONG;
+use winapi::shared::winerror::{E_NOINTERFACE, E_POINTER, HRESULT, S_OK};
+use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+use winapi::Interface;
+use wio::com::ComPtr;
+
+/// Only answered by the objects behind `TypedEffect`, so that they can be told apart from
+/// effects created elsewhere. {6f1c0a52-94d3-4e8b-b7a5-3c2d9e41f86b}
+const IID_TYPED_EFFECT: GUID = GUID {
+    Data1: 0x6f1c_0a52,
+    Data2: 0x94d3,
+    Data3: 0x4e8b,
+    Data4: [0xb7, 0xa5, 0x3c, 0x2d, 0x9e, 0x41, 0xf8, 0x6b],
+};
+
+/// A drawing effect which carries an arbitrary Rust value, e.g. a color or the index of a
+/// brush, for a renderer to read back with
+/// [`ClientEffect::downcast_ref`](struct.ClientEffect.html#method.downcast_ref).
+///
+/// The value lives in a reference counted COM object along with the `TypeId` of `T`, so the
+/// layout can hold on to it and the renderer can check the type before using it.
+pub struct TypedEffect<T> {
+    ptr: ComPtr<IUnknown>,
+    _marker: PhantomData<T>,
+}
+
+#[repr(C)]
+struct EffectObject<T> {
+    // The vtable pointer has to come first, and the TypeId at a fixed offset so it can be
+    // checked before the type of the value is known.
+    vtbl: *const IUnknownVtbl,
+    type_id: TypeId,
+    refcount: AtomicUsize,
+    vtable: IUnknownVtbl,
+    value: T,
+}
+
+impl<T> TypedEffect<T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Wraps `value` into an effect.
+    pub fn new(value: T) -> TypedEffect<T> {
+        let object = Box::into_raw(Box::new(EffectObject {
+            vtbl: std::ptr::null(),
+            type_id: TypeId::of::<T>(),
+            refcount: AtomicUsize::new(1),
+            vtable: IUnknownVtbl {
+                QueryInterface: query_interface,
+                AddRef: add_ref,
+                Release: release::<T>,
+            },
+            value,
+        }));
+        unsafe {
+            (*object).vtbl = &(*object).vtable;
+            TypedEffect {
+                ptr: ComPtr::from_raw(object as *mut IUnknown),
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    /// The value of the effect.
+    pub fn get(&self) -> &T {
+        unsafe { &(*(self.ptr.as_raw() as *const EffectObject<T>)).value }
+    }
+
+    /// Converts the effect to the untyped form renderers receive.
+    pub fn to_client_effect(&self) -> ClientEffect {
+        unsafe {
+            self.ptr.AddRef();
+            ClientEffect::from_raw(self.ptr.as_raw())
+        }
+    }
+}
+
+impl<T> Clone for TypedEffect<T> {
+    fn clone(&self) -> Self {
+        TypedEffect {
+            ptr: self.ptr.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> std::ops::Deref for TypedEffect<T>
+where
+    T: Send + Sync + 'static,
+{
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T> std::fmt::Debug for TypedEffect<T>
+where
+    T: std::fmt::Debug + Send + Sync + 'static,
+{
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_tuple("TypedEffect").field(self.get()).finish()
+    }
+}
+
+unsafe impl<T: Send + Sync> Send for TypedEffect<T> {}
+unsafe impl<T: Send + Sync> Sync for TypedEffect<T> {}
+
+unsafe impl<T> DrawingEffect for TypedEffect<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn get_effect_ptr(&self) -> *mut IUnknown {
+        self.ptr.as_raw()
+    }
+
+    fn from_client_effect(effect: &ClientEffect) -> Option<Self> {
+        effect.downcast_ref::<T>()?;
+        unsafe {
+            let ptr = effect.get_raw();
+            (*ptr).AddRef();
+            Some(TypedEffect {
+                ptr: ComPtr::from_raw(ptr),
+                _marker: PhantomData,
+            })
+        }
+    }
+}
+
+/// Gets the value behind `effect` if it is a `TypedEffect<T>`.
+pub(crate) fn downcast_ref<T: 'static>(effect: &ClientEffect) -> Option<&T> {
+    unsafe {
+        let unknown = &*effect.get_raw();
+        let mut object = std::ptr::null_mut();
+        let hr = unknown.QueryInterface(&IID_TYPED_EFFECT, &mut object);
+        if hr != S_OK || object.is_null() {
+            return None;
+        }
+        // The effect keeps the object alive for as long as it is borrowed
+        unknown.Release();
+
+        let object = object as *const EffectObject<()>;
+        if (*object).type_id != TypeId::of::<T>() {
+            return None;
+        }
+        Some(&(*(object as *const EffectObject<T>)).value)
+    }
+}
+
+unsafe extern "system" fn query_interface(
+    this: *mut IUnknown,
+    riid: REFIID,
+    object: *mut *mut c_void,
+) -> HRESULT {
+    if object.is_null() {
+        return E_POINTER;
+    }
+    if IsEqualIID(&*riid, &IUnknown::uuidof()) || IsEqualIID(&*riid, &IID_TYPED_EFFECT) {
+        add_ref(this);
+        *object = this as *mut _;
+        S_OK
+    } else {
+        *object = std::ptr::null_mut();
+        E_NOINTERFACE
+    }
+}
+
+unsafe extern "system" fn add_ref(this: *mut IUnknown) -> ULONG {
+    let object = this as *const EffectObject<()>;
+    (*object).refcount.fetch_add(1, Ordering::Relaxed) as ULONG + 1
+}
+
+unsafe extern "system" fn release<T>(this: *mut IUnknown) -> ULONG {
+    let object = this as *mut EffectObject<T>;
+    let count = (*object).refcount.fetch_sub(1, Ordering::Release) - 1;
+    if count == 0 {
+        std::sync::atomic::fence(Ordering::Acquire);
+        drop(Box::from_raw(object));
+    }
+    count as ULONG
+}
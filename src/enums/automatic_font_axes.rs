@@ -0,0 +1,11 @@
+#[auto_enum::auto_enum(u32, checked)]
+/// Which variable font axes a layout derives from its other properties instead of taking them
+/// from the font axis values set on it.
+pub enum AutomaticFontAxes {
+    /// Only the explicitly set axis values are used.
+    None = 0,
+
+    /// The optical size (`opsz`) axis follows the font size, so small text uses the sturdier
+    /// shapes designed for it.
+    OpticalSize = 1,
+}
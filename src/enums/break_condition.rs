@@ -1,3 +1,5 @@
+use checked_enum::UncheckedEnum;
+
 #[auto_enum::auto_enum(u32, checked)]
 /// Indicates the condition at the edges of inline object or text used to
 /// determine line-breaking behavior.
@@ -20,3 +22,18 @@ pub enum BreakCondition {
     /// of the adjacent text span or inline object.
     MustBreak = 3,
 }
+
+impl BreakCondition {
+    /// Combines the conditions the two sides of a break opportunity declare for it, e.g. the
+    /// text before an inline object and the object's `preceding` condition, the way
+    /// DirectWrite resolves them: `MustBreak` overrules everything, `MayNotBreak` overrules
+    /// `CanBreak`, and `Neutral` defers to the other side. Two neutral sides stay `Neutral`,
+    /// which the layout treats as no break.
+    pub fn combine(
+        a: UncheckedEnum<BreakCondition>,
+        b: UncheckedEnum<BreakCondition>,
+    ) -> UncheckedEnum<BreakCondition> {
+        // The discriminants are in order of precedence
+        a.value.max(b.value).into()
+    }
+}
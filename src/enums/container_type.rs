@@ -0,0 +1,12 @@
+#[auto_enum::auto_enum(u32, checked)]
+/// The type of container which packed font data is wrapped in.
+pub enum ContainerType {
+    /// The data isn't in a recognized container, and may be a plain font file.
+    Unknown = 0,
+
+    /// The data is a WOFF (Web Open Font Format) container.
+    Woff = 1,
+
+    /// The data is a WOFF2 (Web Open Font Format 2) container.
+    Woff2 = 2,
+}
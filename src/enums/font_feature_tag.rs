@@ -4,6 +4,10 @@ use std::borrow::Cow;
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FontFeatureTag(pub u32);
 
+/// The OpenType tag of a variable font axis. Axis tags are encoded the same way as feature
+/// tags, e.g. `FontAxisTag::from(*b"wght")`.
+pub type FontAxisTag = FontFeatureTag;
+
 #[cfg(target_endian = "little")]
 macro_rules! feature_tag {
     ($v0:expr, $v1:expr, $v2:expr, $v3:expr) => {
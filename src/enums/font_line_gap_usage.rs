@@ -0,0 +1,12 @@
+#[auto_enum::auto_enum(u32, checked)]
+/// Whether the line gap from the font's metrics is included in line spacing.
+pub enum FontLineGapUsage {
+    /// The usage of the line gap depends on the method used for line spacing.
+    Default = 0,
+
+    /// The font's line gap is excluded from line spacing.
+    Disabled = 1,
+
+    /// The font's line gap is included in line spacing.
+    Enabled = 2,
+}
@@ -0,0 +1,16 @@
+#[auto_enum::auto_enum(u32, checked)]
+/// How much of a font's data is available on the local machine. Fonts from the system and
+/// local files are always `Local`; fonts from downloadable font sets start out `Remote` and
+/// become available as their data is downloaded.
+pub enum FontLocality {
+    /// None of the font data is available locally, so it can't be used for layout or
+    /// rendering until it has been downloaded.
+    Remote = 0,
+
+    /// Some of the font data is available locally. Text which only needs the downloaded
+    /// parts can be laid out and rendered.
+    Partial = 1,
+
+    /// All of the font data is available locally.
+    Local = 2,
+}
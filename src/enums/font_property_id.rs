@@ -0,0 +1,47 @@
+#[auto_enum::auto_enum(u32, checked)]
+/// Identifies a string property of a font in a FontSet, used to query or filter the set.
+pub enum FontPropertyId {
+    /// Unspecified font property identifier.
+    None,
+
+    /// Family name for the weight-stretch-style model.
+    FamilyName,
+
+    /// Family name preferred by the designer. This enables font designers to group more than
+    /// four fonts in a single family without losing compatibility with GDI.
+    PreferredFamilyName,
+
+    /// Face name of the font, e.g. Regular or Bold.
+    FaceName,
+
+    /// The full name of the font, e.g. "Arial Bold", from name id 4 in the name table.
+    FullName,
+
+    /// GDI-compatible family name. Because GDI allows a maximum of four fonts per family, fonts
+    /// in the same preferred family may have different GDI-compatible family names.
+    Win32FamilyName,
+
+    /// The postscript name of the font, e.g. "GillSans-Bold", from name id 6 in the name table.
+    PostscriptName,
+
+    /// Script/language tag to identify the scripts or languages that the font was primarily
+    /// designed to support.
+    DesignScriptLanguageTag,
+
+    /// Script/language tag to identify the scripts or languages that the font declares it is
+    /// able to support.
+    SupportedScriptLanguageTag,
+
+    /// Semantic tag to describe the font, e.g. Fancy, Decorative, Handmade, Sans-serif, Swiss,
+    /// Pixel, Futuristic.
+    SemanticTag,
+
+    /// Weight of the font represented as a decimal string in the range 1-999.
+    Weight,
+
+    /// Stretch of the font represented as a decimal string in the range 1-9.
+    Stretch,
+
+    /// Style of the font represented as a decimal string in the range 0-2.
+    Style,
+}
@@ -0,0 +1,31 @@
+#[auto_enum::enum_flags(u32)]
+/// The formats a font can store glyph images in. Outline fonts report `TRUETYPE` or `CFF`,
+/// while color fonts add the formats of their color or bitmap glyphs.
+pub enum GlyphImageFormats {
+    /// The font has no glyph images, or none were found.
+    NONE = 0,
+
+    /// TrueType outlines in the `glyf` table.
+    TRUETYPE = 0x1,
+
+    /// CFF outlines in the `CFF ` or `CFF2` table.
+    CFF = 0x2,
+
+    /// Layered color glyphs in the `COLR` table.
+    COLR = 0x4,
+
+    /// SVG documents in the `SVG ` table.
+    SVG = 0x8,
+
+    /// PNG bitmaps in the `sbix` or `CBDT` table.
+    PNG = 0x10,
+
+    /// JPEG bitmaps in the `sbix` table.
+    JPEG = 0x20,
+
+    /// TIFF bitmaps in the `sbix` table.
+    TIFF = 0x40,
+
+    /// Uncompressed premultiplied BGRA bitmaps in the `sbix` table.
+    PREMULTIPLIED_B8G8R8A8 = 0x80,
+}
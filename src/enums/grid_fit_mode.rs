@@ -0,0 +1,12 @@
+#[auto_enum::auto_enum(u32, checked)]
+/// Specifies whether to enable grid-fitting of glyph outlines (also known as hinting).
+pub enum GridFitMode {
+    /// Choose grid fitting based on the font's table information.
+    Default = 0,
+
+    /// Always disable grid fitting, using the ideal glyph outlines.
+    Disabled = 1,
+
+    /// Enable grid fitting, adjusting glyph outlines for device pixel display.
+    Enabled = 2,
+}
@@ -1,16 +1,26 @@
 //! Enumerations and bit-combining flag structures for DirectWrite.
 
+#[doc(inline)]
+pub use self::automatic_font_axes::AutomaticFontAxes;
 #[doc(inline)]
 pub use self::break_condition::BreakCondition;
 #[doc(inline)]
+pub use self::container_type::ContainerType;
+#[doc(inline)]
 pub use self::flow_direction::FlowDirection;
 #[doc(inline)]
 pub use self::font_face_type::FontFaceType;
 #[doc(inline)]
-pub use self::font_feature_tag::FontFeatureTag;
+pub use self::font_feature_tag::{FontAxisTag, FontFeatureTag};
 #[doc(inline)]
 pub use self::font_file_type::FontFileType;
 #[doc(inline)]
+pub use self::font_line_gap_usage::FontLineGapUsage;
+#[doc(inline)]
+pub use self::font_locality::FontLocality;
+#[doc(inline)]
+pub use self::font_property_id::FontPropertyId;
+#[doc(inline)]
 pub use self::font_simulations::FontSimulations;
 #[doc(inline)]
 pub use self::font_stretch::FontStretch;
@@ -19,6 +29,10 @@ pub use self::font_style::FontStyle;
 #[doc(inline)]
 pub use self::font_weight::FontWeight;
 #[doc(inline)]
+pub use self::glyph_image_formats::GlyphImageFormats;
+#[doc(inline)]
+pub use self::grid_fit_mode::GridFitMode;
+#[doc(inline)]
 pub use self::informational_string_id::InformationalStringId;
 #[doc(inline)]
 pub use self::line_spacing_method::LineSpacingMethod;
@@ -27,6 +41,10 @@ pub use self::measuring_mode::MeasuringMode;
 #[doc(inline)]
 pub use self::number_substitution_method::NumberSubstitutionMethod;
 #[doc(inline)]
+pub use self::optical_alignment::OpticalAlignment;
+#[doc(inline)]
+pub use self::panose_family::PanoseFamily;
+#[doc(inline)]
 pub use self::paragraph_alignment::ParagraphAlignment;
 #[doc(inline)]
 pub use self::pixel_geometry::PixelGeometry;
@@ -35,15 +53,29 @@ pub use self::reading_direction::ReadingDirection;
 #[doc(inline)]
 pub use self::rendering_mode::RenderingMode;
 #[doc(inline)]
+pub use self::rendering_mode1::{RenderingMode1, UnsupportedRenderingMode};
+#[doc(inline)]
+pub use self::script_shapes::ScriptShapes;
+#[doc(inline)]
 pub use self::text_alignment::TextAlignment;
 #[doc(inline)]
+pub use self::text_antialias_mode::TextAntialiasMode;
+#[doc(inline)]
+pub use self::texture_type::TextureType;
+#[doc(inline)]
 pub use self::trimming_granularity::TrimmingGranularity;
 #[doc(inline)]
+pub use self::vertical_glyph_orientation::VerticalGlyphOrientation;
+#[doc(inline)]
 pub use self::word_wrapping::WordWrapping;
 
+#[doc(hidden)]
+pub mod automatic_font_axes;
 #[doc(hidden)]
 pub mod break_condition;
 #[doc(hidden)]
+pub mod container_type;
+#[doc(hidden)]
 pub mod flow_direction;
 #[doc(hidden)]
 pub mod font_face_type;
@@ -52,6 +84,12 @@ pub mod font_feature_tag;
 #[doc(hidden)]
 pub mod font_file_type;
 #[doc(hidden)]
+pub mod font_line_gap_usage;
+#[doc(hidden)]
+pub mod font_locality;
+#[doc(hidden)]
+pub mod font_property_id;
+#[doc(hidden)]
 pub mod font_simulations;
 #[doc(hidden)]
 pub mod font_stretch;
@@ -60,6 +98,10 @@ pub mod font_style;
 #[doc(hidden)]
 pub mod font_weight;
 #[doc(hidden)]
+pub mod glyph_image_formats;
+#[doc(hidden)]
+pub mod grid_fit_mode;
+#[doc(hidden)]
 pub mod informational_string_id;
 #[doc(hidden)]
 pub mod line_spacing_method;
@@ -68,6 +110,10 @@ pub mod measuring_mode;
 #[doc(hidden)]
 pub mod number_substitution_method;
 #[doc(hidden)]
+pub mod optical_alignment;
+#[doc(hidden)]
+pub mod panose_family;
+#[doc(hidden)]
 pub mod paragraph_alignment;
 #[doc(hidden)]
 pub mod pixel_geometry;
@@ -76,8 +122,18 @@ pub mod reading_direction;
 #[doc(hidden)]
 pub mod rendering_mode;
 #[doc(hidden)]
+pub mod rendering_mode1;
+#[doc(hidden)]
+pub mod script_shapes;
+#[doc(hidden)]
 pub mod text_alignment;
 #[doc(hidden)]
+pub mod text_antialias_mode;
+#[doc(hidden)]
+pub mod texture_type;
+#[doc(hidden)]
 pub mod trimming_granularity;
 #[doc(hidden)]
+pub mod vertical_glyph_orientation;
+#[doc(hidden)]
 pub mod word_wrapping;
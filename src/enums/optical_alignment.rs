@@ -0,0 +1,11 @@
+#[auto_enum::auto_enum(u32, checked)]
+/// The optical margin alignment mode.
+pub enum OpticalAlignment {
+    /// Align to the default origin and side-bearings of the glyph.
+    None = 0,
+
+    /// Align to the ink of the glyphs, such that the black box abuts the margins. This makes
+    /// the edges of justified text look straighter, as punctuation and round letters no longer
+    /// appear indented.
+    NoSideBearings = 1,
+}
@@ -0,0 +1,22 @@
+#[auto_enum::auto_enum(u32, checked)]
+/// The kind of typeface a PANOSE classification describes, which decides how the remaining
+/// PANOSE digits are interpreted.
+pub enum PanoseFamily {
+    /// Any kind; also used when the font has no classification.
+    Any = 0,
+
+    /// No kind fits.
+    NoFit = 1,
+
+    /// Latin text and display faces.
+    TextDisplay = 2,
+
+    /// Handwritten and script faces.
+    Script = 3,
+
+    /// Decorative faces.
+    Decorative = 4,
+
+    /// Symbol and pictorial faces.
+    Symbol = 5,
+}
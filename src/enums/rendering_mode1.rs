@@ -0,0 +1,97 @@
+use crate::enums::RenderingMode;
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use winapi::shared::winerror::E_INVALIDARG;
+
+#[auto_enum::auto_enum(u32, checked)]
+/// The extended set of glyph rendering methods introduced in Windows 8.1. The first seven
+/// values are the same as those of [`RenderingMode`](enum.RenderingMode.html), which converts
+/// into this losslessly.
+pub enum RenderingMode1 {
+    /// Specifies that the rendering mode is determined automatically, based on the font and size.
+    Default = 0,
+
+    /// Specifies that no anti-aliasing is performed. Each pixel is either set to the foreground
+    /// color of the text or retains the color of the background.
+    Aliased = 1,
+
+    /// Antialiasing in the horizontal direction, layout-compatible with GDI using
+    /// `CLEARTYPE_QUALITY`.
+    GdiClassic = 2,
+
+    /// Antialiasing in the horizontal direction, layout-compatible with GDI using
+    /// `CLEARTYPE_NATURAL_QUALITY`.
+    GdiNatural = 3,
+
+    /// Antialiasing in the horizontal direction with glyphs positioned at subpixel precision.
+    Natural = 4,
+
+    /// Like `Natural`, but antialiased in both the horizontal and vertical directions.
+    NaturalSymmetric = 5,
+
+    /// Specifies that rendering should bypass the rasterizer and use the outlines directly.
+    Outline = 6,
+
+    /// Like `NaturalSymmetric`, but rendered by drawing the glyphs at a higher resolution and
+    /// downsampling them, which is faster to draw and animate at large sizes at the cost of
+    /// some sharpness. Only understood by APIs which take a `RenderingMode1`.
+    NaturalSymmetricDownsampled = 7,
+}
+
+impl From<RenderingMode> for RenderingMode1 {
+    fn from(mode: RenderingMode) -> RenderingMode1 {
+        match mode {
+            RenderingMode::Default => RenderingMode1::Default,
+            RenderingMode::Aliased => RenderingMode1::Aliased,
+            RenderingMode::GdiClassic => RenderingMode1::GdiClassic,
+            RenderingMode::GdiNatural => RenderingMode1::GdiNatural,
+            RenderingMode::Natural => RenderingMode1::Natural,
+            RenderingMode::NaturalSymmetric => RenderingMode1::NaturalSymmetric,
+            RenderingMode::Outline => RenderingMode1::Outline,
+        }
+    }
+}
+
+impl TryFrom<RenderingMode1> for RenderingMode {
+    type Error = UnsupportedRenderingMode;
+
+    fn try_from(mode: RenderingMode1) -> Result<RenderingMode, UnsupportedRenderingMode> {
+        Ok(match mode {
+            RenderingMode1::Default => RenderingMode::Default,
+            RenderingMode1::Aliased => RenderingMode::Aliased,
+            RenderingMode1::GdiClassic => RenderingMode::GdiClassic,
+            RenderingMode1::GdiNatural => RenderingMode::GdiNatural,
+            RenderingMode1::Natural => RenderingMode::Natural,
+            RenderingMode1::NaturalSymmetric => RenderingMode::NaturalSymmetric,
+            RenderingMode1::Outline => RenderingMode::Outline,
+            RenderingMode1::NaturalSymmetricDownsampled => {
+                return Err(UnsupportedRenderingMode(mode))
+            }
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// A `RenderingMode1` which has no equivalent `RenderingMode`, so it can't be passed to an
+/// API which only understands the original rendering modes.
+pub struct UnsupportedRenderingMode(pub RenderingMode1);
+
+impl fmt::Display for UnsupportedRenderingMode {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "rendering mode {:?} requires an API which takes a RenderingMode1",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedRenderingMode {}
+
+impl From<UnsupportedRenderingMode> for dcommon::Error {
+    fn from(_: UnsupportedRenderingMode) -> dcommon::Error {
+        E_INVALIDARG.into()
+    }
+}
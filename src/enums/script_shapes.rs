@@ -0,0 +1,10 @@
+#[auto_enum::enum_flags(u32)]
+/// Indicates additional shaping requirements for text.
+pub enum ScriptShapes {
+    /// Indicates that there are no additional shaping requirements for text. Text is shaped
+    /// with the writing system default behavior.
+    DEFAULT = 0,
+
+    /// Indicates that text should leave no visible control or format control characters.
+    NO_VISUAL = 1,
+}
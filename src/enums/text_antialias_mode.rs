@@ -0,0 +1,9 @@
+#[auto_enum::auto_enum(u32, checked)]
+/// The antialiasing method used when rasterizing text.
+pub enum TextAntialiasMode {
+    /// Antialiases with ClearType, rendering each color channel of a pixel separately.
+    ClearType = 0,
+
+    /// Antialiases with a single grayscale coverage value per pixel.
+    Grayscale = 1,
+}
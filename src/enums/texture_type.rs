@@ -0,0 +1,12 @@
+#[auto_enum::auto_enum(u32, checked)]
+/// Identifies a type of alpha texture. An alpha texture is a bitmap of alpha values, each
+/// representing the darkness (i.e., opacity) of a pixel or subpixel.
+pub enum TextureType {
+    /// Specifies an alpha texture for aliased text rendering (i.e., each pixel is either fully
+    /// opaque or fully transparent), with one byte per pixel.
+    Aliased1x1 = 0,
+
+    /// Specifies an alpha texture for ClearType text rendering, with three bytes per pixel in
+    /// the horizontal dimension and one byte per pixel in the vertical dimension.
+    ClearType3x1 = 1,
+}
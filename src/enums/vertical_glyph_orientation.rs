@@ -0,0 +1,13 @@
+#[auto_enum::auto_enum(u32, checked)]
+/// Specifies how glyphs are oriented when the reading direction is vertical.
+pub enum VerticalGlyphOrientation {
+    /// In vertical layout, naturally horizontal scripts (Latin, Thai, Arabic, Devanagari)
+    /// are drawn rotated 90 degrees clockwise, while ideographic scripts (Chinese, Japanese,
+    /// Korean) remain upright.
+    Default = 0,
+
+    /// Ideographic scripts and scripts that permit stacking (Latin, Hebrew) are stacked in
+    /// vertical reading layout. Connected scripts (Arabic, Syriac, 'Phags-pa, Ogham) are still
+    /// rotated sideways.
+    Stacked = 1,
+}
@@ -0,0 +1,76 @@
+use crate::descriptions::TextRange;
+use crate::factory::Factory;
+use crate::font::IFont;
+use crate::font_collection::{FontCollection, IFontCollection};
+use crate::font_face::FontFace;
+use crate::font_family::IFontFamily;
+use crate::text_format::TextFormat;
+use crate::text_layout::{ITextLayout, TextLayout};
+use crate::text_renderer::recording;
+
+use dcommon::Error;
+
+#[derive(Clone, Debug)]
+/// A run of text drawn with a single font, as found by
+/// [`Factory::analyze_string`](struct.Factory.html#method.analyze_string).
+pub struct MeasuredRun {
+    /// The UTF-16 code units of the text in the run.
+    pub range: TextRange,
+
+    /// The English name of the family of the font the run is drawn with, or the first name
+    /// the family has if it has no English one. Empty if the font isn't in the format's
+    /// collection or the system collection.
+    pub family: String,
+
+    /// The font face the run is drawn with.
+    pub face: FontFace,
+
+    /// The sum of the advances of the run's glyphs in DIPs.
+    pub width: f32,
+}
+
+pub(crate) fn analyze_string(
+    factory: &Factory,
+    text: &str,
+    format: &TextFormat,
+) -> Result<Vec<MeasuredRun>, Error> {
+    // Wide enough that nothing wraps
+    let layout = TextLayout::create(factory)
+        .with_str(text)
+        .with_format(format)
+        .with_size(std::f32::MAX, std::f32::MAX)
+        .build()?;
+
+    let mut runs = recording::record_glyph_runs(&layout, 1.0)?;
+    runs.sort_by_key(|run| run.text_position);
+
+    let system = FontCollection::system_font_collection(factory, false).ok();
+    let mut measured = Vec::with_capacity(runs.len());
+    for run in runs {
+        let collection = layout.font_collection(run.text_position).ok();
+        let family = collection
+            .map(|c| c.value)
+            .iter()
+            .chain(system.iter())
+            .filter_map(|collection| family_name(collection, &run.font_face))
+            .next()
+            .unwrap_or_default();
+
+        measured.push(MeasuredRun {
+            range: run.text_range(),
+            family,
+            width: run.glyph_advances.iter().sum(),
+            face: run.font_face,
+        });
+    }
+    Ok(measured)
+}
+
+fn family_name(collection: &FontCollection, face: &FontFace) -> Option<String> {
+    let names = collection
+        .font_from_face(face)?
+        .font_family()?
+        .family_name()?;
+    let name = names.get_by_name("en-us").or_else(|| names.get(0))?;
+    Some(name.string())
+}
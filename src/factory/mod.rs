@@ -1,11 +1,56 @@
+use crate::color_glyph_run::ColorGlyphRunEnumerator;
+use crate::descriptions::{GlyphRun, GlyphRunDescription};
+use crate::enums::{ContainerType, MeasuringMode, RenderingMode};
+use crate::font_download::FontDownloadQueue;
+use crate::font_set::FontSet;
+use crate::gdi_interop::GdiInterop;
+use crate::glyph_run_analysis::{CoverageBitmap, GlyphRunAnalysis};
+use crate::helpers::query_interface;
+use crate::registration::Registration;
+use crate::sys::IDWriteFactory5;
+use crate::text_analysis::TextAnalyzer;
+use crate::text_format::TextFormat;
+
+use std::sync::Mutex;
+
+use checked_enum::UncheckedEnum;
 use com_wrapper::ComWrapper;
 use dcommon::Error;
-use winapi::shared::winerror::SUCCEEDED;
-use winapi::um::dwrite::{DWriteCreateFactory, IDWriteFactory, DWRITE_FACTORY_TYPE_SHARED};
+use math2d::{Matrix3x2f, Point2f, Recti};
+use winapi::shared::winerror::{DWRITE_E_NOCOLOR, E_NOINTERFACE, SUCCEEDED};
+use winapi::um::dwrite::{
+    DWriteCreateFactory, IDWriteFactory, DWRITE_FACTORY_TYPE, DWRITE_FACTORY_TYPE_ISOLATED,
+    DWRITE_FACTORY_TYPE_SHARED,
+};
+use winapi::um::dwrite_2::{
+    IDWriteFactory2, DWRITE_GRID_FIT_MODE_DEFAULT, DWRITE_TEXT_ANTIALIAS_MODE_GRAYSCALE,
+};
+use winapi::um::dwrite_3::IDWriteFactory3;
 use winapi::um::unknwnbase::IUnknown;
 use winapi::Interface;
 use wio::com::ComPtr;
 
+#[doc(inline)]
+pub use self::analyze::MeasuredRun;
+
+#[doc(hidden)]
+pub mod analyze;
+
+pub use crate::origin::{clear_mismatch_hook, set_mismatch_hook, FactoryMismatch};
+
+/// Whether this build checks that text formats, font collections, font files and loaders are
+/// only passed to builders of the factory which created them. When they aren't, `build` fails
+/// with `E_INVALIDARG`, after calling the hook installed with [`set_mismatch_hook`][1] with
+/// both factories. True in debug builds with the `factory-tracking` feature; in any other build
+/// the checks are compiled out entirely.
+///
+/// [1]: fn.set_mismatch_hook.html
+pub const FACTORY_TRACKING: bool = crate::origin::ENABLED;
+
+/// The identities of the factories created by `new_isolated`. `new` removes the shared
+/// factory's identity, in case it reuses the address of an isolated factory which was released.
+static ISOLATED: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
 #[repr(transparent)]
 #[derive(Clone, ComWrapper)]
 #[com(send, sync, debug)]
@@ -15,12 +60,76 @@ pub struct Factory {
 }
 
 impl Factory {
+    /// Wraps a factory which is only borrowed, taking a new reference to it.
+    pub(crate) unsafe fn from_borrowed(raw: &IDWriteFactory) -> Factory {
+        raw.AddRef();
+        Factory::from_raw(raw as *const _ as *mut _)
+    }
+
     /// Initializes a new Factory.
     pub fn new() -> Result<Factory, Error> {
+        let factory = Factory::with_type(DWRITE_FACTORY_TYPE_SHARED)?;
+        let identity = factory.identity();
+        ISOLATED.lock().unwrap().retain(|&f| f != identity);
+        Ok(factory)
+    }
+
+    /// Initializes a factory which doesn't share its state with any other factory. Font
+    /// loaders registered with it are only visible to it, and it keeps its own font caches,
+    /// so e.g. a plugin can register custom loaders without affecting its host application.
+    pub fn new_isolated() -> Result<Factory, Error> {
+        let factory = Factory::with_type(DWRITE_FACTORY_TYPE_ISOLATED)?;
+        let identity = factory.identity();
+        let mut isolated = ISOLATED.lock().unwrap();
+        if !isolated.contains(&identity) {
+            isolated.push(identity);
+        }
+        Ok(factory)
+    }
+
+    /// Whether this factory was created with [`new_isolated`][1], i.e. it isn't the shared
+    /// factory which `new` returns to every caller in the process. This is recorded when the
+    /// factory is created, so a factory which only reached this crate through `from_raw` is
+    /// never reported as isolated, even if it is.
+    ///
+    /// DirectWrite has no way to trim the font caches of a factory. An application which needs
+    /// to reclaim that memory can do its work on an isolated factory instead, and once it is
+    /// done call [`unregister_all_loaders`][2] and drop the factory along with everything
+    /// created from it, which releases its caches.
+    ///
+    /// [1]: #method.new_isolated
+    /// [2]: #method.unregister_all_loaders
+    pub fn is_isolated(&self) -> bool {
+        let identity = self.identity();
+        ISOLATED.lock().unwrap().contains(&identity)
+    }
+
+    /// The address of the factory's `IUnknown`, which is the same for every pointer to it.
+    fn identity(&self) -> usize {
+        unsafe {
+            query_interface::<IUnknown>(&self.ptr)
+                .map(|u| u.as_raw() as usize)
+                .unwrap_or(self.ptr.as_raw() as usize)
+        }
+    }
+
+    /// Unregisters every custom file and collection loader which was registered on this
+    /// factory through this crate and hasn't been unregistered yet, including ones whose
+    /// handles were all dropped, returning how many were unregistered. Loaders can be
+    /// registered on the factory again afterwards.
+    ///
+    /// Loaders with files or collections created from them which are still alive are left
+    /// registered, and `HRESULT_FROM_WIN32(ERROR_BUSY)` is returned after the rest have been
+    /// unregistered.
+    pub fn unregister_all_loaders(&self) -> Result<usize, Error> {
+        Registration::unregister_all(self)
+    }
+
+    fn with_type(factory_type: DWRITE_FACTORY_TYPE) -> Result<Factory, Error> {
         unsafe {
             let mut ptr: *mut IDWriteFactory = std::ptr::null_mut();
             let hr = DWriteCreateFactory(
-                DWRITE_FACTORY_TYPE_SHARED,
+                factory_type,
                 &IDWriteFactory::uuidof(),
                 &mut ptr as *mut _ as *mut *mut IUnknown,
             );
@@ -32,6 +141,351 @@ impl Factory {
             }
         }
     }
+
+    /// Translates a glyph run into a sequence of color glyph runs, which can be rendered
+    /// in order to produce the color version of the glyphs. Returns `None` if the run
+    /// doesn't contain any color glyphs, in which case it should be drawn normally.
+    ///
+    /// Requires Windows 8.1; on older versions this fails with `E_NOINTERFACE`.
+    pub fn translate_color_glyph_run(
+        &self,
+        baseline_origin: Point2f,
+        run: &GlyphRun,
+        run_desc: Option<&GlyphRunDescription>,
+        measuring_mode: MeasuringMode,
+    ) -> Result<Option<ColorGlyphRunEnumerator>, Error> {
+        self.translate_color_glyph_run_with_palette(
+            baseline_origin,
+            run,
+            run_desc,
+            measuring_mode,
+            0,
+        )
+    }
+
+    /// Like [`translate_color_glyph_run`][1], but picks the layer colors from the font's
+    /// color palette at `palette_index` instead of the default palette.
+    ///
+    /// [1]: #method.translate_color_glyph_run
+    pub fn translate_color_glyph_run_with_palette(
+        &self,
+        baseline_origin: Point2f,
+        run: &GlyphRun,
+        run_desc: Option<&GlyphRunDescription>,
+        measuring_mode: MeasuringMode,
+        palette_index: u32,
+    ) -> Result<Option<ColorGlyphRunEnumerator>, Error> {
+        unsafe {
+            let factory = query_interface::<IDWriteFactory2>(&self.ptr)?;
+            let raw_run = run.into_raw()?;
+            let raw_desc = run_desc.map(|d| d.into_raw());
+            let mut ptr = std::ptr::null_mut();
+            let hr = factory.TranslateColorGlyphRun(
+                baseline_origin.x,
+                baseline_origin.y,
+                &raw_run,
+                match &raw_desc {
+                    Some(desc) => desc as *const _,
+                    None => std::ptr::null(),
+                },
+                measuring_mode as u32,
+                std::ptr::null(),
+                palette_index,
+                &mut ptr,
+            );
+            if hr == DWRITE_E_NOCOLOR {
+                Ok(None)
+            } else if SUCCEEDED(hr) {
+                Ok(Some(ColorGlyphRunEnumerator::from_raw(ptr)))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Rasterizes a glyph run into a single grayscale coverage bitmap. For runs with color
+    /// glyphs the coverage of every color layer is composited together, ignoring the colors,
+    /// which gives the monochrome look of e.g. emoji when color rendering isn't available or
+    /// wanted. Runs without color glyphs are rasterized as they are, as are all runs on
+    /// systems older than Windows 8.1 where color fonts aren't supported.
+    ///
+    /// `RenderingMode::Default` is not allowed here. The ClearType modes are averaged down to
+    /// grayscale.
+    pub fn color_glyph_run_flattened(
+        &self,
+        baseline_origin: Point2f,
+        run: &GlyphRun,
+        pixels_per_dip: f32,
+        rendering_mode: RenderingMode,
+        measuring_mode: MeasuringMode,
+    ) -> Result<CoverageBitmap, Error> {
+        let layers =
+            match self.translate_color_glyph_run(baseline_origin, run, None, measuring_mode) {
+                Ok(layers) => layers,
+                Err(e) if e.0 == E_NOINTERFACE => None,
+                Err(e) => return Err(e),
+            };
+
+        let rasterize = |run: &GlyphRun, origin: Point2f| {
+            let analysis = self.create_glyph_run_analysis(
+                run,
+                pixels_per_dip,
+                None,
+                rendering_mode,
+                measuring_mode,
+                origin.x,
+                origin.y,
+            )?;
+            CoverageBitmap::from_analysis(&analysis, rendering_mode)
+        };
+
+        let layers = match layers {
+            Some(layers) => layers,
+            None => return rasterize(run, baseline_origin),
+        };
+
+        let mut flattened = CoverageBitmap {
+            bounds: Recti {
+                left: 0,
+                top: 0,
+                right: 0,
+                bottom: 0,
+            },
+            data: Vec::new(),
+        };
+        for layer in layers {
+            let layer = layer?;
+            let coverage = rasterize(&layer.glyph_run(), layer.baseline_origin)?;
+            flattened.composite(&coverage);
+        }
+        Ok(flattened)
+    }
+
+    /// Analyzes a glyph run for rasterization, snapping glyph positions to pixels and computing
+    /// the alpha coverage of each pixel. The `transform` maps DIPs to device pixels after the
+    /// `pixels_per_dip` scale has been applied, and defaults to the identity. Fails with
+    /// `E_INVALIDARG` if the advances or offsets of `run` are neither empty nor as long as its
+    /// glyph indices.
+    ///
+    /// `RenderingMode::Default` is not allowed here; use
+    /// [`IFontFace::recommended_rendering_mode`][1] to pick a concrete mode first.
+    ///
+    /// [1]: font_face/trait.IFontFace.html#method.recommended_rendering_mode
+    pub fn create_glyph_run_analysis(
+        &self,
+        run: &GlyphRun,
+        pixels_per_dip: f32,
+        transform: Option<&Matrix3x2f>,
+        rendering_mode: RenderingMode,
+        measuring_mode: MeasuringMode,
+        baseline_x: f32,
+        baseline_y: f32,
+    ) -> Result<GlyphRunAnalysis, Error> {
+        unsafe {
+            let raw_run = run.into_raw()?;
+            let mut ptr = std::ptr::null_mut();
+            let hr = self.ptr.CreateGlyphRunAnalysis(
+                &raw_run,
+                pixels_per_dip,
+                match transform {
+                    Some(x) => x as *const Matrix3x2f as *const _,
+                    None => std::ptr::null(),
+                },
+                rendering_mode as u32,
+                measuring_mode as u32,
+                baseline_x,
+                baseline_y,
+                &mut ptr,
+            );
+            if SUCCEEDED(hr) {
+                Ok(GlyphRunAnalysis::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Like [`create_glyph_run_analysis`][1], but the analysis is antialiased in grayscale, so
+    /// its `Aliased1x1` texture holds 256 levels of coverage per pixel. Requires Windows 8.1;
+    /// on older versions this fails with `E_NOINTERFACE`.
+    ///
+    /// [1]: #method.create_glyph_run_analysis
+    pub(crate) fn create_grayscale_glyph_run_analysis(
+        &self,
+        run: &GlyphRun,
+        pixels_per_dip: f32,
+        transform: Option<&Matrix3x2f>,
+        rendering_mode: RenderingMode,
+        measuring_mode: MeasuringMode,
+        baseline_x: f32,
+        baseline_y: f32,
+    ) -> Result<GlyphRunAnalysis, Error> {
+        unsafe {
+            let factory = query_interface::<IDWriteFactory2>(&self.ptr)?;
+            // This version takes the DPI scale as part of the transform
+            let t = *transform.unwrap_or(&Matrix3x2f::IDENTITY);
+            let transform = Matrix3x2f {
+                a: pixels_per_dip * t.a,
+                b: pixels_per_dip * t.b,
+                c: pixels_per_dip * t.c,
+                d: pixels_per_dip * t.d,
+                x: t.x,
+                y: t.y,
+            };
+            let raw_run = run.into_raw()?;
+            let mut ptr = std::ptr::null_mut();
+            let hr = factory.CreateGlyphRunAnalysis(
+                &raw_run,
+                &transform as *const Matrix3x2f as *const _,
+                rendering_mode as u32,
+                measuring_mode as u32,
+                DWRITE_GRID_FIT_MODE_DEFAULT,
+                DWRITE_TEXT_ANTIALIAS_MODE_GRAYSCALE,
+                baseline_x,
+                baseline_y,
+                &mut ptr,
+            );
+            if SUCCEEDED(hr) {
+                Ok(GlyphRunAnalysis::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Creates a text analyzer, which splits text into runs of script, bidi level and number
+    /// substitution ahead of shaping.
+    pub fn create_text_analyzer(&self) -> Result<TextAnalyzer, Error> {
+        unsafe {
+            let mut ptr = std::ptr::null_mut();
+            let hr = self.ptr.CreateTextAnalyzer(&mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(TextAnalyzer::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Gets the GDI interop object, for converting between GDI fonts and DirectWrite fonts.
+    pub fn gdi_interop(&self) -> Result<GdiInterop, Error> {
+        unsafe {
+            let mut ptr = std::ptr::null_mut();
+            let hr = self.ptr.GetGdiInterop(&mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(GdiInterop::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Gets the queue of requests to download fonts which are referenced by layouts or font
+    /// faces, but aren't available locally yet. Requires Windows 10; on older versions this
+    /// fails with `E_NOINTERFACE`.
+    pub fn font_download_queue(&self) -> Result<FontDownloadQueue, Error> {
+        unsafe {
+            let factory = query_interface::<IDWriteFactory3>(&self.ptr)?;
+            let mut ptr = std::ptr::null_mut();
+            let hr = factory.GetFontDownloadQueue(&mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(FontDownloadQueue::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Determines whether `data` is packed in a web font container which must be unpacked with
+    /// [`unpack_font_data`][1] before it can be loaded.
+    ///
+    /// Requires Windows 10 Creators Update; on older versions this fails with `E_NOINTERFACE`.
+    ///
+    /// [1]: #method.unpack_font_data
+    pub fn analyze_container(&self, data: &[u8]) -> Result<UncheckedEnum<ContainerType>, Error> {
+        unsafe {
+            let factory = query_interface::<IDWriteFactory5>(&self.ptr)?;
+            let kind = factory.AnalyzeContainerType(data.as_ptr() as *const _, data.len() as u32);
+            Ok(kind.into())
+        }
+    }
+
+    /// Unpacks a WOFF or WOFF2 container, returning the contained OpenType font data. The
+    /// result can be loaded with an [`InMemoryLoader`][1], or by
+    /// [`InMemoryLoader::create_file_from_packed`][2] directly.
+    ///
+    /// Requires Windows 10 Creators Update; on older versions this fails with `E_NOINTERFACE`.
+    ///
+    /// [1]: font_file/loader/struct.InMemoryLoader.html
+    /// [2]: font_file/loader/struct.InMemoryLoader.html#method.create_file_from_packed
+    pub fn unpack_font_data(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        unsafe {
+            let factory = query_interface::<IDWriteFactory5>(&self.ptr)?;
+            let container =
+                factory.AnalyzeContainerType(data.as_ptr() as *const _, data.len() as u32);
+
+            let mut stream = std::ptr::null_mut();
+            let hr = factory.UnpackFontFile(
+                container,
+                data.as_ptr() as *const _,
+                data.len() as u32,
+                &mut stream,
+            );
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            let stream = ComPtr::from_raw(stream);
+
+            let mut size = 0;
+            let hr = stream.GetFileSize(&mut size);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+
+            let mut start = std::ptr::null();
+            let mut context = std::ptr::null_mut();
+            let hr = stream.ReadFileFragment(&mut start, 0, size, &mut context);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            let unpacked = std::slice::from_raw_parts(start as *const u8, size as usize).to_vec();
+            stream.ReleaseFileFragment(context);
+
+            Ok(unpacked)
+        }
+    }
+
+    /// Measures `text` in `format` and reports which font each part of it is drawn with and
+    /// how wide that part is, e.g. to see where font fallback kicked in.
+    ///
+    /// The text is laid out on a single unbounded line (except at hard line breaks) and
+    /// drawn with a recording renderer, and each glyph run becomes one `MeasuredRun`, sorted
+    /// by position. So the fonts are exactly the ones a layout would use, including fallback,
+    /// and runs are also split wherever DirectWrite splits them, e.g. between scripts or
+    /// bidi levels, even when the font stays the same.
+    pub fn analyze_string(
+        &self,
+        text: &str,
+        format: &TextFormat,
+    ) -> Result<Vec<MeasuredRun>, Error> {
+        analyze::analyze_string(self, text, format)
+    }
+
+    /// Gets the font set of all fonts installed on the system, including fonts which are
+    /// only available for download. Requires Windows 10; on older versions this fails with
+    /// `E_NOINTERFACE`.
+    pub fn system_font_set(&self) -> Result<FontSet, Error> {
+        unsafe {
+            let factory = query_interface::<IDWriteFactory3>(&self.ptr)?;
+            let mut ptr = std::ptr::null_mut();
+            let hr = factory.GetSystemFontSet(&mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(FontSet::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
 }
 
 pub unsafe trait IFactory {
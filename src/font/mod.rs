@@ -1,12 +1,15 @@
+use crate::descriptions::{FontAxisRange, Panose, UnicodeRange};
 use crate::enums::font_simulations::FontSimulations;
 use crate::enums::font_stretch::FontStretch;
 use crate::enums::font_style::FontStyle;
 use crate::enums::font_weight::FontWeight;
-use crate::enums::InformationalStringId;
+use crate::enums::{FontLocality, InformationalStringId};
 use crate::font_face::FontFace;
 use crate::font_family::FontFamily;
+use crate::helpers::query_interface;
 use crate::localized_strings::LocalizedStrings;
 use crate::metrics::font::FontMetrics;
+use crate::metrics::font1::FontMetrics1;
 
 use std::mem::MaybeUninit;
 
@@ -15,6 +18,12 @@ use com_wrapper::ComWrapper;
 use dcommon::Error;
 use winapi::shared::winerror::SUCCEEDED;
 use winapi::um::dwrite::IDWriteFont;
+use winapi::um::dwrite_1::{IDWriteFont1, DWRITE_UNICODE_RANGE};
+use winapi::um::dwrite_2::IDWriteFont2;
+use winapi::um::dwrite_3::{
+    IDWriteFont3, IDWriteFontFace5, IDWriteFontResource, DWRITE_FONT_AXIS_RANGE,
+    DWRITE_FONT_AXIS_VALUE,
+};
 use wio::com::ComPtr;
 
 #[derive(Clone, ComWrapper, PartialEq)]
@@ -85,6 +94,40 @@ pub unsafe trait IFont {
         }
     }
 
+    /// Gets at most `max_chars` characters of one informational string, in the given locale.
+    /// If the string has no value for `locale`, the `en-us` value is used, and failing that
+    /// the first value in the font. Returns `None` if the font doesn't contain the string.
+    fn informational_string_truncated(
+        &self,
+        id: InformationalStringId,
+        locale: &str,
+        max_chars: usize,
+    ) -> Result<Option<String>, Error> {
+        let strings = unsafe {
+            let mut ptr = std::ptr::null_mut();
+            let mut exists = 0;
+            let hr = self
+                .raw_font()
+                .GetInformationalStrings(id as u32, &mut ptr, &mut exists);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            if exists == 0 || ptr.is_null() {
+                return Ok(None);
+            }
+            LocalizedStrings::from_raw(ptr)
+        };
+
+        let string = strings
+            .get_by_name(locale)
+            .or_else(|| strings.get_by_name("en-us"))
+            .or_else(|| strings.get(0));
+        match string {
+            Some(string) => string.string_truncated(max_chars).map(Some),
+            None => Ok(None),
+        }
+    }
+
     /// Get metric information for this Font.
     fn metrics(&self) -> FontMetrics {
         unsafe {
@@ -94,6 +137,22 @@ pub unsafe trait IFont {
         }
     }
 
+    /// Get the metrics of this Font along with its glyph bounding box and the recommended
+    /// subscript and superscript placement. On systems older than Windows 8 only the fields
+    /// shared with [`metrics`](#method.metrics) are filled in and the rest are 0.
+    fn metrics1(&self) -> FontMetrics1 {
+        unsafe {
+            match query_interface::<IDWriteFont1>(self.raw_font()) {
+                Ok(font) => {
+                    let mut metrics = MaybeUninit::uninit();
+                    font.GetMetrics(metrics.as_mut_ptr());
+                    metrics.assume_init().into()
+                }
+                Err(_) => FontMetrics1::from_metrics(self.metrics()),
+            }
+        }
+    }
+
     /// Get simulations applied to this Font.
     fn simulations(&self) -> FontSimulations {
         unsafe { FontSimulations(self.raw_font().GetSimulations()) }
@@ -123,11 +182,117 @@ pub unsafe trait IFont {
         }
     }
 
+    /// Gets the ranges of code points in the font's character map, sorted and without
+    /// overlaps. This is much cheaper than creating a font face to check coverage.
+    ///
+    /// Requires Windows 8; on older versions this fails with `E_NOINTERFACE`.
+    fn unicode_ranges(&self) -> Result<Vec<UnicodeRange>, Error> {
+        unsafe {
+            let font = query_interface::<IDWriteFont1>(self.raw_font())?;
+            let mut count = 0;
+            font.GetUnicodeRanges(0, std::ptr::null_mut(), &mut count);
+
+            let mut ranges: Vec<DWRITE_UNICODE_RANGE> = Vec::with_capacity(count as usize);
+            let hr = font.GetUnicodeRanges(count, ranges.as_mut_ptr(), &mut count);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            ranges.set_len(count as usize);
+            Ok(ranges.into_iter().map(UnicodeRange::from).collect())
+        }
+    }
+
     /// Determines if this Font is a "Symbol" Font.
     fn is_symbol_font(&self) -> bool {
         unsafe { self.raw_font().IsSymbolFont() != 0 }
     }
 
+    /// Determines whether the font has color glyphs, e.g. an emoji font, which renderers
+    /// should draw with `Factory::translate_color_glyph_run` to get their colors. On systems older than
+    /// Windows 8.1 this is always false.
+    fn is_color_font(&self) -> bool {
+        unsafe {
+            match query_interface::<IDWriteFont2>(self.raw_font()) {
+                Ok(font) => font.IsColorFont() != 0,
+                Err(_) => false,
+            }
+        }
+    }
+
+    /// Gets how much of the font's data is available locally. Fonts from downloadable font
+    /// sets can't be drawn until their data arrives, so this lets a renderer avoid blocking
+    /// on them. On systems older than Windows 10 every font is `Local`.
+    fn locality(&self) -> UncheckedEnum<FontLocality> {
+        unsafe {
+            match query_interface::<IDWriteFont3>(self.raw_font()) {
+                Ok(font) => font.GetLocality().into(),
+                Err(_) => (FontLocality::Local as u32).into(),
+            }
+        }
+    }
+
+    /// Gets the PANOSE classification of the font. On systems older than Windows 8 this is
+    /// always all zeroes, i.e. family kind `Any`, as is the classification of fonts which
+    /// don't declare one.
+    fn panose(&self) -> Panose {
+        unsafe {
+            match query_interface::<IDWriteFont1>(self.raw_font()) {
+                Ok(font) => {
+                    let mut panose = std::mem::zeroed();
+                    font.GetPanose(&mut panose);
+                    panose.into()
+                }
+                Err(_) => Panose::default(),
+            }
+        }
+    }
+
+    /// Determines whether the font has design axes which can vary, i.e. is a variable font.
+    ///
+    /// Requires Windows 10 April 2018 Update (version 1803); on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn has_variations(&self) -> Result<bool, Error> {
+        unsafe { Ok(font_resource(self.raw_font())?.HasVariations() != 0) }
+    }
+
+    /// Gets the minimum, default and maximum value of each design axis of the font, e.g. to
+    /// give the sliders of a font picker the right bounds. Static fonts report their implicit
+    /// axes, such as the weight, with the minimum and maximum equal to the default.
+    ///
+    /// Requires Windows 10 April 2018 Update (version 1803); on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn axis_ranges(&self) -> Result<Vec<FontAxisRange>, Error> {
+        unsafe {
+            let resource = font_resource(self.raw_font())?;
+            let count = resource.GetFontAxisCount();
+            let mut ranges: Vec<DWRITE_FONT_AXIS_RANGE> = Vec::with_capacity(count as usize);
+            let mut defaults: Vec<DWRITE_FONT_AXIS_VALUE> = Vec::with_capacity(count as usize);
+
+            let hr = resource.GetFontAxisRanges(ranges.as_mut_ptr(), count);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            let hr = resource.GetDefaultFontAxisValues(defaults.as_mut_ptr(), count);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            ranges.set_len(count as usize);
+            defaults.set_len(count as usize);
+
+            // Both lists are in the order of the font's axes.
+            Ok(ranges
+                .iter()
+                .zip(&defaults)
+                .map(|(range, default)| FontAxisRange {
+                    tag: range.axisTag.into(),
+                    min: range.minValue,
+                    default: default.value,
+                    max: range.maxValue,
+                })
+                .collect())
+        }
+    }
+
     unsafe fn raw_font(&self) -> &IDWriteFont;
 }
 
@@ -136,3 +301,21 @@ unsafe impl IFont for Font {
         &self.ptr
     }
 }
+
+unsafe fn font_resource(font: &IDWriteFont) -> Result<ComPtr<IDWriteFontResource>, Error> {
+    let mut face = std::ptr::null_mut();
+    let hr = font.CreateFontFace(&mut face);
+    if !SUCCEEDED(hr) {
+        return Err(hr.into());
+    }
+    let face = ComPtr::from_raw(face);
+    let face = query_interface::<IDWriteFontFace5>(&*face)?;
+
+    let mut resource = std::ptr::null_mut();
+    let hr = face.GetFontResource(&mut resource);
+    if SUCCEEDED(hr) {
+        Ok(ComPtr::from_raw(resource))
+    } else {
+        Err(hr.into())
+    }
+}
@@ -1,7 +1,10 @@
 use crate::descriptions::{FontKey, KeyPayload};
-use crate::factory::IFactory;
+use crate::factory::{Factory, IFactory};
 use crate::font_collection::loader::CollectionLoaderHandle;
-use crate::font_collection::FontCollection;
+use crate::font_collection::{FontCollection, IFontCollection};
+use crate::font_family::IFontFamily;
+use crate::origin;
+use crate::profiling;
 
 use std::mem;
 use std::ptr;
@@ -18,7 +21,7 @@ pub struct FontCollectionBuilder<'a, K>
 where
     K: FontKey + ?Sized,
 {
-    factory: &'a dyn IFactory,
+    factory: Factory,
     loader: Option<&'a CollectionLoaderHandle<K>>,
     key: Option<&'a K>,
 }
@@ -27,9 +30,9 @@ impl<'a, K> FontCollectionBuilder<'a, K>
 where
     K: FontKey + ?Sized,
 {
-    pub(super) fn new(factory: &'a dyn IFactory) -> Self {
+    pub(super) fn new(factory: &dyn IFactory) -> Self {
         FontCollectionBuilder {
-            factory,
+            factory: unsafe { Factory::from_borrowed(factory.raw_f()) },
             loader: None,
             key: None,
         }
@@ -38,11 +41,29 @@ where
     /// Finalize the builder, attempting to create the FontCollection with the
     /// specified parameters.
     pub fn build(self) -> Result<FontCollection, Error> {
+        let start = match profiling::start() {
+            Some(start) => start,
+            None => return self.build_collection(),
+        };
+
+        let collection = self.build_collection()?;
+        profiling::report(start, |p, dur| {
+            let fonts = (0..collection.family_count())
+                .filter_map(|i| collection.family(i))
+                .map(|family| unsafe { family.raw_fontfamily().GetFontCount() as usize })
+                .sum();
+            p.collection_built(fonts, dur)
+        });
+        Ok(collection)
+    }
+
+    fn build_collection(self) -> Result<FontCollection, Error> {
         let loader = self.loader.expect("Font Loader must be specified");
         let key = KeyPayload::new(self.key.expect("Key must be specified"));
 
         unsafe {
             let f = self.factory.raw_f();
+            origin::check("collection loader", loader.factory(), f)?;
 
             let mut ptr = ptr::null_mut();
             let hr = f.CreateCustomFontCollection(
@@ -53,7 +74,8 @@ where
             );
 
             if SUCCEEDED(hr) {
-                Ok(FontCollection::from_raw(ptr))
+                origin::record(ptr, f);
+                Ok(FontCollection::from_raw(ptr).with_loader(loader.dependency()))
             } else {
                 Err(hr.into())
             }
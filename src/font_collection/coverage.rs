@@ -0,0 +1,56 @@
+use crate::font::{Font, IFont};
+use crate::font_face::IFontFace;
+use crate::helpers::query_interface;
+
+use std::cmp::Ordering;
+
+use dcommon::Error;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::dwrite_1::{IDWriteFont1, DWRITE_UNICODE_RANGE};
+
+/// Counts how many of the sorted, deduplicated `scalars` the font can display. Reads the
+/// font's character map ranges when the font exposes them, which is much cheaper than
+/// creating a font face; `ranges` is scratch space reused between calls.
+pub(crate) fn covered_count(
+    font: &Font,
+    scalars: &[u32],
+    ranges: &mut Vec<DWRITE_UNICODE_RANGE>,
+) -> Result<usize, Error> {
+    unsafe {
+        if let Ok(font1) = query_interface::<IDWriteFont1>(font.raw_font()) {
+            let mut count = 0;
+            font1.GetUnicodeRanges(0, std::ptr::null_mut(), &mut count);
+
+            ranges.clear();
+            ranges.reserve(count as usize);
+            let hr = font1.GetUnicodeRanges(count, ranges.as_mut_ptr(), &mut count);
+            if SUCCEEDED(hr) {
+                ranges.set_len(count as usize);
+                return Ok(count_in_ranges(scalars, ranges));
+            }
+        }
+    }
+
+    let face = font.create_face()?;
+    let indices = face.glyph_indices(scalars)?;
+    Ok(indices.iter().filter(|&&i| i != 0).count())
+}
+
+fn count_in_ranges(scalars: &[u32], ranges: &[DWRITE_UNICODE_RANGE]) -> usize {
+    // The ranges are sorted and don't overlap
+    scalars
+        .iter()
+        .filter(|&&c| {
+            let found = ranges.binary_search_by(|r| {
+                if r.last < c {
+                    Ordering::Less
+                } else if r.first > c {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            });
+            found.is_ok()
+        })
+        .count()
+}
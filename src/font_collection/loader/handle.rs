@@ -2,12 +2,14 @@ use crate::descriptions::FontKey;
 use crate::factory::Factory;
 use crate::font_collection::loader::com_loader::ComFontCollectionLoader;
 use crate::font_collection::loader::FontCollectionLoader;
+use crate::registration::{LoaderDependency, LoaderKind, Registration};
 
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 use com_wrapper::ComWrapper;
 use dcommon::Error;
-use winapi::shared::winerror::SUCCEEDED;
+use winapi::shared::winerror::{E_INVALIDARG, SUCCEEDED};
 use winapi::um::dwrite::IDWriteFontCollectionLoader;
 use wio::com::ComPtr;
 
@@ -16,6 +18,7 @@ use wio::com::ComPtr;
 /// a new FontCollection.
 pub struct CollectionLoaderHandle<K: FontKey + ?Sized> {
     pub(crate) ptr: ComPtr<IDWriteFontCollectionLoader>,
+    registration: Option<Arc<Registration>>,
     _marker: PhantomData<K>,
 }
 
@@ -29,25 +32,45 @@ impl<K: FontKey + ?Sized> CollectionLoaderHandle<K> {
             let com = ComFontCollectionLoader::new(loader);
             let hr = (*factory.get_raw()).RegisterFontCollectionLoader(com.as_raw());
             if SUCCEEDED(hr) {
-                Ok(CollectionLoaderHandle::from_ptr(com))
+                let registration =
+                    Registration::insert(factory, com.as_raw() as usize, LoaderKind::Collection);
+                Ok(CollectionLoaderHandle {
+                    ptr: com,
+                    registration: Some(registration),
+                    _marker: PhantomData,
+                })
             } else {
                 Err(hr.into())
             }
         }
     }
 
-    /// Unregister the loader from the factory so that it can be deallocated.
-    pub fn unregister(self, factory: &Factory) {
-        unsafe {
-            (*factory.get_raw()).UnregisterFontCollectionLoader(self.ptr.as_raw());
+    /// Unregister the loader from the factory it was registered on. This fails with
+    /// `HRESULT_FROM_WIN32(ERROR_BUSY)` while any collections created from this loader are still
+    /// alive, and the loader stays registered in that case.
+    pub fn unregister(&self) -> Result<(), Error> {
+        match &self.registration {
+            Some(registration) => registration.unregister(),
+            None => Err(E_INVALIDARG.into()),
         }
     }
+
+    /// The factory this loader was registered on. Returns `None` if the handle was recreated
+    /// from a raw pointer to a loader which wasn't registered through this crate.
+    pub fn factory(&self) -> Option<&Factory> {
+        self.registration.as_ref().map(|r| r.factory())
+    }
+
+    pub(crate) fn dependency(&self) -> Option<LoaderDependency> {
+        self.registration.as_ref().map(|r| r.dependency())
+    }
 }
 
 impl<K: FontKey + ?Sized> Clone for CollectionLoaderHandle<K> {
     fn clone(&self) -> Self {
         CollectionLoaderHandle {
             ptr: self.ptr.clone(),
+            registration: self.registration.clone(),
             _marker: PhantomData,
         }
     }
@@ -72,8 +95,10 @@ impl<K: FontKey + ?Sized> ComWrapper for CollectionLoaderHandle<K> {
     }
 
     unsafe fn from_ptr(ptr: ComPtr<IDWriteFontCollectionLoader>) -> Self {
+        let registration = Registration::find(ptr.as_raw() as usize);
         CollectionLoaderHandle {
             ptr,
+            registration,
             _marker: PhantomData,
         }
     }
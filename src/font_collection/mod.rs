@@ -1,14 +1,20 @@
 //! Font collections and types for building application-defined collections.
 
 use crate::descriptions::FontKey;
+use crate::enums::{FontStretch, FontStyle, FontWeight};
 use crate::factory::IFactory;
 use crate::font::Font;
 use crate::font_face::FontFace;
-use crate::font_family::FontFamily;
+use crate::font_family::{FontFamily, IFontFamily};
+use crate::origin;
+use crate::policy::SkipOrFail;
+use crate::registration::LoaderDependency;
+
+use std::cmp::Ordering;
 
 use com_wrapper::ComWrapper;
 use dcommon::Error;
-use winapi::shared::winerror::SUCCEEDED;
+use winapi::shared::winerror::{E_FAIL, SUCCEEDED};
 use winapi::um::dwrite::IDWriteFontCollection;
 use wio::com::ComPtr;
 use wio::wide::ToWide;
@@ -20,20 +26,72 @@ pub use crate::font_collection::builder::FontCollectionBuilder;
 pub mod builder;
 pub mod loader;
 
-#[derive(Clone, ComWrapper, PartialEq)]
-#[com(send, sync, debug)]
-#[repr(transparent)]
+mod coverage;
+
+#[derive(Clone)]
 /// An object that encapsulates a set of fonts, such as the set of fonts installed on the system,
 /// or the set of fonts in a particular directory. The font collection API can be used to discover
 /// what font families and fonts are available, and to obtain some metadata about the fonts.
 pub struct FontCollection {
     ptr: ComPtr<IDWriteFontCollection>,
+    // Keeps a custom loader from being unregistered while this object is alive.
+    loader: Option<LoaderDependency>,
+}
+
+raw_interop!(FontCollection, IDWriteFontCollection);
+
+impl FontCollection {
+    pub(crate) fn with_loader(mut self, loader: Option<LoaderDependency>) -> Self {
+        self.loader = loader;
+        self
+    }
+}
+
+unsafe impl Send for FontCollection {}
+unsafe impl Sync for FontCollection {}
+
+impl ComWrapper for FontCollection {
+    type Interface = IDWriteFontCollection;
+
+    unsafe fn get_raw(&self) -> *mut IDWriteFontCollection {
+        self.ptr.as_raw()
+    }
+
+    unsafe fn into_raw(self) -> *mut IDWriteFontCollection {
+        self.ptr.into_raw()
+    }
+
+    unsafe fn from_raw(raw: *mut IDWriteFontCollection) -> Self {
+        Self::from_ptr(ComPtr::from_raw(raw))
+    }
+
+    unsafe fn from_ptr(ptr: ComPtr<IDWriteFontCollection>) -> Self {
+        FontCollection { ptr, loader: None }
+    }
+
+    unsafe fn into_ptr(self) -> ComPtr<IDWriteFontCollection> {
+        self.ptr
+    }
+}
+
+impl PartialEq for FontCollection {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr.as_raw() == other.ptr.as_raw()
+    }
+}
+
+impl std::fmt::Debug for FontCollection {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("FontCollection")
+            .field("ptr", &self.ptr.as_raw())
+            .finish()
+    }
 }
 
 impl FontCollection {
     /// Construct a builder for a FontCollection. You'll need a CollectionLoaderHandle
     /// and its associated Key type.
-    pub fn create<'a, K>(factory: &'a dyn IFactory) -> FontCollectionBuilder<'a, K>
+    pub fn create<'a, K>(factory: &dyn IFactory) -> FontCollectionBuilder<'a, K>
     where
         K: FontKey,
     {
@@ -51,6 +109,7 @@ impl FontCollection {
             let check = if check_for_updates { 1 } else { 0 };
             let hr = factory.raw_f().GetSystemFontCollection(&mut fc, check);
             if SUCCEEDED(hr) {
+                origin::record(fc, factory.raw_f());
                 Ok(FontCollection::from_raw(fc))
             } else {
                 Err(hr.into())
@@ -58,10 +117,107 @@ impl FontCollection {
         }
     }
 
+    /// Gets the name of every family in the collection in the given locale, falling back to
+    /// the family's first name if it has none in that locale. Families which can't be fetched
+    /// are handled according to `policy`.
+    pub fn family_names(
+        &self,
+        locale: &str,
+        mut policy: SkipOrFail,
+    ) -> Result<Vec<String>, Error> {
+        let mut names = Vec::with_capacity(self.family_count() as usize);
+        for index in 0..self.family_count() {
+            let family = match policy.check(index, self.try_family(index))? {
+                Some(family) => family,
+                None => continue,
+            };
+
+            let name = family.family_name().and_then(|strings| {
+                strings
+                    .get_by_name(locale)
+                    .or_else(|| strings.get(0))
+                    .map(|s| s.string())
+            });
+            if let Some(name) = policy.check(index, name.ok_or_else(|| E_FAIL.into()))? {
+                names.push(name);
+            }
+        }
+        Ok(names)
+    }
+
+    /// Ranks the families of this collection by how much of `sample` they can display, returning
+    /// at most `limit` families with the fraction of the sample's distinct characters covered by
+    /// each family's regular font. Families are sorted from best to worst, and families with
+    /// equal scores keep their order in the collection.
+    ///
+    /// Once `limit` families cover the whole sample, the remaining families are skipped, since
+    /// none of them could rank higher.
+    ///
+    /// Families which can't be fetched are handled according to `policy`.
+    pub fn rank_by_coverage(
+        &self,
+        sample: &str,
+        limit: usize,
+        mut policy: SkipOrFail,
+    ) -> Result<Vec<(FontFamily, f32)>, Error> {
+        let mut scalars: Vec<u32> = sample.chars().map(|c| c as u32).collect();
+        scalars.sort();
+        scalars.dedup();
+
+        let mut ranked: Vec<(FontFamily, f32)> = Vec::new();
+        if limit == 0 {
+            return Ok(ranked);
+        }
+
+        let mut ranges = Vec::new();
+        let mut perfect = 0;
+        for index in 0..self.family_count() {
+            let family = match policy.check(index, self.try_family(index))? {
+                Some(family) => family,
+                None => continue,
+            };
+            let font = match family.first_matching_font(
+                FontWeight::NORMAL,
+                FontStretch::Normal,
+                FontStyle::Normal,
+            ) {
+                Some(font) => font,
+                None => continue,
+            };
+
+            let score = if scalars.is_empty() {
+                1.0
+            } else {
+                let covered = coverage::covered_count(&font, &scalars, &mut ranges)?;
+                covered as f32 / scalars.len() as f32
+            };
+
+            if score >= 1.0 {
+                perfect += 1;
+            }
+            ranked.push((family, score));
+            if perfect >= limit {
+                break;
+            }
+        }
+
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        ranked.truncate(limit);
+        Ok(ranked)
+    }
+
     /// Get an iterator of all font families in this collection
     pub fn all_families<'a>(&'a self) -> impl Iterator<Item = FontFamily> + 'a {
         (0..self.family_count()).filter_map(move |i| self.family(i))
     }
+
+    /// Get an iterator of every family index in this collection paired with the result of
+    /// fetching that family. Unlike `all_families`, failures are reported rather than left out.
+    pub fn enumerate_families<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (u32, Result<FontFamily, Error>)> + 'a {
+        (0..self.family_count()).map(move |i| (i, self.try_family(i)))
+    }
 }
 
 pub unsafe trait IFontCollection {
@@ -90,13 +246,19 @@ pub unsafe trait IFontCollection {
 
     /// Gets a FontFamily object given a zero-based font family index
     fn family(&self, index: u32) -> Option<FontFamily> {
+        self.try_family(index).ok()
+    }
+
+    /// Gets a FontFamily object given a zero-based font family index, returning the error if
+    /// it can't be fetched.
+    fn try_family(&self, index: u32) -> Result<FontFamily, Error> {
         unsafe {
             let mut ff = std::ptr::null_mut();
             let hr = self.raw_fontcol().GetFontFamily(index, &mut ff);
             if SUCCEEDED(hr) {
-                Some(FontFamily::from_raw(ff))
+                Ok(FontFamily::from_raw(ff))
             } else {
-                None
+                Err(hr.into())
             }
         }
     }
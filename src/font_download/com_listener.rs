@@ -0,0 +1,95 @@
+use crate::font_download::{FontDownloadListener, FontDownloadQueue};
+
+use com_impl::Refcount;
+use com_impl::VTable;
+use com_wrapper::ComWrapper;
+use winapi::shared::winerror::{HRESULT, SUCCEEDED};
+use winapi::um::dwrite_3::{
+    IDWriteFontDownloadListener, IDWriteFontDownloadListenerVtbl, IDWriteFontDownloadQueue,
+};
+use winapi::um::unknwnbase::IUnknown;
+use wio::com::ComPtr;
+
+#[repr(C)]
+#[derive(com_impl::ComImpl)]
+pub struct ComFontDownloadListener<T>
+where
+    T: FontDownloadListener,
+{
+    vtable: VTable<IDWriteFontDownloadListenerVtbl>,
+    refcount: Refcount,
+    listener: T,
+}
+
+impl<T> ComFontDownloadListener<T>
+where
+    T: FontDownloadListener,
+{
+    pub fn new(listener: T) -> ComPtr<IDWriteFontDownloadListener> {
+        let ptr = Self::create_raw(listener);
+        let ptr = ptr as *mut IDWriteFontDownloadListener;
+        unsafe { ComPtr::from_raw(ptr) }
+    }
+}
+
+#[com_impl::com_impl]
+unsafe impl<T> IDWriteFontDownloadListener for ComFontDownloadListener<T>
+where
+    T: FontDownloadListener,
+{
+    #[panic(abort)]
+    unsafe fn download_completed(
+        &self,
+        queue: *mut IDWriteFontDownloadQueue,
+        _context: *mut IUnknown,
+        result: HRESULT,
+    ) {
+        (*queue).AddRef();
+        let queue = FontDownloadQueue::from_raw(queue);
+        let result = if SUCCEEDED(result) {
+            Ok(())
+        } else {
+            Err(result.into())
+        };
+        self.listener.download_completed(&queue, result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ComFontDownloadListener;
+    use crate::font_download::FontDownloadQueue;
+    use crate::Factory;
+
+    use std::sync::{Arc, Mutex};
+
+    use com_wrapper::ComWrapper;
+    use winapi::shared::winerror::{E_ABORT, S_OK};
+
+    #[test]
+    fn download_completed_reaches_the_listener() {
+        let factory = Factory::new().unwrap();
+        let queue = factory.font_download_queue().unwrap();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let listener_received = received.clone();
+        let listener = ComFontDownloadListener::new(move |queue: &FontDownloadQueue, result| {
+            let queue = unsafe { queue.get_raw() } as usize;
+            listener_received.lock().unwrap().push((queue, result));
+        });
+
+        unsafe {
+            let queue_ptr = queue.get_raw();
+            listener.DownloadCompleted(queue_ptr, std::ptr::null_mut(), E_ABORT);
+            listener.DownloadCompleted(queue_ptr, std::ptr::null_mut(), S_OK);
+        }
+
+        let received = received.lock().unwrap();
+        let queue_ptr = unsafe { queue.get_raw() } as usize;
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].0, queue_ptr);
+        assert_eq!(received[0].1.as_ref().map_err(|e| e.0), Err(E_ABORT));
+        assert_eq!(received[1].0, queue_ptr);
+        assert!(received[1].1.is_ok());
+    }
+}
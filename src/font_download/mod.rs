@@ -0,0 +1,156 @@
+//! FontDownloadQueue and the listener trait for being notified when downloadable fonts arrive.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate directwrite;
+//! # fn main() {
+//! use directwrite::font_download::FontDownloadQueue;
+//! use directwrite::{Factory, TextFormat, TextLayout};
+//!
+//! let factory = Factory::new().unwrap();
+//! let format = TextFormat::create(&factory)
+//!     .with_family("Segoe UI")
+//!     .with_size(16.0)
+//!     .build()
+//!     .unwrap();
+//! let layout = TextLayout::create(&factory)
+//!     .with_str("Text which may use fonts that aren't downloaded yet")
+//!     .with_format(&format)
+//!     .with_size(400.0, 100.0)
+//!     .build()
+//!     .unwrap();
+//!
+//! // Laying out or drawing text in a remote font adds a request to the queue.
+//! let queue = factory.font_download_queue().unwrap();
+//! if !queue.is_empty() {
+//!     let _token = queue
+//!         .add_listener(|_: &FontDownloadQueue, result| {
+//!             if result.is_ok() {
+//!                 // Rebuild layouts using the downloaded fonts here.
+//!             }
+//!         })
+//!         .unwrap();
+//!     queue.begin_download().unwrap();
+//! }
+//! # let _ = layout;
+//! # }
+//! ```
+
+use crate::font_download::com_listener::ComFontDownloadListener;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::dwrite_3::IDWriteFontDownloadQueue;
+use wio::com::ComPtr;
+
+pub(crate) mod com_listener;
+
+#[repr(transparent)]
+#[derive(Clone, ComWrapper)]
+#[com(send, sync, debug)]
+/// The queue of requests to download fonts which are referenced but aren't available locally.
+/// Requests are added while laying out or drawing text in remote fonts, and downloaded when
+/// `begin_download` is called. Obtained from [`Factory::font_download_queue`][1].
+///
+/// Requires Windows 10.
+///
+/// [1]: ../struct.Factory.html#method.font_download_queue
+pub struct FontDownloadQueue {
+    ptr: ComPtr<IDWriteFontDownloadQueue>,
+}
+
+impl FontDownloadQueue {
+    /// Determines whether there are any pending download requests.
+    pub fn is_empty(&self) -> bool {
+        unsafe { self.ptr.IsEmpty() != 0 }
+    }
+
+    /// Begins an asynchronous download of all pending requests. Listeners are notified when
+    /// the download completes. Does nothing if the queue is empty.
+    pub fn begin_download(&self) -> Result<(), Error> {
+        unsafe {
+            let hr = self.ptr.BeginDownload(std::ptr::null_mut());
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Removes all pending requests from the queue, and cancels the download in progress if
+    /// there is one.
+    pub fn cancel_download(&self) -> Result<(), Error> {
+        unsafe {
+            let hr = self.ptr.CancelDownload();
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Gets a counter which is incremented each time a download completes, whether or not it
+    /// succeeded. This can be used to tell whether cached layouts may need to be rebuilt.
+    pub fn generation_count(&self) -> u64 {
+        unsafe { self.ptr.GetGenerationCount() }
+    }
+
+    /// Registers a listener to be notified when downloads complete. The listener remains
+    /// registered until it is removed with `remove_listener`.
+    pub fn add_listener(
+        &self,
+        listener: impl FontDownloadListener,
+    ) -> Result<ListenerToken, Error> {
+        unsafe {
+            let com = ComFontDownloadListener::new(listener);
+            let mut token = 0;
+            let hr = self.ptr.AddListener(com.as_raw(), &mut token);
+            if SUCCEEDED(hr) {
+                Ok(ListenerToken(token))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Unregisters a listener which was added with `add_listener`.
+    pub fn remove_listener(&self, token: ListenerToken) -> Result<(), Error> {
+        unsafe {
+            let hr = self.ptr.RemoveListener(token.0);
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+}
+
+/// Receives notifications when a download started with
+/// [`FontDownloadQueue::begin_download`][1] completes. Notifications may arrive on any thread.
+///
+/// [1]: struct.FontDownloadQueue.html#method.begin_download
+pub trait FontDownloadListener: Send + Sync + 'static {
+    /// Called when a download completes, successfully or not. The queue may already contain
+    /// new requests made while the download was in progress.
+    fn download_completed(&self, queue: &FontDownloadQueue, result: Result<(), Error>);
+}
+
+impl<F> FontDownloadListener for F
+where
+    F: Fn(&FontDownloadQueue, Result<(), Error>) + Send + Sync + 'static,
+{
+    fn download_completed(&self, queue: &FontDownloadQueue, result: Result<(), Error>) {
+        self(queue, result)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+/// Identifies a listener registered with [`FontDownloadQueue::add_listener`][1].
+///
+/// [1]: struct.FontDownloadQueue.html#method.add_listener
+pub struct ListenerToken(u32);
@@ -1,55 +1,223 @@
 use crate::enums::{FontFaceType, FontSimulations};
+use crate::factory::{Factory, IFactory};
 use crate::font_face::FontFace;
-use crate::font_file::FontFile;
+use crate::font_file::{FontFile, IFontFile};
+use crate::origin;
 
 use std::ptr;
 
+use checked_enum::UncheckedEnum;
+use com_wrapper::ComWrapper;
 use dcommon::error::Error;
-use winapi::shared::winerror::SUCCEEDED;
+use winapi::shared::winerror::{DWRITE_E_FILEFORMAT, E_INVALIDARG, SUCCEEDED};
 use winapi::um::dwrite::{IDWriteFactory, IDWriteFontFace, IDWriteFontFile};
 use wio::com::ComPtr;
 
 #[must_use]
 /// Facilitates construction of FontFace objects.
 ///
-/// `font_face_type`, `files`, and `face_index` are all required.
-/// `font_face_simulation_flags` defaults to NONE
-pub struct FontFaceBuilder<'a, 'b> {
-    factory: &'a IDWriteFactory,
+/// `font_face_type`, `files`, and `face_index` are all required, unless `infer_face_type` is
+/// used in place of `font_face_type`. `font_face_simulation_flags` defaults to NONE
+pub struct FontFaceBuilder<'a> {
+    factory: Factory,
     font_face_type: Option<FontFaceType>,
-    files: Option<&'b [FontFile]>,
+    files: Option<&'a [FontFile]>,
     face_index: Option<u32>,
     font_face_simulation_flags: FontSimulations,
+    validate: bool,
+    infer_face_type: bool,
 }
 
-impl<'a, 'b> FontFaceBuilder<'a, 'b> {
-    pub(super) fn new(factory: &'a IDWriteFactory) -> FontFaceBuilder<'a, 'b> {
+/// Describes why [`FontFaceBuilder::build_detailed`][1] failed.
+///
+/// [1]: struct.FontFaceBuilder.html#method.build_detailed
+pub enum FontFaceBuildError {
+    /// Analyzing the files or creating the face failed.
+    Failed(Error),
+
+    /// None of the files are in a format DirectWrite supports.
+    Unsupported,
+
+    /// The face index is past the number of faces in the files.
+    FaceIndexOutOfRange {
+        /// The index passed to `with_face_index`.
+        requested: u32,
+
+        /// The number of faces the files contain.
+        faces: u32,
+    },
+
+    /// The face type doesn't match what the files contain.
+    FaceTypeMismatch {
+        /// The type passed to `with_font_face_type`.
+        requested: FontFaceType,
+
+        /// The type analysis found.
+        found: UncheckedEnum<FontFaceType>,
+    },
+}
+
+impl std::fmt::Debug for FontFaceBuildError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FontFaceBuildError::Failed(error) => fmt.debug_tuple("Failed").field(error).finish(),
+            FontFaceBuildError::Unsupported => fmt.write_str("Unsupported"),
+            FontFaceBuildError::FaceIndexOutOfRange { requested, faces } => fmt
+                .debug_struct("FaceIndexOutOfRange")
+                .field("requested", requested)
+                .field("faces", faces)
+                .finish(),
+            FontFaceBuildError::FaceTypeMismatch { requested, found } => fmt
+                .debug_struct("FaceTypeMismatch")
+                .field("requested", requested)
+                .field("found", &found.value)
+                .finish(),
+        }
+    }
+}
+
+impl From<Error> for FontFaceBuildError {
+    fn from(error: Error) -> Self {
+        FontFaceBuildError::Failed(error)
+    }
+}
+
+impl From<FontFaceBuildError> for Error {
+    fn from(error: FontFaceBuildError) -> Self {
+        match error {
+            FontFaceBuildError::Failed(error) => error,
+            FontFaceBuildError::Unsupported => DWRITE_E_FILEFORMAT.into(),
+            _ => E_INVALIDARG.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for FontFaceBuildError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FontFaceBuildError::Failed(error) => {
+                write!(fmt, "failed to create font face: {:?}", error)
+            }
+            FontFaceBuildError::Unsupported => {
+                write!(fmt, "no file is in a supported font format")
+            }
+            FontFaceBuildError::FaceIndexOutOfRange { requested, faces } => write!(
+                fmt,
+                "face index {} requested but the files have {} face{}",
+                requested,
+                faces,
+                if *faces == 1 { "" } else { "s" }
+            ),
+            FontFaceBuildError::FaceTypeMismatch { requested, found } => write!(
+                fmt,
+                "file is {} but {} face type requested",
+                face_type_name(found.value),
+                face_type_name(requested.to_u32())
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FontFaceBuildError {}
+
+fn face_type_name(value: u32) -> &'static str {
+    const NAMES: &[(FontFaceType, &str)] = &[
+        (FontFaceType::CFF, "CFF"),
+        (FontFaceType::TrueType, "TrueType"),
+        (FontFaceType::OpenTypeCollection, "an OpenType collection"),
+        (FontFaceType::Type1, "Type 1"),
+        (FontFaceType::Vector, "a vector font"),
+        (FontFaceType::Bitmap, "a bitmap font"),
+        (FontFaceType::RawCFF, "raw CFF"),
+    ];
+    NAMES
+        .iter()
+        .find(|&&(ty, _)| ty.to_u32() == value)
+        .map(|&(_, name)| name)
+        .unwrap_or("of an unknown type")
+}
+
+impl<'a> FontFaceBuilder<'a> {
+    pub(super) fn new(factory: &IDWriteFactory) -> FontFaceBuilder<'a> {
         FontFaceBuilder {
-            factory,
+            factory: unsafe { Factory::from_borrowed(factory) },
             font_face_type: None,
             files: None,
             face_index: None,
             font_face_simulation_flags: FontSimulations::NONE,
+            validate: false,
+            infer_face_type: false,
         }
     }
 
     /// Finalizes construction of the FontFace.
     pub fn build(self) -> Result<FontFace, Error> {
+        self.build_detailed().map_err(Into::into)
+    }
+
+    /// Like `build`, but when validation is enabled, describes what didn't match the files.
+    pub fn build_detailed(self) -> Result<FontFace, FontFaceBuildError> {
+        let files = self.files.expect("`files` must be specified");
+        let face_index = self.face_index.expect("`face_index` must be specified");
+        for file in files {
+            unsafe { origin::check_object("font file", file.get_raw(), self.factory.raw_f())? }
+        }
+
+        let font_face_type = if self.validate || self.infer_face_type {
+            // Type 1 faces are split over several files, so consider all of them
+            let analyses = files
+                .iter()
+                .map(|file| file.analyze())
+                .collect::<Result<Vec<_>, Error>>()?;
+            let supported: Vec<_> = analyses.iter().filter(|a| a.supported).collect();
+            let found = supported
+                .iter()
+                .map(|a| a.face_type)
+                .find(|ty| ty.value != FontFaceType::Unknown.to_u32())
+                .ok_or(FontFaceBuildError::Unsupported)?;
+
+            let font_face_type = if self.infer_face_type {
+                match FACE_TYPES.iter().find(|&&ty| ty.to_u32() == found.value) {
+                    Some(&ty) => ty,
+                    None => return Err(FontFaceBuildError::Unsupported),
+                }
+            } else {
+                self.font_face_type
+                    .expect("`font_face_type` must be specified")
+            };
+
+            if self.validate {
+                let faces = supported.iter().map(|a| a.num_faces).max().unwrap_or(0);
+                if face_index >= faces {
+                    return Err(FontFaceBuildError::FaceIndexOutOfRange {
+                        requested: face_index,
+                        faces,
+                    });
+                }
+                if !supported
+                    .iter()
+                    .any(|a| a.face_type.value == font_face_type.to_u32())
+                {
+                    return Err(FontFaceBuildError::FaceTypeMismatch {
+                        requested: font_face_type,
+                        found,
+                    });
+                }
+            }
+            font_face_type
+        } else {
+            self.font_face_type
+                .expect("`font_face_type` must be specified")
+        };
+
         unsafe {
-            let font_face_type = self
-                .font_face_type
-                .expect("`font_face_type` must be specified");
-            let files = self.files.expect("`files` must be specified");
-            let face_index = self.face_index.expect("`face_index` must be specified");
+            let raw_files: Vec<*mut IDWriteFontFile> = files.iter().map(|f| f.get_raw()).collect();
 
             let mut ptr: *mut IDWriteFontFace = ptr::null_mut();
-            let result = self.factory.CreateFontFace(
+            let result = self.factory.raw_f().CreateFontFace(
                 font_face_type.to_u32(),
-                files.len() as u32,
-                // FontFile is a repr(C) wrapper of a single *mut IDWriteFontFile,
-                // so a *const [FontFace] is safely castable to pointer to an array
-                // of fontfile pointers.
-                files.as_ptr() as *const *mut IDWriteFontFile,
+                raw_files.len() as u32,
+                raw_files.as_ptr(),
                 face_index,
                 self.font_face_simulation_flags.0,
                 &mut ptr,
@@ -59,7 +227,7 @@ impl<'a, 'b> FontFaceBuilder<'a, 'b> {
                 let ptr = ComPtr::from_raw(ptr);
                 Ok(FontFace { ptr: ptr })
             } else {
-                Err(From::from(result))
+                Err(FontFaceBuildError::Failed(result.into()))
             }
         }
     }
@@ -71,7 +239,7 @@ impl<'a, 'b> FontFaceBuilder<'a, 'b> {
     }
 
     /// Specify the list of font files to be loaded
-    pub fn with_files(mut self, files: &'b [FontFile]) -> Self {
+    pub fn with_files(mut self, files: &'a [FontFile]) -> Self {
         self.files = Some(files);
         self
     }
@@ -90,4 +258,29 @@ impl<'a, 'b> FontFaceBuilder<'a, 'b> {
         self.font_face_simulation_flags = font_face_simulation_flags;
         self
     }
+
+    /// Specify whether `build` should analyze the files first and check that the face type and
+    /// index agree with them, instead of leaving DirectWrite to reject the combination with a
+    /// bare `E_INVALIDARG`. Use `build_detailed` to find out what didn't match.
+    pub fn with_validation(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Take the face type from analyzing the files, replacing any passed to
+    /// `with_font_face_type`.
+    pub fn infer_face_type(mut self) -> Self {
+        self.infer_face_type = true;
+        self
+    }
 }
+
+const FACE_TYPES: &[FontFaceType] = &[
+    FontFaceType::CFF,
+    FontFaceType::TrueType,
+    FontFaceType::OpenTypeCollection,
+    FontFaceType::Type1,
+    FontFaceType::Vector,
+    FontFaceType::Bitmap,
+    FontFaceType::RawCFF,
+];
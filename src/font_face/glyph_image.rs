@@ -0,0 +1,61 @@
+use math2d::{Point2i, Sizeu};
+use winapi::ctypes::c_void;
+use winapi::um::dwrite_3::IDWriteFontFace4;
+use wio::com::ComPtr;
+
+/// The image of a color or bitmap glyph, such as the PNG of an emoji, as stored in the font.
+/// Like [`FontTable`](struct.FontTable.html), it borrows the font's data and releases it when
+/// dropped.
+pub struct GlyphImageData<'a> {
+    pub(super) face: ComPtr<IDWriteFontFace4>,
+    pub(super) context: *mut c_void,
+    pub(super) data: &'a [u8],
+
+    /// An identifier which is the same for every glyph using the same image data, so images
+    /// can be cached by it.
+    pub unique_data_id: u32,
+
+    /// The size of the em square the image was designed for, which may differ from the size
+    /// that was asked for if the font doesn't have an image of that size.
+    pub pixels_per_em: u32,
+
+    /// The size of the image in pixels. 0 by 0 for SVG images.
+    pub pixel_size: Sizeu,
+
+    /// The left origin of the glyph for horizontal text, in image pixels.
+    pub horizontal_left_origin: Point2i,
+
+    /// The right origin of the glyph for horizontal text, in image pixels.
+    pub horizontal_right_origin: Point2i,
+
+    /// The top origin of the glyph for vertical text, in image pixels.
+    pub vertical_top_origin: Point2i,
+
+    /// The bottom origin of the glyph for vertical text, in image pixels.
+    pub vertical_bottom_origin: Point2i,
+}
+
+impl<'a> GlyphImageData<'a> {
+    #[inline]
+    /// Gets the encoded image, e.g. a PNG file or an SVG document.
+    pub fn data(&self) -> &[u8] {
+        self.data
+    }
+}
+
+impl<'a> AsRef<[u8]> for GlyphImageData<'a> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.data
+    }
+}
+
+impl<'a> Drop for GlyphImageData<'a> {
+    fn drop(&mut self) {
+        if !self.context.is_null() {
+            unsafe {
+                self.face.ReleaseGlyphImageData(self.context);
+            }
+        }
+    }
+}
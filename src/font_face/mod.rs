@@ -2,31 +2,47 @@
 
 use crate::descriptions::GlyphOffset;
 use crate::enums::font_feature_tag::FontFeatureTag;
-use crate::enums::{FontFaceType, FontSimulations, MeasuringMode, RenderingMode};
+use crate::enums::{
+    FontFaceType, FontSimulations, GlyphImageFormats, GridFitMode, MeasuringMode, RenderingMode,
+    RenderingMode1,
+};
 use crate::factory::IFactory;
 use crate::font_file::FontFile;
 use crate::geometry_sink::{self, GeometrySink};
+use crate::helpers::query_interface;
 use crate::metrics::{FontMetrics, GlyphMetrics};
 use crate::rendering_params::IRenderingParams;
 
-use std::{mem, ptr, u32};
+use std::{mem, ptr};
 
 use checked_enum::UncheckedEnum;
 use com_wrapper::ComWrapper;
 use dcommon::Error;
-use math2d::Matrix3x2f;
-use winapi::shared::winerror::SUCCEEDED;
-use winapi::um::dwrite::{IDWriteFontFace, IDWriteFontFile, DWRITE_GLYPH_METRICS};
+use math2d::{Matrix3x2f, Point2i, Sizeu};
+use winapi::shared::winerror::{E_INVALIDARG, SUCCEEDED};
+use winapi::um::dcommon::{
+    D2D1_POINT_2L, DWRITE_GLYPH_IMAGE_DATA, DWRITE_GLYPH_IMAGE_FORMATS_JPEG,
+    DWRITE_GLYPH_IMAGE_FORMATS_PNG, DWRITE_GLYPH_IMAGE_FORMATS_PREMULTIPLIED_B8G8R8A8,
+    DWRITE_GLYPH_IMAGE_FORMATS_TIFF,
+};
+use winapi::um::dwrite::{IDWriteFontFace, DWRITE_GLYPH_METRICS};
+use winapi::um::dwrite_1::{DWRITE_OUTLINE_THRESHOLD_ALIASED, DWRITE_OUTLINE_THRESHOLD_ANTIALIASED};
+use winapi::um::dwrite_3::{IDWriteFontFace3, IDWriteFontFace4};
 use wio::com::ComPtr;
 
 #[doc(inline)]
-pub use self::builder::FontFaceBuilder;
+pub use self::builder::{FontFaceBuildError, FontFaceBuilder};
+#[doc(inline)]
+pub use self::glyph_image::GlyphImageData;
 #[doc(inline)]
 pub use self::table::FontTable;
 
 #[doc(hidden)]
 pub mod builder;
 #[doc(hidden)]
+pub mod glyph_image;
+mod stylistic_sets;
+#[doc(hidden)]
 pub mod table;
 
 #[repr(transparent)]
@@ -39,9 +55,11 @@ pub struct FontFace {
     ptr: ComPtr<IDWriteFontFace>,
 }
 
+raw_interop!(FontFace, IDWriteFontFace);
+
 impl FontFace {
     /// Initializes a builder for creating a FontFace
-    pub fn create<'a, 'b>(factory: &'a dyn IFactory) -> FontFaceBuilder<'a, 'b> {
+    pub fn create<'a>(factory: &dyn IFactory) -> FontFaceBuilder<'a> {
         unsafe { FontFaceBuilder::new(factory.raw_f()) }
     }
 }
@@ -92,12 +110,10 @@ pub unsafe trait IFontFace {
                 return Err(hr.into());
             }
 
-            assert_eq!(
-                mem::size_of::<*mut IDWriteFontFile>(),
-                mem::size_of::<FontFile>()
-            );
-
-            Ok(mem::transmute::<Vec<*mut _>, Vec<FontFile>>(native_files))
+            Ok(native_files
+                .into_iter()
+                .map(|ptr| FontFile::from_raw(ptr))
+                .collect())
         }
     }
 
@@ -117,6 +133,7 @@ pub unsafe trait IFontFace {
                 indices.as_mut_ptr(),
             );
             if SUCCEEDED(hr) {
+                indices.set_len(code_points.len());
                 Ok(indices)
             } else {
                 Err(hr.into())
@@ -184,6 +201,51 @@ pub unsafe trait IFontFace {
         }
     }
 
+    /// Measures a run of glyphs laid out with `glyph_advances` (in DIPs) at `em_size`, from
+    /// the glyphs' design metrics. Right-to-left runs are placed starting from their right
+    /// edge, as DirectWrite draws them. Fails with `E_INVALIDARG` if there isn't exactly one
+    /// advance per glyph.
+    fn run_extents(
+        &self,
+        em_size: f32,
+        glyph_indices: &[u16],
+        glyph_advances: &[f32],
+        is_rtl: bool,
+    ) -> Result<RunExtents, Error> {
+        if glyph_indices.len() != glyph_advances.len() {
+            return Err(E_INVALIDARG.into());
+        }
+        let advance: f32 = glyph_advances.iter().sum();
+        if glyph_indices.is_empty() {
+            return Ok(RunExtents::default());
+        }
+
+        let scale = em_size / self.metrics().design_units_per_em as f32;
+        let glyphs = self.design_glyph_metrics(glyph_indices, false)?;
+
+        let mut pen = if is_rtl { advance } else { 0.0 };
+        let (mut ink_left, mut ink_right) = (std::f32::INFINITY, std::f32::NEG_INFINITY);
+        for (glyph, &glyph_advance) in glyphs.iter().zip(glyph_advances) {
+            if is_rtl {
+                pen -= glyph_advance;
+            }
+            let width = glyph.advance_width as f32;
+            let left = pen + glyph.left_side_bearing as f32 * scale;
+            let right = pen + (width - glyph.right_side_bearing as f32) * scale;
+            ink_left = ink_left.min(left);
+            ink_right = ink_right.max(right);
+            if !is_rtl {
+                pen += glyph_advance;
+            }
+        }
+
+        Ok(RunExtents {
+            advance,
+            left_overhang: -ink_left,
+            right_overhang: ink_right - advance,
+        })
+    }
+
     /// Attempt to determine the recommended rendering mode for this font face
     /// with the given parameters.
     fn recommended_rendering_mode(
@@ -211,6 +273,30 @@ pub unsafe trait IFontFace {
         }
     }
 
+    /// Like `recommended_rendering_mode`, but able to recommend the rendering modes only
+    /// `RenderingMode1` can express, with the outline threshold for aliased text when `params`
+    /// ask for `Aliased`. Faces which predate Windows 10 give the same recommendation as
+    /// `recommended_rendering_mode`.
+    fn recommended_rendering_mode1(
+        &self,
+        em_size: f32,
+        pixels_per_dip: f32,
+        measuring_mode: MeasuringMode,
+        params: &dyn IRenderingParams,
+    ) -> Result<UncheckedEnum<RenderingMode1>, Error> {
+        let recommended = unsafe {
+            recommended_rendering(
+                self.raw_fontface(),
+                em_size,
+                pixels_per_dip,
+                false,
+                measuring_mode as u32,
+                params,
+            )?
+        };
+        Ok(recommended.0)
+    }
+
     /// Obtains the file format type of a font face.
     fn font_type(&self) -> UncheckedEnum<FontFaceType> {
         unsafe { self.raw_fontface().GetType().into() }
@@ -221,6 +307,132 @@ pub unsafe trait IFontFace {
         unsafe { FontSimulations(self.raw_fontface().GetSimulations()) }
     }
 
+    /// Determines whether the face has embedded bitmaps which are meant to be used instead of
+    /// the glyph outlines when drawing at `em_size` DIPs on a display with `pixels_per_dip`.
+    /// Color bitmaps (`sbix`, `CBDT`) are scaled to any size, while monochrome and grayscale
+    /// bitmaps (`EBDT`, common in CJK fonts) are only used at the exact pixel sizes they were
+    /// drawn for.
+    ///
+    /// When this returns true, a software renderer should rasterize the glyphs through a
+    /// [`GlyphRunAnalysis`](../glyph_run_analysis/struct.GlyphRunAnalysis.html) created at this
+    /// em size with a rendering mode other than `Outline` and no scaling or rotating transform,
+    /// which lets DirectWrite substitute the bitmaps. Going through `glyph_run_outline` always
+    /// draws the outlines.
+    fn has_embedded_bitmap(&self, em_size: f32, pixels_per_dip: f32) -> bool {
+        unsafe {
+            if let Ok(face) = query_interface::<IDWriteFontFace4>(self.raw_fontface()) {
+                if face.GetGlyphImageFormats_1() & BITMAP_IMAGE_FORMATS != 0 {
+                    return true;
+                }
+            }
+        }
+
+        // Older versions of Windows don't report image formats, so the strike tables are
+        // checked directly. CBLC shares the layout of EBLC.
+        let ppem = (em_size * pixels_per_dip).round() as u32;
+        let tables = [
+            FontFeatureTag(u32::from_le_bytes(*b"EBLC")),
+            FontFeatureTag(u32::from_le_bytes(*b"CBLC")),
+        ];
+        tables
+            .iter()
+            .filter_map(|&tag| self.font_table(tag))
+            .any(|table| has_bitmap_strike(table.data(), ppem))
+    }
+
+    /// Gets every format the font stores glyph images in.
+    ///
+    /// Requires Windows 10 Anniversary Update; on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn glyph_image_formats(&self) -> Result<GlyphImageFormats, Error> {
+        unsafe {
+            let face = query_interface::<IDWriteFontFace4>(self.raw_fontface())?;
+            Ok(GlyphImageFormats(face.GetGlyphImageFormats_1()))
+        }
+    }
+
+    /// Gets the formats a single glyph has images in, for any size from `ppem_first` to
+    /// `ppem_last` pixels per em.
+    ///
+    /// Requires Windows 10 Anniversary Update; on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn glyph_formats(
+        &self,
+        glyph: u16,
+        ppem_first: u32,
+        ppem_last: u32,
+    ) -> Result<GlyphImageFormats, Error> {
+        unsafe {
+            let face = query_interface::<IDWriteFontFace4>(self.raw_fontface())?;
+            let mut formats = 0;
+            let hr = face.GetGlyphImageFormats_2(glyph, ppem_first, ppem_last, &mut formats);
+            if SUCCEEDED(hr) {
+                Ok(GlyphImageFormats(formats))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Gets the image of a glyph in one of the formats `SVG`, `PNG`, `JPEG`, `TIFF` or
+    /// `PREMULTIPLIED_B8G8R8A8`, at the size closest to `ppem` pixels per em that the font has.
+    /// Fails with `E_INVALIDARG` if `format` isn't exactly one of these, or the glyph has no
+    /// image in that format at any size; outline and `COLR` glyphs have no image data.
+    ///
+    /// Requires Windows 10 Anniversary Update; on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn glyph_image_data<'a>(
+        &'a self,
+        glyph: u16,
+        ppem: u32,
+        format: GlyphImageFormats,
+    ) -> Result<GlyphImageData<'a>, Error> {
+        let images = GlyphImageFormats::SVG.0
+            | GlyphImageFormats::PNG.0
+            | GlyphImageFormats::JPEG.0
+            | GlyphImageFormats::TIFF.0
+            | GlyphImageFormats::PREMULTIPLIED_B8G8R8A8.0;
+        if format.0 & images != format.0 || !format.0.is_power_of_two() {
+            return Err(E_INVALIDARG.into());
+        }
+        // What DirectWrite returns for a missing format isn't documented
+        if self.glyph_formats(glyph, 1, std::u32::MAX)?.0 & format.0 == 0 {
+            return Err(E_INVALIDARG.into());
+        }
+
+        unsafe {
+            let face = query_interface::<IDWriteFontFace4>(self.raw_fontface())?;
+            let mut data: DWRITE_GLYPH_IMAGE_DATA = mem::zeroed();
+            let mut context = ptr::null_mut();
+            let hr = face.GetGlyphImageData(glyph, ppem, format.0, &mut data, &mut context);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+
+            let bytes = if data.imageData.is_null() {
+                &[][..]
+            } else {
+                std::slice::from_raw_parts(data.imageData as *const u8, data.imageDataSize as usize)
+            };
+            let point = |p: D2D1_POINT_2L| Point2i { x: p.x, y: p.y };
+            Ok(GlyphImageData {
+                face,
+                context,
+                data: bytes,
+                unique_data_id: data.uniqueDataId,
+                pixels_per_em: data.pixelsPerEm,
+                pixel_size: Sizeu {
+                    width: data.pixelSize.width,
+                    height: data.pixelSize.height,
+                },
+                horizontal_left_origin: point(data.horizontalLeftOrigin),
+                horizontal_right_origin: point(data.horizontalRightOrigin),
+                vertical_top_origin: point(data.verticalTopOrigin),
+                vertical_bottom_origin: point(data.verticalBottomOrigin),
+            })
+        }
+    }
+
     /// Determines whether the font is a symbol font.
     fn is_symbol_font(&self) -> bool {
         unsafe { self.raw_fontface().IsSymbolFont() > 0 }
@@ -326,6 +538,29 @@ pub unsafe trait IFontFace {
         }
     }
 
+    /// Lists the stylistic sets (`ss01` through `ss20`) the font supports, together with the
+    /// names the font gives them for display in a UI, like "Single-story a". Sets the font
+    /// doesn't name have `None`. Fonts without a GSUB table have no stylistic sets.
+    fn stylistic_set_names(&self) -> Result<Vec<(FontFeatureTag, Option<String>)>, Error> {
+        let gsub = match self.font_table(FontFeatureTag::from_array(*b"GSUB")) {
+            Some(gsub) => gsub,
+            None => return Ok(Vec::new()),
+        };
+        let sets = stylistic_sets::stylistic_sets(gsub.data())?;
+
+        let name = self.font_table(FontFeatureTag::from_array(*b"name"));
+        Ok(sets
+            .into_iter()
+            .map(|(tag, name_id)| {
+                let text = match (&name, name_id) {
+                    (Some(name), Some(id)) => stylistic_sets::name_string(name.data(), id),
+                    _ => None,
+                };
+                (tag, text)
+            })
+            .collect())
+    }
+
     fn as_font_face(&self) -> FontFace {
         unsafe {
             let ptr = self.raw_fontface();
@@ -342,3 +577,86 @@ unsafe impl IFontFace for FontFace {
         &self.ptr
     }
 }
+
+/// A recommended rendering mode, with the grid fitting mode if the face could report one.
+pub(crate) type RecommendedRendering = (
+    UncheckedEnum<RenderingMode1>,
+    Option<UncheckedEnum<GridFitMode>>,
+);
+
+/// Asks DirectWrite which rendering mode to draw glyphs of `face` with, and which grid fitting
+/// mode when the face supports `IDWriteFontFace3`. Sizes past the outline threshold for the
+/// antialiasing `params` ask for are recommended `Outline`; older faces use DirectWrite's own
+/// threshold.
+pub(crate) unsafe fn recommended_rendering(
+    face: &IDWriteFontFace,
+    em_size: f32,
+    pixels_per_dip: f32,
+    is_sideways: bool,
+    measuring_mode: u32,
+    params: &dyn IRenderingParams,
+) -> Result<RecommendedRendering, Error> {
+    let raw_params = params.raw_rp() as *const _ as *mut _;
+    let face3 = match query_interface::<IDWriteFontFace3>(face) {
+        Ok(face3) => face3,
+        Err(_) => {
+            let mut mode = 0;
+            let hr = face.GetRecommendedRenderingMode(
+                em_size,
+                pixels_per_dip,
+                measuring_mode,
+                raw_params,
+                &mut mode,
+            );
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            return Ok((mode.into(), None));
+        }
+    };
+
+    let threshold = if params.rendering_mode1().value == RenderingMode1::Aliased as u32 {
+        DWRITE_OUTLINE_THRESHOLD_ALIASED
+    } else {
+        DWRITE_OUTLINE_THRESHOLD_ANTIALIASED
+    };
+    let dpi = 96.0 * pixels_per_dip;
+    let (mut mode, mut grid_fit) = (0, 0);
+    let hr = face3.GetRecommendedRenderingMode(
+        em_size,
+        dpi,
+        dpi,
+        ptr::null(),
+        is_sideways as i32,
+        threshold,
+        measuring_mode,
+        raw_params,
+        &mut mode,
+        &mut grid_fit,
+    );
+    if SUCCEEDED(hr) {
+        Ok((mode.into(), Some(grid_fit.into())))
+    } else {
+        Err(hr.into())
+    }
+}
+
+const BITMAP_IMAGE_FORMATS: u32 = DWRITE_GLYPH_IMAGE_FORMATS_PNG
+    | DWRITE_GLYPH_IMAGE_FORMATS_JPEG
+    | DWRITE_GLYPH_IMAGE_FORMATS_TIFF
+    | DWRITE_GLYPH_IMAGE_FORMATS_PREMULTIPLIED_B8G8R8A8;
+
+/// Checks whether an EBLC/CBLC table has a strike of `ppem` pixels per em.
+fn has_bitmap_strike(table: &[u8], ppem: u32) -> bool {
+    // The header is a 4 byte version and a 4 byte count, followed by 48 byte BitmapSize
+    // records in which ppemY is at byte 45.
+    const RECORD_SIZE: usize = 48;
+    if table.len() < 8 {
+        return false;
+    }
+    let num_sizes = u32::from_be_bytes([table[4], table[5], table[6], table[7]]) as usize;
+    table[8..]
+        .chunks_exact(RECORD_SIZE)
+        .take(num_sizes)
+        .any(|record| record[45] as u32 == ppem)
+}
@@ -0,0 +1,132 @@
+//! Reads the UI names of stylistic sets out of the GSUB and name tables.
+
+use crate::enums::FontFeatureTag;
+
+use dcommon::Error;
+use winapi::shared::winerror::DWRITE_E_FILEFORMAT;
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Lists the `ss01`-`ss20` features in a GSUB table once each, in the order they first appear,
+/// along with the name ID their FeatureParams point at, if any.
+pub(crate) fn stylistic_sets(gsub: &[u8]) -> Result<Vec<(FontFeatureTag, Option<u16>)>, Error> {
+    let bad_table = || Error::from(DWRITE_E_FILEFORMAT);
+
+    let feature_list = u16_at(gsub, 6).ok_or_else(bad_table)? as usize;
+    let count = u16_at(gsub, feature_list).ok_or_else(bad_table)? as usize;
+
+    let mut sets: Vec<(FontFeatureTag, Option<u16>)> = Vec::new();
+    for i in 0..count {
+        // FeatureRecords are a 4 byte tag followed by a 2 byte offset to the Feature table
+        let record = feature_list + 2 + i * 6;
+        let tag = gsub.get(record..record + 4).ok_or_else(bad_table)?;
+        if !is_stylistic_set(tag) {
+            continue;
+        }
+        let tag = FontFeatureTag::from_slice(tag);
+
+        let feature = feature_list + u16_at(gsub, record + 4).ok_or_else(bad_table)? as usize;
+        let params = u16_at(gsub, feature).ok_or_else(bad_table)? as usize;
+        // The FeatureParams for stylistic sets are a version followed by the UI name ID
+        let name_id = match params {
+            0 => None,
+            params => u16_at(gsub, feature + params + 2),
+        };
+
+        match sets.iter_mut().find(|set| set.0 == tag) {
+            Some(set) => set.1 = set.1.or(name_id),
+            None => sets.push((tag, name_id)),
+        }
+    }
+    Ok(sets)
+}
+
+/// Whether `tag` is one of `ss01` to `ss20`, the only stylistic sets OpenType registers.
+fn is_stylistic_set(tag: &[u8]) -> bool {
+    match tag {
+        [b's', b's', tens @ b'0'..=b'2', ones @ b'0'..=b'9'] => {
+            let number = (tens - b'0') * 10 + (ones - b'0');
+            (1..=20).contains(&number)
+        }
+        _ => false,
+    }
+}
+
+/// Finds the string with the given ID in a name table, preferring US English Windows names.
+pub(crate) fn name_string(name: &[u8], name_id: u16) -> Option<String> {
+    let count = u16_at(name, 2)? as usize;
+    let storage = u16_at(name, 4)? as usize;
+
+    let mut best: Option<(u32, &[u8])> = None;
+    for i in 0..count {
+        let record = 6 + i * 12;
+        if u16_at(name, record + 6)? != name_id {
+            continue;
+        }
+        let platform = u16_at(name, record)?;
+        let language = u16_at(name, record + 4)?;
+        // Only the Unicode and Windows platforms store UTF-16
+        let rank = match (platform, language) {
+            (3, 0x0409) => 0,
+            (3, _) => 1,
+            (0, _) => 2,
+            _ => continue,
+        };
+        let length = u16_at(name, record + 8)? as usize;
+        let offset = storage + u16_at(name, record + 10)? as usize;
+        let data = match name.get(offset..offset + length) {
+            Some(data) => data,
+            None => continue,
+        };
+        if best.map(|(best, _)| rank < best).unwrap_or(true) {
+            best = Some((rank, data));
+        }
+    }
+
+    let utf16: Vec<u16> = best?
+        .1
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect();
+    Some(String::from_utf16_lossy(&utf16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stylistic_sets;
+    use crate::enums::FontFeatureTag;
+
+    /// Builds a GSUB table whose feature list holds `tags`, none of them with FeatureParams.
+    fn gsub(tags: &[&[u8; 4]]) -> Vec<u8> {
+        // Version 1.0, with the feature list right after the header
+        let mut data = vec![0, 1, 0, 0, 0, 0, 0, 10, 0, 0];
+        data.extend_from_slice(&(tags.len() as u16).to_be_bytes());
+        let features = 2 + 6 * tags.len();
+        for (i, tag) in tags.iter().enumerate() {
+            data.extend_from_slice(&tag[..]);
+            data.extend_from_slice(&((features + 4 * i) as u16).to_be_bytes());
+        }
+        for _ in tags {
+            data.extend_from_slice(&[0, 0, 0, 0]);
+        }
+        data
+    }
+
+    #[test]
+    fn only_registered_stylistic_sets() {
+        let table = gsub(&[
+            b"ss00", b"ss01", b"liga", b"ss21", b"ss99", b"ss20", b"ss01",
+        ]);
+        let sets = stylistic_sets(&table).unwrap();
+        assert_eq!(
+            sets,
+            vec![
+                (FontFeatureTag::STYLISTIC_SET_1, None),
+                (FontFeatureTag::STYLISTIC_SET_20, None),
+            ]
+        );
+    }
+}
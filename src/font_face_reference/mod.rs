@@ -0,0 +1,69 @@
+//! FontFaceReference, a lightweight reference to a font face which can be resolved on demand.
+
+use crate::enums::FontSimulations;
+use crate::font_face::FontFace;
+use crate::font_file::FontFile;
+
+use std::ptr;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::dwrite::IDWriteFontFace;
+use winapi::um::dwrite_3::IDWriteFontFaceReference;
+use wio::com::ComPtr;
+
+#[repr(transparent)]
+#[derive(Clone, ComWrapper)]
+#[com(send, sync, debug)]
+/// A reference to a font face, made up of a font file, a face index and simulations. Unlike a
+/// FontFace, no font data needs to be loaded until the face is actually created.
+pub struct FontFaceReference {
+    ptr: ComPtr<IDWriteFontFaceReference>,
+}
+
+pub unsafe trait IFontFaceReference {
+    /// Creates a font face from the reference for use with layout, shaping, or rendering.
+    fn create_font_face(&self) -> Result<FontFace, Error> {
+        unsafe {
+            let mut ptr = ptr::null_mut();
+            let hr = self.raw_ffref().CreateFontFace(&mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(FontFace::from_raw(ptr as *mut IDWriteFontFace))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Obtains the zero-based index of the font face in its font file or files.
+    fn face_index(&self) -> u32 {
+        unsafe { self.raw_ffref().GetFontFaceIndex() }
+    }
+
+    /// Obtains the algorithmic style simulation flags of the font face.
+    fn simulations(&self) -> FontSimulations {
+        unsafe { FontSimulations(self.raw_ffref().GetSimulations()) }
+    }
+
+    /// Obtains the font file representing the font face.
+    fn font_file(&self) -> Result<FontFile, Error> {
+        unsafe {
+            let mut ptr = ptr::null_mut();
+            let hr = self.raw_ffref().GetFontFile(&mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(FontFile::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    unsafe fn raw_ffref(&self) -> &IDWriteFontFaceReference;
+}
+
+unsafe impl IFontFaceReference for FontFaceReference {
+    unsafe fn raw_ffref(&self) -> &IDWriteFontFaceReference {
+        &self.ptr
+    }
+}
@@ -0,0 +1,131 @@
+use crate::factory::{Factory, IFactory};
+use crate::font_collection::FontCollection;
+use crate::font_fallback::FontFallback;
+use crate::helpers::query_interface;
+
+use std::ops::RangeInclusive;
+use std::ptr;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::dwrite::IDWriteFactory;
+use winapi::um::dwrite_1::DWRITE_UNICODE_RANGE;
+use winapi::um::dwrite_2::IDWriteFactory2;
+use wio::com::ComPtr;
+use wio::wide::ToWide;
+
+#[must_use]
+/// Builder for a FontFallback.
+///
+/// Mappings are consulted in the order they were added, and the first one covering a
+/// character decides its font. `system_fallback` defaults to false, so characters that no
+/// mapping covers are drawn with the missing glyph unless it is enabled.
+pub struct FontFallbackBuilder<'a> {
+    factory: Factory,
+    mappings: Vec<Mapping<'a>>,
+    system_fallback: bool,
+}
+
+struct Mapping<'a> {
+    ranges: Vec<DWRITE_UNICODE_RANGE>,
+    families: Vec<Vec<u16>>,
+    collection: Option<&'a FontCollection>,
+}
+
+impl<'a> FontFallbackBuilder<'a> {
+    /// Initialize a new builder.
+    pub fn new(factory: &IDWriteFactory) -> FontFallbackBuilder<'a> {
+        FontFallbackBuilder {
+            factory: unsafe { Factory::from_borrowed(factory) },
+            mappings: Vec::new(),
+            system_fallback: false,
+        }
+    }
+
+    /// Build the FontFallback. Requires Windows 8.1; on older versions this fails with
+    /// `E_NOINTERFACE`.
+    pub fn build(self) -> Result<FontFallback, Error> {
+        unsafe {
+            let factory = query_interface::<IDWriteFactory2>(self.factory.raw_f())?;
+
+            let mut ptr = ptr::null_mut();
+            let hr = factory.CreateFontFallbackBuilder(&mut ptr);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            let builder = ComPtr::from_raw(ptr);
+
+            for mapping in &self.mappings {
+                let mut families: Vec<*const u16> =
+                    mapping.families.iter().map(|f| f.as_ptr()).collect();
+                let hr = builder.AddMapping(
+                    mapping.ranges.as_ptr(),
+                    mapping.ranges.len() as u32,
+                    families.as_mut_ptr(),
+                    families.len() as u32,
+                    mapping
+                        .collection
+                        .map(|c| c.get_raw())
+                        .unwrap_or(ptr::null_mut()),
+                    ptr::null(),
+                    ptr::null(),
+                    1.0,
+                );
+                if !SUCCEEDED(hr) {
+                    return Err(hr.into());
+                }
+            }
+
+            if self.system_fallback {
+                let mut system = ptr::null_mut();
+                let hr = factory.GetSystemFontFallback(&mut system);
+                if !SUCCEEDED(hr) {
+                    return Err(hr.into());
+                }
+                let system = ComPtr::from_raw(system);
+                let hr = builder.AddMappings(system.as_raw());
+                if !SUCCEEDED(hr) {
+                    return Err(hr.into());
+                }
+            }
+
+            let mut ptr = ptr::null_mut();
+            let hr = builder.CreateFontFallback(&mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(FontFallback::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Map the code points in `ranges` to the first of `families` which has the character,
+    /// looking the families up in `collection`, or the system font collection if `None`.
+    pub fn with_mapping(
+        mut self,
+        ranges: &[RangeInclusive<u32>],
+        families: &[&str],
+        collection: Option<&'a FontCollection>,
+    ) -> Self {
+        self.mappings.push(Mapping {
+            ranges: ranges
+                .iter()
+                .map(|r| DWRITE_UNICODE_RANGE {
+                    first: *r.start(),
+                    last: *r.end(),
+                })
+                .collect(),
+            families: families.iter().map(|f| f.to_wide_null()).collect(),
+            collection,
+        });
+        self
+    }
+
+    /// Specify whether the system's own fallback mappings are appended after the ones added
+    /// with `with_mapping`.
+    pub fn with_system_fallback(mut self, enabled: bool) -> Self {
+        self.system_fallback = enabled;
+        self
+    }
+}
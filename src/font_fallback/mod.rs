@@ -0,0 +1,57 @@
+//! FontFallback and types for building new ones.
+
+use crate::factory::IFactory;
+use crate::helpers::query_interface;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::dwrite_2::{IDWriteFactory2, IDWriteFontFallback};
+use wio::com::ComPtr;
+
+#[doc(inline)]
+pub use self::builder::FontFallbackBuilder;
+
+#[doc(hidden)]
+pub mod builder;
+
+#[repr(transparent)]
+#[derive(Clone, ComWrapper)]
+#[com(send, sync, debug)]
+/// Decides which font is used for characters that the font requested by a format or layout
+/// can't display. Requires Windows 8.1.
+pub struct FontFallback {
+    ptr: ComPtr<IDWriteFontFallback>,
+}
+
+impl FontFallback {
+    /// Gets the fallback which DirectWrite uses by default, built from the fonts installed on
+    /// the system. Requires Windows 8.1; on older versions this fails with `E_NOINTERFACE`.
+    pub fn system(factory: &dyn IFactory) -> Result<FontFallback, Error> {
+        unsafe {
+            let factory = query_interface::<IDWriteFactory2>(factory.raw_f())?;
+            let mut ptr = std::ptr::null_mut();
+            let hr = factory.GetSystemFontFallback(&mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(FontFallback::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Get a builder for a fallback with application-defined mappings.
+    pub fn create<'a>(factory: &dyn IFactory) -> FontFallbackBuilder<'a> {
+        unsafe { FontFallbackBuilder::new(factory.raw_f()) }
+    }
+}
+
+pub unsafe trait IFontFallback {
+    unsafe fn raw_fallback(&self) -> &IDWriteFontFallback;
+}
+
+unsafe impl IFontFallback for FontFallback {
+    unsafe fn raw_fallback(&self) -> &IDWriteFontFallback {
+        &self.ptr
+    }
+}
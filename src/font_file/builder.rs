@@ -1,6 +1,8 @@
 use crate::descriptions::{FontKey, KeyPayload};
+use crate::factory::{Factory, IFactory};
 use crate::font_file::loader::handle::FileLoaderHandle;
 use crate::font_file::FontFile;
+use crate::origin;
 
 use std::ptr;
 
@@ -23,7 +25,7 @@ use wio::wide::ToWide;
 ///
 /// `file_path` and `last_write_time` are mutually exclusive with `loader` and `key`.
 pub struct FontFileBuilder<'a, K: FontKey + ?Sized> {
-    factory: &'a IDWriteFactory,
+    factory: Factory,
     source: Source<'a, K>,
 }
 
@@ -41,9 +43,9 @@ enum Source<'a, K: FontKey + ?Sized> {
 
 impl<'a, K: FontKey + ?Sized> FontFileBuilder<'a, K> {
     /// Initializes a builder for a FontFile.
-    pub fn new(factory: &'a IDWriteFactory) -> Self {
+    pub fn new(factory: &IDWriteFactory) -> Self {
         FontFileBuilder {
-            factory,
+            factory: unsafe { Factory::from_borrowed(factory) },
             source: Source::Unspecified,
         }
     }
@@ -64,13 +66,14 @@ impl<'a, K: FontKey + ?Sized> FontFileBuilder<'a, K> {
                     };
 
                     let mut ptr = ptr::null_mut();
-                    let hr = self.factory.CreateFontFileReference(
+                    let hr = self.factory.raw_f().CreateFontFileReference(
                         path.as_ptr(),
                         last_write_time,
                         &mut ptr,
                     );
 
                     if SUCCEEDED(hr) {
+                        origin::record(ptr, self.factory.raw_f());
                         Ok(FontFile::from_raw(ptr))
                     } else {
                         Err(hr.into())
@@ -78,11 +81,12 @@ impl<'a, K: FontKey + ?Sized> FontFileBuilder<'a, K> {
                 }
                 Source::Custom { loader, key } => {
                     let loader = loader.expect("`loader` must be specified");
+                    origin::check("file loader", loader.factory(), self.factory.raw_f())?;
                     let key = KeyPayload::new(key.expect("`key` must be specified"));
                     let key_size = std::mem::size_of_val(&key) as u32;
 
                     let mut ptr = ptr::null_mut();
-                    let hr = self.factory.CreateCustomFontFileReference(
+                    let hr = self.factory.raw_f().CreateCustomFontFileReference(
                         &key as *const _ as *const _,
                         key_size,
                         loader.get_raw(),
@@ -90,7 +94,8 @@ impl<'a, K: FontKey + ?Sized> FontFileBuilder<'a, K> {
                     );
 
                     if SUCCEEDED(hr) {
-                        Ok(FontFile::from_raw(ptr))
+                        origin::record(ptr, self.factory.raw_f());
+                        Ok(FontFile::from_raw(ptr).with_loader(loader.dependency()))
                     } else {
                         Err(hr.into())
                     }
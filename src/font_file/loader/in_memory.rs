@@ -0,0 +1,114 @@
+use crate::factory::Factory;
+use crate::font_file::loader::com_stream::ComFontFileStream;
+use crate::font_file::loader::SharedDataStream;
+use crate::font_file::FontFile;
+use crate::helpers::query_interface;
+use crate::origin;
+use crate::sys::{IDWriteFactory5, IDWriteInMemoryFontFileLoader};
+
+use std::ptr;
+use std::sync::Arc;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::dwrite::IDWriteFactory;
+use wio::com::ComPtr;
+
+#[derive(Clone)]
+/// The system-provided loader for font files whose data is already in memory. This avoids
+/// having to implement a [`FontFileLoader`][1] when all you have is a buffer of font data.
+///
+/// Requires Windows 10 Creators Update. On older systems `register` fails with
+/// `E_NOINTERFACE`, and a custom loader with an [`OwnedDataStream`][2] or
+/// [`SharedDataStream`][3] should be used instead.
+///
+/// [1]: trait.FontFileLoader.html
+/// [2]: struct.OwnedDataStream.html
+/// [3]: struct.SharedDataStream.html
+pub struct InMemoryLoader {
+    ptr: ComPtr<IDWriteInMemoryFontFileLoader>,
+    factory: ComPtr<IDWriteFactory>,
+}
+
+unsafe impl Send for InMemoryLoader {}
+unsafe impl Sync for InMemoryLoader {}
+
+impl InMemoryLoader {
+    /// Creates the in-memory loader and registers it into the factory.
+    pub fn register(factory: &Factory) -> Result<InMemoryLoader, Error> {
+        unsafe {
+            let factory_ptr = factory.clone().into_ptr();
+            let factory5 = query_interface::<IDWriteFactory5>(&factory_ptr)?;
+
+            let mut ptr = ptr::null_mut();
+            let hr = factory5.CreateInMemoryFontFileLoader(&mut ptr);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            let ptr = ComPtr::from_raw(ptr);
+
+            let hr = factory_ptr.RegisterFontFileLoader(ptr.as_raw() as *mut _);
+            if SUCCEEDED(hr) {
+                Ok(InMemoryLoader {
+                    ptr,
+                    factory: factory_ptr,
+                })
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Creates a font file referencing `data`. The data is not copied; it is kept alive for as
+    /// long as DirectWrite holds a reference to the file.
+    pub fn create_file(&self, data: impl Into<Arc<[u8]>>) -> Result<FontFile, Error> {
+        let stream = SharedDataStream::new(data, 0);
+        let (data_ptr, data_len) = (stream.data.as_ptr(), stream.data.len());
+
+        unsafe {
+            // The stream object is only used as an owner of the data which DirectWrite
+            // releases when the file is no longer needed.
+            let owner = ComFontFileStream::new(stream);
+
+            let mut ptr = ptr::null_mut();
+            let hr = self.ptr.CreateInMemoryFontFileReference(
+                self.factory.as_raw(),
+                data_ptr as *const _,
+                data_len as u32,
+                owner.as_raw() as *mut _,
+                &mut ptr,
+            );
+            if SUCCEEDED(hr) {
+                origin::record(ptr, &*self.factory);
+                Ok(FontFile::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Unpacks a WOFF or WOFF2 container with [`Factory::unpack_font_data`][1] and creates a
+    /// font file referencing the unpacked data.
+    ///
+    /// [1]: ../../struct.Factory.html#method.unpack_font_data
+    pub fn create_file_from_packed(&self, data: &[u8]) -> Result<FontFile, Error> {
+        let factory = unsafe { Factory::from_ptr(self.factory.clone()) };
+        let unpacked = factory.unpack_font_data(data)?;
+        self.create_file(unpacked)
+    }
+
+    /// Gets the number of font files which have been created with this loader.
+    pub fn file_count(&self) -> u32 {
+        unsafe { self.ptr.GetFileCount() }
+    }
+
+    /// Unregister the loader. Files which were already created remain valid, but new
+    /// font faces can no longer be created from them.
+    pub fn unregister(self) {
+        unsafe {
+            self.factory
+                .UnregisterFontFileLoader(self.ptr.as_raw() as *mut _);
+        }
+    }
+}
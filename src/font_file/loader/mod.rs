@@ -42,6 +42,24 @@
 //! # let _ = file;
 //! # }
 //! ```
+//!
+//! # Lifetimes
+//!
+//! A [`FileLoaderHandle`][1] keeps the factory it was registered on alive, and every
+//! `FontFile` created from it keeps the registration alive in turn. Collection loaders work the
+//! same way with the `FontCollection`s created from them:
+//!
+//! ```text
+//! FontFile -> FileLoaderHandle -> Factory
+//! FontCollection -> CollectionLoaderHandle -> Factory
+//! ```
+//!
+//! Because of this, the factory and handles can be dropped in any order. Unregistering a loader
+//! is refused with `HRESULT_FROM_WIN32(ERROR_BUSY)` while files or collections created from it
+//! are still alive, so drop those first. Objects DirectWrite hands back on its own, such as the
+//! files returned by `IFontFace::files`, don't count towards this.
+//!
+//! [1]: struct.FileLoaderHandle.html
 
 use crate::descriptions::FontKey;
 use crate::factory::Factory;
@@ -56,6 +74,8 @@ pub use self::file_stream::FileStream;
 #[doc(inline)]
 pub use self::handle::FileLoaderHandle;
 #[doc(inline)]
+pub use self::in_memory::InMemoryLoader;
+#[doc(inline)]
 pub use self::mmap_stream::MmapStream;
 #[doc(inline)]
 pub use self::owned_stream::OwnedDataStream;
@@ -76,6 +96,8 @@ pub mod file_stream;
 #[doc(hidden)]
 pub mod handle;
 #[doc(hidden)]
+pub mod in_memory;
+#[doc(hidden)]
 pub mod mmap_stream;
 #[doc(hidden)]
 pub mod owned_stream;
@@ -183,8 +205,8 @@ where
 
 /// A fragment of memory managed by a `FontFileStream`
 pub struct Fragment {
-    key: usize,
-    data: *const u8,
+    pub(crate) key: usize,
+    pub(crate) data: *const u8,
 }
 
 impl Fragment {
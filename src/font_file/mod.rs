@@ -4,6 +4,9 @@ use crate::descriptions::FontKey;
 use crate::enums::FontFaceType;
 use crate::enums::FontFileType;
 use crate::factory::IFactory;
+use crate::registration::LoaderDependency;
+
+use std::ptr;
 
 use checked_enum::UncheckedEnum;
 use com_wrapper::ComWrapper;
@@ -14,26 +17,106 @@ use wio::com::ComPtr;
 
 #[doc(inline)]
 pub use crate::font_file::builder::FontFileBuilder;
+#[doc(inline)]
+pub use crate::font_file::stream::FontFileStreamReader;
 
 #[doc(hidden)]
 pub mod builder;
 pub mod loader;
+#[doc(hidden)]
+pub mod stream;
 
-#[derive(Clone, ComWrapper, PartialEq)]
-#[com(send, sync, debug)]
-#[repr(transparent)]
+#[derive(Clone)]
 /// Represents a font file. Applications such as font managers or font viewers can call `analyze`
 /// to find out if a particular file is a font file, and whether it is a font type that is
 /// supported by the font system.
 pub struct FontFile {
     ptr: ComPtr<IDWriteFontFile>,
+    // Keeps a custom loader from being unregistered while this object is alive.
+    loader: Option<LoaderDependency>,
+}
+
+impl FontFile {
+    pub(crate) fn with_loader(mut self, loader: Option<LoaderDependency>) -> Self {
+        self.loader = loader;
+        self
+    }
+}
+
+unsafe impl Send for FontFile {}
+unsafe impl Sync for FontFile {}
+
+impl ComWrapper for FontFile {
+    type Interface = IDWriteFontFile;
+
+    unsafe fn get_raw(&self) -> *mut IDWriteFontFile {
+        self.ptr.as_raw()
+    }
+
+    unsafe fn into_raw(self) -> *mut IDWriteFontFile {
+        self.ptr.into_raw()
+    }
+
+    unsafe fn from_raw(raw: *mut IDWriteFontFile) -> Self {
+        Self::from_ptr(ComPtr::from_raw(raw))
+    }
+
+    unsafe fn from_ptr(ptr: ComPtr<IDWriteFontFile>) -> Self {
+        FontFile { ptr, loader: None }
+    }
+
+    unsafe fn into_ptr(self) -> ComPtr<IDWriteFontFile> {
+        self.ptr
+    }
+}
+
+impl PartialEq for FontFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr.as_raw() == other.ptr.as_raw()
+    }
+}
+
+impl std::fmt::Debug for FontFile {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("FontFile")
+            .field("ptr", &self.ptr.as_raw())
+            .finish()
+    }
 }
 
 impl FontFile {
     /// Initializes a builder for creating a FontFile from either custom loaders or a file path.
-    pub fn create<K: FontKey + ?Sized>(factory: &dyn IFactory) -> FontFileBuilder<K> {
+    pub fn create<'a, K: FontKey + ?Sized>(factory: &dyn IFactory) -> FontFileBuilder<'a, K> {
         unsafe { FontFileBuilder::new(factory.raw_f()) }
     }
+
+    /// Opens the stream this file's loader provides for it, giving access to the raw bytes
+    /// of the file no matter which loader it was created with.
+    pub fn open_stream(&self) -> Result<FontFileStreamReader, Error> {
+        unsafe {
+            let mut key = ptr::null();
+            let mut key_size = 0;
+            let hr = self.ptr.GetReferenceKey(&mut key, &mut key_size);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+
+            let mut loader = ptr::null_mut();
+            let hr = self.ptr.GetLoader(&mut loader);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            let loader = ComPtr::from_raw(loader);
+
+            let mut stream = ptr::null_mut();
+            let hr = loader.CreateStreamFromKey(key, key_size, &mut stream);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+
+            FontFileStreamReader::new(ComPtr::from_raw(stream), self.loader.clone())
+        }
+    }
 }
 
 pub unsafe trait IFontFile {
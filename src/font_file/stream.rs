@@ -0,0 +1,135 @@
+use crate::registration::LoaderDependency;
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use dcommon::Error;
+use winapi::shared::winerror::{E_INVALIDARG, SUCCEEDED};
+use winapi::um::dwrite::IDWriteFontFileStream;
+use wio::com::ComPtr;
+
+/// Reads the raw bytes of a [`FontFile`](struct.FontFile.html) through the stream its loader
+/// provides, regardless of where the file came from. Created by
+/// [`FontFile::open_stream`](struct.FontFile.html#method.open_stream).
+///
+/// Every read acquires a fragment of the stream, copies out of it, and releases it again
+/// before returning, so no borrowed font data outlives a call.
+pub struct FontFileStreamReader {
+    stream: ComPtr<IDWriteFontFileStream>,
+    len: u64,
+    pos: u64,
+    // Keeps a custom loader registered while its stream is being read.
+    _loader: Option<LoaderDependency>,
+}
+
+impl FontFileStreamReader {
+    pub(crate) unsafe fn new(
+        stream: ComPtr<IDWriteFontFileStream>,
+        loader: Option<LoaderDependency>,
+    ) -> Result<FontFileStreamReader, Error> {
+        let mut len = 0;
+        let hr = stream.GetFileSize(&mut len);
+        if !SUCCEEDED(hr) {
+            return Err(hr.into());
+        }
+
+        Ok(FontFileStreamReader {
+            stream,
+            len,
+            pos: 0,
+            _loader: loader,
+        })
+    }
+
+    /// The size of the file in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Copies bytes starting at `offset` into `buf`, returning how many were copied. This is
+    /// less than `buf.len()` only when the end of the file is reached. Does not move the
+    /// position used by `Read` and `Seek`.
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, Error> {
+        if offset >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let count = std::cmp::min(buf.len() as u64, self.len - offset) as usize;
+        unsafe {
+            let mut start = std::ptr::null();
+            let mut context = std::ptr::null_mut();
+            let hr = self
+                .stream
+                .ReadFileFragment(&mut start, offset, count as u64, &mut context);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            let data = std::slice::from_raw_parts(start as *const u8, count);
+            buf[..count].copy_from_slice(data);
+            self.stream.ReleaseFileFragment(context);
+        }
+        Ok(count)
+    }
+
+    /// Copies the whole file into a new buffer.
+    pub fn read_all(&self) -> Result<Vec<u8>, Error> {
+        if self.len > usize::max_value() as u64 {
+            return Err(E_INVALIDARG.into());
+        }
+        let mut data = vec![0; self.len as usize];
+        let count = self.read_at(0, &mut data)?;
+        data.truncate(count);
+        Ok(data)
+    }
+}
+
+impl Read for FontFileStreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self
+            .read_at(self.pos, buf)
+            .map_err(|e| io::Error::from_raw_os_error(e.0))?;
+        self.pos += count as u64;
+        Ok(count)
+    }
+}
+
+impl Seek for FontFileStreamReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => offset_by(self.len, offset),
+            SeekFrom::Current(offset) => offset_by(self.pos, offset),
+        };
+        match pos {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek to a negative position",
+            )),
+        }
+    }
+}
+
+fn offset_by(base: u64, offset: i64) -> Option<u64> {
+    if offset < 0 {
+        base.checked_sub(offset.wrapping_neg() as u64)
+    } else {
+        base.checked_add(offset as u64)
+    }
+}
+
+impl std::fmt::Debug for FontFileStreamReader {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("FontFileStreamReader")
+            .field("stream", &self.stream.as_raw())
+            .field("len", &self.len)
+            .field("pos", &self.pos)
+            .finish()
+    }
+}
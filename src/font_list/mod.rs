@@ -4,6 +4,7 @@ use crate::font_collection::FontCollection;
 use std::ptr;
 
 use com_wrapper::ComWrapper;
+use dcommon::Error;
 use winapi::shared::winerror::SUCCEEDED;
 use winapi::um::dwrite::IDWriteFontList;
 use wio::com::ComPtr;
@@ -21,6 +22,12 @@ impl FontList {
     pub fn all_fonts<'a>(&'a self) -> impl Iterator<Item = Font> + Clone + 'a {
         (0..self.count()).filter_map(move |i| self.get(i))
     }
+
+    /// Get an iterator over every index in the list paired with the result of fetching that
+    /// font. Unlike `all_fonts`, failures are reported rather than left out.
+    pub fn enumerate_fonts<'a>(&'a self) -> impl Iterator<Item = (u32, Result<Font, Error>)> + 'a {
+        (0..self.count()).map(move |i| (i, self.try_get(i)))
+    }
 }
 
 pub unsafe trait IFontList {
@@ -44,13 +51,19 @@ pub unsafe trait IFontList {
 
     /// Get a specific font in the list at the given index.
     fn get(&self, i: u32) -> Option<Font> {
+        self.try_get(i).ok()
+    }
+
+    /// Get a specific font in the list at the given index, returning the error if it can't be
+    /// fetched.
+    fn try_get(&self, i: u32) -> Result<Font, Error> {
         unsafe {
             let mut ptr = ptr::null_mut();
             let hr = self.raw_fontlist().GetFont(i, &mut ptr);
             if SUCCEEDED(hr) {
-                Some(Font::from_raw(ptr))
+                Ok(Font::from_raw(ptr))
             } else {
-                None
+                Err(hr.into())
             }
         }
     }
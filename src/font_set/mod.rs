@@ -0,0 +1,172 @@
+//! FontSet, a flat list of fonts which can be queried by their properties.
+
+use crate::enums::{FontPropertyId, FontStretch, FontStyle, FontWeight};
+use crate::font_face_reference::FontFaceReference;
+use crate::helpers::query_interface;
+use crate::localized_strings::LocalizedStrings;
+use crate::sys::IDWriteFontSet1;
+
+use std::ptr;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::dwrite_3::{IDWriteFontSet, DWRITE_FONT_PROPERTY};
+use wio::com::ComPtr;
+use wio::wide::ToWide;
+
+#[repr(transparent)]
+#[derive(Clone, ComWrapper)]
+#[com(send, sync, debug)]
+/// An unordered set of font face references. Unlike a FontCollection, fonts in a set are not
+/// grouped into families, and their properties can be read without creating a FontFace for
+/// each of them.
+///
+/// Requires Windows 10.
+pub struct FontSet {
+    ptr: ComPtr<IDWriteFontSet>,
+}
+
+pub unsafe trait IFontSet {
+    /// Get the number of fonts in the set.
+    fn font_count(&self) -> u32 {
+        unsafe { self.raw_fontset().GetFontCount() }
+    }
+
+    /// Gets a reference to the font at the specified index, which may be local or remote.
+    fn font_face_reference(&self, index: u32) -> Result<FontFaceReference, Error> {
+        unsafe {
+            let mut ptr = ptr::null_mut();
+            let hr = self.raw_fontset().GetFontFaceReference(index, &mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(FontFaceReference::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Returns the property values of the font at the specified index, indexed by locale name.
+    /// Returns `None` if the font does not have the property.
+    fn property_values(
+        &self,
+        index: u32,
+        property: FontPropertyId,
+    ) -> Result<Option<LocalizedStrings>, Error> {
+        unsafe {
+            let mut ptr = ptr::null_mut();
+            let mut exists = 0;
+            let hr = self.raw_fontset().GetPropertyValues_1(
+                index,
+                property as u32,
+                &mut exists,
+                &mut ptr,
+            );
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+
+            if exists != 0 && !ptr.is_null() {
+                Ok(Some(LocalizedStrings::from_raw(ptr)))
+            } else {
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the subset of fonts in this set which belong to the specified family, ordered by
+    /// how closely they match the requested weight, stretch and style.
+    fn matching_fonts(
+        &self,
+        family: &str,
+        weight: FontWeight,
+        stretch: FontStretch,
+        style: FontStyle,
+    ) -> Result<FontSet, Error> {
+        unsafe {
+            let family = family.to_wide_null();
+            let mut ptr = ptr::null_mut();
+            let hr = self.raw_fontset().GetMatchingFonts_2(
+                family.as_ptr(),
+                weight.0,
+                stretch as u32,
+                style as u32,
+                &mut ptr,
+            );
+            if SUCCEEDED(hr) {
+                Ok(FontSet::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Returns the subset of fonts in this set which match the property filters. If
+    /// `select_any_property` is true a font is included when it matches any of the filters,
+    /// otherwise it must match all of them.
+    ///
+    /// Requires Windows 10 April 2018 Update; on older versions this fails with `E_NOINTERFACE`.
+    fn filtered(
+        &self,
+        property_filters: &[FontProperty],
+        select_any_property: bool,
+    ) -> Result<FontSet, Error> {
+        unsafe {
+            let set = query_interface::<IDWriteFontSet1>(self.raw_fontset())?;
+
+            let strings: Vec<_> = property_filters
+                .iter()
+                .map(|p| {
+                    (
+                        p.value.to_wide_null(),
+                        p.locale.unwrap_or("").to_wide_null(),
+                    )
+                })
+                .collect();
+            let properties: Vec<_> = property_filters
+                .iter()
+                .zip(&strings)
+                .map(|(p, (value, locale))| DWRITE_FONT_PROPERTY {
+                    propertyId: p.id as u32,
+                    propertyValue: value.as_ptr(),
+                    localeName: locale.as_ptr(),
+                })
+                .collect();
+
+            let mut ptr = ptr::null_mut();
+            let hr = set.GetFilteredFonts_3(
+                properties.as_ptr(),
+                properties.len() as u32,
+                select_any_property as i32,
+                &mut ptr,
+            );
+            if SUCCEEDED(hr) {
+                Ok(FontSet::from_raw(ptr as *mut IDWriteFontSet))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    unsafe fn raw_fontset(&self) -> &IDWriteFontSet;
+}
+
+unsafe impl IFontSet for FontSet {
+    unsafe fn raw_fontset(&self) -> &IDWriteFontSet {
+        &self.ptr
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+/// A font property value used to filter a FontSet.
+pub struct FontProperty<'a> {
+    /// The property to compare.
+    pub id: FontPropertyId,
+
+    /// The value the property must have. Numeric properties such as weight are written as
+    /// decimal strings, e.g. `"700"`.
+    pub value: &'a str,
+
+    /// The locale of the value, or `None` to match the value in any locale.
+    pub locale: Option<&'a str>,
+}
@@ -0,0 +1,93 @@
+//! GdiInterop, for bridging fonts selected into GDI device contexts into DirectWrite.
+
+use crate::bitmap_render_target::BitmapRenderTarget;
+use crate::font::Font;
+use crate::font_face::FontFace;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use winapi::shared::windef::HDC;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::dwrite::IDWriteGdiInterop;
+use winapi::um::wingdi::LOGFONTW;
+use wio::com::ComPtr;
+
+#[repr(transparent)]
+#[derive(Clone, ComWrapper)]
+#[com(send, sync, debug)]
+/// Provides interoperability with GDI, such as methods to convert a font face to a `LOGFONTW`
+/// structure, or to get a font face from the font selected into a device context. Obtained from
+/// [`Factory::gdi_interop`][1].
+///
+/// [1]: ../struct.Factory.html#method.gdi_interop
+pub struct GdiInterop {
+    ptr: ComPtr<IDWriteGdiInterop>,
+}
+
+impl GdiInterop {
+    /// Creates a font face matching the font currently selected into the device context.
+    ///
+    /// <div style="padding: 10px 10px 2px 10px; margin: 10px; background-color: #F2F2F2">
+    ///
+    /// **Safety**
+    /// This method is safe to take an `HDC` because `HDC` is just a handle and will
+    /// therefore return an error if an invalid one is passed instead of creating memory
+    /// safety errors.
+    ///
+    /// </div>
+    pub fn create_font_face_from_hdc(&self, hdc: HDC) -> Result<FontFace, Error> {
+        unsafe {
+            let mut ptr = std::ptr::null_mut();
+            let hr = self.ptr.CreateFontFaceFromHdc(hdc, &mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(FontFace::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Creates a bitmap render target of the given size, along with a memory device context
+    /// compatible with the screen which the bitmap is selected into.
+    pub fn create_bitmap_render_target(
+        &self,
+        width: u32,
+        height: u32,
+    ) -> Result<BitmapRenderTarget, Error> {
+        unsafe {
+            let mut ptr = std::ptr::null_mut();
+            let hr =
+                self.ptr
+                    .CreateBitmapRenderTarget(std::ptr::null_mut(), width, height, &mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(BitmapRenderTarget::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Creates a font from the system font collection which matches the GDI font description.
+    /// Fails with `DWRITE_E_NOFONT` if no installed font matches it.
+    pub fn create_font_from_logfont(&self, logfont: &LOGFONTW) -> Result<Font, Error> {
+        unsafe {
+            let mut ptr = std::ptr::null_mut();
+            let hr = self.ptr.CreateFontFromLOGFONT(logfont, &mut ptr);
+            if SUCCEEDED(hr) {
+                Ok(Font::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+}
+
+pub unsafe trait IGdiInterop {
+    unsafe fn raw_gdi(&self) -> &IDWriteGdiInterop;
+}
+
+unsafe impl IGdiInterop for GdiInterop {
+    unsafe fn raw_gdi(&self) -> &IDWriteGdiInterop {
+        &self.ptr
+    }
+}
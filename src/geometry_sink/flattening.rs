@@ -0,0 +1,122 @@
+use crate::geometry_sink::GeometrySink;
+
+use dcommon::Error;
+use math2d::{BezierSegment, Point2f};
+
+/// Subdivision stops at this depth even if the tolerance is not met yet, which bounds the
+/// number of lines a single curve can produce to 2^16.
+const MAX_DEPTH: u32 = 16;
+
+#[derive(Clone, Debug)]
+/// A GeometrySink which approximates every cubic bezier with straight lines and forwards
+/// them to another sink, passing everything else through unchanged. The inner sink never
+/// sees a call to `add_beziers`.
+pub struct FlatteningSink<S: GeometrySink> {
+    inner: S,
+    tolerance: f32,
+    current: Point2f,
+    points: Vec<Point2f>,
+}
+
+impl<S: GeometrySink> FlatteningSink<S> {
+    /// Wraps `inner`. Curves are subdivided until no part of the flattened lines strays
+    /// further than `tolerance` DIPs from the curve.
+    pub fn new(inner: S, tolerance: f32) -> FlatteningSink<S> {
+        FlatteningSink {
+            inner,
+            tolerance,
+            current: Point2f::new(0.0, 0.0),
+            points: Vec::new(),
+        }
+    }
+
+    /// The sink the lines are forwarded to.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwraps the sink the lines are forwarded to.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn flatten(&mut self, p0: Point2f, p1: Point2f, p2: Point2f, p3: Point2f, depth: u32) {
+        if depth >= MAX_DEPTH || flat_enough(p0, p1, p2, p3, self.tolerance) {
+            self.points.push(p3);
+            return;
+        }
+
+        // Split at t = 0.5 with de Casteljau's algorithm
+        let p01 = midpoint(p0, p1);
+        let p12 = midpoint(p1, p2);
+        let p23 = midpoint(p2, p3);
+        let p012 = midpoint(p01, p12);
+        let p123 = midpoint(p12, p23);
+        let mid = midpoint(p012, p123);
+
+        self.flatten(p0, p01, p012, mid, depth + 1);
+        self.flatten(mid, p123, p23, p3, depth + 1);
+    }
+}
+
+fn midpoint(a: Point2f, b: Point2f) -> Point2f {
+    Point2f::new((a.x + b.x) * 0.5, (a.y + b.y) * 0.5)
+}
+
+/// The curve never strays further from its chord than its control points do, so it is flat
+/// enough once both control points are within the tolerance of the chord.
+fn flat_enough(p0: Point2f, p1: Point2f, p2: Point2f, p3: Point2f, tolerance: f32) -> bool {
+    let (dx, dy) = (p3.x - p0.x, p3.y - p0.y);
+    let len = (dx * dx + dy * dy).sqrt();
+    let distance = |p: Point2f| {
+        if len <= std::f32::EPSILON {
+            ((p.x - p0.x).powi(2) + (p.y - p0.y).powi(2)).sqrt()
+        } else {
+            ((p.x - p0.x) * dy - (p.y - p0.y) * dx).abs() / len
+        }
+    };
+    distance(p1) <= tolerance && distance(p2) <= tolerance
+}
+
+impl<S: GeometrySink> GeometrySink for FlatteningSink<S> {
+    fn set_fill_mode(&mut self, mode: u32) {
+        self.inner.set_fill_mode(mode)
+    }
+
+    fn set_segment_flags(&mut self, flags: u32) {
+        self.inner.set_segment_flags(flags)
+    }
+
+    fn begin_figure(&mut self, start: Point2f, begin_flag: u32) {
+        self.current = start;
+        self.inner.begin_figure(start, begin_flag)
+    }
+
+    fn add_beziers(&mut self, beziers: &[BezierSegment]) {
+        self.points.clear();
+        for bezier in beziers {
+            let start = self.current;
+            self.flatten(start, bezier.p1, bezier.p2, bezier.p3, 0);
+            self.current = bezier.p3;
+        }
+
+        let points = std::mem::replace(&mut self.points, Vec::new());
+        self.inner.add_lines(&points);
+        self.points = points;
+    }
+
+    fn add_lines(&mut self, points: &[Point2f]) {
+        if let Some(&last) = points.last() {
+            self.current = last;
+        }
+        self.inner.add_lines(points)
+    }
+
+    fn end_figure(&mut self, end_flag: u32) {
+        self.inner.end_figure(end_flag)
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.inner.close()
+    }
+}
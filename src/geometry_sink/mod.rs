@@ -2,6 +2,22 @@ use dcommon::Error;
 use math2d::BezierSegment;
 use math2d::Point2f;
 
+#[doc(inline)]
+pub use self::collecting::{CollectingSink, PathElement};
+#[doc(inline)]
+pub use self::flattening::FlatteningSink;
+#[doc(inline)]
+pub use self::svg::SvgPathSink;
+
+#[doc(hidden)]
+pub mod collecting;
+
+#[doc(hidden)]
+pub mod flattening;
+
+#[doc(hidden)]
+pub mod svg;
+
 pub(crate) mod com_sink;
 
 /// A sink for geometry made of straight lines and cubic bezier curves.
@@ -0,0 +1,84 @@
+use crate::geometry_sink::GeometrySink;
+
+use std::fmt::Write;
+
+use dcommon::Error;
+use math2d::{BezierSegment, Point2f};
+use winapi::um::d2d1::D2D1_FIGURE_END_CLOSED;
+
+#[derive(Clone, Debug)]
+/// A GeometrySink which writes the geometry as the path data of an SVG `<path d="...">`
+/// element, using absolute `M`, `L`, `C` and `Z` commands.
+pub struct SvgPathSink {
+    path: String,
+    precision: usize,
+}
+
+impl SvgPathSink {
+    /// Creates an empty sink which writes coordinates with `precision` digits after the
+    /// decimal point.
+    pub fn new(precision: usize) -> SvgPathSink {
+        SvgPathSink {
+            path: String::new(),
+            precision,
+        }
+    }
+
+    /// The path data written so far.
+    pub fn as_str(&self) -> &str {
+        &self.path
+    }
+
+    /// Takes the path data.
+    pub fn into_svg(self) -> String {
+        self.path
+    }
+
+    fn command(&mut self, command: char, points: &[Point2f]) {
+        if !self.path.is_empty() {
+            self.path.push(' ');
+        }
+        self.path.push(command);
+        for point in points {
+            let precision = self.precision;
+            write!(
+                self.path,
+                " {:.*},{:.*}",
+                precision, point.x, precision, point.y
+            )
+            .unwrap();
+        }
+    }
+}
+
+impl GeometrySink for SvgPathSink {
+    fn set_fill_mode(&mut self, _mode: u32) {}
+
+    fn set_segment_flags(&mut self, _flags: u32) {}
+
+    fn begin_figure(&mut self, start: Point2f, _begin_flag: u32) {
+        self.command('M', &[start]);
+    }
+
+    fn add_beziers(&mut self, beziers: &[BezierSegment]) {
+        for bezier in beziers {
+            self.command('C', &[bezier.p1, bezier.p2, bezier.p3]);
+        }
+    }
+
+    fn add_lines(&mut self, points: &[Point2f]) {
+        for &point in points {
+            self.command('L', &[point]);
+        }
+    }
+
+    fn end_figure(&mut self, end_flag: u32) {
+        if end_flag == D2D1_FIGURE_END_CLOSED {
+            self.command('Z', &[]);
+        }
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
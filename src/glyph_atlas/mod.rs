@@ -0,0 +1,124 @@
+//! Rasterizing glyphs into a texture atlas. The crate doesn't own the atlas; placement is
+//! delegated to an [`AtlasAllocator`](trait.AtlasAllocator.html), so GPU renderers can plug in
+//! whatever packing strategy their texture cache uses.
+
+use crate::descriptions::{GlyphOffset, GlyphRun};
+use crate::enums::{MeasuringMode, RenderingMode};
+use crate::factory::Factory;
+use crate::font_face::FontFace;
+use crate::glyph_run_analysis::CoverageBitmap;
+
+use dcommon::Error;
+use winapi::shared::winerror::E_OUTOFMEMORY;
+
+#[doc(inline)]
+pub use self::shelf::ShelfAllocator;
+
+#[doc(hidden)]
+pub mod shelf;
+
+/// Decides where glyph bitmaps go in an atlas.
+pub trait AtlasAllocator {
+    /// Reserves a `w` by `h` pixel area, returning the coordinates of its top-left corner, or
+    /// `None` if the atlas has no room left.
+    fn allocate(&mut self, w: u32, h: u32) -> Option<(u32, u32)>;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Where a rasterized glyph was placed in the atlas, and how to position it when drawing.
+pub struct GlyphAtlasEntry {
+    /// The glyph that was rasterized.
+    pub glyph_index: u16,
+
+    /// Width of the glyph bitmap in pixels. Zero for glyphs without ink, such as spaces.
+    pub width: u32,
+
+    /// Height of the glyph bitmap in pixels. Zero for glyphs without ink, such as spaces.
+    pub height: u32,
+
+    /// Horizontal offset from the pen position to the left edge of the bitmap, in pixels.
+    pub left: i32,
+
+    /// Vertical offset from the baseline to the top edge of the bitmap, in pixels. Usually
+    /// negative, as y grows downwards.
+    pub top: i32,
+
+    /// The x coordinate of the bitmap in the atlas, as returned by the allocator.
+    pub atlas_x: u32,
+
+    /// The y coordinate of the bitmap in the atlas, as returned by the allocator.
+    pub atlas_y: u32,
+}
+
+/// Rasterizes each glyph at its own origin and places it in the atlas using `allocator`.
+/// Returns the entries in the same order as `glyph_indices`, each paired with its 8-bit
+/// coverage bitmap (`width * height` bytes, row by row), ready to be copied to the atlas.
+///
+/// `RenderingMode::Aliased` produces hard-edged coverage; the ClearType modes are rendered at
+/// subpixel resolution and averaged down to grayscale. Glyphs without ink take no space in the
+/// atlas. Fails with `E_OUTOFMEMORY` if the allocator runs out of room.
+pub fn rasterize_glyphs(
+    factory: &Factory,
+    font_face: &FontFace,
+    font_em_size: f32,
+    pixels_per_dip: f32,
+    rendering_mode: RenderingMode,
+    glyph_indices: &[u16],
+    allocator: &mut dyn AtlasAllocator,
+) -> Result<Vec<(GlyphAtlasEntry, Vec<u8>)>, Error> {
+    let advances = [0.0];
+    let offsets = [GlyphOffset {
+        advance_offset: 0.0,
+        ascender_offset: 0.0,
+    }];
+
+    let mut entries = Vec::with_capacity(glyph_indices.len());
+    for glyph_index in glyph_indices {
+        let run = GlyphRun {
+            font_face,
+            font_em_size,
+            glyph_indices: std::slice::from_ref(glyph_index),
+            glyph_advances: &advances,
+            glyph_offsets: &offsets,
+            is_sideways: false,
+            bidi_level: 0,
+        };
+        let analysis = factory.create_glyph_run_analysis(
+            &run,
+            pixels_per_dip,
+            None,
+            rendering_mode,
+            MeasuringMode::Natural,
+            0.0,
+            0.0,
+        )?;
+
+        let coverage = CoverageBitmap::from_analysis(&analysis, rendering_mode)?;
+        let (width, height) = (coverage.width(), coverage.height());
+
+        let mut entry = GlyphAtlasEntry {
+            glyph_index: *glyph_index,
+            width,
+            height,
+            left: coverage.bounds.left,
+            top: coverage.bounds.top,
+            atlas_x: 0,
+            atlas_y: 0,
+        };
+        if width == 0 || height == 0 {
+            entries.push((entry, Vec::new()));
+            continue;
+        }
+
+        let (x, y) = match allocator.allocate(width, height) {
+            Some(pos) => pos,
+            None => return Err(E_OUTOFMEMORY.into()),
+        };
+        entry.atlas_x = x;
+        entry.atlas_y = y;
+
+        entries.push((entry, coverage.data));
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,67 @@
+use crate::glyph_atlas::AtlasAllocator;
+
+#[derive(Clone, Debug)]
+/// A simple allocator which fills the atlas left to right in rows ("shelves"), starting a new
+/// shelf below the tallest item of the current one when a row is full. Space is never reclaimed;
+/// start over with a fresh allocator when the atlas is rebuilt.
+pub struct ShelfAllocator {
+    width: u32,
+    height: u32,
+    padding: u32,
+    x: u32,
+    y: u32,
+    shelf_height: u32,
+}
+
+impl ShelfAllocator {
+    /// Creates an allocator for an empty atlas of the given size.
+    pub fn new(width: u32, height: u32) -> ShelfAllocator {
+        ShelfAllocator {
+            width,
+            height,
+            padding: 0,
+            x: 0,
+            y: 0,
+            shelf_height: 0,
+        }
+    }
+
+    /// Leaves `padding` empty pixels to the right of and below every allocation, so that
+    /// bilinear sampling doesn't bleed between neighbouring glyphs.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// The width of the atlas.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the atlas.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl AtlasAllocator for ShelfAllocator {
+    fn allocate(&mut self, w: u32, h: u32) -> Option<(u32, u32)> {
+        if w > self.width {
+            return None;
+        }
+
+        if self.x + w > self.width {
+            self.y += self.shelf_height;
+            self.x = 0;
+            self.shelf_height = 0;
+        }
+        if self.y + h > self.height {
+            return None;
+        }
+
+        let pos = (self.x, self.y);
+        self.x += w + self.padding;
+        self.shelf_height = self.shelf_height.max(h + self.padding);
+        Some(pos)
+    }
+}
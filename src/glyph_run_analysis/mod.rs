@@ -0,0 +1,205 @@
+//! GlyphRunAnalysis, the rasterized form of a glyph run which can be copied into alpha textures.
+
+use crate::enums::{RenderingMode, TextureType};
+use crate::rendering_params::IRenderingParams;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use math2d::Recti;
+use winapi::shared::windef::RECT;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::dwrite::IDWriteGlyphRunAnalysis;
+use wio::com::ComPtr;
+
+#[repr(transparent)]
+#[derive(Clone, ComWrapper)]
+#[com(send, sync, debug)]
+/// Contains the information needed by renderers to draw glyph runs with glyph positions
+/// already snapped to pixels and rasterized. Create one with
+/// [`Factory::create_glyph_run_analysis`][1].
+///
+/// [1]: ../struct.Factory.html#method.create_glyph_run_analysis
+pub struct GlyphRunAnalysis {
+    ptr: ComPtr<IDWriteGlyphRunAnalysis>,
+}
+
+pub unsafe trait IGlyphRunAnalysis {
+    /// Gets the bounding rectangle of the physical pixels affected by the glyph run. The
+    /// rectangle is empty if the glyphs don't have any ink for the given texture type, e.g.
+    /// when asking for ClearType bounds of an analysis created with an aliased rendering mode.
+    fn alpha_texture_bounds(&self, texture_type: TextureType) -> Result<Recti, Error> {
+        unsafe {
+            let mut rect: RECT = std::mem::zeroed();
+            let hr = self
+                .raw_analysis()
+                .GetAlphaTextureBounds(texture_type as u32, &mut rect);
+            if SUCCEEDED(hr) {
+                Ok(Recti {
+                    left: rect.left,
+                    top: rect.top,
+                    right: rect.right,
+                    bottom: rect.bottom,
+                })
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Creates an alpha texture of the specified type for glyphs within the given bounds.
+    /// The texture is returned row by row, with one byte per pixel for aliased textures and
+    /// three bytes per pixel (red, green, blue subpixels) for ClearType textures.
+    fn create_alpha_texture(
+        &self,
+        texture_type: TextureType,
+        bounds: Recti,
+    ) -> Result<Vec<u8>, Error> {
+        let width = (bounds.right - bounds.left).max(0) as usize;
+        let height = (bounds.bottom - bounds.top).max(0) as usize;
+        let bytes_per_pixel = match texture_type {
+            TextureType::Aliased1x1 => 1,
+            TextureType::ClearType3x1 => 3,
+        };
+        let mut buffer = vec![0u8; width * height * bytes_per_pixel];
+        if buffer.is_empty() {
+            return Ok(buffer);
+        }
+
+        unsafe {
+            let rect = RECT {
+                left: bounds.left,
+                top: bounds.top,
+                right: bounds.right,
+                bottom: bounds.bottom,
+            };
+            let hr = self.raw_analysis().CreateAlphaTexture(
+                texture_type as u32,
+                &rect,
+                buffer.as_mut_ptr(),
+                buffer.len() as u32,
+            );
+            if SUCCEEDED(hr) {
+                Ok(buffer)
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Gets the properties required for ClearType blending, as the tuple
+    /// `(gamma, enhanced_contrast, cleartype_level)`. These are derived from the rendering
+    /// parameters, adjusted for the rendering mode of this analysis.
+    fn alpha_blend_params(&self, params: &dyn IRenderingParams) -> Result<(f32, f32, f32), Error> {
+        unsafe {
+            let (mut gamma, mut contrast, mut cleartype) = (0.0, 0.0, 0.0);
+            let hr = self.raw_analysis().GetAlphaBlendParams(
+                params.raw_rp() as *const _ as *mut _,
+                &mut gamma,
+                &mut contrast,
+                &mut cleartype,
+            );
+            if SUCCEEDED(hr) {
+                Ok((gamma, contrast, cleartype))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    unsafe fn raw_analysis(&self) -> &IDWriteGlyphRunAnalysis;
+}
+
+unsafe impl IGlyphRunAnalysis for GlyphRunAnalysis {
+    unsafe fn raw_analysis(&self) -> &IDWriteGlyphRunAnalysis {
+        &self.ptr
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A grayscale coverage bitmap in device pixels, with one byte per pixel stored row by row.
+pub struct CoverageBitmap {
+    /// The pixels covered by the bitmap. Pixel `(x, y)` is at index
+    /// `(y - bounds.top) * width + (x - bounds.left)` of `data`.
+    pub bounds: Recti,
+
+    /// The coverage of each pixel, from 0 (empty) to 255 (fully covered).
+    pub data: Vec<u8>,
+}
+
+impl CoverageBitmap {
+    /// The width of the bitmap in pixels.
+    pub fn width(&self) -> u32 {
+        (self.bounds.right - self.bounds.left).max(0) as u32
+    }
+
+    /// The height of the bitmap in pixels.
+    pub fn height(&self) -> u32 {
+        (self.bounds.bottom - self.bounds.top).max(0) as u32
+    }
+
+    /// Returns true if no pixel has any coverage.
+    pub fn is_empty(&self) -> bool {
+        self.data.iter().all(|&c| c == 0)
+    }
+
+    /// Rasterizes an analysis created with `rendering_mode`. The ClearType modes are rendered
+    /// at subpixel resolution and averaged down to grayscale.
+    pub(crate) fn from_analysis(
+        analysis: &dyn IGlyphRunAnalysis,
+        rendering_mode: RenderingMode,
+    ) -> Result<CoverageBitmap, Error> {
+        let texture_type = match rendering_mode {
+            RenderingMode::Aliased => TextureType::Aliased1x1,
+            _ => TextureType::ClearType3x1,
+        };
+
+        let bounds = analysis.alpha_texture_bounds(texture_type)?;
+        let texture = analysis.create_alpha_texture(texture_type, bounds)?;
+        let data = match texture_type {
+            TextureType::Aliased1x1 => texture,
+            TextureType::ClearType3x1 => texture
+                .chunks(3)
+                .map(|rgb| ((rgb[0] as u32 + rgb[1] as u32 + rgb[2] as u32) / 3) as u8)
+                .collect(),
+        };
+        Ok(CoverageBitmap { bounds, data })
+    }
+
+    /// Draws `other` on top of this bitmap, growing the bounds to fit both.
+    pub(crate) fn composite(&mut self, other: &CoverageBitmap) {
+        if other.width() == 0 || other.height() == 0 {
+            return;
+        }
+        if self.width() == 0 || self.height() == 0 {
+            *self = other.clone();
+            return;
+        }
+
+        let bounds = Recti {
+            left: self.bounds.left.min(other.bounds.left),
+            top: self.bounds.top.min(other.bounds.top),
+            right: self.bounds.right.max(other.bounds.right),
+            bottom: self.bounds.bottom.max(other.bounds.bottom),
+        };
+        let width = (bounds.right - bounds.left) as usize;
+        let height = (bounds.bottom - bounds.top) as usize;
+
+        let mut data = vec![0u8; width * height];
+        for layer in [&*self, other].iter() {
+            let layer_width = layer.width() as usize;
+            let dx = (layer.bounds.left - bounds.left) as usize;
+            let dy = (layer.bounds.top - bounds.top) as usize;
+            for (row, src) in layer.data.chunks(layer_width).enumerate() {
+                let start = (dy + row) * width + dx;
+                for (dst, &c) in data[start..start + layer_width].iter_mut().zip(src) {
+                    // Coverage accumulates like alpha under the "over" operator
+                    let under = *dst as u32;
+                    *dst = (under + c as u32 * (255 - under) / 255) as u8;
+                }
+            }
+        }
+
+        self.bounds = bounds;
+        self.data = data;
+    }
+}
@@ -0,0 +1,90 @@
+//! Small utilities shared between the wrapper modules.
+
+use std::ptr;
+
+use dcommon::Error;
+use winapi::shared::winerror::{ERROR_INSUFFICIENT_BUFFER, HRESULT, HRESULT_FROM_WIN32, SUCCEEDED};
+use winapi::um::unknwnbase::IUnknown;
+use winapi::Interface;
+use wio::com::ComPtr;
+
+/// Queries an object for one of the newer versions of its interface. Objects created on older
+/// versions of Windows will fail with `E_NOINTERFACE`, which is passed along as the error.
+pub(crate) unsafe fn query_interface<T: Interface>(unknown: &IUnknown) -> Result<ComPtr<T>, Error> {
+    let mut ptr = ptr::null_mut();
+    let hr = unknown.QueryInterface(&T::uuidof(), &mut ptr);
+    if SUCCEEDED(hr) {
+        Ok(ComPtr::from_raw(ptr as *mut T))
+    } else {
+        Err(hr.into())
+    }
+}
+
+/// Reads a string which DirectWrite hands out through a separate length query. If the string
+/// grew between the two calls the length is queried again, and the returned string never
+/// includes the NUL terminator.
+pub(crate) fn get_wide_string(
+    mut length: impl FnMut() -> Result<u32, Error>,
+    mut fill: impl FnMut(&mut [u16]) -> HRESULT,
+) -> Result<String, Error> {
+    loop {
+        let mut buf = vec![0u16; length()? as usize + 1];
+        let hr = fill(&mut buf);
+        if hr == HRESULT_FROM_WIN32(ERROR_INSUFFICIENT_BUFFER) {
+            continue;
+        }
+        if !SUCCEEDED(hr) {
+            return Err(hr.into());
+        }
+        return Ok(from_wide_nul(&buf));
+    }
+}
+
+/// Converts a NUL terminated UTF-16 buffer to a String, stopping at the terminator.
+pub(crate) fn from_wide_nul(buf: &[u16]) -> String {
+    let end = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    String::from_utf16_lossy(&buf[..end])
+}
+
+/// Implements the documented raw pointer interop methods on a wrapper type.
+macro_rules! raw_interop {
+    ($wrapper:ident, $interface:ty) => {
+        impl $wrapper {
+            /// Borrows the raw interface pointer, e.g. to pass to another crate's bindings. The
+            /// reference count is not changed, so the pointer is only valid for as long as
+            /// `self` is alive, and must not be released.
+            pub fn as_raw_ptr(&self) -> *mut $interface {
+                unsafe { com_wrapper::ComWrapper::get_raw(self) }
+            }
+
+            /// Returns the raw interface pointer with a new reference added. The caller owns that
+            /// reference and is responsible for calling `Release` on it exactly once.
+            pub fn to_raw_addref(&self) -> *mut $interface {
+                unsafe {
+                    let ptr = com_wrapper::ComWrapper::get_raw(self);
+                    (*ptr).AddRef();
+                    ptr
+                }
+            }
+
+            /// Wraps a pointer borrowed from elsewhere, adding a reference for the wrapper. The
+            /// caller's own reference is untouched and must still be released by the caller.
+            ///
+            /// The pointer must be a valid, non-null pointer to the interface.
+            pub unsafe fn from_raw_addref(ptr: *mut $interface) -> Self {
+                debug_assert!(!ptr.is_null());
+                (*ptr).AddRef();
+                com_wrapper::ComWrapper::from_raw(ptr)
+            }
+
+            /// Wraps a pointer, taking over the caller's reference. The wrapper releases it when
+            /// dropped, so the caller must not release it again.
+            ///
+            /// The pointer must be a valid, non-null pointer to the interface.
+            pub unsafe fn from_raw_owned(ptr: *mut $interface) -> Self {
+                debug_assert!(!ptr.is_null());
+                com_wrapper::ComWrapper::from_raw(ptr)
+            }
+        }
+    };
+}
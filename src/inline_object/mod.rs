@@ -6,6 +6,7 @@ use crate::factory::Factory;
 use crate::inline_object::custom::CustomInlineObject;
 use crate::metrics::overhang::OverhangMetrics;
 use crate::metrics::InlineObjectMetrics;
+use crate::pixel_snapping::IPixelSnapping;
 use crate::text_format::TextFormat;
 use crate::text_renderer::DrawContext;
 use crate::text_renderer::TextRenderer;
@@ -14,7 +15,7 @@ use checked_enum::UncheckedEnum;
 use com_wrapper::ComWrapper;
 use dcommon::helpers::unwrap_opt_com;
 use dcommon::Error;
-use math2d::Point2f;
+use math2d::{Matrix3x2f, Point2f};
 use winapi::shared::winerror::SUCCEEDED;
 use winapi::um::dwrite::IDWriteInlineObject;
 use wio::com::ComPtr;
@@ -105,6 +106,7 @@ impl InlineObject {
     }
 }
 
+#[derive(Copy, Clone)]
 /// The pair of break conditions for an inline object.
 pub struct BreakConditions {
     /// The condition with regards to the text that precedes the object.
@@ -138,3 +140,17 @@ pub struct DrawingContext<'a> {
     /// [1]: ../struct.TextLayout.html#method.set_drawing_effect
     pub client_effect: Option<&'a ClientEffect>,
 }
+
+impl<'a> DrawingContext<'a> {
+    /// Gets the transform the renderer applies to the surrounding text, mapping DIPs to the
+    /// renderer's coordinate space. Objects which rasterize their own content should use this
+    /// along with [`pixels_per_dip`](#method.pixels_per_dip) to match the text's scale.
+    pub fn current_transform(&self) -> Result<Matrix3x2f, Error> {
+        self.renderer.current_transform(&self.client_context)
+    }
+
+    /// Gets the number of physical pixels per DIP the renderer is drawing with.
+    pub fn pixels_per_dip(&self) -> Result<f32, Error> {
+        self.renderer.pixels_per_dip(&self.client_context)
+    }
+}
@@ -3,44 +3,71 @@
 
 #![cfg(windows)]
 
+pub use crate::bitmap_render_target::BitmapRenderTarget;
 pub use crate::factory::Factory;
 pub use crate::font::Font;
 pub use crate::font_collection::FontCollection;
 pub use crate::font_face::FontFace;
+pub use crate::font_face_reference::FontFaceReference;
+pub use crate::font_fallback::FontFallback;
 pub use crate::font_family::FontFamily;
 pub use crate::font_file::FontFile;
 pub use crate::font_list::FontList;
+pub use crate::font_set::FontSet;
+pub use crate::gdi_interop::GdiInterop;
 pub use crate::geometry_sink::GeometrySink;
+pub use crate::glyph_run_analysis::GlyphRunAnalysis;
 pub use crate::inline_object::InlineObject;
+pub use crate::policy::SkipOrFail;
 pub use crate::rendering_params::RenderingParams;
 pub use crate::text_format::TextFormat;
 pub use crate::text_layout::TextLayout;
 pub use crate::text_renderer::TextRenderer;
 pub use crate::typography::Typography;
 
+#[macro_use]
+mod helpers;
+mod origin;
+mod registration;
+
+pub mod bitmap_render_target;
+pub mod color_glyph_run;
 pub mod descriptions;
 pub mod effects;
 pub mod enums;
 pub mod factory;
 pub mod font;
 pub mod font_collection;
+pub mod font_download;
 pub mod font_face;
+pub mod font_face_reference;
+pub mod font_fallback;
 pub mod font_family;
 pub mod font_file;
 pub mod font_list;
+pub mod font_set;
+pub mod gdi_interop;
 pub mod geometry_sink;
+pub mod glyph_atlas;
+pub mod glyph_run_analysis;
 pub mod inline_object;
 pub mod localized_strings;
 pub mod metrics;
 pub mod number_substitution;
 pub mod pixel_snapping;
+pub mod policy;
+pub mod profiling;
 pub mod rendering_params;
+#[cfg(feature = "test-support")]
+pub mod test_support;
 pub mod text_analysis;
 pub mod text_format;
 pub mod text_layout;
 pub mod text_renderer;
 pub mod typography;
 
+mod sys;
+
 /// Shortcut to initialize a Factory, which is required to access all other
 /// functionality in the library.
 pub fn initialize() -> Result<Factory, dcommon::Error> {
@@ -5,7 +5,8 @@ use std::fmt;
 use std::os::windows::ffi::{OsStrExt, OsStringExt};
 
 use com_wrapper::ComWrapper;
-use winapi::shared::winerror::SUCCEEDED;
+use dcommon::Error;
+use winapi::shared::winerror::{ERROR_INSUFFICIENT_BUFFER, HRESULT_FROM_WIN32, SUCCEEDED};
 use winapi::um::dwrite::IDWriteLocalizedStrings;
 use wio::com::ComPtr;
 
@@ -137,6 +138,50 @@ impl<'a> LocalizedString<'a> {
                 .unwrap_or_else(|s| s.to_string_lossy().into_owned())
         }
     }
+
+    /// Get at most `max_chars` characters of the string value, cut at a char boundary.
+    ///
+    /// The value is read into a buffer with room for `max_chars` characters, so a short value
+    /// takes a single call and no more memory than the result. DirectWrite fails with
+    /// `E_NOT_SUFFICIENT_BUFFER` rather than filling a buffer which is too small, so a value
+    /// longer than that is read again in full before being cut.
+    pub fn string_truncated(&self, max_chars: usize) -> Result<String, Error> {
+        unsafe {
+            // A char is at most two UTF-16 code units, plus the NUL terminator
+            let bound = max_chars
+                .saturating_mul(2)
+                .saturating_add(1)
+                .min(u32::MAX as usize);
+            let mut data = vec![0u16; bound];
+            let hr = self
+                .ptr
+                .GetString(self.idx, data.as_mut_ptr(), bound as u32);
+            if hr == HRESULT_FROM_WIN32(ERROR_INSUFFICIENT_BUFFER) {
+                let mut length = 0;
+                let hr = self.ptr.GetStringLength(self.idx, &mut length);
+                if !SUCCEEDED(hr) {
+                    return Err(hr.into());
+                }
+
+                data = vec![0u16; length as usize + 1];
+                let hr = self.ptr.GetString(self.idx, data.as_mut_ptr(), length + 1);
+                if !SUCCEEDED(hr) {
+                    return Err(hr.into());
+                }
+            } else if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+
+            let length = data.iter().position(|&c| c == 0).unwrap_or(data.len());
+            let chars = std::char::decode_utf16(data[..length].iter().cloned())
+                .map(|c| c.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+                .take(max_chars);
+
+            let mut result = String::with_capacity(max_chars.min(length));
+            result.extend(chars);
+            Ok(result)
+        }
+    }
 }
 
 impl<'a> fmt::Debug for LocalizedString<'a> {
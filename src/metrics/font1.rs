@@ -0,0 +1,124 @@
+use crate::descriptions::DBool;
+use crate::metrics::font::FontMetrics;
+
+use std::ops::Deref;
+
+use winapi::um::dwrite_1::DWRITE_FONT_METRICS1;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+/// Font metrics with the bounding box of all glyphs and the recommended placement of
+/// subscripts and superscripts, as reported by Windows 8 and later. Derefs to
+/// [`FontMetrics`](struct.FontMetrics.html) for the common fields. All values are in font
+/// design units.
+pub struct FontMetrics1 {
+    /// The metrics shared with `FontMetrics`.
+    pub metrics: FontMetrics,
+
+    /// The left edge of the box which bounds every glyph in the font.
+    pub glyph_box_left: i16,
+
+    /// The top edge of the glyph bounding box.
+    pub glyph_box_top: i16,
+
+    /// The right edge of the glyph bounding box.
+    pub glyph_box_right: i16,
+
+    /// The bottom edge of the glyph bounding box.
+    pub glyph_box_bottom: i16,
+
+    /// The horizontal offset of a subscript from the baseline origin.
+    pub subscript_position_x: i16,
+
+    /// The vertical offset of a subscript. Positive values move it below the baseline.
+    pub subscript_position_y: i16,
+
+    /// The horizontal size of a subscript, in design units of the full-size font.
+    pub subscript_size_x: i16,
+
+    /// The vertical size of a subscript.
+    pub subscript_size_y: i16,
+
+    /// The horizontal offset of a superscript from the baseline origin.
+    pub superscript_position_x: i16,
+
+    /// The vertical offset of a superscript. Positive values move it above the baseline.
+    pub superscript_position_y: i16,
+
+    /// The horizontal size of a superscript.
+    pub superscript_size_x: i16,
+
+    /// The vertical size of a superscript.
+    pub superscript_size_y: i16,
+
+    /// Whether the ascent, descent and line gap come from the font's typographic metrics
+    /// (the `USE_TYPO_METRICS` flag of the OS/2 table) rather than the Windows metrics.
+    pub has_typographic_metrics: DBool,
+}
+
+#[cfg(test)]
+dcommon::member_compat_test! {
+    font_metrics1_compat:
+    FontMetrics1 <=> DWRITE_FONT_METRICS1 {
+        metrics.design_units_per_em <=> designUnitsPerEm,
+        metrics.ascent <=> ascent,
+        metrics.descent <=> descent,
+        metrics.line_gap <=> lineGap,
+        metrics.cap_height <=> capHeight,
+        metrics.x_height <=> xHeight,
+        metrics.underline_position <=> underlinePosition,
+        metrics.underline_thickness <=> underlineThickness,
+        metrics.strikethrough_position <=> strikethroughPosition,
+        metrics.strikethrough_thickness <=> strikethroughThickness,
+        glyph_box_left <=> glyphBoxLeft,
+        glyph_box_top <=> glyphBoxTop,
+        glyph_box_right <=> glyphBoxRight,
+        glyph_box_bottom <=> glyphBoxBottom,
+        subscript_position_x <=> subscriptPositionX,
+        subscript_position_y <=> subscriptPositionY,
+        subscript_size_x <=> subscriptSizeX,
+        subscript_size_y <=> subscriptSizeY,
+        superscript_position_x <=> superscriptPositionX,
+        superscript_position_y <=> superscriptPositionY,
+        superscript_size_x <=> superscriptSizeX,
+        superscript_size_y <=> superscriptSizeY,
+        has_typographic_metrics <=> hasTypographicMetrics,
+    }
+}
+
+impl FontMetrics1 {
+    /// Metrics for fonts which can't report the extended values: the common fields of
+    /// `metrics` with everything else zeroed.
+    pub fn from_metrics(metrics: FontMetrics) -> FontMetrics1 {
+        FontMetrics1 {
+            metrics,
+            glyph_box_left: 0,
+            glyph_box_top: 0,
+            glyph_box_right: 0,
+            glyph_box_bottom: 0,
+            subscript_position_x: 0,
+            subscript_position_y: 0,
+            subscript_size_x: 0,
+            subscript_size_y: 0,
+            superscript_position_x: 0,
+            superscript_position_y: 0,
+            superscript_size_x: 0,
+            superscript_size_y: 0,
+            has_typographic_metrics: DBool::FALSE,
+        }
+    }
+}
+
+impl Deref for FontMetrics1 {
+    type Target = FontMetrics;
+
+    fn deref(&self) -> &FontMetrics {
+        &self.metrics
+    }
+}
+
+impl From<DWRITE_FONT_METRICS1> for FontMetrics1 {
+    fn from(metrics: DWRITE_FONT_METRICS1) -> Self {
+        unsafe { std::mem::transmute(metrics) }
+    }
+}
@@ -1,3 +1,4 @@
+use math2d::Point2f;
 use winapi::um::dwrite::DWRITE_GLYPH_METRICS;
 
 #[repr(C)]
@@ -45,3 +46,80 @@ impl From<DWRITE_GLYPH_METRICS> for GlyphMetrics {
         unsafe { std::mem::transmute(metrics) }
     }
 }
+
+#[derive(Copy, Clone, Debug)]
+/// Glyph metrics converted from font design units into DIPs for a particular font size.
+pub struct ScaledGlyphMetrics {
+    /// The unscaled metrics, in font design units.
+    pub design: GlyphMetrics,
+
+    /// The factor converting design units into DIPs, `font_em_size / design_units_per_em`.
+    pub scale: f32,
+}
+
+impl ScaledGlyphMetrics {
+    /// Scale design metrics for a font of `font_em_size` DIPs.
+    pub fn new(design: GlyphMetrics, font_em_size: f32, design_units_per_em: u16) -> Self {
+        ScaledGlyphMetrics {
+            design,
+            scale: font_em_size / design_units_per_em as f32,
+        }
+    }
+
+    /// The left side bearing in DIPs.
+    pub fn left_side_bearing_dip(&self) -> f32 {
+        self.design.left_side_bearing as f32 * self.scale
+    }
+
+    /// The horizontal advance in DIPs.
+    pub fn advance_width_dip(&self) -> f32 {
+        self.design.advance_width as f32 * self.scale
+    }
+
+    /// The right side bearing in DIPs.
+    pub fn right_side_bearing_dip(&self) -> f32 {
+        self.design.right_side_bearing as f32 * self.scale
+    }
+
+    /// The top side bearing in DIPs, measured from the vertical origin.
+    pub fn top_side_bearing_dip(&self) -> f32 {
+        self.design.top_side_bearing as f32 * self.scale
+    }
+
+    /// The vertical advance in DIPs, used when the glyph is written sideways.
+    pub fn advance_height_dip(&self) -> f32 {
+        self.design.advance_height as f32 * self.scale
+    }
+
+    /// The bottom side bearing in DIPs.
+    pub fn bottom_side_bearing_dip(&self) -> f32 {
+        self.design.bottom_side_bearing as f32 * self.scale
+    }
+
+    /// The width of the glyph's black box in DIPs.
+    pub fn black_box_width_dip(&self) -> f32 {
+        let m = &self.design;
+        (m.advance_width as i32 - m.left_side_bearing - m.right_side_bearing) as f32 * self.scale
+    }
+
+    /// The height of the glyph's black box in DIPs.
+    pub fn black_box_height_dip(&self) -> f32 {
+        let m = &self.design;
+        (m.advance_height as i32 - m.top_side_bearing - m.bottom_side_bearing) as f32 * self.scale
+    }
+
+    /// The position of the vertical origin in DIPs, relative to the (horizontal) glyph origin
+    /// with y pointing down. The vertical origin sits half the advance width to the right of the
+    /// glyph origin, `vertical_origin_y` above it.
+    pub fn vertical_origin(&self) -> Point2f {
+        Point2f::new(
+            self.advance_width_dip() / 2.0,
+            -(self.design.vertical_origin_y as f32 * self.scale),
+        )
+    }
+
+    /// Whether the glyph has a non-empty black box, i.e. it leaves ink when drawn.
+    pub fn has_ink(&self) -> bool {
+        self.black_box_width_dip() > 0.0 && self.black_box_height_dip() > 0.0
+    }
+}
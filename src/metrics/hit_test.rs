@@ -1,6 +1,6 @@
 use crate::descriptions::DBool;
 
-use math2d::{Point2f, Sizef};
+use math2d::{Point2f, Rectf, Sizef};
 use winapi::um::dwrite::DWRITE_HIT_TEST_METRICS;
 
 #[repr(C)]
@@ -23,6 +23,18 @@ pub struct HitTestMetrics {
     pub is_trimmed: DBool,
 }
 
+impl HitTestMetrics {
+    /// The hit region as a rectangle.
+    pub fn rect(&self) -> Rectf {
+        Rectf {
+            left: self.position.x,
+            top: self.position.y,
+            right: self.position.x + self.size.width,
+            bottom: self.position.y + self.size.height,
+        }
+    }
+}
+
 #[cfg(test)]
 dcommon::member_compat_test! {
     hit_test_metrics_compat:
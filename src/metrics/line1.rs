@@ -0,0 +1,50 @@
+use crate::metrics::line::LineMetrics;
+
+use std::ops::Deref;
+
+use winapi::um::dwrite_3::DWRITE_LINE_METRICS1;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+/// Line metrics with the leading reported by Windows 10 and later. Derefs to
+/// [`LineMetrics`](struct.LineMetrics.html) for the common fields.
+pub struct LineMetrics1 {
+    /// The metrics shared with `LineMetrics`.
+    pub metrics: LineMetrics,
+
+    /// The white space before the content of the line. This is included in the line height
+    /// and baseline distance.
+    pub leading_before: f32,
+
+    /// The white space after the content of the line. This is included in the height of the
+    /// line.
+    pub leading_after: f32,
+}
+
+#[cfg(test)]
+dcommon::member_compat_test! {
+    line_metrics1_compat:
+    LineMetrics1 <=> DWRITE_LINE_METRICS1 {
+        metrics.length <=> length,
+        metrics.trailing_whitespace_length <=> trailingWhitespaceLength,
+        metrics.newline_length <=> newlineLength,
+        metrics.height <=> height,
+        metrics.baseline <=> baseline,
+        leading_before <=> leadingBefore,
+        leading_after <=> leadingAfter,
+    }
+}
+
+impl Deref for LineMetrics1 {
+    type Target = LineMetrics;
+
+    fn deref(&self) -> &LineMetrics {
+        &self.metrics
+    }
+}
+
+impl From<DWRITE_LINE_METRICS1> for LineMetrics1 {
+    fn from(metrics: DWRITE_LINE_METRICS1) -> Self {
+        unsafe { std::mem::transmute(metrics) }
+    }
+}
@@ -5,7 +5,9 @@ pub use crate::metrics::cluster::ClusterMetrics;
 #[doc(inline)]
 pub use crate::metrics::font::FontMetrics;
 #[doc(inline)]
-pub use crate::metrics::glyph::GlyphMetrics;
+pub use crate::metrics::font1::FontMetrics1;
+#[doc(inline)]
+pub use crate::metrics::glyph::{GlyphMetrics, ScaledGlyphMetrics};
 #[doc(inline)]
 pub use crate::metrics::hit_test::HitTestMetrics;
 #[doc(inline)]
@@ -13,15 +15,25 @@ pub use crate::metrics::inline_object::InlineObjectMetrics;
 #[doc(inline)]
 pub use crate::metrics::line::LineMetrics;
 #[doc(inline)]
+pub use crate::metrics::line1::LineMetrics1;
+#[doc(inline)]
 pub use crate::metrics::overhang::OverhangMetrics;
 #[doc(inline)]
+pub use crate::metrics::run_extents::RunExtents;
+#[doc(inline)]
 pub use crate::metrics::text::TextMetrics;
+#[doc(inline)]
+pub use crate::metrics::text1::TextMetrics1;
+#[doc(inline)]
+pub use crate::metrics::vertical_run::VerticalRunMetrics;
 
 #[doc(hidden)]
 pub mod cluster;
 #[doc(hidden)]
 pub mod font;
 #[doc(hidden)]
+pub mod font1;
+#[doc(hidden)]
 pub mod glyph;
 #[doc(hidden)]
 pub mod hit_test;
@@ -30,6 +42,14 @@ pub mod inline_object;
 #[doc(hidden)]
 pub mod line;
 #[doc(hidden)]
+pub mod line1;
+#[doc(hidden)]
 pub mod overhang;
 #[doc(hidden)]
+pub mod run_extents;
+#[doc(hidden)]
 pub mod text;
+#[doc(hidden)]
+pub mod text1;
+#[doc(hidden)]
+pub mod vertical_run;
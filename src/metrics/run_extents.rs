@@ -0,0 +1,17 @@
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+/// The horizontal extent of a run of glyphs, as returned by `IFontFace::run_extents`, for
+/// placing runs by hand.
+///
+/// Like [`OverhangMetrics`](struct.OverhangMetrics.html), positive overhangs mean the ink
+/// extends outside the box spanned by the run's advances, while negative values mean there is
+/// whitespace inside it.
+pub struct RunExtents {
+    /// The sum of the glyph advances, in DIPs.
+    pub advance: f32,
+
+    /// How far the ink extends past the left edge of the run.
+    pub left_overhang: f32,
+
+    /// How far the ink extends past the right edge of the run.
+    pub right_overhang: f32,
+}
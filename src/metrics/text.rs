@@ -42,6 +42,19 @@ pub struct TextMetrics {
     pub line_count: u32,
 }
 
+impl TextMetrics {
+    /// The width DirectWrite aligns the text with. Trailing whitespace hangs past the margin
+    /// and is ignored when aligning, so pass `false` to get the width used for centered and
+    /// trailing text, or `true` to also cover the whitespace, e.g. for a selection background.
+    pub fn alignment_width(&self, include_trailing: bool) -> f32 {
+        if include_trailing {
+            self.width_including_trailing_whitespace
+        } else {
+            self.width
+        }
+    }
+}
+
 impl From<DWRITE_TEXT_METRICS> for TextMetrics {
     fn from(metrics: DWRITE_TEXT_METRICS) -> Self {
         unsafe { std::mem::transmute(metrics) }
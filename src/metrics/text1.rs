@@ -0,0 +1,50 @@
+use crate::metrics::text::TextMetrics;
+
+use std::ops::Deref;
+
+use winapi::um::dwrite_2::DWRITE_TEXT_METRICS1;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+/// Text metrics with the additional information reported by Windows 8.1 and later. Derefs to
+/// [`TextMetrics`](struct.TextMetrics.html) for the common fields.
+pub struct TextMetrics1 {
+    /// The metrics shared with `TextMetrics`.
+    pub metrics: TextMetrics,
+
+    /// The height of the formatted text, taking into account the trailing whitespace at the end
+    /// of each line. This matters for vertical reading directions, where trailing whitespace
+    /// extends the text downwards.
+    pub height_including_trailing_whitespace: f32,
+}
+
+#[cfg(test)]
+dcommon::member_compat_test! {
+    text_metrics1_compat:
+    TextMetrics1 <=> DWRITE_TEXT_METRICS1 {
+        metrics.left <=> left,
+        metrics.top <=> top,
+        metrics.width <=> width,
+        metrics.width_including_trailing_whitespace <=> widthIncludingTrailingWhitespace,
+        metrics.height <=> height,
+        metrics.layout_width <=> layoutWidth,
+        metrics.layout_height <=> layoutHeight,
+        metrics.max_bidi_reordering_depth <=> maxBidiReorderingDepth,
+        metrics.line_count <=> lineCount,
+        height_including_trailing_whitespace <=> heightIncludingTrailingWhitespace,
+    }
+}
+
+impl Deref for TextMetrics1 {
+    type Target = TextMetrics;
+
+    fn deref(&self) -> &TextMetrics {
+        &self.metrics
+    }
+}
+
+impl From<DWRITE_TEXT_METRICS1> for TextMetrics1 {
+    fn from(metrics: DWRITE_TEXT_METRICS1) -> Self {
+        unsafe { std::mem::transmute(metrics) }
+    }
+}
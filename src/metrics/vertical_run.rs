@@ -0,0 +1,42 @@
+use crate::descriptions::GlyphRun;
+
+use dcommon::Error;
+
+#[derive(Copy, Clone, Debug, Default)]
+/// The extents of a sideways glyph run once it has been rotated into a vertical line. All
+/// values are in DIPs.
+pub struct VerticalRunMetrics {
+    /// The total advance of the run down the line.
+    pub length: f32,
+
+    /// How far the ink of the run extends to the left of the vertical baseline.
+    pub left: f32,
+
+    /// How far the ink of the run extends to the right of the vertical baseline.
+    pub right: f32,
+}
+
+impl VerticalRunMetrics {
+    /// Computes the extents of a run as it would be drawn in a vertical line. This is meant for
+    /// runs with `is_sideways` set, as otherwise the font may not supply real vertical metrics.
+    pub fn from_glyph_run(run: &GlyphRun) -> Result<VerticalRunMetrics, Error> {
+        let metrics = run.scaled_metrics()?;
+
+        let mut result = VerticalRunMetrics::default();
+        for (i, m) in metrics.iter().enumerate() {
+            result.length += run
+                .glyph_advances
+                .get(i)
+                .cloned()
+                .unwrap_or(m.advance_height_dip());
+
+            if m.has_ink() {
+                let center = m.vertical_origin().x;
+                result.left = result.left.max(center - m.left_side_bearing_dip());
+                result.right = result.right.max(center - m.right_side_bearing_dip());
+            }
+        }
+
+        Ok(result)
+    }
+}
@@ -11,16 +11,16 @@ use wio::wide::ToWide;
 
 /// Builder for a `NumberSubstitution` object.
 pub struct NumberSubstitutionBuilder<'a> {
-    factory: &'a Factory,
+    factory: Factory,
     method: Option<NumberSubstitutionMethod>,
     locale: Cow<'a, [u16]>,
     ignore_user_override: bool,
 }
 
 impl<'a> NumberSubstitutionBuilder<'a> {
-    pub(crate) fn new(factory: &'a Factory) -> Self {
+    pub(crate) fn new(factory: &Factory) -> Self {
         NumberSubstitutionBuilder {
-            factory,
+            factory: factory.clone(),
             method: None,
             locale: Cow::Borrowed(DEFAULT_LOCALE),
             ignore_user_override: false,
@@ -22,7 +22,7 @@ pub struct NumberSubstitution {
 
 impl NumberSubstitution {
     /// Initialize a builder.
-    pub fn create(factory: &Factory) -> NumberSubstitutionBuilder {
+    pub fn create<'a>(factory: &Factory) -> NumberSubstitutionBuilder<'a> {
         NumberSubstitutionBuilder::new(factory)
     }
 }
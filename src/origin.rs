@@ -0,0 +1,175 @@
+//! Debug tracking of the factory each object was created by.
+//!
+//! Objects created by one factory mostly work with another, except when they don't: a font
+//! collection from an isolated factory passed to a different factory typically fails with
+//! `E_INVALIDARG` a few calls later, far from the mistake. With the `factory-tracking`
+//! feature enabled in a debug build, the builders and constructors record which factory
+//! created the formats, collections, layouts and font files they return, and the builders
+//! which take such objects fail right away with `E_INVALIDARG`. A hook installed with
+//! [`set_mismatch_hook`](fn.set_mismatch_hook.html) is told which factories were mixed up.
+//!
+//! The factories are kept in a table keyed by object address rather than in the wrappers, so
+//! tracking changes neither the layout of the wrappers nor when anything is released. The
+//! table holds no references, and an entry is replaced whenever this crate creates or hands
+//! out another object at the same address. Objects which reach this crate only through
+//! `from_raw` aren't recorded, so one which happens to reuse the address of a released object
+//! may be reported against that object's factory; tracking is a debugging aid only.
+//!
+//! Without the feature, or in release builds, every check is an empty function and nothing
+//! is recorded, so the checks cost nothing.
+
+use crate::factory::Factory;
+
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use dcommon::Error;
+use winapi::um::dwrite::IDWriteFactory;
+
+/// Whether objects are tracked, i.e. the `factory-tracking` feature is enabled in a debug
+/// build.
+pub(crate) const ENABLED: bool = cfg!(all(debug_assertions, feature = "factory-tracking"));
+
+static HOOK: RwLock<Option<Arc<dyn Fn(&FactoryMismatch) + Send + Sync>>> = RwLock::new(None);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// An object which was passed to a builder of a factory other than the one which created it.
+pub struct FactoryMismatch {
+    /// What kind of object it was, e.g. `"font collection"`.
+    pub what: &'static str,
+    /// The address of the factory which created the object.
+    pub origin: usize,
+    /// The address of the factory it was used with.
+    pub factory: usize,
+}
+
+impl fmt::Display for FactoryMismatch {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "a {} created by factory {:#x} was used with factory {:#x}; objects can only be \
+             used with the factory which created them",
+            self.what, self.origin, self.factory
+        )
+    }
+}
+
+/// Installs a function which is called with every mismatch found by factory tracking, before
+/// the builder fails with `E_INVALIDARG`, e.g. to log it. Replaces any previous hook. It is
+/// never called in builds without [`FACTORY_TRACKING`](constant.FACTORY_TRACKING.html).
+pub fn set_mismatch_hook(hook: impl Fn(&FactoryMismatch) + Send + Sync + 'static) {
+    *HOOK.write().unwrap() = Some(Arc::new(hook));
+}
+
+/// Removes the hook installed with [`set_mismatch_hook`](fn.set_mismatch_hook.html), if any.
+pub fn clear_mismatch_hook() {
+    *HOOK.write().unwrap() = None;
+}
+
+#[cfg(all(debug_assertions, feature = "factory-tracking"))]
+mod imp {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    static ORIGINS: Mutex<Option<HashMap<usize, usize>>> = Mutex::new(None);
+
+    pub fn record(object: usize, factory: usize) {
+        let mut origins = ORIGINS.lock().unwrap();
+        origins
+            .get_or_insert_with(HashMap::new)
+            .insert(object, factory);
+    }
+
+    pub fn inherit(object: usize, from: usize) {
+        let mut origins = ORIGINS.lock().unwrap();
+        let origins = origins.get_or_insert_with(HashMap::new);
+        match origins.get(&from).cloned() {
+            Some(factory) => origins.insert(object, factory),
+            None => origins.remove(&object),
+        };
+    }
+
+    pub fn origin(object: usize) -> Option<usize> {
+        ORIGINS
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|origins| origins.get(&object).cloned())
+    }
+}
+
+#[cfg(not(all(debug_assertions, feature = "factory-tracking")))]
+mod imp {
+    #[inline(always)]
+    pub fn record(_object: usize, _factory: usize) {}
+
+    #[inline(always)]
+    pub fn inherit(_object: usize, _from: usize) {}
+
+    #[inline(always)]
+    pub fn origin(_object: usize) -> Option<usize> {
+        None
+    }
+}
+
+/// Records that `factory` created `object`.
+#[inline(always)]
+pub(crate) fn record<T>(object: *const T, factory: &IDWriteFactory) {
+    imp::record(object as usize, factory as *const _ as usize)
+}
+
+/// Records that `object` comes from the same factory as `from`, e.g. the collection a
+/// format was created with.
+#[inline(always)]
+pub(crate) fn inherit<T, U>(object: *const T, from: *const U) {
+    imp::inherit(object as usize, from as usize)
+}
+
+/// Fails with `E_INVALIDARG` if `object` is known to have been created by a factory other
+/// than `factory`.
+#[inline(always)]
+pub(crate) fn check_object<T>(
+    what: &'static str,
+    object: *const T,
+    factory: &IDWriteFactory,
+) -> Result<(), Error> {
+    match imp::origin(object as usize) {
+        Some(origin) => compare(what, origin, factory),
+        None => Ok(()),
+    }
+}
+
+/// Fails with `E_INVALIDARG` if `origin` is a factory other than `factory`, e.g. the factory a
+/// loader was registered on.
+#[inline(always)]
+pub(crate) fn check(
+    what: &'static str,
+    origin: Option<&Factory>,
+    factory: &IDWriteFactory,
+) -> Result<(), Error> {
+    use com_wrapper::ComWrapper;
+
+    match origin {
+        Some(origin) if ENABLED => compare(what, unsafe { origin.get_raw() as usize }, factory),
+        _ => Ok(()),
+    }
+}
+
+fn compare(what: &'static str, origin: usize, factory: &IDWriteFactory) -> Result<(), Error> {
+    use winapi::shared::winerror::E_INVALIDARG;
+
+    let factory = factory as *const IDWriteFactory as usize;
+    if origin == factory {
+        return Ok(());
+    }
+
+    let hook = HOOK.read().unwrap().clone();
+    if let Some(hook) = hook {
+        hook(&FactoryMismatch {
+            what,
+            origin,
+            factory,
+        });
+    }
+    Err(E_INVALIDARG.into())
+}
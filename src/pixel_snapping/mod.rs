@@ -69,3 +69,16 @@ unsafe impl IPixelSnapping for PixelSnapping {
         &self.ptr
     }
 }
+
+/// Rounds a baseline position in DIPs to the nearest device pixel boundary, for renderers which
+/// don't disable pixel snapping. Snapping keeps the glyphs of a line from being blurred over
+/// two rows of pixels; turn it off while text is animating, or it will jitter.
+///
+/// Returns the baseline unchanged if `pixels_per_dip` isn't a positive number.
+pub fn snap_baseline(baseline_dip: f32, pixels_per_dip: f32) -> f32 {
+    if pixels_per_dip > 0.0 && pixels_per_dip.is_finite() {
+        (baseline_dip * pixels_per_dip).round() / pixels_per_dip
+    } else {
+        baseline_dip
+    }
+}
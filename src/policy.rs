@@ -0,0 +1,34 @@
+//! Policies for how the convenience collectors handle failures at individual indices.
+
+use dcommon::Error;
+
+/// Decides what a collector such as
+/// [`FontCollection::family_names`](../font_collection/struct.FontCollection.html#method.family_names)
+/// does when fetching one of the items fails.
+pub enum SkipOrFail<'a> {
+    /// Abort at the first failing index and return its error.
+    Fail,
+
+    /// Leave failing items out of the results, recording each index along with its error
+    /// so the caller can tell the results are incomplete.
+    Skip(&'a mut Vec<(u32, Error)>),
+}
+
+impl<'a> SkipOrFail<'a> {
+    /// Applies the policy to the result for `index`. Returns `Ok(None)` if the item should be
+    /// skipped.
+    pub(crate) fn check<T>(
+        &mut self,
+        index: u32,
+        result: Result<T, Error>,
+    ) -> Result<Option<T>, Error> {
+        match (result, self) {
+            (Ok(item), _) => Ok(Some(item)),
+            (Err(e), SkipOrFail::Fail) => Err(e),
+            (Err(e), SkipOrFail::Skip(log)) => {
+                log.push((index, e));
+                Ok(None)
+            }
+        }
+    }
+}
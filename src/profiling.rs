@@ -0,0 +1,230 @@
+//! Opt-in timing of layout creation, drawing and font collection construction.
+//!
+//! Nothing is measured until a profiler is installed with [`set_profiler`](fn.set_profiler.html);
+//! until then the instrumented calls only pay for a single atomic load.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PROFILER: RwLock<Option<Arc<dyn Profiler>>> = RwLock::new(None);
+
+/// Receives timings from the instrumented parts of the library. Every callback is made on the
+/// thread which did the work, after it finished successfully.
+pub trait Profiler: Send + Sync + 'static {
+    /// A `TextLayout` with `chars` UTF-16 code units was built.
+    fn layout_created(&self, chars: usize, dur: Duration) {
+        let _ = (chars, dur);
+    }
+
+    /// A layout drew `runs` glyph runs through `ITextLayout::draw`. Runs are counted as they
+    /// reach a renderer implemented with `CustomTextRenderer`; renderers which only exist at
+    /// the COM level, e.g. ones from `TextRenderer::from_raw`, are drawn through directly and
+    /// their runs aren't counted.
+    fn layout_drawn(&self, runs: usize, dur: Duration) {
+        let _ = (runs, dur);
+    }
+
+    /// A custom `FontCollection` containing `fonts` fonts was built.
+    fn collection_built(&self, fonts: usize, dur: Duration) {
+        let _ = (fonts, dur);
+    }
+}
+
+/// Installs the profiler which receives all timings from now on, replacing any previous one.
+pub fn set_profiler(profiler: impl Profiler) {
+    *PROFILER.write().unwrap() = Some(Arc::new(profiler));
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// Removes the installed profiler, if any, and stops measuring.
+pub fn clear_profiler() {
+    ENABLED.store(false, Ordering::Release);
+    *PROFILER.write().unwrap() = None;
+}
+
+/// Starts timing an operation if a profiler is installed.
+pub(crate) fn start() -> Option<Instant> {
+    if ENABLED.load(Ordering::Acquire) {
+        Some(Instant::now())
+    } else {
+        None
+    }
+}
+
+thread_local! {
+    /// Glyph runs which reached a `CustomTextRenderer` on this thread during the current draw.
+    /// DirectWrite calls the renderer on the thread which called `Draw`, before it returns.
+    static DRAWN_RUNS: Cell<usize> = Cell::new(0);
+}
+
+/// Counts a glyph run passed to a `CustomTextRenderer`.
+pub(crate) fn count_run() {
+    if ENABLED.load(Ordering::Relaxed) {
+        DRAWN_RUNS.with(|runs| runs.set(runs.get() + 1));
+    }
+}
+
+/// Runs `draw` and returns its result along with the number of glyph runs counted while it
+/// ran. Draws nested inside `draw`, e.g. by an inline object, count towards both.
+pub(crate) fn count_runs<T>(draw: impl FnOnce() -> T) -> (T, usize) {
+    let outer = DRAWN_RUNS.with(|runs| runs.replace(0));
+    let result = draw();
+    let runs = DRAWN_RUNS.with(|runs| runs.replace(0));
+    DRAWN_RUNS.with(|counted| counted.set(outer + runs));
+    (result, runs)
+}
+
+/// Hands the time elapsed since `start` to the installed profiler.
+pub(crate) fn report(start: Instant, event: impl FnOnce(&dyn Profiler, Duration)) {
+    let dur = start.elapsed();
+    let profiler = PROFILER.read().unwrap().clone();
+    if let Some(profiler) = profiler {
+        event(&*profiler, dur);
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+/// A profiler which prints every event to stderr.
+pub struct LoggingProfiler;
+
+impl Profiler for LoggingProfiler {
+    fn layout_created(&self, chars: usize, dur: Duration) {
+        eprintln!(
+            "directwrite: created layout of {} chars in {:?}",
+            chars, dur
+        );
+    }
+
+    fn layout_drawn(&self, runs: usize, dur: Duration) {
+        eprintln!("directwrite: drew layout with {} runs in {:?}", runs, dur);
+    }
+
+    fn collection_built(&self, fonts: usize, dur: Duration) {
+        eprintln!(
+            "directwrite: built collection of {} fonts in {:?}",
+            fonts, dur
+        );
+    }
+}
+
+#[derive(Clone, Default)]
+/// A profiler which keeps every timing so they can be summarized later, e.g. at shutdown.
+/// Clones share the same data, so keep one to read the summary from after installing another.
+pub struct SummaryProfiler {
+    data: Arc<Mutex<SummaryData>>,
+}
+
+#[derive(Default)]
+struct SummaryData {
+    layouts: Samples,
+    draws: Samples,
+    collections: Samples,
+}
+
+#[derive(Default)]
+struct Samples {
+    items: usize,
+    durations: Vec<Duration>,
+}
+
+impl Samples {
+    fn add(&mut self, items: usize, dur: Duration) {
+        self.items += items;
+        self.durations.push(dur);
+    }
+
+    fn summarize(&self) -> EventSummary {
+        let mut sorted = self.durations.clone();
+        sorted.sort();
+        let percentile = |p: usize| match sorted.len() {
+            0 => Duration::from_secs(0),
+            len => sorted[((len - 1) * p + 50) / 100],
+        };
+        EventSummary {
+            count: sorted.len(),
+            items: self.items,
+            total: sorted.iter().sum(),
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: sorted.last().cloned().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+/// Aggregated timings for one kind of event.
+pub struct EventSummary {
+    /// How many times the event happened.
+    pub count: usize,
+
+    /// The sum of the sizes reported with each event, i.e. characters, runs or fonts.
+    pub items: usize,
+
+    /// The total time spent.
+    pub total: Duration,
+
+    /// The median duration.
+    pub p50: Duration,
+
+    /// The 90th percentile duration.
+    pub p90: Duration,
+
+    /// The 99th percentile duration.
+    pub p99: Duration,
+
+    /// The longest duration.
+    pub max: Duration,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+/// The timings collected by a [`SummaryProfiler`](struct.SummaryProfiler.html).
+pub struct Summary {
+    /// `TextLayoutBuilder::build` calls, with `items` counting characters.
+    pub layouts_created: EventSummary,
+
+    /// `ITextLayout::draw` calls, with `items` counting glyph runs.
+    pub layouts_drawn: EventSummary,
+
+    /// `FontCollectionBuilder::build` calls, with `items` counting fonts.
+    pub collections_built: EventSummary,
+}
+
+impl SummaryProfiler {
+    /// Creates a profiler with nothing recorded yet.
+    pub fn new() -> SummaryProfiler {
+        SummaryProfiler::default()
+    }
+
+    /// Summarizes everything recorded so far.
+    pub fn summary(&self) -> Summary {
+        let data = self.data.lock().unwrap();
+        Summary {
+            layouts_created: data.layouts.summarize(),
+            layouts_drawn: data.draws.summarize(),
+            collections_built: data.collections.summarize(),
+        }
+    }
+
+    /// Forgets everything recorded so far.
+    pub fn reset(&self) {
+        *self.data.lock().unwrap() = SummaryData::default();
+    }
+}
+
+impl Profiler for SummaryProfiler {
+    fn layout_created(&self, chars: usize, dur: Duration) {
+        self.data.lock().unwrap().layouts.add(chars, dur);
+    }
+
+    fn layout_drawn(&self, runs: usize, dur: Duration) {
+        self.data.lock().unwrap().draws.add(runs, dur);
+    }
+
+    fn collection_built(&self, fonts: usize, dur: Duration) {
+        self.data.lock().unwrap().collections.add(fonts, dur);
+    }
+}
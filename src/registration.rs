@@ -0,0 +1,165 @@
+//! Bookkeeping for custom loaders registered into a Factory.
+//!
+//! The ownership graph looks like this:
+//!
+//! ```text
+//! FontFile / FontCollection --(LoaderDependency)--+
+//!                                                 v
+//! FileLoaderHandle / CollectionLoaderHandle --> Registration --> Factory
+//!                                                 ^
+//! REGISTRY (keyed by loader pointer) -------------+
+//! ```
+//!
+//! Every handle to a loader and every object created from it shares one `Registration`, which
+//! keeps the factory the loader was registered on alive. The registry keeps its entry until the
+//! loader is unregistered, so that `unregister_all` still finds loaders whose handles were all
+//! dropped, and a handle recreated from a raw loader pointer can find the registration again.
+//! Objects created from a loader additionally count as dependents, and unregistering is refused
+//! while any of them exist. Dependents are only added under the registry lock, so none can
+//! appear between that check and the loader being unregistered.
+
+use crate::factory::Factory;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use winapi::shared::winerror::{ERROR_BUSY, HRESULT_FROM_WIN32, SUCCEEDED};
+
+static REGISTRY: Mutex<Vec<(usize, Arc<Registration>)>> = Mutex::new(Vec::new());
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum LoaderKind {
+    File,
+    Collection,
+}
+
+/// Shared state for a loader which is registered into a factory.
+pub(crate) struct Registration {
+    factory: Factory,
+    loader: usize,
+    kind: LoaderKind,
+    dependents: AtomicUsize,
+}
+
+impl Registration {
+    /// Records that `loader` was just registered into `factory`. Any stale entry for the same
+    /// pointer is replaced.
+    pub fn insert(factory: &Factory, loader: usize, kind: LoaderKind) -> Arc<Registration> {
+        let mut registry = REGISTRY.lock().unwrap();
+        registry.retain(|(ptr, _)| *ptr != loader);
+
+        let reg = Arc::new(Registration {
+            factory: factory.clone(),
+            loader,
+            kind,
+            dependents: AtomicUsize::new(0),
+        });
+        registry.push((loader, reg.clone()));
+        reg
+    }
+
+    /// Finds the registration for a loader pointer, if it is still registered.
+    pub fn find(loader: usize) -> Option<Arc<Registration>> {
+        let registry = REGISTRY.lock().unwrap();
+        registry
+            .iter()
+            .find(|(ptr, _)| *ptr == loader)
+            .map(|(_, reg)| reg.clone())
+    }
+
+    /// Unregisters every loader still registered on `factory`. Loaders which still have
+    /// dependents are skipped, and reported with `HRESULT_FROM_WIN32(ERROR_BUSY)` once the others
+    /// are unregistered.
+    pub fn unregister_all(factory: &Factory) -> Result<usize, Error> {
+        let registrations: Vec<_> = {
+            let registry = REGISTRY.lock().unwrap();
+            registry
+                .iter()
+                .map(|(_, reg)| reg.clone())
+                .filter(|reg| unsafe { reg.factory.get_raw() == factory.get_raw() })
+                .collect()
+        };
+
+        let mut unregistered = 0;
+        let mut busy = false;
+        for reg in registrations {
+            match reg.unregister() {
+                Ok(()) => unregistered += 1,
+                Err(e) if e.0 == HRESULT_FROM_WIN32(ERROR_BUSY) => busy = true,
+                Err(e) => return Err(e),
+            }
+        }
+
+        if busy {
+            Err(HRESULT_FROM_WIN32(ERROR_BUSY).into())
+        } else {
+            Ok(unregistered)
+        }
+    }
+
+    /// The factory this loader was registered on.
+    pub fn factory(&self) -> &Factory {
+        &self.factory
+    }
+
+    /// Marks a new object as depending on this loader for as long as the returned value lives.
+    pub fn dependency(self: &Arc<Self>) -> LoaderDependency {
+        // Taken so that `unregister` can't see no dependents and then unregister the loader
+        // underneath this one.
+        let _registry = REGISTRY.lock().unwrap();
+        self.dependents.fetch_add(1, Ordering::SeqCst);
+        LoaderDependency { reg: self.clone() }
+    }
+
+    /// The number of live objects created from this loader.
+    pub fn dependents(&self) -> usize {
+        self.dependents.load(Ordering::SeqCst)
+    }
+
+    /// Unregisters the loader from its factory, unless objects created from it are still alive,
+    /// in which case `HRESULT_FROM_WIN32(ERROR_BUSY)` is returned.
+    pub fn unregister(&self) -> Result<(), Error> {
+        // Holding the registry lock keeps new dependents and new registrations of the same
+        // pointer from racing with the removal below.
+        let mut registry = REGISTRY.lock().unwrap();
+        if self.dependents() > 0 {
+            return Err(HRESULT_FROM_WIN32(ERROR_BUSY).into());
+        }
+
+        let hr = unsafe {
+            let factory = &*self.factory.get_raw();
+            match self.kind {
+                LoaderKind::File => factory.UnregisterFontFileLoader(self.loader as *mut _),
+                LoaderKind::Collection => {
+                    factory.UnregisterFontCollectionLoader(self.loader as *mut _)
+                }
+            }
+        };
+        if !SUCCEEDED(hr) {
+            return Err(hr.into());
+        }
+
+        registry.retain(|(ptr, _)| *ptr != self.loader);
+        Ok(())
+    }
+}
+
+/// Held by objects created from a custom loader. While any of these exist the loader cannot be
+/// unregistered, and the factory it was registered on is kept alive.
+pub(crate) struct LoaderDependency {
+    reg: Arc<Registration>,
+}
+
+impl Clone for LoaderDependency {
+    fn clone(&self) -> Self {
+        self.reg.dependency()
+    }
+}
+
+impl Drop for LoaderDependency {
+    fn drop(&mut self) {
+        self.reg.dependents.fetch_sub(1, Ordering::SeqCst);
+    }
+}
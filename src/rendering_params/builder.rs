@@ -0,0 +1,183 @@
+use crate::enums::{GridFitMode, PixelGeometry, RenderingMode, RenderingMode1};
+use crate::factory::{Factory, IFactory};
+use crate::helpers::query_interface;
+use crate::rendering_params::RenderingParams;
+
+use std::convert::TryFrom;
+
+use com_wrapper::ComWrapper;
+use dcommon::error::Error;
+use winapi::shared::winerror::{E_INVALIDARG, SUCCEEDED};
+use winapi::um::dwrite::IDWriteFactory;
+use winapi::um::dwrite_2::IDWriteFactory2;
+use winapi::um::dwrite_3::IDWriteFactory3;
+
+#[must_use]
+/// Builder for custom RenderingParams. Unspecified values default to a gamma of 1.8,
+/// enhanced contrast of 0.5, full ClearType, RGB pixel geometry and the default rendering mode.
+///
+/// Specifying a grid fit mode or grayscale enhanced contrast requires Windows 8.1, and the
+/// `NaturalSymmetricDownsampled` rendering mode requires Windows 10; on older versions `build`
+/// fails with `E_NOINTERFACE`.
+pub struct RenderingParamsBuilder {
+    factory: Factory,
+    gamma: f32,
+    enhanced_contrast: f32,
+    cleartype_level: f32,
+    pixel_geometry: PixelGeometry,
+    rendering_mode: RenderingMode1,
+    grid_fit_mode: Option<GridFitMode>,
+    enhanced_contrast_grayscale: Option<f32>,
+}
+
+impl RenderingParamsBuilder {
+    /// Initialize a new builder.
+    pub fn new(factory: &IDWriteFactory) -> RenderingParamsBuilder {
+        RenderingParamsBuilder {
+            factory: unsafe { Factory::from_borrowed(factory) },
+            gamma: 1.8,
+            enhanced_contrast: 0.5,
+            cleartype_level: 1.0,
+            pixel_geometry: PixelGeometry::Rgb,
+            rendering_mode: RenderingMode1::Default,
+            grid_fit_mode: None,
+            enhanced_contrast_grayscale: None,
+        }
+    }
+
+    /// Finalize the builder. Fails with `E_INVALIDARG` if the gamma is not in `(0, 256]`, either
+    /// enhanced contrast is negative, or the ClearType level is not in `[0, 1]`.
+    pub fn build(self) -> Result<RenderingParams, Error> {
+        // Written so that NaN fails every check.
+        let grayscale_valid = match self.enhanced_contrast_grayscale {
+            Some(contrast) => contrast >= 0.0,
+            None => true,
+        };
+        let valid = grayscale_valid
+            && self.gamma > 0.0
+            && self.gamma <= 256.0
+            && self.enhanced_contrast >= 0.0
+            && (0.0..=1.0).contains(&self.cleartype_level);
+        if !valid {
+            return Err(E_INVALIDARG.into());
+        }
+
+        // Only the newest factory understands the rendering modes added by RenderingMode1.
+        let rendering_mode = match RenderingMode::try_from(self.rendering_mode) {
+            Ok(mode) => mode,
+            Err(_) => return self.build3(),
+        };
+
+        if self.grid_fit_mode.is_some() || self.enhanced_contrast_grayscale.is_some() {
+            return self.build2(rendering_mode);
+        }
+
+        unsafe {
+            let mut ptr = std::ptr::null_mut();
+            let hr = self.factory.raw_f().CreateCustomRenderingParams(
+                self.gamma,
+                self.enhanced_contrast,
+                self.cleartype_level,
+                self.pixel_geometry as u32,
+                rendering_mode as u32,
+                &mut ptr,
+            );
+            if SUCCEEDED(hr) {
+                Ok(RenderingParams::from_raw(ptr))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    fn build2(self, rendering_mode: RenderingMode) -> Result<RenderingParams, Error> {
+        unsafe {
+            let factory = query_interface::<IDWriteFactory2>(self.factory.raw_f())?;
+            let mut ptr = std::ptr::null_mut();
+            let hr = factory.CreateCustomRenderingParams(
+                self.gamma,
+                self.enhanced_contrast,
+                self.enhanced_contrast_grayscale
+                    .unwrap_or(self.enhanced_contrast),
+                self.cleartype_level,
+                self.pixel_geometry as u32,
+                rendering_mode as u32,
+                self.grid_fit_mode.unwrap_or(GridFitMode::Default) as u32,
+                &mut ptr,
+            );
+            if SUCCEEDED(hr) {
+                Ok(RenderingParams::from_raw(ptr as *mut _))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    fn build3(self) -> Result<RenderingParams, Error> {
+        unsafe {
+            let factory = query_interface::<IDWriteFactory3>(self.factory.raw_f())?;
+            let mut ptr = std::ptr::null_mut();
+            let hr = factory.CreateCustomRenderingParams(
+                self.gamma,
+                self.enhanced_contrast,
+                self.enhanced_contrast_grayscale
+                    .unwrap_or(self.enhanced_contrast),
+                self.cleartype_level,
+                self.pixel_geometry as u32,
+                self.rendering_mode as u32,
+                self.grid_fit_mode.unwrap_or(GridFitMode::Default) as u32,
+                &mut ptr,
+            );
+            if SUCCEEDED(hr) {
+                Ok(RenderingParams::from_raw(ptr as *mut _))
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Specify the gamma used for gamma correction. Must be greater than zero and at most 256.
+    pub fn with_gamma(mut self, gamma: f32) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    /// Specify the amount to increase the darkness of text. Must not be negative.
+    pub fn with_enhanced_contrast(mut self, enhanced_contrast: f32) -> Self {
+        self.enhanced_contrast = enhanced_contrast;
+        self
+    }
+
+    /// Specify the degree of ClearType, from 0 (grayscale antialiasing) to 1 (full ClearType).
+    pub fn with_cleartype_level(mut self, cleartype_level: f32) -> Self {
+        self.cleartype_level = cleartype_level;
+        self
+    }
+
+    /// Specify the arrangement of subpixels on the display.
+    pub fn with_pixel_geometry(mut self, pixel_geometry: PixelGeometry) -> Self {
+        self.pixel_geometry = pixel_geometry;
+        self
+    }
+
+    /// Specify the method used to render glyphs. Either a `RenderingMode` or a
+    /// `RenderingMode1` is accepted.
+    pub fn with_rendering_mode(mut self, rendering_mode: impl Into<RenderingMode1>) -> Self {
+        self.rendering_mode = rendering_mode.into();
+        self
+    }
+
+    /// Specify whether glyph outlines are grid fitted. Disabling grid fitting gives smoother
+    /// animation of text at the cost of sharpness.
+    pub fn with_grid_fit_mode(mut self, grid_fit_mode: GridFitMode) -> Self {
+        self.grid_fit_mode = Some(grid_fit_mode);
+        self
+    }
+
+    /// Specify the enhanced contrast used for grayscale antialiasing, if it should differ
+    /// from the contrast used for ClearType. Must not be negative.
+    pub fn with_enhanced_contrast_grayscale(mut self, enhanced_contrast: f32) -> Self {
+        self.enhanced_contrast_grayscale = Some(enhanced_contrast);
+        self
+    }
+}
@@ -1,6 +1,9 @@
+use crate::enums::grid_fit_mode::GridFitMode;
 use crate::enums::pixel_geometry::PixelGeometry;
 use crate::enums::rendering_mode::RenderingMode;
+use crate::enums::rendering_mode1::RenderingMode1;
 use crate::factory::IFactory;
+use crate::helpers::query_interface;
 
 use checked_enum::UncheckedEnum;
 use com_wrapper::ComWrapper;
@@ -8,8 +11,17 @@ use dcommon::Error;
 use winapi::shared::windef::HMONITOR;
 use winapi::shared::winerror::SUCCEEDED;
 use winapi::um::dwrite::IDWriteRenderingParams;
+use winapi::um::dwrite_1::IDWriteRenderingParams1;
+use winapi::um::dwrite_2::IDWriteRenderingParams2;
+use winapi::um::dwrite_3::IDWriteRenderingParams3;
 use wio::com::ComPtr;
 
+#[doc(inline)]
+pub use crate::rendering_params::builder::RenderingParamsBuilder;
+
+#[doc(hidden)]
+pub mod builder;
+
 #[repr(transparent)]
 #[derive(Clone, ComWrapper)]
 #[com(send, sync, debug)]
@@ -19,7 +31,15 @@ pub struct RenderingParams {
     ptr: ComPtr<IDWriteRenderingParams>,
 }
 
+raw_interop!(RenderingParams, IDWriteRenderingParams);
+
 impl RenderingParams {
+    /// Initializes a builder for rendering parameters with custom values, for when the
+    /// monitor defaults aren't appropriate, e.g. to force grayscale antialiasing.
+    pub fn create(factory: &dyn IFactory) -> RenderingParamsBuilder {
+        unsafe { RenderingParamsBuilder::new(factory.raw_f()) }
+    }
+
     /// Get the rendering parameters appropriate for rendering on the given monitor.
     ///
     /// <div style="padding: 10px 10px 2px 10px; margin: 10px; background-color: #F2F2F2">
@@ -85,6 +105,29 @@ pub unsafe trait IRenderingParams {
         unsafe { self.raw_rp().GetEnhancedContrast() }
     }
 
+    /// Gets the enhanced contrast used when text is drawn with grayscale antialiasing. For
+    /// parameters created without a separate grayscale value, or on systems older than
+    /// Windows 8, this is the same as `enhanced_contrast`.
+    fn enhanced_contrast_grayscale(&self) -> f32 {
+        unsafe {
+            match query_interface::<IDWriteRenderingParams1>(self.raw_rp()) {
+                Ok(params) => params.GetGrayscaleEnhancedContrast(),
+                Err(_) => self.enhanced_contrast(),
+            }
+        }
+    }
+
+    /// Gets whether glyph outlines are grid fitted. On systems older than Windows 8.1 this
+    /// is always `Default`, meaning grid fitting is decided by the font.
+    fn grid_fit_mode(&self) -> UncheckedEnum<GridFitMode> {
+        unsafe {
+            match query_interface::<IDWriteRenderingParams2>(self.raw_rp()) {
+                Ok(params) => params.GetGridFitMode().into(),
+                Err(_) => (GridFitMode::Default as u32).into(),
+            }
+        }
+    }
+
     /// Gets the gamma value used for gamma correction. Valid values must be greater than zero
     /// and cannot exceed 256.
     ///
@@ -112,6 +155,18 @@ pub unsafe trait IRenderingParams {
         unsafe { self.raw_rp().GetRenderingMode().into() }
     }
 
+    /// Gets the rendering mode including the values only `RenderingMode1` can express, such as
+    /// `NaturalSymmetricDownsampled`. On systems older than Windows 10 this is the same as
+    /// `rendering_mode`.
+    fn rendering_mode1(&self) -> UncheckedEnum<RenderingMode1> {
+        unsafe {
+            match query_interface::<IDWriteRenderingParams3>(self.raw_rp()) {
+                Ok(params) => params.GetRenderingMode1().into(),
+                Err(_) => self.raw_rp().GetRenderingMode().into(),
+            }
+        }
+    }
+
     unsafe fn raw_rp(&self) -> &IDWriteRenderingParams;
 }
 
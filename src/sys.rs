@@ -0,0 +1,200 @@
+//! Declarations for DirectWrite interfaces which are newer than the bindings in winapi.
+
+#![allow(dead_code, non_snake_case)]
+
+use winapi::ctypes::c_void;
+use winapi::shared::basetsd::UINT32;
+use winapi::shared::minwindef::{BOOL, FLOAT};
+use winapi::um::dcommon::{D2D1_POINT_2F, DWRITE_GLYPH_IMAGE_FORMATS, DWRITE_MEASURING_MODE};
+use winapi::um::dwrite::DWRITE_TEXT_RANGE;
+use winapi::um::dwrite::{
+    IDWriteFactory, IDWriteFontFile, IDWriteFontFileLoader, IDWriteFontFileLoaderVtbl,
+    IDWriteFontFileStream, DWRITE_GLYPH_RUN, DWRITE_GLYPH_RUN_DESCRIPTION, DWRITE_MATRIX,
+};
+use winapi::um::dwrite_3::{
+    IDWriteFactory3, IDWriteFactory3Vtbl, IDWriteFontSet, IDWriteFontSetVtbl, IDWriteTextLayout3,
+    IDWriteTextLayout3Vtbl, DWRITE_FONT_PROPERTY,
+};
+use winapi::um::unknwnbase::IUnknown;
+use winapi::um::winnt::{HRESULT, WCHAR};
+use winapi::RIDL;
+
+RIDL! {#[uuid(0x4b0b5bd3, 0x0797, 0x4549, 0x8a, 0xc5, 0xfe, 0x91, 0x5c, 0xc5, 0x38, 0x56)]
+interface IDWriteFactory4(IDWriteFactory4Vtbl): IDWriteFactory3(IDWriteFactory3Vtbl) {
+    fn TranslateColorGlyphRun(
+        baselineOrigin: D2D1_POINT_2F,
+        glyphRun: *const DWRITE_GLYPH_RUN,
+        glyphRunDescription: *const DWRITE_GLYPH_RUN_DESCRIPTION,
+        desiredGlyphImageFormats: DWRITE_GLYPH_IMAGE_FORMATS,
+        measuringMode: DWRITE_MEASURING_MODE,
+        worldAndDpiTransform: *const DWRITE_MATRIX,
+        colorPaletteIndex: UINT32,
+        colorLayers: *mut *mut IUnknown,
+    ) -> HRESULT,
+    fn ComputeGlyphOrigins_2(
+        glyphRun: *const DWRITE_GLYPH_RUN,
+        measuringMode: DWRITE_MEASURING_MODE,
+        baselineOrigin: D2D1_POINT_2F,
+        worldAndDpiTransform: *const DWRITE_MATRIX,
+        glyphOrigins: *mut D2D1_POINT_2F,
+    ) -> HRESULT,
+    fn ComputeGlyphOrigins_1(
+        glyphRun: *const DWRITE_GLYPH_RUN,
+        baselineOrigin: D2D1_POINT_2F,
+        glyphOrigins: *mut D2D1_POINT_2F,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x958db99a, 0xbe2a, 0x4f09, 0xaf, 0x7d, 0x65, 0x18, 0x98, 0x03, 0xd1, 0xd3)]
+interface IDWriteFactory5(IDWriteFactory5Vtbl): IDWriteFactory4(IDWriteFactory4Vtbl) {
+    fn CreateFontSetBuilder(
+        fontSetBuilder: *mut *mut IUnknown,
+    ) -> HRESULT,
+    fn CreateInMemoryFontFileLoader(
+        newLoader: *mut *mut IDWriteInMemoryFontFileLoader,
+    ) -> HRESULT,
+    fn CreateHttpFontFileLoader(
+        referrerUrl: *const WCHAR,
+        extraHeaders: *const WCHAR,
+        newLoader: *mut *mut IUnknown,
+    ) -> HRESULT,
+    fn AnalyzeContainerType(
+        fileData: *const c_void,
+        fileDataSize: UINT32,
+    ) -> UINT32,
+    fn UnpackFontFile(
+        containerType: UINT32,
+        fileData: *const c_void,
+        fileDataSize: UINT32,
+        unpackedFontStream: *mut *mut IDWriteFontFileStream,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0xdc102f47, 0xa12d, 0x4b1c, 0x82, 0x2d, 0x9e, 0x11, 0x7e, 0x33, 0x04, 0x3f)]
+interface IDWriteInMemoryFontFileLoader(IDWriteInMemoryFontFileLoaderVtbl):
+    IDWriteFontFileLoader(IDWriteFontFileLoaderVtbl) {
+    fn CreateInMemoryFontFileReference(
+        factory: *mut IDWriteFactory,
+        fontData: *const c_void,
+        fontDataSize: UINT32,
+        ownerObject: *mut IUnknown,
+        fontFile: *mut *mut IDWriteFontFile,
+    ) -> HRESULT,
+    fn GetFileCount() -> UINT32,
+}}
+
+pub type DWRITE_FONT_AXIS_TAG = UINT32;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct DWRITE_FONT_AXIS_VALUE {
+    pub axisTag: DWRITE_FONT_AXIS_TAG,
+    pub value: FLOAT,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct DWRITE_FONT_AXIS_RANGE {
+    pub axisTag: DWRITE_FONT_AXIS_TAG,
+    pub minValue: FLOAT,
+    pub maxValue: FLOAT,
+}
+
+RIDL! {#[uuid(0x7e9fda85, 0x6c92, 0x4053, 0xbc, 0x47, 0x7a, 0xe3, 0x53, 0x0d, 0xb4, 0xd3)]
+interface IDWriteFontSet1(IDWriteFontSet1Vtbl): IDWriteFontSet(IDWriteFontSetVtbl) {
+    fn GetMatchingFonts(
+        fontProperty: *const DWRITE_FONT_PROPERTY,
+        fontAxisValues: *const DWRITE_FONT_AXIS_VALUE,
+        fontAxisValueCount: UINT32,
+        matchingFonts: *mut *mut IDWriteFontSet1,
+    ) -> HRESULT,
+    fn GetFirstFontResources(
+        filteredFontSet: *mut *mut IDWriteFontSet1,
+    ) -> HRESULT,
+    fn GetFilteredFonts_3(
+        properties: *const DWRITE_FONT_PROPERTY,
+        propertyCount: UINT32,
+        selectAnyProperty: BOOL,
+        filteredFontSet: *mut *mut IDWriteFontSet1,
+    ) -> HRESULT,
+    fn GetFilteredFonts_2(
+        fontAxisRanges: *const DWRITE_FONT_AXIS_RANGE,
+        fontAxisRangeCount: UINT32,
+        selectAnyRange: BOOL,
+        filteredFontSet: *mut *mut IDWriteFontSet1,
+    ) -> HRESULT,
+    fn GetFilteredFonts_1(
+        indices: *const UINT32,
+        indexCount: UINT32,
+        filteredFontSet: *mut *mut IDWriteFontSet1,
+    ) -> HRESULT,
+    fn GetFilteredFontIndices_2(
+        properties: *const DWRITE_FONT_PROPERTY,
+        propertyCount: UINT32,
+        selectAnyProperty: BOOL,
+        indices: *mut UINT32,
+        maxIndexCount: UINT32,
+        actualIndexCount: *mut UINT32,
+    ) -> HRESULT,
+    fn GetFilteredFontIndices_1(
+        fontAxisRanges: *const DWRITE_FONT_AXIS_RANGE,
+        fontAxisRangeCount: UINT32,
+        selectAnyRange: BOOL,
+        indices: *mut UINT32,
+        maxIndexCount: UINT32,
+        actualIndexCount: *mut UINT32,
+    ) -> HRESULT,
+    fn GetFontAxisRanges_2(
+        fontAxisRanges: *mut DWRITE_FONT_AXIS_RANGE,
+        maxFontAxisRangeCount: UINT32,
+        actualFontAxisRangeCount: *mut UINT32,
+    ) -> HRESULT,
+    fn GetFontAxisRanges_1(
+        listIndex: UINT32,
+        fontAxisRanges: *mut DWRITE_FONT_AXIS_RANGE,
+        maxFontAxisRangeCount: UINT32,
+        actualFontAxisRangeCount: *mut UINT32,
+    ) -> HRESULT,
+    fn GetFontFaceReference(
+        listIndex: UINT32,
+        fontFaceReference: *mut *mut IUnknown,
+    ) -> HRESULT,
+    fn CreateFontResource(
+        listIndex: UINT32,
+        fontResource: *mut *mut IUnknown,
+    ) -> HRESULT,
+    fn CreateFontFace(
+        listIndex: UINT32,
+        fontFace: *mut *mut IUnknown,
+    ) -> HRESULT,
+    fn GetFontLocality(
+        listIndex: UINT32,
+    ) -> UINT32,
+}}
+
+pub type DWRITE_AUTOMATIC_FONT_AXES = UINT32;
+pub const DWRITE_AUTOMATIC_FONT_AXES_NONE: DWRITE_AUTOMATIC_FONT_AXES = 0;
+pub const DWRITE_AUTOMATIC_FONT_AXES_OPTICAL_SIZE: DWRITE_AUTOMATIC_FONT_AXES = 1;
+
+RIDL! {#[uuid(0x05a9bf42, 0x223f, 0x4441, 0xb5, 0xfb, 0x82, 0x63, 0x68, 0x5f, 0x55, 0xe9)]
+interface IDWriteTextLayout4(IDWriteTextLayout4Vtbl):
+    IDWriteTextLayout3(IDWriteTextLayout3Vtbl) {
+    fn SetFontAxisValues(
+        fontAxisValues: *const DWRITE_FONT_AXIS_VALUE,
+        fontAxisValueCount: UINT32,
+        textRange: DWRITE_TEXT_RANGE,
+    ) -> HRESULT,
+    fn GetFontAxisValueCount(
+        currentPosition: UINT32,
+    ) -> UINT32,
+    fn GetFontAxisValues(
+        currentPosition: UINT32,
+        fontAxisValues: *mut DWRITE_FONT_AXIS_VALUE,
+        fontAxisValueCount: UINT32,
+        textRange: *mut DWRITE_TEXT_RANGE,
+    ) -> HRESULT,
+    fn GetAutomaticFontAxes() -> DWRITE_AUTOMATIC_FONT_AXES,
+    fn SetAutomaticFontAxes(
+        automaticFontAxes: DWRITE_AUTOMATIC_FONT_AXES,
+    ) -> HRESULT,
+}}
@@ -0,0 +1,532 @@
+//! Fixtures for testing custom font file loaders. Only available with the `test-support`
+//! feature.
+//!
+//! [`MockStream`](struct.MockStream.html) wraps the streams of a loader and keeps a
+//! [`FragmentLog`](struct.FragmentLog.html) of every fragment DirectWrite reads and releases,
+//! [`FaultyStream`](struct.FaultyStream.html) simulates a read error part way through a file,
+//! and [`assert_loader_roundtrip`](fn.assert_loader_roundtrip.html) checks that DirectWrite can
+//! load a font through a loader from start to finish.
+//! [`failing_collection`](fn.failing_collection.html) makes one family of a collection
+//! impossible to fetch, for checking how the collectors on `FontCollection` handle it.
+//!
+//! Stream methods are called from inside DirectWrite, where a panic would abort the process,
+//! so the streams only record problems. The `FragmentLog` kept by the test reports them
+//! instead, and panics if it is dropped while any are left unchecked.
+
+use crate::factory::Factory;
+use crate::font_collection::{FontCollection, IFontCollection};
+use crate::font_face::{FontFace, FontFaceBuildError, IFontFace};
+use crate::font_file::loader::{FontFileLoader, FontFileStream, Fragment};
+use crate::font_file::{Analysis, FontFile, IFontFile};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use com_impl::{Refcount, VTable};
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use winapi::shared::minwindef::{BOOL, UINT32};
+use winapi::shared::ntdef::WCHAR;
+use winapi::shared::winerror::{DWRITE_E_FILEFORMAT, E_FAIL, HRESULT};
+use winapi::um::dwrite::{
+    IDWriteFont, IDWriteFontCollection, IDWriteFontCollectionVtbl, IDWriteFontFace,
+    IDWriteFontFamily,
+};
+
+/// The shared record of the fragments read from one or more `MockStream`s. Clones share the
+/// same record.
+pub struct FragmentLog {
+    state: Arc<Mutex<LogState>>,
+}
+
+#[derive(Default)]
+struct LogState {
+    // FragmentLog handles, which unlike the streams are dropped by the test itself
+    handles: usize,
+    next_stream: usize,
+    next_key: usize,
+    reads: Vec<(u64, u64)>,
+    // Our key -> (stream, key of the wrapped stream, offset, length)
+    outstanding: HashMap<usize, (usize, usize, u64, u64)>,
+    problems: Vec<String>,
+}
+
+impl FragmentLog {
+    /// Creates an empty log.
+    pub fn new() -> FragmentLog {
+        let state = LogState {
+            handles: 1,
+            ..Default::default()
+        };
+        FragmentLog {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Wraps `stream` so that its fragments are recorded in this log.
+    pub fn wrap<S: FontFileStream>(&self, stream: S) -> MockStream<S> {
+        wrap(&self.state, stream)
+    }
+
+    /// The `(offset, length)` of every fragment read so far, in the order they were read.
+    pub fn reads(&self) -> Vec<(u64, u64)> {
+        self.lock().reads.clone()
+    }
+
+    /// The number of fragments which have been read but not released yet.
+    pub fn outstanding(&self) -> usize {
+        self.lock().outstanding.len()
+    }
+
+    /// Takes the problems recorded so far, such as releasing a fragment twice or dropping a
+    /// stream with fragments still outstanding.
+    pub fn take_problems(&self) -> Vec<String> {
+        std::mem::replace(&mut self.lock().problems, Vec::new())
+    }
+
+    /// Panics if any fragment is outstanding or any problem was recorded.
+    pub fn assert_balanced(&self) {
+        let problems = self.take_problems();
+        let outstanding = self.outstanding();
+        assert!(
+            problems.is_empty() && outstanding == 0,
+            "unbalanced font file stream: {} fragment(s) outstanding, problems: {:?}",
+            outstanding,
+            problems
+        );
+    }
+
+    fn lock(&self) -> MutexGuard<LogState> {
+        lock(&self.state)
+    }
+}
+
+fn lock(state: &Mutex<LogState>) -> MutexGuard<LogState> {
+    // A panicking test shouldn't hide the log from the other threads
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn wrap<S>(state: &Arc<Mutex<LogState>>, stream: S) -> MockStream<S> {
+    let mut locked = lock(state);
+    let id = locked.next_stream;
+    locked.next_stream += 1;
+    MockStream {
+        inner: stream,
+        id,
+        state: state.clone(),
+    }
+}
+
+fn new_handle(state: &Arc<Mutex<LogState>>) -> FragmentLog {
+    lock(state).handles += 1;
+    FragmentLog {
+        state: state.clone(),
+    }
+}
+
+impl Default for FragmentLog {
+    fn default() -> Self {
+        FragmentLog::new()
+    }
+}
+
+impl Clone for FragmentLog {
+    fn clone(&self) -> Self {
+        new_handle(&self.state)
+    }
+}
+
+impl Drop for FragmentLog {
+    fn drop(&mut self) {
+        let mut state = self.lock();
+        state.handles -= 1;
+        // Only the last handle checks, and not while already unwinding
+        if state.handles > 0 || std::thread::panicking() {
+            return;
+        }
+        let problems = std::mem::replace(&mut state.problems, Vec::new());
+        drop(state);
+        assert!(
+            problems.is_empty(),
+            "font file stream problems were never checked: {:?}",
+            problems
+        );
+    }
+}
+
+impl fmt::Debug for FragmentLog {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.lock();
+        fmt.debug_struct("FragmentLog")
+            .field("reads", &state.reads.len())
+            .field("outstanding", &state.outstanding.len())
+            .field("problems", &state.problems)
+            .finish()
+    }
+}
+
+/// A stream which forwards to another one and records every `read_fragment` and
+/// `release_fragment` in a [`FragmentLog`](struct.FragmentLog.html). Each fragment gets a key
+/// of its own, so releasing one twice or releasing one that was never read is caught even if
+/// the wrapped stream uses the same key for everything.
+///
+/// Dropping the stream with fragments outstanding is recorded as a problem in the log.
+pub struct MockStream<S> {
+    inner: S,
+    id: usize,
+    // Not a FragmentLog, as streams are dropped inside DirectWrite where they mustn't panic
+    state: Arc<Mutex<LogState>>,
+}
+
+impl<S: FontFileStream> FontFileStream for MockStream<S> {
+    fn file_size(&self) -> u64 {
+        self.inner.file_size()
+    }
+
+    fn last_write_time(&self) -> u64 {
+        self.inner.last_write_time()
+    }
+
+    fn read_fragment(&self, offset: u64, length: u64) -> Result<Fragment, Error> {
+        if offset
+            .checked_add(length)
+            .map_or(true, |end| end > self.file_size())
+        {
+            lock(&self.state).problems.push(format!(
+                "read of {} bytes at {} past the end of the file",
+                length, offset
+            ));
+        }
+
+        let fragment = self.inner.read_fragment(offset, length)?;
+        let mut state = lock(&self.state);
+        let key = state.next_key;
+        state.next_key += 1;
+        state.reads.push((offset, length));
+        state
+            .outstanding
+            .insert(key, (self.id, fragment.key, offset, length));
+        unsafe { Ok(Fragment::new(key, fragment.data)) }
+    }
+
+    fn release_fragment(&self, key: usize) {
+        let mut state = lock(&self.state);
+        let released = match state.outstanding.get(&key) {
+            Some(&(stream, inner_key, _, _)) if stream == self.id => {
+                state.outstanding.remove(&key);
+                Some(inner_key)
+            }
+            _ => None,
+        };
+        drop(state);
+        match released {
+            Some(inner_key) => self.inner.release_fragment(inner_key),
+            None => lock(&self.state)
+                .problems
+                .push(format!("released fragment {} which isn't outstanding", key)),
+        }
+    }
+}
+
+impl<S> Drop for MockStream<S> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.state);
+        let id = self.id;
+        let mut leaked = Vec::new();
+        state
+            .outstanding
+            .retain(|_, &mut (stream, _, offset, length)| {
+                if stream == id {
+                    leaked.push((offset, length));
+                }
+                stream != id
+            });
+        if !leaked.is_empty() {
+            leaked.sort();
+            let problem = format!("stream dropped with fragments outstanding: {:?}", leaked);
+            state.problems.push(problem);
+        }
+    }
+}
+
+/// A loader which wraps another one and hands out its streams as `MockStream`s, all
+/// recording into the same log.
+pub struct MockLoader<L> {
+    inner: L,
+    // Like MockStream, this is usually dropped by DirectWrite
+    state: Arc<Mutex<LogState>>,
+}
+
+impl<L: FontFileLoader> MockLoader<L> {
+    /// Wraps `loader` with a new log.
+    pub fn new(loader: L) -> MockLoader<L> {
+        MockLoader {
+            inner: loader,
+            state: FragmentLog::new().state.clone(),
+        }
+    }
+
+    /// A handle to the log the streams record into. Take it before registering the loader.
+    pub fn log(&self) -> FragmentLog {
+        new_handle(&self.state)
+    }
+}
+
+impl<L: FontFileLoader> FontFileLoader for MockLoader<L> {
+    type Key = L::Key;
+    type Stream = MockStream<L::Stream>;
+
+    fn create_stream(&self, key: &L::Key) -> Result<MockStream<L::Stream>, Error> {
+        self.inner.create_stream(key).map(|s| wrap(&self.state, s))
+    }
+}
+
+/// A stream which forwards to another one, but fails every read which reaches `fail_at` or
+/// beyond, like a file which can't be read completely.
+pub struct FaultyStream<S> {
+    inner: S,
+    fail_at: u64,
+    error: Error,
+}
+
+impl<S: FontFileStream> FaultyStream<S> {
+    /// Fails reads reaching `fail_at` with `E_FAIL`.
+    pub fn new(inner: S, fail_at: u64) -> FaultyStream<S> {
+        FaultyStream {
+            inner,
+            fail_at,
+            error: E_FAIL.into(),
+        }
+    }
+
+    /// Sets the error the failing reads return.
+    pub fn with_error(mut self, error: Error) -> Self {
+        self.error = error;
+        self
+    }
+}
+
+impl<S: FontFileStream> FontFileStream for FaultyStream<S> {
+    fn file_size(&self) -> u64 {
+        self.inner.file_size()
+    }
+
+    fn last_write_time(&self) -> u64 {
+        self.inner.last_write_time()
+    }
+
+    fn read_fragment(&self, offset: u64, length: u64) -> Result<Fragment, Error> {
+        if offset.saturating_add(length) > self.fail_at {
+            return Err(self.error.0.into());
+        }
+        self.inner.read_fragment(offset, length)
+    }
+
+    fn release_fragment(&self, key: usize) {
+        self.inner.release_fragment(key)
+    }
+}
+
+/// Wraps `collection` in one which fails to fetch the family at `failing_index` with `error`,
+/// like a collection holding a font which can't be read. Everything else is forwarded.
+pub fn failing_collection(
+    collection: &FontCollection,
+    failing_index: u32,
+    error: Error,
+) -> FontCollection {
+    let ptr = FailingCollection::create_raw(collection.clone(), failing_index, error.0);
+    unsafe { FontCollection::from_raw(ptr as *mut IDWriteFontCollection) }
+}
+
+#[repr(C)]
+#[derive(com_impl::ComImpl)]
+#[interfaces(IDWriteFontCollection)]
+struct FailingCollection {
+    vtable: VTable<IDWriteFontCollectionVtbl>,
+    refcount: Refcount,
+    // Holds on to the loader of a custom collection as well
+    inner: FontCollection,
+    failing_index: u32,
+    error: HRESULT,
+}
+
+#[com_impl::com_impl]
+unsafe impl IDWriteFontCollection for FailingCollection {
+    pub unsafe extern "system" fn get_font_family_count(&self) -> UINT32 {
+        self.inner.raw_fontcol().GetFontFamilyCount()
+    }
+
+    pub unsafe extern "system" fn get_font_family(
+        &self,
+        index: UINT32,
+        font_family: *mut *mut IDWriteFontFamily,
+    ) -> HRESULT {
+        if index == self.failing_index {
+            return self.error;
+        }
+        self.inner.raw_fontcol().GetFontFamily(index, font_family)
+    }
+
+    pub unsafe extern "system" fn find_family_name(
+        &self,
+        family_name: *const WCHAR,
+        index: *mut UINT32,
+        exists: *mut BOOL,
+    ) -> HRESULT {
+        self.inner
+            .raw_fontcol()
+            .FindFamilyName(family_name, index, exists)
+    }
+
+    pub unsafe extern "system" fn get_font_from_font_face(
+        &self,
+        font_face: *mut IDWriteFontFace,
+        font: *mut *mut IDWriteFont,
+    ) -> HRESULT {
+        self.inner
+            .raw_fontcol()
+            .GetFontFromFontFace(font_face, font)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The step of a loader roundtrip which failed.
+pub enum RoundtripStep {
+    /// Creating the isolated factory.
+    CreateFactory,
+    /// Registering the loader.
+    Register,
+    /// Creating the font file from the key.
+    BuildFile,
+    /// Analyzing the font file.
+    Analyze,
+    /// Creating a font face from the file.
+    CreateFace,
+    /// Unregistering the loader once everything was dropped.
+    Unregister,
+}
+
+impl fmt::Display for RoundtripStep {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            RoundtripStep::CreateFactory => "creating an isolated factory",
+            RoundtripStep::Register => "registering the loader",
+            RoundtripStep::BuildFile => "creating the font file",
+            RoundtripStep::Analyze => "analyzing the font file",
+            RoundtripStep::CreateFace => "creating the font face",
+            RoundtripStep::Unregister => "unregistering the loader",
+        })
+    }
+}
+
+#[derive(Debug)]
+/// Why a loader roundtrip failed.
+pub struct RoundtripError {
+    /// The step which failed.
+    pub step: RoundtripStep,
+
+    /// The error the step failed with.
+    pub error: Error,
+
+    /// Further details, e.g. what analysis found when creating the face failed.
+    pub details: String,
+}
+
+impl fmt::Display for RoundtripError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "loader roundtrip failed {}: HRESULT 0x{:08X}",
+            self.step, self.error.0 as u32
+        )?;
+        if !self.details.is_empty() {
+            write!(fmt, " ({})", self.details)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RoundtripError {}
+
+/// What a successful loader roundtrip found out about the file.
+pub struct RoundtripReport {
+    /// The analysis of the font file.
+    pub analysis: Analysis,
+
+    /// The number of glyphs of the first face in the file.
+    pub glyph_count: u16,
+}
+
+/// Like [`assert_loader_roundtrip`](fn.assert_loader_roundtrip.html), but returns which step
+/// failed instead of panicking.
+pub fn check_loader_roundtrip<L>(loader: L, key: &L::Key) -> Result<RoundtripReport, RoundtripError>
+where
+    L: FontFileLoader,
+{
+    let fail = |step, error: Error, details: String| RoundtripError {
+        step,
+        error,
+        details,
+    };
+
+    let factory = Factory::new_isolated()
+        .map_err(|e| fail(RoundtripStep::CreateFactory, e, String::new()))?;
+    let handle = loader
+        .register(&factory)
+        .map_err(|e| fail(RoundtripStep::Register, e, String::new()))?;
+
+    let report = {
+        let file = FontFile::create(&factory)
+            .with_loader(&handle)
+            .with_key(key)
+            .build()
+            .map_err(|e| fail(RoundtripStep::BuildFile, e, String::new()))?;
+        let analysis = file
+            .analyze()
+            .map_err(|e| fail(RoundtripStep::Analyze, e, String::new()))?;
+        if !analysis.supported {
+            let details = format!("file type {} is not supported", analysis.file_type.value);
+            return Err(fail(
+                RoundtripStep::Analyze,
+                DWRITE_E_FILEFORMAT.into(),
+                details,
+            ));
+        }
+
+        let files = [file];
+        let face = FontFace::create(&factory)
+            .with_files(&files)
+            .infer_face_type()
+            .with_face_index(0)
+            .build_detailed()
+            .map_err(|e: FontFaceBuildError| {
+                let details = e.to_string();
+                fail(RoundtripStep::CreateFace, e.into(), details)
+            })?;
+
+        RoundtripReport {
+            glyph_count: face.glyph_count(),
+            analysis,
+        }
+    };
+
+    // The file and face were dropped above, so nothing keeps the loader busy any more
+    handle
+        .unregister()
+        .map_err(|e| fail(RoundtripStep::Unregister, e, String::new()))?;
+    Ok(report)
+}
+
+/// Registers `loader` on a fresh isolated factory, creates a font file for `key`, analyzes
+/// it, creates a face from it and unregisters the loader again, panicking with the step that
+/// failed and why if any of this goes wrong.
+pub fn assert_loader_roundtrip<L>(loader: L, key: &L::Key) -> RoundtripReport
+where
+    L: FontFileLoader,
+{
+    match check_loader_roundtrip(loader, key) {
+        Ok(report) => report,
+        Err(e) => panic!("{}", e),
+    }
+}
@@ -0,0 +1,456 @@
+use crate::descriptions::{GlyphOffset, LineBreakpoint, ScriptAnalysis, TextRange};
+use crate::enums::ScriptShapes;
+use crate::font_face::FontFace;
+use crate::helpers::query_interface;
+use crate::number_substitution::NumberSubstitution;
+use crate::text_analysis::itemization::{Itemization, ItemizedRun};
+use crate::text_analysis::shaping::{
+    GlyphPlacements, RawFeatureRanges, ShapedGlyphs, ShapingInput,
+};
+use crate::text_analysis::sink::{AnalysisResults, CollectingAnalysisSink};
+use crate::text_analysis::source::TextAnalysisSource;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use winapi::shared::winerror::{
+    ERROR_INSUFFICIENT_BUFFER, E_INVALIDARG, HRESULT_FROM_WIN32, SUCCEEDED,
+};
+use winapi::um::dwrite::{
+    IDWriteTextAnalyzer, DWRITE_GLYPH_OFFSET, DWRITE_SHAPING_GLYPH_PROPERTIES,
+    DWRITE_SHAPING_TEXT_PROPERTIES,
+};
+use winapi::um::dwrite_1::IDWriteTextAnalyzer1;
+use wio::com::ComPtr;
+use wio::wide::ToWide;
+
+#[repr(transparent)]
+#[derive(Clone, ComWrapper)]
+#[com(send, sync, debug)]
+/// Analyzes text for script boundaries, bidirectional levels and number substitution.
+/// Obtained from [`Factory::create_text_analyzer`][1].
+///
+/// [1]: ../struct.Factory.html#method.create_text_analyzer
+pub struct TextAnalyzer {
+    ptr: ComPtr<IDWriteTextAnalyzer>,
+}
+
+impl TextAnalyzer {
+    /// Splits `range` of the source text into runs which share the same script, resolved
+    /// bidi level and number substitution. The runs cover the whole range, and adjacent runs
+    /// always differ in at least one of those properties.
+    pub fn itemize(
+        &self,
+        source: &TextAnalysisSource,
+        range: TextRange,
+    ) -> Result<Itemization, Error> {
+        let passes = [Pass::Script, Pass::Bidi, Pass::NumberSubstitution];
+        let results = self.collect(source, range, &passes)?;
+        Ok(Itemization::from_runs(merge_results(range, results)))
+    }
+
+    /// Finds the runs of text in a single writing system script, as used to pick shaping
+    /// rules. Runs are returned in text order and cover the whole range.
+    pub fn analyze_script(
+        &self,
+        source: &TextAnalysisSource,
+        text_start: u32,
+        text_length: u32,
+    ) -> Result<Vec<ScriptRun>, Error> {
+        let range = TextRange {
+            start: text_start,
+            length: text_length,
+        };
+        let mut results = self.collect(source, range, &[Pass::Script])?;
+        results.scripts.sort_by_key(|s| s.0.start);
+
+        let runs = results
+            .scripts
+            .into_iter()
+            .map(|(range, script_analysis)| ScriptRun {
+                start: range.start,
+                length: range.length,
+                script_analysis,
+            });
+        Ok(runs.collect())
+    }
+
+    /// Looks up the ISO 15924 number of the script a run was analyzed as, e.g. 215 for Latin
+    /// or 500 for Han. Requires Windows 8; on older versions this fails with `E_NOINTERFACE`.
+    pub fn iso_script_number(&self, script_analysis: ScriptAnalysis) -> Result<u32, Error> {
+        unsafe {
+            let analyzer = query_interface::<IDWriteTextAnalyzer1>(&self.ptr)?;
+            let mut props = std::mem::zeroed();
+            let hr = analyzer.GetScriptProperties(script_analysis.into(), &mut props);
+            if SUCCEEDED(hr) {
+                Ok(props.isoScriptNumber)
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Checks whether all of `text` is simple in `font_face`, i.e. each character maps to a
+    /// single glyph and no script in it needs complex shaping. Requires Windows 8; on older
+    /// versions this fails with `E_NOINTERFACE`.
+    pub fn is_text_simple(&self, text: &[u16], font_face: &FontFace) -> Result<bool, Error> {
+        unsafe {
+            let analyzer = query_interface::<IDWriteTextAnalyzer1>(&self.ptr)?;
+            let mut is_simple = 0;
+            let mut length_read = 0;
+            let mut glyphs = vec![0u16; text.len()];
+            let hr = analyzer.GetTextComplexity(
+                text.as_ptr(),
+                text.len() as u32,
+                font_face.get_raw(),
+                &mut is_simple,
+                &mut length_read,
+                glyphs.as_mut_ptr(),
+            );
+            if SUCCEEDED(hr) {
+                Ok(is_simple != 0 && length_read as usize >= text.len())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Resolves the bidirectional embedding levels of the text according to the Unicode
+    /// bidi algorithm. Odd levels are right-to-left. Runs are returned in text order.
+    pub fn analyze_bidi(
+        &self,
+        source: &TextAnalysisSource,
+        start: u32,
+        length: u32,
+    ) -> Result<Vec<BidiRun>, Error> {
+        let range = TextRange { start, length };
+        let mut results = self.collect(source, range, &[Pass::Bidi])?;
+        results.bidi_levels.sort_by_key(|b| b.0.start);
+
+        let runs =
+            results
+                .bidi_levels
+                .into_iter()
+                .map(|(range, explicit_level, resolved_level)| BidiRun {
+                    start: range.start,
+                    length: range.length,
+                    explicit_level,
+                    resolved_level,
+                });
+        Ok(runs.collect())
+    }
+
+    /// Finds the line breaking properties of the text according to the Unicode line breaking
+    /// algorithm, for layout engines which do their own wrapping. The result has exactly one
+    /// entry per UTF-16 code unit of the range, so the entry for text position `p` is at index
+    /// `p - start`.
+    pub fn analyze_line_breaks(
+        &self,
+        source: &TextAnalysisSource,
+        start: u32,
+        length: u32,
+    ) -> Result<Vec<LineBreakpoint>, Error> {
+        let range = TextRange { start, length };
+        let results = self.collect(source, range, &[Pass::LineBreaks])?;
+
+        let mut breakpoints = vec![LineBreakpoint::default(); length as usize];
+        for (pos, reported) in results.line_breakpoints {
+            let offset = pos.saturating_sub(start) as usize;
+            let skip = start.saturating_sub(pos) as usize;
+            for (dst, src) in breakpoints
+                .iter_mut()
+                .skip(offset)
+                .zip(reported.into_iter().skip(skip))
+            {
+                *dst = src;
+            }
+        }
+        Ok(breakpoints)
+    }
+
+    /// Shapes a run of text, converting its characters into the glyphs of the font face which
+    /// display them. This is where ligatures, contextual forms and other OpenType substitutions
+    /// are applied. Pass the result to `get_glyph_placements` to position the glyphs.
+    pub fn get_glyphs(&self, run: &ShapingInput) -> Result<ShapedGlyphs, Error> {
+        let text_len = run.text.len();
+        let locale = run.locale.map(|l| l.to_wide_null());
+        let mut features = RawFeatureRanges::new(run.features);
+        let script_analysis = run.script_analysis.into();
+
+        let mut cluster_map = vec![0u16; text_len];
+        let mut text_props = vec![DWRITE_SHAPING_TEXT_PROPERTIES { bit_fields: 0 }; text_len];
+
+        // The estimate recommended by the DirectWrite documentation, grown whenever the
+        // shaper reports that it needs more room.
+        let mut max_glyphs = 3 * text_len / 2 + 16;
+        loop {
+            let mut glyph_indices = vec![0u16; max_glyphs];
+            let mut glyph_props =
+                vec![DWRITE_SHAPING_GLYPH_PROPERTIES { bit_fields: 0 }; max_glyphs];
+            let mut glyph_count = 0;
+
+            let hr = unsafe {
+                self.ptr.GetGlyphs(
+                    run.text.as_ptr(),
+                    text_len as u32,
+                    run.font_face.get_raw(),
+                    run.is_sideways as i32,
+                    run.is_right_to_left as i32,
+                    &script_analysis,
+                    locale
+                        .as_ref()
+                        .map(|l| l.as_ptr())
+                        .unwrap_or(std::ptr::null()),
+                    run.number_substitution
+                        .map(|n| n.get_raw())
+                        .unwrap_or(std::ptr::null_mut()),
+                    features.features(),
+                    features.lengths(),
+                    features.count(),
+                    max_glyphs as u32,
+                    cluster_map.as_mut_ptr(),
+                    text_props.as_mut_ptr(),
+                    glyph_indices.as_mut_ptr(),
+                    glyph_props.as_mut_ptr(),
+                    &mut glyph_count,
+                )
+            };
+
+            if hr == HRESULT_FROM_WIN32(ERROR_INSUFFICIENT_BUFFER) {
+                max_glyphs *= 2;
+                continue;
+            }
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+
+            glyph_indices.truncate(glyph_count as usize);
+            glyph_props.truncate(glyph_count as usize);
+            return Ok(ShapedGlyphs {
+                glyph_indices,
+                cluster_map,
+                text_props: text_props.into_iter().map(Into::into).collect(),
+                glyph_props: glyph_props.into_iter().map(Into::into).collect(),
+            });
+        }
+    }
+
+    /// Positions the glyphs produced by `get_glyphs` for the same run, applying kerning, mark
+    /// attachment and other OpenType positioning features. `run` must be the input the glyphs
+    /// were shaped from, and `font_em_size` is the size the glyphs will be drawn at in DIPs.
+    /// Together with the glyph indices, the result forms a `GlyphRun` which can be drawn.
+    ///
+    /// Fails with `E_INVALIDARG` if the lengths of `shaped` don't match each other and the
+    /// text, or if its cluster map refers to a glyph past the end of `glyph_indices`.
+    pub fn get_glyph_placements(
+        &self,
+        run: &ShapingInput,
+        shaped: &ShapedGlyphs,
+        font_em_size: f32,
+    ) -> Result<GlyphPlacements, Error> {
+        let text_len = run.text.len();
+        let glyph_count = shaped.glyph_indices.len();
+        if shaped.cluster_map.len() != text_len
+            || shaped.text_props.len() != text_len
+            || shaped.glyph_props.len() != glyph_count
+            || shaped
+                .cluster_map
+                .iter()
+                .any(|&glyph| glyph as usize >= glyph_count)
+        {
+            return Err(E_INVALIDARG.into());
+        }
+
+        let locale = run.locale.map(|l| l.to_wide_null());
+        let mut features = RawFeatureRanges::new(run.features);
+        let script_analysis = run.script_analysis.into();
+        let mut text_props: Vec<DWRITE_SHAPING_TEXT_PROPERTIES> =
+            shaped.text_props.iter().map(|&p| p.into()).collect();
+        let glyph_props: Vec<DWRITE_SHAPING_GLYPH_PROPERTIES> =
+            shaped.glyph_props.iter().map(|&p| p.into()).collect();
+
+        let mut advances = vec![0.0; glyph_count];
+        let mut offsets = vec![
+            GlyphOffset {
+                advance_offset: 0.0,
+                ascender_offset: 0.0,
+            };
+            glyph_count
+        ];
+
+        let hr = unsafe {
+            self.ptr.GetGlyphPlacements(
+                run.text.as_ptr(),
+                shaped.cluster_map.as_ptr(),
+                text_props.as_mut_ptr(),
+                text_len as u32,
+                shaped.glyph_indices.as_ptr(),
+                glyph_props.as_ptr(),
+                glyph_count as u32,
+                run.font_face.get_raw(),
+                font_em_size,
+                run.is_sideways as i32,
+                run.is_right_to_left as i32,
+                &script_analysis,
+                locale
+                    .as_ref()
+                    .map(|l| l.as_ptr())
+                    .unwrap_or(std::ptr::null()),
+                features.features(),
+                features.lengths(),
+                features.count(),
+                advances.as_mut_ptr(),
+                offsets.as_mut_ptr() as *mut DWRITE_GLYPH_OFFSET,
+            )
+        };
+        if SUCCEEDED(hr) {
+            Ok(GlyphPlacements { advances, offsets })
+        } else {
+            Err(hr.into())
+        }
+    }
+
+    fn collect(
+        &self,
+        source: &TextAnalysisSource,
+        range: TextRange,
+        passes: &[Pass],
+    ) -> Result<AnalysisResults, Error> {
+        unsafe {
+            let sink = CollectingAnalysisSink::new();
+            let src = source.get_raw();
+            let snk = sink.as_raw();
+            let (start, length) = (range.start, range.length);
+
+            for pass in passes {
+                let hr = match pass {
+                    Pass::Script => self.ptr.AnalyzeScript(src, start, length, snk),
+                    Pass::Bidi => self.ptr.AnalyzeBidi(src, start, length, snk),
+                    Pass::LineBreaks => self.ptr.AnalyzeLineBreakpoints(src, start, length, snk),
+                    Pass::NumberSubstitution => {
+                        self.ptr.AnalyzeNumberSubstitution(src, start, length, snk)
+                    }
+                };
+                if !SUCCEEDED(hr) {
+                    return Err(hr.into());
+                }
+            }
+
+            Ok(CollectingAnalysisSink::take_results(&sink))
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// A run of text in a single script, as returned by
+/// [`TextAnalyzer::analyze_script`](struct.TextAnalyzer.html#method.analyze_script).
+pub struct ScriptRun {
+    /// The first text position of the run.
+    pub start: u32,
+
+    /// The number of UTF-16 code units in the run.
+    pub length: u32,
+
+    /// The script of the run and its shaping requirements.
+    pub script_analysis: ScriptAnalysis,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// A run of text with a single bidi level, as returned by
+/// [`TextAnalyzer::analyze_bidi`](struct.TextAnalyzer.html#method.analyze_bidi).
+pub struct BidiRun {
+    /// The first text position of the run.
+    pub start: u32,
+
+    /// The number of UTF-16 code units in the run.
+    pub length: u32,
+
+    /// The level set by explicit embedding, override and isolate characters.
+    pub explicit_level: u8,
+
+    /// The final level of the run. Odd levels are right-to-left.
+    pub resolved_level: u8,
+}
+
+#[derive(Copy, Clone)]
+enum Pass {
+    Script,
+    Bidi,
+    NumberSubstitution,
+    LineBreaks,
+}
+
+pub unsafe trait ITextAnalyzer {
+    unsafe fn raw_analyzer(&self) -> &IDWriteTextAnalyzer;
+}
+
+unsafe impl ITextAnalyzer for TextAnalyzer {
+    unsafe fn raw_analyzer(&self) -> &IDWriteTextAnalyzer {
+        &self.ptr
+    }
+}
+
+/// Combines the separately reported properties into runs over `range`. Positions which the
+/// analyzer didn't report on keep the default value of that property.
+fn merge_results(range: TextRange, results: AnalysisResults) -> Vec<ItemizedRun> {
+    let end = range.start + range.length;
+
+    let mut bounds = vec![range.start, end];
+    let starts = results.scripts.iter().map(|s| &s.0);
+    let starts = starts.chain(results.bidi_levels.iter().map(|b| &b.0));
+    let starts = starts.chain(results.number_substitutions.iter().map(|n| &n.0));
+    for r in starts {
+        bounds.push(r.start.max(range.start).min(end));
+        bounds.push((r.start + r.length).max(range.start).min(end));
+    }
+    bounds.sort();
+    bounds.dedup();
+
+    let default_script = ScriptAnalysis {
+        script: 0,
+        shapes: ScriptShapes::DEFAULT,
+    };
+
+    let mut runs: Vec<ItemizedRun> = Vec::new();
+    for pair in bounds.windows(2) {
+        let pos = pair[0];
+        let contains = |r: &TextRange| r.start <= pos && pos < r.start + r.length;
+
+        let script_analysis = results
+            .scripts
+            .iter()
+            .rev()
+            .find(|s| contains(&s.0))
+            .map(|s| s.1)
+            .unwrap_or(default_script);
+        let bidi_level = results
+            .bidi_levels
+            .iter()
+            .rev()
+            .find(|b| contains(&b.0))
+            .map(|b| b.2)
+            .unwrap_or(0);
+        let number_substitution: Option<NumberSubstitution> = results
+            .number_substitutions
+            .iter()
+            .rev()
+            .find(|n| contains(&n.0))
+            .and_then(|n| n.1.clone());
+
+        let run = ItemizedRun {
+            range: TextRange {
+                start: pos,
+                length: pair[1] - pos,
+            },
+            script_analysis,
+            bidi_level,
+            number_substitution,
+        };
+
+        match runs.last_mut() {
+            Some(last) if last.same_properties(&run) => last.range.length += run.range.length,
+            _ => runs.push(run),
+        }
+    }
+
+    runs
+}
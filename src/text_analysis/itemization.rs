@@ -0,0 +1,164 @@
+use crate::descriptions::{ScriptAnalysis, TextRange};
+use crate::number_substitution::NumberSubstitution;
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use winapi::shared::winerror::E_INVALIDARG;
+
+#[derive(Clone, Debug)]
+/// A run of text which shares the same script, bidi level and number substitution, and can
+/// therefore be shaped in one go.
+pub struct ItemizedRun {
+    /// The text covered by this run.
+    pub range: TextRange,
+
+    /// The script of the run, as reported by the analyzer.
+    pub script_analysis: ScriptAnalysis,
+
+    /// The resolved bidi level of the run. Odd levels are right-to-left.
+    pub bidi_level: u8,
+
+    /// The number substitution that applies to the run, if the source specified one.
+    pub number_substitution: Option<NumberSubstitution>,
+}
+
+impl ItemizedRun {
+    /// Whether the two runs could be shaped as one, ignoring where they are in the text.
+    /// Number substitutions are compared by identity.
+    pub fn same_properties(&self, other: &ItemizedRun) -> bool {
+        let same_substitution = match (&self.number_substitution, &other.number_substitution) {
+            (Some(a), Some(b)) => unsafe { a.get_raw() == b.get_raw() },
+            (None, None) => true,
+            _ => false,
+        };
+
+        self.script_analysis == other.script_analysis
+            && self.bidi_level == other.bidi_level
+            && same_substitution
+    }
+
+    fn end(&self) -> u32 {
+        self.range.start + self.range.length
+    }
+}
+
+impl PartialEq for ItemizedRun {
+    fn eq(&self, other: &ItemizedRun) -> bool {
+        self.range == other.range && self.same_properties(other)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// The result of [`TextAnalyzer::itemize`][1], kept around so that edits only need the
+/// text around them to be analyzed again.
+///
+/// After an edit, [`adjust_for_edit`][2] shifts the runs that follow it and removes the ones it
+/// may have changed, leaving a dirty window. Itemize that window of the edited text and hand the
+/// result to [`splice`][3] to bring the itemization up to date.
+///
+/// The runs touching an edit are always invalidated, since the edited text can merge with them
+/// or change how their neutral characters resolve. Bidi levels can in rare cases depend on
+/// text further away (e.g. an inserted directional isolate or embedding); when inserting those
+/// controls, itemize the whole paragraph instead.
+///
+/// [1]: struct.TextAnalyzer.html#method.itemize
+/// [2]: #method.adjust_for_edit
+/// [3]: #method.splice
+pub struct Itemization {
+    runs: Vec<ItemizedRun>,
+    dirty: Option<TextRange>,
+}
+
+impl Itemization {
+    pub(crate) fn from_runs(runs: Vec<ItemizedRun>) -> Itemization {
+        Itemization { runs, dirty: None }
+    }
+
+    /// The runs which are still valid, in text order. While there is a dirty window, it is not
+    /// covered by any run.
+    pub fn runs(&self) -> &[ItemizedRun] {
+        &self.runs
+    }
+
+    /// The range of text which needs to be itemized again, if there has been an edit since the
+    /// last call to [`splice`](#method.splice).
+    pub fn dirty(&self) -> Option<TextRange> {
+        self.dirty
+    }
+
+    /// Updates the itemization for an edit which replaced `removed` code units at `position`
+    /// with `inserted` new ones. Runs after the edit are shifted, and runs touching it are
+    /// removed. Returns the range of the edited text which must be itemized again; if there
+    /// was already a dirty window, the returned range covers both.
+    pub fn adjust_for_edit(&mut self, position: u32, removed: u32, inserted: u32) -> TextRange {
+        let mut edit_start = position;
+        let mut edit_end = position + removed;
+        if let Some(dirty) = self.dirty {
+            edit_start = edit_start.min(dirty.start);
+            edit_end = edit_end.max(dirty.start + dirty.length);
+        }
+
+        // Runs which overlap or border the edit are invalidated; the dirty window grows to
+        // cover all of them.
+        let touches = |run: &ItemizedRun| run.range.start <= edit_end && run.end() >= edit_start;
+        let mut start = edit_start;
+        let mut end = edit_end;
+        for run in self.runs.iter().filter(|&run| touches(run)) {
+            start = start.min(run.range.start);
+            end = end.max(run.end());
+        }
+
+        let shift = |pos: u32| pos - removed + inserted;
+        self.runs.retain(|run| !touches(run));
+        for run in &mut self.runs {
+            if run.range.start > edit_end {
+                run.range.start = shift(run.range.start);
+            }
+        }
+
+        let dirty = TextRange {
+            start,
+            length: shift(end) - start,
+        };
+        self.dirty = Some(dirty);
+        dirty
+    }
+
+    /// Fills the dirty window with the runs of `fresh`, which must be an itemization of exactly
+    /// the range returned by [`adjust_for_edit`](#method.adjust_for_edit). Runs which end up
+    /// next to an identical run are merged, so the result is the same as itemizing all of the
+    /// text from scratch.
+    pub fn splice(&mut self, fresh: Itemization) -> Result<(), Error> {
+        let dirty = match self.dirty {
+            Some(dirty) => dirty,
+            None => return Err(E_INVALIDARG.into()),
+        };
+        let covered: u32 = fresh.runs.iter().map(|r| r.range.length).sum();
+        let starts_at = fresh.runs.first().map(|r| r.range.start);
+        if fresh.dirty.is_some()
+            || covered != dirty.length
+            || (covered != 0 && starts_at != Some(dirty.start))
+        {
+            return Err(E_INVALIDARG.into());
+        }
+
+        let index = self
+            .runs
+            .iter()
+            .position(|run| run.range.start > dirty.start)
+            .unwrap_or(self.runs.len());
+        let tail = self.runs.split_off(index);
+
+        for run in fresh.runs.into_iter().chain(tail) {
+            match self.runs.last_mut() {
+                Some(last) if last.end() == run.range.start && last.same_properties(&run) => {
+                    last.range.length += run.range.length
+                }
+                _ => self.runs.push(run),
+            }
+        }
+
+        self.dirty = None;
+        Ok(())
+    }
+}
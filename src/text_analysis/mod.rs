@@ -1,3 +1,22 @@
-//! Unfinished module
+//! Script, bidi and number substitution analysis of text.
 
+#[doc(inline)]
+pub use self::analyzer::{BidiRun, ITextAnalyzer, ScriptRun, TextAnalyzer};
+#[doc(inline)]
+pub use self::itemization::{Itemization, ItemizedRun};
+#[doc(inline)]
+pub use self::shaping::{FeatureRange, GlyphPlacements, ShapedGlyphs, ShapingInput};
+#[doc(inline)]
+pub use self::utf16_index::Utf16Index;
+
+#[doc(hidden)]
+pub mod analyzer;
+#[doc(hidden)]
+pub mod itemization;
+#[doc(hidden)]
+pub mod shaping;
 pub mod source;
+#[doc(hidden)]
+pub mod utf16_index;
+
+pub(crate) mod sink;
@@ -0,0 +1,130 @@
+use crate::descriptions::{
+    FontFeature, GlyphOffset, ScriptAnalysis, ShapingGlyphProperties, ShapingTextProperties,
+};
+use crate::font_face::FontFace;
+use crate::number_substitution::NumberSubstitution;
+
+use winapi::um::dwrite::{DWRITE_FONT_FEATURE, DWRITE_TYPOGRAPHIC_FEATURES};
+
+#[derive(Copy, Clone)]
+/// A run of text to be shaped by
+/// [`TextAnalyzer::get_glyphs`](struct.TextAnalyzer.html#method.get_glyphs). The run should be a
+/// single item as produced by itemization, i.e. one script, one bidi level and one font.
+pub struct ShapingInput<'a> {
+    /// The UTF-16 text of the run.
+    pub text: &'a [u16],
+
+    /// The font face to shape with.
+    pub font_face: &'a FontFace,
+
+    /// Whether the text is intended to be drawn rotated 90 degrees, as in vertical text.
+    pub is_sideways: bool,
+
+    /// Whether the text is right-to-left, i.e. its resolved bidi level is odd.
+    pub is_right_to_left: bool,
+
+    /// The script of the run, as found by
+    /// [`analyze_script`](struct.TextAnalyzer.html#method.analyze_script).
+    pub script_analysis: ScriptAnalysis,
+
+    /// The locale of the text, or `None` to use the user's default.
+    pub locale: Option<&'a str>,
+
+    /// How digits in the text should be substituted, if at all.
+    pub number_substitution: Option<&'a NumberSubstitution>,
+
+    /// The typographic features applied to the run. The lengths of the ranges must add up to
+    /// the length of `text`; leave this empty to shape with the font's default features.
+    pub features: &'a [FeatureRange<'a>],
+}
+
+#[derive(Copy, Clone, Debug)]
+/// A set of typographic features applied to a number of consecutive UTF-16 code units.
+pub struct FeatureRange<'a> {
+    /// The features to apply.
+    pub features: &'a [FontFeature],
+
+    /// The number of code units the features apply to.
+    pub length: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// The output of [`TextAnalyzer::get_glyphs`](struct.TextAnalyzer.html#method.get_glyphs).
+pub struct ShapedGlyphs {
+    /// The glyphs which render the text, in visual order for left-to-right text and in logical
+    /// order for right-to-left text.
+    pub glyph_indices: Vec<u16>,
+
+    /// Maps each UTF-16 code unit of the text to the index of the first glyph of its cluster.
+    pub cluster_map: Vec<u16>,
+
+    /// The shaping properties of each code unit of the text.
+    pub text_props: Vec<ShapingTextProperties>,
+
+    /// The shaping properties of each glyph.
+    pub glyph_props: Vec<ShapingGlyphProperties>,
+}
+
+#[derive(Clone, Debug, Default)]
+/// The output of
+/// [`TextAnalyzer::get_glyph_placements`](struct.TextAnalyzer.html#method.get_glyph_placements),
+/// ready to be used as the `glyph_advances` and `glyph_offsets` of a `GlyphRun`.
+pub struct GlyphPlacements {
+    /// The advance width of each glyph, in DIPs.
+    pub advances: Vec<f32>,
+
+    /// The offset of each glyph from its pen position, in DIPs.
+    pub offsets: Vec<GlyphOffset>,
+}
+
+/// The feature ranges of a shaping call in the array-of-pointers form DirectWrite expects.
+pub(crate) struct RawFeatureRanges {
+    features: Vec<DWRITE_TYPOGRAPHIC_FEATURES>,
+    pointers: Vec<*const DWRITE_TYPOGRAPHIC_FEATURES>,
+    lengths: Vec<u32>,
+}
+
+impl RawFeatureRanges {
+    pub fn new(ranges: &[FeatureRange]) -> RawFeatureRanges {
+        let features: Vec<_> = ranges
+            .iter()
+            .map(|range| DWRITE_TYPOGRAPHIC_FEATURES {
+                // FontFeature has the same layout as DWRITE_FONT_FEATURE, and DirectWrite
+                // doesn't write through this pointer.
+                features: range.features.as_ptr() as *const DWRITE_FONT_FEATURE as *mut _,
+                featureCount: range.features.len() as u32,
+            })
+            .collect();
+        let pointers = features.iter().map(|f| f as *const _).collect();
+        let lengths = ranges.iter().map(|range| range.length).collect();
+
+        RawFeatureRanges {
+            features,
+            pointers,
+            lengths,
+        }
+    }
+
+    /// The `features` argument, or null if there are no ranges.
+    pub fn features(&mut self) -> *mut *const DWRITE_TYPOGRAPHIC_FEATURES {
+        if self.features.is_empty() {
+            std::ptr::null_mut()
+        } else {
+            self.pointers.as_mut_ptr()
+        }
+    }
+
+    /// The `featureRangeLengths` argument, or null if there are no ranges.
+    pub fn lengths(&self) -> *const u32 {
+        if self.lengths.is_empty() {
+            std::ptr::null()
+        } else {
+            self.lengths.as_ptr()
+        }
+    }
+
+    /// The `featureRanges` argument.
+    pub fn count(&self) -> u32 {
+        self.lengths.len() as u32
+    }
+}
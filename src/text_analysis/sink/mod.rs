@@ -0,0 +1,113 @@
+use crate::descriptions::{LineBreakpoint, ScriptAnalysis, TextRange};
+use crate::number_substitution::NumberSubstitution;
+
+use std::cell::RefCell;
+
+use com_impl::{Refcount, VTable};
+use com_wrapper::ComWrapper;
+use winapi::shared::winerror::{E_FAIL, HRESULT, S_OK};
+use winapi::um::dwrite::{
+    IDWriteNumberSubstitution, IDWriteTextAnalysisSink, IDWriteTextAnalysisSinkVtbl,
+    DWRITE_LINE_BREAKPOINT, DWRITE_SCRIPT_ANALYSIS,
+};
+use wio::com::ComPtr;
+
+/// Everything the analyzer reported to a sink, in the order it was reported.
+#[derive(Default)]
+pub(crate) struct AnalysisResults {
+    pub scripts: Vec<(TextRange, ScriptAnalysis)>,
+    pub bidi_levels: Vec<(TextRange, u8, u8)>,
+    pub number_substitutions: Vec<(TextRange, Option<NumberSubstitution>)>,
+    pub line_breakpoints: Vec<(u32, Vec<LineBreakpoint>)>,
+}
+
+/// An analysis sink which records every callback so the results can be read back after the
+/// analysis has finished. Analysis runs synchronously on the calling thread, so the results
+/// don't need to be synchronized.
+#[repr(C)]
+#[derive(com_impl::ComImpl)]
+pub(crate) struct CollectingAnalysisSink {
+    vtbl: VTable<IDWriteTextAnalysisSinkVtbl>,
+    refcount: Refcount,
+    results: RefCell<AnalysisResults>,
+}
+
+impl CollectingAnalysisSink {
+    pub fn new() -> ComPtr<IDWriteTextAnalysisSink> {
+        let ptr = Self::create_raw(RefCell::new(AnalysisResults::default()));
+        let ptr = ptr as *mut IDWriteTextAnalysisSink;
+        unsafe { ComPtr::from_raw(ptr) }
+    }
+
+    /// Takes the results out of a sink created with `new`.
+    pub unsafe fn take_results(sink: &ComPtr<IDWriteTextAnalysisSink>) -> AnalysisResults {
+        let this = &*(sink.as_raw() as *const CollectingAnalysisSink);
+        std::mem::replace(&mut *this.results.borrow_mut(), AnalysisResults::default())
+    }
+}
+
+#[com_impl::com_impl]
+unsafe impl IDWriteTextAnalysisSink for CollectingAnalysisSink {
+    #[panic(result = "E_FAIL")]
+    unsafe fn set_script_analysis(
+        &self,
+        pos: u32,
+        len: u32,
+        analysis: *const DWRITE_SCRIPT_ANALYSIS,
+    ) -> HRESULT {
+        let range = TextRange {
+            start: pos,
+            length: len,
+        };
+        let mut results = self.results.borrow_mut();
+        results.scripts.push((range, (*analysis).into()));
+        S_OK
+    }
+
+    #[panic(result = "E_FAIL")]
+    unsafe fn set_line_breakpoints(
+        &self,
+        pos: u32,
+        len: u32,
+        breakpoints: *const DWRITE_LINE_BREAKPOINT,
+    ) -> HRESULT {
+        let breakpoints = std::slice::from_raw_parts(breakpoints, len as usize);
+        let breakpoints = breakpoints.iter().map(|&bp| bp.into()).collect();
+        let mut results = self.results.borrow_mut();
+        results.line_breakpoints.push((pos, breakpoints));
+        S_OK
+    }
+
+    #[panic(result = "E_FAIL")]
+    unsafe fn set_bidi_level(&self, pos: u32, len: u32, explicit: u8, resolved: u8) -> HRESULT {
+        let range = TextRange {
+            start: pos,
+            length: len,
+        };
+        let mut results = self.results.borrow_mut();
+        results.bidi_levels.push((range, explicit, resolved));
+        S_OK
+    }
+
+    #[panic(result = "E_FAIL")]
+    unsafe fn set_number_substitution(
+        &self,
+        pos: u32,
+        len: u32,
+        substitution: *mut IDWriteNumberSubstitution,
+    ) -> HRESULT {
+        let range = TextRange {
+            start: pos,
+            length: len,
+        };
+        let substitution = if substitution.is_null() {
+            None
+        } else {
+            (*substitution).AddRef();
+            Some(NumberSubstitution::from_raw(substitution))
+        };
+        let mut results = self.results.borrow_mut();
+        results.number_substitutions.push((range, substitution));
+        S_OK
+    }
+}
@@ -6,6 +6,11 @@ use winapi::um::dwrite::IDWriteTextAnalysisSource;
 use wio::com::ComPtr;
 
 pub mod custom;
+#[doc(hidden)]
+pub mod string;
+
+#[doc(inline)]
+pub use self::string::StringAnalysisSource;
 
 #[repr(transparent)]
 #[derive(ComWrapper)]
@@ -0,0 +1,74 @@
+use crate::enums::reading_direction::ReadingDirection;
+use crate::number_substitution::NumberSubstitution;
+use crate::text_analysis::source::TextAnalysisProvider;
+use crate::text_analysis::utf16_index::Utf16Index;
+
+#[derive(Clone, Debug)]
+/// A ready-made analysis source for a `&str` with a single locale and number substitution.
+///
+/// The text is encoded once into a [`Utf16Index`](../struct.Utf16Index.html), which
+/// [`index`](#method.index) exposes for converting the positions of the analysis results back
+/// to byte offsets.
+pub struct StringAnalysisSource {
+    index: Utf16Index,
+    locale: Vec<u16>,
+    substitution: NumberSubstitution,
+    reading_direction: ReadingDirection,
+}
+
+impl StringAnalysisSource {
+    /// Creates a left-to-right source for `text` in `locale`.
+    pub fn new(text: &str, locale: &str, substitution: NumberSubstitution) -> Self {
+        StringAnalysisSource {
+            index: Utf16Index::new(text),
+            locale: locale.encode_utf16().chain(Some(0)).collect(),
+            substitution,
+            reading_direction: ReadingDirection::LeftToRight,
+        }
+    }
+
+    /// Sets the reading direction of the paragraph.
+    pub fn with_reading_direction(mut self, reading_direction: ReadingDirection) -> Self {
+        self.reading_direction = reading_direction;
+        self
+    }
+
+    /// The index of the source text.
+    pub fn index(&self) -> &Utf16Index {
+        &self.index
+    }
+}
+
+impl TextAnalysisProvider for StringAnalysisSource {
+    fn locale_name(&self, position: u32) -> (&[u16], u32) {
+        (
+            &self.locale,
+            self.index.len_utf16().saturating_sub(position),
+        )
+    }
+
+    fn number_substitution(&self, position: u32) -> (NumberSubstitution, u32) {
+        (
+            self.substitution.clone(),
+            self.index.len_utf16().saturating_sub(position),
+        )
+    }
+
+    fn paragraph_reading_direction(&self) -> ReadingDirection {
+        self.reading_direction
+    }
+
+    fn text_at(&self, position: u32) -> Option<&[u16]> {
+        self.index
+            .utf16()
+            .get(position as usize..)
+            .filter(|t| !t.is_empty())
+    }
+
+    fn text_before(&self, position: u32) -> Option<&[u16]> {
+        self.index
+            .utf16()
+            .get(..position as usize)
+            .filter(|t| !t.is_empty())
+    }
+}
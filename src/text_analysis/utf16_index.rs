@@ -0,0 +1,119 @@
+use std::ops::Range;
+
+/// Number of chars between the checkpoints of a `Utf16Index`, which bounds how far a
+/// conversion has to scan.
+const CHUNK: usize = 64;
+
+#[derive(Clone, Debug, Default)]
+/// Maps between byte offsets into a `str` and the UTF-16 positions DirectWrite uses, and keeps
+/// the UTF-16 encoding around so text sources can hand out slices of it.
+///
+/// Building the index encodes the text once and records a checkpoint every 64 chars, so each
+/// conversion is a binary search followed by a short scan instead of re-encoding everything
+/// before the position.
+pub struct Utf16Index {
+    text: Box<str>,
+    utf16: Box<[u16]>,
+    // (byte offset, UTF-16 position) of every CHUNK-th char, starting with (0, 0)
+    checkpoints: Vec<(usize, u32)>,
+}
+
+impl Utf16Index {
+    /// Encodes `text` and indexes it.
+    pub fn new(text: &str) -> Utf16Index {
+        let mut utf16 = Vec::with_capacity(text.len());
+        let mut checkpoints = Vec::with_capacity(text.len() / CHUNK + 1);
+        for (i, (byte, c)) in text.char_indices().enumerate() {
+            if i % CHUNK == 0 {
+                checkpoints.push((byte, utf16.len() as u32));
+            }
+            let mut buf = [0; 2];
+            utf16.extend_from_slice(c.encode_utf16(&mut buf));
+        }
+        if checkpoints.is_empty() {
+            checkpoints.push((0, 0));
+        }
+
+        Utf16Index {
+            text: text.into(),
+            utf16: utf16.into_boxed_slice(),
+            checkpoints,
+        }
+    }
+
+    /// The indexed text.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The whole text encoded as UTF-16.
+    pub fn utf16(&self) -> &[u16] {
+        &self.utf16
+    }
+
+    /// The length of the text in UTF-16 code units.
+    pub fn len_utf16(&self) -> u32 {
+        self.utf16.len() as u32
+    }
+
+    /// Whether the text is empty.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// The UTF-16 code units in `range`, which is in UTF-16 positions.
+    ///
+    /// Panics if the range is out of bounds, like slicing does.
+    pub fn slice_utf16(&self, range: Range<u32>) -> &[u16] {
+        &self.utf16[range.start as usize..range.end as usize]
+    }
+
+    /// Converts a byte offset into the text to a UTF-16 position. An offset inside a
+    /// multi-byte char maps to the position of that char, and offsets past the end map to the
+    /// end of the text.
+    pub fn utf16_from_byte(&self, byte: usize) -> u32 {
+        let i = self.checkpoints.partition_point(|&(b, _)| b <= byte) - 1;
+        let (mut b, mut pos) = self.checkpoints[i];
+        for c in self.text[b..].chars() {
+            if b + c.len_utf8() > byte {
+                break;
+            }
+            b += c.len_utf8();
+            pos += c.len_utf16() as u32;
+        }
+        pos
+    }
+
+    /// Converts a UTF-16 position to a byte offset into the text. A position between the two
+    /// halves of a surrogate pair maps to the start of that char, and positions past the end
+    /// map to the end of the text.
+    pub fn byte_from_utf16(&self, position: u32) -> usize {
+        let i = self.checkpoints.partition_point(|&(_, p)| p <= position) - 1;
+        let (mut b, mut pos) = self.checkpoints[i];
+        for c in self.text[b..].chars() {
+            if pos + c.len_utf16() as u32 > position {
+                break;
+            }
+            b += c.len_utf8();
+            pos += c.len_utf16() as u32;
+        }
+        b
+    }
+
+    /// Converts a range of byte offsets to a range of UTF-16 positions.
+    pub fn utf16_range(&self, bytes: Range<usize>) -> Range<u32> {
+        self.utf16_from_byte(bytes.start)..self.utf16_from_byte(bytes.end)
+    }
+
+    /// Converts a range of UTF-16 positions to a range of byte offsets, which can be used to
+    /// slice the text.
+    pub fn byte_range(&self, positions: Range<u32>) -> Range<usize> {
+        self.byte_from_utf16(positions.start)..self.byte_from_utf16(positions.end)
+    }
+}
+
+impl<'a> From<&'a str> for Utf16Index {
+    fn from(text: &'a str) -> Utf16Index {
+        Utf16Index::new(text)
+    }
+}
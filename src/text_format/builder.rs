@@ -1,5 +1,7 @@
 use crate::enums::{FontStretch, FontStyle, FontWeight};
+use crate::factory::{Factory, IFactory};
 use crate::font_collection::FontCollection;
+use crate::origin;
 use crate::text_format::TextFormat;
 
 use std::ptr;
@@ -8,13 +10,12 @@ use com_wrapper::ComWrapper;
 use dcommon::error::Error;
 use winapi::shared::winerror::SUCCEEDED;
 use winapi::um::dwrite::{IDWriteFactory, IDWriteTextFormat};
-use wio::com::ComPtr;
 use wio::wide::ToWide;
 
 #[must_use]
 /// Builder for a TextFormat.
 pub struct TextFormatBuilder<'a> {
-    factory: &'a IDWriteFactory,
+    factory: Factory,
     family: Option<&'a str>,
     collection: Option<&'a FontCollection>,
     weight: FontWeight,
@@ -26,9 +27,9 @@ pub struct TextFormatBuilder<'a> {
 
 impl<'a> TextFormatBuilder<'a> {
     /// Initialize a new builder.
-    pub fn new(factory: &'a IDWriteFactory) -> TextFormatBuilder<'a> {
+    pub fn new(factory: &IDWriteFactory) -> TextFormatBuilder<'a> {
         TextFormatBuilder {
-            factory,
+            factory: unsafe { Factory::from_borrowed(factory) },
             family: None,
             collection: None,
             weight: FontWeight::NORMAL,
@@ -51,12 +52,15 @@ impl<'a> TextFormatBuilder<'a> {
                 .collection
                 .map(|col| col.get_raw())
                 .unwrap_or(ptr::null_mut());
+            if !collection.is_null() {
+                origin::check_object("font collection", collection, self.factory.raw_f())?;
+            }
 
             let size = self.size.expect("`size` must be specified");
             let locale = self.locale.unwrap_or("en-US").to_wide_null();
 
             let mut ptr: *mut IDWriteTextFormat = ptr::null_mut();
-            let result = self.factory.CreateTextFormat(
+            let result = self.factory.raw_f().CreateTextFormat(
                 family.as_ptr(),
                 collection,
                 self.weight.0,
@@ -68,9 +72,8 @@ impl<'a> TextFormatBuilder<'a> {
             );
 
             if SUCCEEDED(result) {
-                Ok(TextFormat {
-                    ptr: ComPtr::from_raw(ptr),
-                })
+                origin::record(ptr, self.factory.raw_f());
+                Ok(TextFormat::from_raw(ptr))
             } else {
                 Err(From::from(result))
             }
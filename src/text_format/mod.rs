@@ -1,21 +1,27 @@
 //! TextFormat and types for building new ones.
 
-use crate::descriptions::Trimming;
+use crate::descriptions::{LineSpacing2, Trimming};
 use crate::enums::*;
 use crate::factory::Factory;
 use crate::font_collection::FontCollection;
+use crate::font_fallback::FontFallback;
+use crate::helpers::{get_wide_string, query_interface};
 use crate::inline_object::InlineObject;
+use crate::number_substitution::NumberSubstitution;
+use crate::origin;
+use crate::text_analysis::{StringAnalysisSource, TextAnalysisSource};
+use crate::text_layout::{ITextLayout, TextLayout};
 
-use std::ffi::OsString;
 use std::ptr;
 
 use checked_enum::UncheckedEnum;
 use com_wrapper::ComWrapper;
 use dcommon::Error;
-use winapi::shared::winerror::SUCCEEDED;
+use winapi::shared::winerror::{E_INVALIDARG, SUCCEEDED};
 use winapi::um::dwrite::IDWriteTextFormat;
+use winapi::um::dwrite_2::{IDWriteTextFormat1, IDWriteTextLayout2};
+use winapi::um::dwrite_3::{IDWriteTextFormat2, IDWriteTextLayout3};
 use wio::com::ComPtr;
-use wio::wide::FromWide;
 
 #[doc(inline)]
 pub use self::builder::TextFormatBuilder;
@@ -32,11 +38,109 @@ pub struct TextFormat {
     ptr: ComPtr<IDWriteTextFormat>,
 }
 
+raw_interop!(TextFormat, IDWriteTextFormat);
+
 impl TextFormat {
     /// Get a builder for creating a new text format.
-    pub fn create<'a>(factory: &'a Factory) -> TextFormatBuilder<'a> {
+    pub fn create<'a>(factory: &Factory) -> TextFormatBuilder<'a> {
         unsafe { TextFormatBuilder::new(&*factory.get_raw()) }
     }
+
+    /// Lays out `text` with this format once for each measuring mode and reports the
+    /// resulting sizes side by side. Useful for tracking down text that only breaks or
+    /// clips when rendered with GDI-compatible metrics.
+    pub fn compare_measuring_modes(
+        &self,
+        factory: &Factory,
+        text: &str,
+        width: f32,
+    ) -> Result<MeasuringModeComparison, Error> {
+        let measure = |mode| -> Result<MeasuredLayout, Error> {
+            let layout = TextLayout::create(factory)
+                .with_str(text)
+                .with_format(self)
+                .with_width(width)
+                .with_height(std::f32::MAX)
+                .with_measuring_mode(mode)
+                .build()?;
+            let metrics = layout.metrics();
+            Ok(MeasuredLayout {
+                measuring_mode: mode,
+                width: metrics.width,
+                width_including_trailing_whitespace: metrics.width_including_trailing_whitespace,
+                height: metrics.height,
+                line_count: metrics.line_count,
+            })
+        };
+
+        Ok(MeasuringModeComparison {
+            natural: measure(MeasuringMode::Natural)?,
+            gdi_classic: measure(MeasuringMode::GdiClassic)?,
+            gdi_natural: measure(MeasuringMode::GdiNatural)?,
+        })
+    }
+
+    /// Suggests a uniform line spacing for text like `sample_text` set in this format.
+    ///
+    /// The sample is laid out to find the natural line height of the fonts it actually uses
+    /// (including fallback fonts), and that height is then raised to a minimum based on the
+    /// sample's dominant script, as found by [`TextAnalyzer::analyze_script`][1]: 1.5 times the
+    /// font size for Chinese, Japanese and Korean text, which needs more room between lines to
+    /// stay legible, and 1.2 times for everything else. The result is advisory only; nothing is
+    /// applied to the format. Requires Windows 8 for the script properties.
+    ///
+    /// [1]: ../text_analysis/struct.TextAnalyzer.html#method.analyze_script
+    pub fn script_aware_line_spacing(
+        &self,
+        factory: &Factory,
+        sample_text: &str,
+    ) -> Result<LineSpacing, Error> {
+        let layout = TextLayout::create(factory)
+            .with_str(sample_text)
+            .with_format(self)
+            .with_width(std::f32::MAX)
+            .with_height(std::f32::MAX)
+            .build()?;
+
+        let (mut height, mut baseline) = (0.0f32, 0.0f32);
+        for line in layout.line_metrics() {
+            if line.height > height {
+                height = line.height;
+                baseline = line.baseline;
+            }
+        }
+
+        // Weigh the scripts DirectWrite finds in the sample by their length
+        let substitution = NumberSubstitution::create(factory)
+            .with_method(NumberSubstitutionMethod::None)
+            .build()?;
+        let locale = self.locale_name().unwrap_or_default();
+        let source = StringAnalysisSource::new(sample_text, &locale, substitution);
+        let len = source.index().len_utf16();
+        let source = TextAnalysisSource::new(source);
+        let analyzer = factory.create_text_analyzer()?;
+        let (mut cjk, mut other) = (0, 0);
+        for run in analyzer.analyze_script(&source, 0, len)? {
+            // Runs of e.g. whitespace and control characters say nothing about the script
+            if run.script_analysis.shapes.is_set(ScriptShapes::NO_VISUAL) {
+                continue;
+            }
+            if is_cjk(analyzer.iso_script_number(run.script_analysis)?) {
+                cjk += run.length;
+            } else {
+                other += run.length;
+            }
+        }
+        let ratio = if cjk > other { 1.5 } else { 1.2 };
+
+        let spacing = height.max(self.font_size() * ratio);
+        Ok(LineSpacing {
+            method: LineSpacingMethod::Uniform.into(),
+            spacing,
+            // Split the extra room evenly above and below the natural line.
+            baseline: baseline + (spacing - height) / 2.0,
+        })
+    }
 }
 
 pub unsafe trait ITextFormat {
@@ -51,6 +155,7 @@ pub unsafe trait ITextFormat {
             let mut ptr = ptr::null_mut();
             let hr = self.raw_tf().GetFontCollection(&mut ptr);
             if SUCCEEDED(hr) && ptr != ptr::null_mut() {
+                origin::inherit(ptr, self.raw_tf() as *const _);
                 Some(FontCollection::from_raw(ptr))
             } else {
                 None
@@ -58,23 +163,43 @@ pub unsafe trait ITextFormat {
         }
     }
 
-    /// Get the name of the font family specified for this format.
-    fn font_family_name(&self) -> Option<String> {
+    /// Gets the fallback used for characters the format's font can't display, or `None` if the
+    /// system fallback is used. Works on both formats and layouts. Requires Windows 8.1; on
+    /// older versions this fails with `E_NOINTERFACE`.
+    fn font_fallback(&self) -> Result<Option<FontFallback>, Error> {
         unsafe {
-            let len = self.raw_tf().GetFontFamilyNameLength();
-            let mut buf = Vec::with_capacity(len as usize + 1);
-            let hr = self.raw_tf().GetFontFamilyName(buf.as_mut_ptr(), len + 1);
-            if SUCCEEDED(hr) {
-                buf.set_len(len as usize);
-                let osstr = OsString::from_wide(&buf);
-                let ff_name = osstr.to_string_lossy().into_owned();
-                Some(ff_name)
+            let mut ptr = ptr::null_mut();
+            let hr = match query_interface::<IDWriteTextFormat1>(self.raw_tf()) {
+                Ok(format) => format.GetFontFallback(&mut ptr),
+                Err(_) => {
+                    let layout = query_interface::<IDWriteTextLayout2>(self.raw_tf())?;
+                    layout.GetFontFallback(&mut ptr)
+                }
+            };
+            if !SUCCEEDED(hr) {
+                Err(hr.into())
+            } else if ptr.is_null() {
+                Ok(None)
             } else {
-                None
+                Ok(Some(FontFallback::from_raw(ptr)))
             }
         }
     }
 
+    /// Get the name of the font family specified for this format.
+    fn font_family_name(&self) -> Option<String> {
+        unsafe {
+            get_wide_string(
+                || Ok(self.raw_tf().GetFontFamilyNameLength()),
+                |buf| {
+                    self.raw_tf()
+                        .GetFontFamilyName(buf.as_mut_ptr(), buf.len() as u32)
+                },
+            )
+            .ok()
+        }
+    }
+
     /// Get the font size associated with this format.
     fn font_size(&self) -> f32 {
         unsafe { self.raw_tf().GetFontSize() }
@@ -100,6 +225,20 @@ pub unsafe trait ITextFormat {
         unsafe { self.raw_tf().GetIncrementalTabStop() }
     }
 
+    /// Gets whether the last line is wrapped like the others. When disabled, the last line can
+    /// overflow the layout width, which lets single-line text be trimmed instead of wrapping.
+    /// Works on both formats and layouts. Requires Windows 8.1; on older versions this fails
+    /// with `E_NOINTERFACE`.
+    fn last_line_wrapping(&self) -> Result<bool, Error> {
+        unsafe {
+            if let Ok(format) = query_interface::<IDWriteTextFormat1>(self.raw_tf()) {
+                return Ok(format.GetLastLineWrapping() != 0);
+            }
+            let layout = query_interface::<IDWriteTextLayout2>(self.raw_tf())?;
+            Ok(layout.GetLastLineWrapping() != 0)
+        }
+    }
+
     /// Get the line spacing information for this format.
     fn line_spacing(&self) -> Result<LineSpacing, Error> {
         unsafe {
@@ -122,25 +261,51 @@ pub unsafe trait ITextFormat {
         }
     }
 
-    /// Get the locale used for this format.
-    fn locale_name(&self) -> Result<String, Error> {
+    /// Gets the line spacing including the options added in Windows 10. Works on both formats
+    /// and layouts. Requires Windows 10; on older versions this fails with `E_NOINTERFACE`.
+    fn line_spacing2(&self) -> Result<LineSpacing2, Error> {
         unsafe {
-            let len = self.raw_tf().GetLocaleNameLength();
-            let mut buf = Vec::with_capacity(len as usize + 1);
-            let hr = self.raw_tf().GetLocaleName(buf.as_mut_ptr(), len + 1);
+            let mut spacing = std::mem::zeroed();
+            let hr = match query_interface::<IDWriteTextFormat2>(self.raw_tf()) {
+                Ok(format) => format.GetLineSpacing(&mut spacing),
+                Err(_) => {
+                    let layout = query_interface::<IDWriteTextLayout3>(self.raw_tf())?;
+                    layout.GetLineSpacing(&mut spacing)
+                }
+            };
             if SUCCEEDED(hr) {
-                buf.set_len(len as usize);
-                let osstr = OsString::from_wide(&buf);
-                let loc_name = osstr
-                    .into_string()
-                    .unwrap_or_else(|e| e.to_string_lossy().into_owned());
-                Ok(loc_name)
+                Ok(spacing.into())
             } else {
                 Err(hr.into())
             }
         }
     }
 
+    /// Get the locale used for this format.
+    fn locale_name(&self) -> Result<String, Error> {
+        unsafe {
+            get_wide_string(
+                || Ok(self.raw_tf().GetLocaleNameLength()),
+                |buf| {
+                    self.raw_tf()
+                        .GetLocaleName(buf.as_mut_ptr(), buf.len() as u32)
+                },
+            )
+        }
+    }
+
+    /// Gets how glyphs are aligned to the margins. Works on both formats and layouts. Requires
+    /// Windows 8.1; on older versions this fails with `E_NOINTERFACE`.
+    fn optical_alignment(&self) -> Result<UncheckedEnum<OpticalAlignment>, Error> {
+        unsafe {
+            if let Ok(format) = query_interface::<IDWriteTextFormat1>(self.raw_tf()) {
+                return Ok(format.GetOpticalAlignment().into());
+            }
+            let layout = query_interface::<IDWriteTextLayout2>(self.raw_tf())?;
+            Ok(layout.GetOpticalAlignment().into())
+        }
+    }
+
     /// Get the paragraph alignment of text under this format.
     fn paragraph_alignment(&self) -> UncheckedEnum<ParagraphAlignment> {
         unsafe { self.raw_tf().GetParagraphAlignment().into() }
@@ -195,6 +360,27 @@ pub unsafe trait ITextFormat {
         }
     }
 
+    /// Sets the fallback used for characters the format's font can't display. On a layout this
+    /// only affects that layout, leaving the format it was created from untouched. Works on
+    /// both formats and layouts. Requires Windows 8.1; on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn set_font_fallback(&mut self, fallback: &FontFallback) -> Result<(), Error> {
+        unsafe {
+            let hr = match query_interface::<IDWriteTextFormat1>(self.raw_tf()) {
+                Ok(format) => format.SetFontFallback(fallback.get_raw()),
+                Err(_) => {
+                    let layout = query_interface::<IDWriteTextLayout2>(self.raw_tf())?;
+                    layout.SetFontFallback(fallback.get_raw())
+                }
+            };
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
     /// Set the incremental tabstop value for text under this format.
     fn set_incremental_tabstop(&mut self, value: f32) -> Result<(), Error> {
         unsafe {
@@ -207,7 +393,29 @@ pub unsafe trait ITextFormat {
         }
     }
 
-    /// Set the line spacing metrics for text under this format.
+    /// Sets whether the last line is wrapped like the others. Disable this to let the last line
+    /// overflow into the trimming sign instead of wrapping. Works on both formats and layouts.
+    /// Requires Windows 8.1; on older versions this fails with `E_NOINTERFACE`.
+    fn set_last_line_wrapping(&mut self, enabled: bool) -> Result<(), Error> {
+        unsafe {
+            let hr = match query_interface::<IDWriteTextFormat1>(self.raw_tf()) {
+                Ok(format) => format.SetLastLineWrapping(enabled as i32),
+                Err(_) => {
+                    let layout = query_interface::<IDWriteTextLayout2>(self.raw_tf())?;
+                    layout.SetLastLineWrapping(enabled as i32)
+                }
+            };
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Set the line spacing metrics for text under this format. The proportional method,
+    /// leading and line gap options are only available through
+    /// [`set_line_spacing2`](#method.set_line_spacing2).
     fn set_line_spacing(
         &mut self,
         method: LineSpacingMethod,
@@ -226,6 +434,53 @@ pub unsafe trait ITextFormat {
         }
     }
 
+    /// Sets the line spacing including the options added in Windows 10, such as proportional
+    /// spacing. Works on both formats and layouts. Fails with `E_INVALIDARG` if a proportional
+    /// factor is negative. Requires Windows 10; on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn set_line_spacing2(&mut self, spacing: &LineSpacing2) -> Result<(), Error> {
+        if !spacing.is_valid() {
+            return Err(E_INVALIDARG.into());
+        }
+
+        unsafe {
+            let raw = spacing.into_raw();
+            let hr = match query_interface::<IDWriteTextFormat2>(self.raw_tf()) {
+                Ok(format) => format.SetLineSpacing(&raw),
+                Err(_) => {
+                    let layout = query_interface::<IDWriteTextLayout3>(self.raw_tf())?;
+                    layout.SetLineSpacing(&raw)
+                }
+            };
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Sets how glyphs are aligned to the margins. `NoSideBearings` aligns the ink of the glyphs
+    /// to the margins, which noticeably straightens the edges of justified text. Works on both
+    /// formats and layouts. Requires Windows 8.1; on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn set_optical_alignment(&mut self, alignment: OpticalAlignment) -> Result<(), Error> {
+        unsafe {
+            let hr = match query_interface::<IDWriteTextFormat1>(self.raw_tf()) {
+                Ok(format) => format.SetOpticalAlignment(alignment as u32),
+                Err(_) => {
+                    let layout = query_interface::<IDWriteTextLayout2>(self.raw_tf())?;
+                    layout.SetOpticalAlignment(alignment as u32)
+                }
+            };
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
     /// Set the paragraph alignment for text under this format.
     fn set_paragraph_alignment(&mut self, value: ParagraphAlignment) -> Result<(), Error> {
         unsafe {
@@ -305,6 +560,23 @@ unsafe impl ITextFormat for TextFormat {
     }
 }
 
+/// Whether `iso_script_number` is one of the ISO 15924 scripts of Chinese, Japanese or Korean.
+fn is_cjk(iso_script_number: u32) -> bool {
+    match iso_script_number {
+        285 // Bopo
+        | 286 // Hang
+        | 287 // Kore
+        | 410 // Hira
+        | 411 // Kana
+        | 412 // Hrkt
+        | 413 // Jpan
+        | 500 // Hani
+        | 501 // Hans
+        | 502 => true, // Hant
+        _ => false,
+    }
+}
+
 /// Information about the line spacing of a format.
 pub struct LineSpacing {
     /// The method used for line spacing in a text layout.
@@ -316,3 +588,55 @@ pub struct LineSpacing {
     /// The distance from top of line to baseline. A reasonable ratio to `spacing` is 80 percent.
     pub baseline: f32,
 }
+
+#[derive(Copy, Clone, Debug)]
+/// The size of a piece of text when laid out with a particular measuring mode.
+pub struct MeasuredLayout {
+    /// The measuring mode the text was laid out with.
+    pub measuring_mode: MeasuringMode,
+
+    /// The width of the formatted text, ignoring trailing whitespace at the end of each line.
+    pub width: f32,
+
+    /// The width of the formatted text, including trailing whitespace.
+    pub width_including_trailing_whitespace: f32,
+
+    /// The height of the formatted text.
+    pub height: f32,
+
+    /// The number of lines the text was broken into.
+    pub line_count: u32,
+}
+
+#[derive(Copy, Clone, Debug)]
+/// The results of laying out the same text with each of the measuring modes, as returned by
+/// [`TextFormat::compare_measuring_modes`][1].
+///
+/// [1]: struct.TextFormat.html#method.compare_measuring_modes
+pub struct MeasuringModeComparison {
+    /// The layout using ideal, resolution-independent glyph metrics.
+    pub natural: MeasuredLayout,
+
+    /// The layout using GDI-compatible metrics (`CLEARTYPE_QUALITY`).
+    pub gdi_classic: MeasuredLayout,
+
+    /// The layout using GDI-compatible metrics (`CLEARTYPE_NATURAL_QUALITY`).
+    pub gdi_natural: MeasuredLayout,
+}
+
+impl MeasuringModeComparison {
+    /// All three measurements, in the order natural, GDI classic, GDI natural.
+    pub fn entries(&self) -> [MeasuredLayout; 3] {
+        [self.natural, self.gdi_classic, self.gdi_natural]
+    }
+
+    /// Whether either of the GDI modes produced a different width or number of lines than the
+    /// natural layout.
+    pub fn gdi_differs(&self) -> bool {
+        [self.gdi_classic, self.gdi_natural].iter().any(|m| {
+            m.line_count != self.natural.line_count
+                || m.width_including_trailing_whitespace
+                    != self.natural.width_including_trailing_whitespace
+        })
+    }
+}
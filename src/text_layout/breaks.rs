@@ -0,0 +1,95 @@
+use crate::descriptions::{LineBreakpoint, TextRange};
+use crate::enums::{NumberSubstitutionMethod, ReadingDirection};
+use crate::factory::Factory;
+use crate::number_substitution::NumberSubstitution;
+use crate::text_analysis::source::{TextAnalysisProvider, TextAnalysisSource};
+use crate::text_format::ITextFormat;
+use crate::text_layout::{ITextLayout, TextLayout};
+
+use dcommon::Error;
+
+/// A copy of a layout's text and locales, as the analysis source needs to own its data.
+struct LayoutText {
+    text: Vec<u16>,
+    // The end of each locale run, with the NUL-terminated locale name
+    locales: Vec<(u32, Vec<u16>)>,
+    substitution: NumberSubstitution,
+    reading_direction: ReadingDirection,
+}
+
+impl LayoutText {
+    fn run_length(&self, end: u32, position: u32) -> u32 {
+        end.saturating_sub(position)
+    }
+}
+
+impl TextAnalysisProvider for LayoutText {
+    fn locale_name(&self, position: u32) -> (&[u16], u32) {
+        let (end, locale) = self
+            .locales
+            .iter()
+            .find(|(end, _)| position < *end)
+            .or_else(|| self.locales.last())
+            .expect("non-empty text has at least one locale run");
+        (locale, self.run_length(*end, position))
+    }
+
+    fn number_substitution(&self, position: u32) -> (NumberSubstitution, u32) {
+        let len = self.text.len() as u32;
+        (self.substitution.clone(), self.run_length(len, position))
+    }
+
+    fn paragraph_reading_direction(&self) -> ReadingDirection {
+        self.reading_direction
+    }
+
+    fn text_at(&self, position: u32) -> Option<&[u16]> {
+        self.text.get(position as usize..).filter(|t| !t.is_empty())
+    }
+
+    fn text_before(&self, position: u32) -> Option<&[u16]> {
+        self.text.get(..position as usize).filter(|t| !t.is_empty())
+    }
+}
+
+/// Runs DirectWrite's line breaking analysis over `text`, which the layout was built from,
+/// using the locales set on the layout.
+pub(crate) fn line_breakpoints(
+    layout: &TextLayout,
+    text: &[u16],
+    factory: &Factory,
+) -> Result<Vec<LineBreakpoint>, Error> {
+    let len = text.len() as u32;
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut locales = Vec::new();
+    let mut position = 0;
+    while position < len {
+        let run = ITextLayout::locale_name(layout, position)?;
+        let TextRange { start, length } = run.range;
+        // Step past empty or stale ranges so the loop always ends
+        let end = start.saturating_add(length).min(len).max(position + 1);
+        locales.push((end, run.value.encode_utf16().chain(Some(0)).collect()));
+        position = end;
+    }
+
+    let substitution = NumberSubstitution::create(factory)
+        .with_method(NumberSubstitutionMethod::None)
+        .build()?;
+    let reading_direction = match layout.reading_direction().value {
+        rtl if rtl == ReadingDirection::RightToLeft as u32 => ReadingDirection::RightToLeft,
+        _ => ReadingDirection::LeftToRight,
+    };
+    let source = TextAnalysisSource::new(LayoutText {
+        text: text.to_vec(),
+        locales,
+        substitution,
+        reading_direction,
+    });
+
+    factory
+        .create_text_analyzer()?
+        .analyze_line_breaks(&source, 0, len)
+}
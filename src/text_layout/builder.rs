@@ -1,12 +1,26 @@
+use crate::descriptions::TextRange;
+use crate::enums::{
+    AutomaticFontAxes, FontStretch, FontStyle, FontWeight, MeasuringMode, OpticalAlignment,
+    VerticalGlyphOrientation,
+};
+use crate::factory::{Factory, IFactory};
+use crate::helpers::query_interface;
+use crate::inline_object::InlineObject;
+use crate::origin;
+use crate::profiling;
+use crate::text_layout::{AttributeSpan, ITextLayout};
+use crate::typography::Typography;
 use crate::{TextFormat, TextLayout};
 
 use std::borrow::Cow;
+use std::ops::Range;
 use std::ptr;
 
 use com_wrapper::ComWrapper;
 use dcommon::Error;
-use winapi::shared::winerror::SUCCEEDED;
+use winapi::shared::winerror::{E_INVALIDARG, SUCCEEDED};
 use winapi::um::dwrite::*;
+use winapi::um::dwrite_2::IDWriteTextLayout2;
 use wio::com::ComPtr;
 use wio::wide::ToWide;
 
@@ -16,53 +30,204 @@ use wio::wide::ToWide;
 /// `text`, `format`, `width`, and `height` are not optional.
 ///
 /// `centered` defaults to false.
+///
+/// `measuring_mode` defaults to `Natural`.
+///
+/// `vertical_glyph_orientation` is left at the layout's default unless specified.
+///
+/// `optical_alignment` is left at the format's setting unless specified.
+///
+/// `auto_optical_size` defaults to false.
+///
+/// The `with_range_*` methods queue formatting for a range of the text, which is applied in
+/// the order it was specified right after the layout is created.
 pub struct TextLayoutBuilder<'a> {
-    factory: &'a IDWriteFactory,
+    factory: Factory,
     text: Option<Cow<'a, [u16]>>,
+    absolute_offset: u32,
+    retain_text: bool,
     format: Option<&'a TextFormat>,
     width: Option<f32>,
     height: Option<f32>,
     centered: bool,
+    measuring_mode: MeasuringMode,
+    vertical_glyph_orientation: Option<VerticalGlyphOrientation>,
+    optical_alignment: Option<OpticalAlignment>,
+    auto_optical_size: bool,
+    ranges: Vec<AttributeSpan<'a>>,
+}
+
+#[derive(Debug)]
+/// Describes why [`TextLayoutBuilder::build`][1] failed.
+///
+/// [1]: struct.TextLayoutBuilder.html#method.build
+pub struct LayoutBuildError {
+    /// The ranged attribute which could not be applied, or `None` if creating the layout
+    /// itself failed.
+    pub attribute: Option<&'static str>,
+
+    /// The range the attribute was being applied to.
+    pub range: Option<TextRange>,
+
+    /// The error DirectWrite reported.
+    pub error: Error,
+}
+
+impl From<Error> for LayoutBuildError {
+    fn from(error: Error) -> Self {
+        LayoutBuildError {
+            attribute: None,
+            range: None,
+            error,
+        }
+    }
+}
+
+impl From<LayoutBuildError> for Error {
+    fn from(error: LayoutBuildError) -> Self {
+        error.error
+    }
+}
+
+impl std::fmt::Display for LayoutBuildError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (self.attribute, self.range) {
+            (Some(attribute), Some(range)) => write!(
+                fmt,
+                "failed to apply {} to text range {}..{}: {:?}",
+                attribute,
+                range.start,
+                range.start + range.length,
+                self.error
+            ),
+            _ => write!(fmt, "failed to create text layout: {:?}", self.error),
+        }
+    }
 }
 
+impl std::error::Error for LayoutBuildError {}
+
 impl<'a> TextLayoutBuilder<'a> {
     /// Initialize a new builder
-    pub fn new(factory: &'a IDWriteFactory) -> TextLayoutBuilder<'a> {
+    pub fn new(factory: &IDWriteFactory) -> TextLayoutBuilder<'a> {
         TextLayoutBuilder {
-            factory,
+            factory: unsafe { Factory::from_borrowed(factory) },
             text: None,
+            absolute_offset: 0,
+            retain_text: false,
             format: None,
             width: None,
             height: None,
             centered: false,
+            measuring_mode: MeasuringMode::Natural,
+            vertical_glyph_orientation: None,
+            optical_alignment: None,
+            auto_optical_size: false,
+            ranges: Vec::new(),
         }
     }
 
-    /// Build the TextLayout from the passed parameters.
-    pub fn build(self) -> Result<TextLayout, Error> {
+    /// Build the TextLayout from the passed parameters. If applying one of the ranged
+    /// attributes fails the error says which attribute and range it was; it converts into a
+    /// plain `Error` with `?` or `Into`.
+    pub fn build(self) -> Result<TextLayout, LayoutBuildError> {
+        let start = match profiling::start() {
+            Some(start) => start,
+            None => return self.build_layout(),
+        };
+
+        let chars = self.text.as_ref().map(|text| text.len()).unwrap_or(0);
+        let layout = self.build_layout()?;
+        profiling::report(start, |p, dur| p.layout_created(chars, dur));
+        Ok(layout)
+    }
+
+    fn build_layout(mut self) -> Result<TextLayout, LayoutBuildError> {
+        let ranges = std::mem::replace(&mut self.ranges, Vec::new());
+        let mut layout = self.create_layout()?;
+        for span in ranges {
+            span.apply(&mut layout).map_err(|error| LayoutBuildError {
+                attribute: Some(span.name()),
+                range: Some(span.range()),
+                error,
+            })?;
+        }
+        Ok(layout)
+    }
+
+    fn create_layout(self) -> Result<TextLayout, Error> {
         unsafe {
             let text = self.text.expect("`text` must be specified");
             let format = self.format.expect("`format` must be specified");
             let width = self.width.expect("`width` or `size` must be specified");
             let height = self.height.expect("`height` or `size` must be specified");
 
+            origin::check_object("text format", format.get_raw(), self.factory.raw_f())?;
+
+            // Every position in the layout has to have a document position
+            if text.len() as u64 + self.absolute_offset as u64 > u32::MAX as u64 {
+                return Err(E_INVALIDARG.into());
+            }
+
             let mut ptr: *mut IDWriteTextLayout = ptr::null_mut();
-            let hr = self.factory.CreateTextLayout(
-                text.as_ptr(),
-                text.len() as u32,
-                format.get_raw(),
-                width,
-                height,
-                &mut ptr,
-            );
+            let hr = match self.measuring_mode {
+                MeasuringMode::Natural => self.factory.raw_f().CreateTextLayout(
+                    text.as_ptr(),
+                    text.len() as u32,
+                    format.get_raw(),
+                    width,
+                    height,
+                    &mut ptr,
+                ),
+                mode => self.factory.raw_f().CreateGdiCompatibleTextLayout(
+                    text.as_ptr(),
+                    text.len() as u32,
+                    format.get_raw(),
+                    width,
+                    height,
+                    1.0,
+                    ptr::null(),
+                    match mode {
+                        MeasuringMode::GdiNatural => 1,
+                        _ => 0,
+                    },
+                    &mut ptr,
+                ),
+            };
 
             if SUCCEEDED(hr) {
+                origin::record(ptr, self.factory.raw_f());
                 let ptr = ComPtr::from_raw(ptr);
                 if self.centered {
                     ptr.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_CENTER);
                 }
+                if let Some(orientation) = self.vertical_glyph_orientation {
+                    let layout = query_interface::<IDWriteTextLayout2>(&ptr)?;
+                    let hr = layout.SetVerticalGlyphOrientation(orientation as u32);
+                    if !SUCCEEDED(hr) {
+                        return Err(hr.into());
+                    }
+                }
+                if let Some(alignment) = self.optical_alignment {
+                    let layout = query_interface::<IDWriteTextLayout2>(&ptr)?;
+                    let hr = layout.SetOpticalAlignment(alignment as u32);
+                    if !SUCCEEDED(hr) {
+                        return Err(hr.into());
+                    }
+                }
 
-                Ok(TextLayout::from_ptr(ptr))
+                let mut layout = TextLayout::from_ptr(ptr)
+                    .with_absolute_offset(self.absolute_offset)
+                    .with_text_len(text.len() as u32)
+                    .with_factory(&self.factory);
+                if self.retain_text {
+                    let text = text.into_owned().into_boxed_slice();
+                    layout = layout.with_source_text(text);
+                }
+                if self.auto_optical_size {
+                    layout.set_automatic_font_axes(AutomaticFontAxes::OpticalSize)?;
+                }
+                Ok(layout)
             } else {
                 Err(hr.into())
             }
@@ -78,12 +243,57 @@ impl<'a> TextLayoutBuilder<'a> {
         self
     }
 
-    /// Specify the text from a UTF-16 string.
+    /// Specify the text from a UTF-16 string, such as a buffer kept by a text editing engine.
+    /// The buffer is used as-is: it does not need a NUL terminator, and any NULs inside it
+    /// are laid out as part of the text.
     pub fn with_text(mut self, text: &'a [u16]) -> Self {
         self.text = Some(Cow::Borrowed(text));
         self
     }
 
+    /// Specify the text as a window into a larger UTF-8 document, such as a single visible line.
+    /// Only `text[window]` is laid out; `absolute_offset` is the UTF-16 position of the start of
+    /// the window in the document, and is used by the `*_absolute` methods on the built layout.
+    /// `build` fails with `E_INVALIDARG` if the window would end past `u32::MAX` in the
+    /// document.
+    ///
+    /// Panics if the window doesn't lie on `char` boundaries.
+    pub fn with_str_window(
+        mut self,
+        text: &str,
+        window: Range<usize>,
+        absolute_offset: u32,
+    ) -> Self {
+        self.text = Some(text[window].to_wide().into());
+        self.absolute_offset = absolute_offset;
+        self
+    }
+
+    /// Specify the text as a window into a larger UTF-16 document. Only `text[window]` is laid
+    /// out, without copying it; `absolute_offset` is the document position of the start of the
+    /// window, and is used by the `*_absolute` methods on the built layout. `build` fails with
+    /// `E_INVALIDARG` if the window would end past `u32::MAX` in the document.
+    pub fn with_text_window(
+        mut self,
+        text: &'a [u16],
+        window: Range<usize>,
+        absolute_offset: u32,
+    ) -> Self {
+        self.text = Some(Cow::Borrowed(&text[window]));
+        self.absolute_offset = absolute_offset;
+        self
+    }
+
+    /// Keep a copy of the text on the built layout, for
+    /// [`TextLayout::text_utf16`](struct.TextLayout.html#method.text_utf16) and for
+    /// [`break_conditions_at`](struct.TextLayout.html#method.break_conditions_at), which fails
+    /// without it. Off by default, as the copy costs as much memory again as the text; the
+    /// length is always kept.
+    pub fn with_retained_text(mut self, retain_text: bool) -> Self {
+        self.retain_text = retain_text;
+        self
+    }
+
     /// Specify the text format (Font) used with this text.
     pub fn with_format(mut self, format: &'a TextFormat) -> Self {
         self.format = Some(format);
@@ -112,4 +322,151 @@ impl<'a> TextLayoutBuilder<'a> {
         self.centered = centered;
         self
     }
+
+    /// Specify the measuring mode used to lay out the text. The GDI modes create a
+    /// GDI-compatible layout at 1 pixel per DIP, where glyph advances are rounded to
+    /// whole pixels.
+    pub fn with_measuring_mode(mut self, mode: MeasuringMode) -> Self {
+        self.measuring_mode = mode;
+        self
+    }
+
+    /// Specify how glyphs are oriented when the reading direction is vertical. Requires
+    /// Windows 8.1; on older versions `build` fails with `E_NOINTERFACE`.
+    pub fn with_vertical_glyph_orientation(
+        mut self,
+        orientation: VerticalGlyphOrientation,
+    ) -> Self {
+        self.vertical_glyph_orientation = Some(orientation);
+        self
+    }
+
+    /// Specify how glyphs are aligned to the margins. Requires Windows 8.1; on older versions
+    /// `build` fails with `E_NOINTERFACE`.
+    pub fn with_optical_alignment(mut self, alignment: OpticalAlignment) -> Self {
+        self.optical_alignment = Some(alignment);
+        self
+    }
+
+    /// Specify whether the optical size axis of variable fonts should follow the font size,
+    /// so that e.g. small text uses the sturdier shapes designed for it. Fonts without an
+    /// `opsz` axis are unaffected. Requires Windows 10 April 2018 Update; on older versions
+    /// `build` fails with `E_NOINTERFACE` when this is enabled.
+    pub fn with_auto_optical_size(mut self, enabled: bool) -> Self {
+        self.auto_optical_size = enabled;
+        self
+    }
+
+    /// Set the font weight of a range of the text.
+    pub fn with_range_weight(self, weight: FontWeight, range: impl Into<TextRange>) -> Self {
+        self.with_range(AttributeSpan::Weight {
+            range: range.into(),
+            value: weight,
+        })
+    }
+
+    /// Set the font style of a range of the text.
+    pub fn with_range_style(self, style: FontStyle, range: impl Into<TextRange>) -> Self {
+        self.with_range(AttributeSpan::Style {
+            range: range.into(),
+            value: style,
+        })
+    }
+
+    /// Set the font stretch of a range of the text.
+    pub fn with_range_stretch(self, stretch: FontStretch, range: impl Into<TextRange>) -> Self {
+        self.with_range(AttributeSpan::Stretch {
+            range: range.into(),
+            value: stretch,
+        })
+    }
+
+    /// Set the font size in DIPs of a range of the text.
+    pub fn with_range_font_size(self, size: f32, range: impl Into<TextRange>) -> Self {
+        self.with_range(AttributeSpan::FontSize {
+            range: range.into(),
+            value: size,
+        })
+    }
+
+    /// Set the font family of a range of the text.
+    pub fn with_range_family(self, family: &'a str, range: impl Into<TextRange>) -> Self {
+        self.with_range(AttributeSpan::FontFamily {
+            range: range.into(),
+            value: family,
+        })
+    }
+
+    /// Set the locale of a range of the text.
+    pub fn with_range_locale(self, locale: &'a str, range: impl Into<TextRange>) -> Self {
+        self.with_range(AttributeSpan::Locale {
+            range: range.into(),
+            value: locale,
+        })
+    }
+
+    /// Set the locales of several ranges of the text at once, e.g. to mark a phrase in another
+    /// language so that it is shaped and broken into lines by that language's rules.
+    pub fn with_locale_ranges(self, ranges: &[(TextRange, &'a str)]) -> Self {
+        ranges.iter().fold(self, |builder, &(range, locale)| {
+            builder.with_range_locale(locale, range)
+        })
+    }
+
+    /// Set whether a range of the text is underlined.
+    pub fn with_range_underline(self, underline: bool, range: impl Into<TextRange>) -> Self {
+        self.with_range(AttributeSpan::Underline {
+            range: range.into(),
+            value: underline,
+        })
+    }
+
+    /// Set whether a range of the text is struck through.
+    pub fn with_range_strikethrough(
+        self,
+        strikethrough: bool,
+        range: impl Into<TextRange>,
+    ) -> Self {
+        self.with_range(AttributeSpan::Strikethrough {
+            range: range.into(),
+            value: strikethrough,
+        })
+    }
+
+    /// Set whether pair kerning is enabled for a range of the text.
+    pub fn with_range_pair_kerning(self, enabled: bool, range: impl Into<TextRange>) -> Self {
+        self.with_range(AttributeSpan::PairKerning {
+            range: range.into(),
+            value: enabled,
+        })
+    }
+
+    /// Set the font features of a range of the text.
+    pub fn with_range_typography(
+        self,
+        typography: &'a Typography,
+        range: impl Into<TextRange>,
+    ) -> Self {
+        self.with_range(AttributeSpan::Typography {
+            range: range.into(),
+            value: typography,
+        })
+    }
+
+    /// Replace a range of the text with an inline object.
+    pub fn with_range_inline_object(
+        self,
+        obj: &'a InlineObject,
+        range: impl Into<TextRange>,
+    ) -> Self {
+        self.with_range(AttributeSpan::InlineObject {
+            range: range.into(),
+            value: obj,
+        })
+    }
+
+    fn with_range(mut self, span: AttributeSpan<'a>) -> Self {
+        self.ranges.push(span);
+        self
+    }
 }
@@ -1,20 +1,37 @@
 //! TextLayout and types for building new ones.
 
-use crate::descriptions::TextRange;
+use crate::descriptions::{FontAxisValue, TextRange};
 use crate::effects::client_effect::ClientEffect;
 use crate::effects::DrawingEffect;
-use crate::enums::{FontStretch, FontStyle, FontWeight};
+use crate::enums::{
+    AutomaticFontAxes, BreakCondition, FontStretch, FontStyle, FontWeight, GridFitMode,
+    MeasuringMode, ParagraphAlignment, ReadingDirection, RenderingMode1, TextAlignment,
+    VerticalGlyphOrientation,
+};
 use crate::factory::Factory;
-use crate::font_collection::FontCollection;
-use crate::inline_object::InlineObject;
+use crate::font::IFont;
+use crate::font_collection::{FontCollection, IFontCollection};
+use crate::font_face::{self, IFontFace};
+use crate::font_family::IFontFamily;
+use crate::helpers::{get_wide_string, query_interface};
+use crate::inline_object::{BreakConditions, InlineObject};
 use crate::metrics::cluster::ClusterMetrics;
 use crate::metrics::hit_test::HitTestMetrics;
 use crate::metrics::line::LineMetrics;
+use crate::metrics::line1::LineMetrics1;
 use crate::metrics::overhang::OverhangMetrics;
 use crate::metrics::text::TextMetrics;
+use crate::metrics::text1::TextMetrics1;
+use crate::origin;
+use crate::profiling;
+use crate::rendering_params::IRenderingParams;
+use crate::sys::{IDWriteTextLayout4, DWRITE_FONT_AXIS_VALUE};
 use crate::text_format::ITextFormat;
+use crate::text_renderer::raster::{RasterImage, RasterOptions, RasterRenderer};
+use crate::text_renderer::recording;
 use crate::text_renderer::DrawContext;
 use crate::text_renderer::ITextRenderer;
+use crate::text_renderer::TextRenderer;
 use crate::typography::Typography;
 
 use std::mem::MaybeUninit;
@@ -22,18 +39,44 @@ use std::mem::MaybeUninit;
 use checked_enum::UncheckedEnum;
 use com_wrapper::ComWrapper;
 use dcommon::Error;
-use winapi::shared::winerror::{SUCCEEDED, S_OK};
+#[cfg(feature = "d2d")]
+use math2d::Color;
+use math2d::{Point2f, Rectf};
+use winapi::shared::winerror::{
+    ERROR_INSUFFICIENT_BUFFER, ERROR_INVALID_STATE, E_INVALIDARG, HRESULT_FROM_WIN32, SUCCEEDED,
+    S_OK,
+};
+#[cfg(feature = "d2d")]
+use winapi::um::d2d1::{
+    ID2D1Brush, ID2D1RenderTarget, D2D1_COLOR_F, D2D1_DRAW_TEXT_OPTIONS_NONE, D2D1_POINT_2F,
+};
 use winapi::um::dwrite::*;
+use winapi::um::dwrite_1::IDWriteTextLayout1;
+use winapi::um::dwrite_2::IDWriteTextLayout2;
+use winapi::um::dwrite_3::{IDWriteTextLayout3, DWRITE_LINE_METRICS1};
 use wio::com::ComPtr;
 use wio::wide::ToWide;
 
 const E_NOT_SUFFICIENT_BUFFER: i32 = -2147024774;
 
+/// The width of the rectangle returned by `ITextLayout::caret_rect`, in DIPs.
+pub const CARET_WIDTH: f32 = 1.0;
+
+#[doc(inline)]
+pub use self::builder::{LayoutBuildError, TextLayoutBuilder};
 #[doc(inline)]
-pub use self::builder::TextLayoutBuilder;
+pub use self::runs::AttributeRuns;
+#[doc(inline)]
+pub use self::spans::{AttributeSpan, SpanError};
 
+mod breaks;
 #[doc(hidden)]
 pub mod builder;
+pub mod parallel;
+#[doc(hidden)]
+pub mod runs;
+#[doc(hidden)]
+pub mod spans;
 
 #[derive(Copy, Clone, Debug)]
 /// Represents a value that has an associated range for which the text has the
@@ -69,25 +112,431 @@ impl<T> std::ops::Deref for RangeValue<T> {
 /// A function result that is either a pair of T and an associated text range, or a DWriteError.
 pub type RangeResult<T> = Result<RangeValue<T>, Error>;
 
-#[derive(ComWrapper)]
-#[com(send, sync, debug)]
-#[repr(transparent)]
 /// The TextLayout interface represents a block of text after it has been fully
 /// analyzed and formatted.
+///
+/// A layout built from a window of a larger document remembers where that window starts, so
+/// the `*_absolute` methods can take and return positions in the whole document. Positions
+/// passed to the `ITextLayout` methods are always relative to the start of the layout's text.
+/// The offset lives in the wrapper, not in DirectWrite's object: a layout made with
+/// `from_raw` or `from_ptr`, or one round-tripped through `into_raw`, has an offset of 0.
 pub struct TextLayout {
     ptr: ComPtr<IDWriteTextLayout>,
+    absolute_offset: u32,
+    text_len: Option<u32>,
+    text: Option<Box<[u16]>>,
+    // The factory the layout was built with, for analyzing its text
+    factory: Option<Factory>,
 }
 
+raw_interop!(TextLayout, IDWriteTextLayout);
+
 impl TextLayout {
     /// Initialize a builder for a new TextLayout.
-    pub fn create<'a>(factory: &'a Factory) -> TextLayoutBuilder<'a> {
+    pub fn create<'a>(factory: &Factory) -> TextLayoutBuilder<'a> {
         unsafe { TextLayoutBuilder::new(&*factory.get_raw()) }
     }
+
+    /// Rasterizes the layout on the CPU, with its origin at pixel `(0, 0)`. ClearType output
+    /// is BGRA blended against `options.background`, while grayscale output is an A8 mask.
+    /// See [`RasterRenderer`][1] for what gets drawn.
+    ///
+    /// [1]: text_renderer/raster/struct.RasterRenderer.html
+    pub fn rasterize(
+        &self,
+        factory: &Factory,
+        options: &RasterOptions,
+        pixels_per_dip: f32,
+    ) -> Result<RasterImage, Error> {
+        let canvas = RasterRenderer::new(factory, *options, pixels_per_dip);
+        let mut renderer = TextRenderer::new(canvas.clone());
+        // SAFETY: the renderer is our own RasterRenderer, which never reads the context
+        unsafe {
+            self.draw(&mut renderer, 0.0, 0.0, &DrawContext::from_usize(0))?;
+        }
+        Ok(canvas.finish())
+    }
+
+    pub(crate) fn with_absolute_offset(mut self, absolute_offset: u32) -> Self {
+        self.absolute_offset = absolute_offset;
+        self
+    }
+
+    pub(crate) fn with_text_len(mut self, text_len: u32) -> Self {
+        self.text_len = Some(text_len);
+        self
+    }
+
+    pub(crate) fn with_factory(mut self, factory: &Factory) -> Self {
+        self.factory = Some(factory.clone());
+        self
+    }
+
+    pub(crate) fn with_source_text(mut self, text: Box<[u16]>) -> Self {
+        self.text = Some(text);
+        self
+    }
+
+    /// The UTF-16 text this layout was built from, exactly as it was passed to the builder.
+    /// DirectWrite never hands the text back, so this is only `Some` for layouts built with
+    /// [`with_retained_text`][1], and never for ones wrapped with `from_raw`.
+    ///
+    /// [1]: struct.TextLayoutBuilder.html#method.with_retained_text
+    pub fn text_utf16(&self) -> Option<&[u16]> {
+        self.text.as_ref().map(|text| &text[..])
+    }
+
+    /// The length of the layout's text in UTF-16 code units, which is one past the last valid
+    /// text position. For layouts not built by a [`TextLayoutBuilder`][1] the length isn't
+    /// known, and this is the total length of all lines.
+    ///
+    /// [1]: struct.TextLayoutBuilder.html
+    pub fn text_len(&self) -> u32 {
+        match self.text_len {
+            Some(len) => len,
+            None => self.line_metrics().iter().map(|line| line.length).sum(),
+        }
+    }
+
+    /// The document position of the first UTF-16 code unit in this layout. Zero unless the
+    /// layout was built with [`with_str_window`][1] or [`with_text_window`][2].
+    ///
+    /// [1]: struct.TextLayoutBuilder.html#method.with_str_window
+    /// [2]: struct.TextLayoutBuilder.html#method.with_text_window
+    pub fn absolute_offset(&self) -> u32 {
+        self.absolute_offset
+    }
+
+    /// Converts a position in this layout to a position in the document, or `None` if the
+    /// document position would not fit in a `u32`.
+    pub fn to_absolute(&self, position: u32) -> Option<u32> {
+        self.absolute_offset.checked_add(position)
+    }
+
+    /// Converts a range in this layout, such as one reported with an attribute or an item of
+    /// the `*_runs` iterators, to a range in the document. `None` if it would end past
+    /// `u32::MAX`.
+    pub fn to_absolute_range(&self, range: TextRange) -> Option<TextRange> {
+        let start = self.to_absolute(range.start)?;
+        start.checked_add(range.length)?;
+        Some(TextRange {
+            start,
+            length: range.length,
+        })
+    }
+
+    /// Positions reported by DirectWrite are at most the length of the text, and `build`
+    /// rejects windows which would end past `u32::MAX`, so these never overflow.
+    fn offset_position(&self, position: u32) -> u32 {
+        self.absolute_offset + position
+    }
+
+    /// Converts a document position to a layout position, failing with `E_INVALIDARG` if it
+    /// comes before the layout's window.
+    fn relative_or_invalid(&self, absolute_position: u32) -> Result<u32, Error> {
+        self.to_relative(absolute_position)
+            .ok_or_else(|| Error::from(E_INVALIDARG))
+    }
+
+    /// Clips a document range to the layout's window and converts it to a layout range, or
+    /// `None` if nothing of it is left. Fails with `E_INVALIDARG` if the range would end past
+    /// `u32::MAX`.
+    fn window_range(
+        &self,
+        absolute_position: u32,
+        length: u32,
+    ) -> Result<Option<TextRange>, Error> {
+        let end = absolute_position
+            .checked_add(length)
+            .ok_or_else(|| Error::from(E_INVALIDARG))?;
+        let start = absolute_position.max(self.absolute_offset);
+        if end <= start {
+            return Ok(None);
+        }
+        Ok(Some(TextRange {
+            start: start - self.absolute_offset,
+            length: end - start,
+        }))
+    }
+
+    /// Converts a document position to a position in this layout, or `None` if the position
+    /// comes before the layout's window.
+    pub fn to_relative(&self, absolute_position: u32) -> Option<u32> {
+        absolute_position.checked_sub(self.absolute_offset)
+    }
+
+    /// Like [`hit_test_point`][1], but the returned metrics use document positions.
+    ///
+    /// [1]: trait.ITextLayout.html#method.hit_test_point
+    pub fn hit_test_point_absolute(&self, point_x: f32, point_y: f32) -> HitTestPoint {
+        let mut hit = self.hit_test_point(point_x, point_y);
+        hit.metrics.text_position = self.offset_position(hit.metrics.text_position);
+        hit
+    }
+
+    /// Like [`insertion_index_at`][1], but returns a document position.
+    ///
+    /// [1]: trait.ITextLayout.html#method.insertion_index_at
+    pub fn insertion_index_at_absolute(&self, point_x: f32, point_y: f32) -> u32 {
+        self.offset_position(self.insertion_index_at(point_x, point_y))
+    }
+
+    /// Like [`hit_test_text_position`][1], but takes and returns document positions. Fails with
+    /// `E_INVALIDARG` if the position is outside the layout's window.
+    ///
+    /// [1]: trait.ITextLayout.html#method.hit_test_text_position
+    pub fn hit_test_text_position_absolute(
+        &self,
+        absolute_position: u32,
+        trailing: bool,
+    ) -> Result<HitTestTextPosition, Error> {
+        let position = self.relative_or_invalid(absolute_position)?;
+        let mut hit = self.hit_test_text_position(position, trailing)?;
+        hit.metrics.text_position = self.offset_position(hit.metrics.text_position);
+        Ok(hit)
+    }
+
+    /// Like [`hit_test_text_range`][1], but takes and returns document positions. The part of
+    /// the range before the layout's window is ignored. Fails with `E_INVALIDARG` if the range
+    /// would end past `u32::MAX`.
+    ///
+    /// [1]: trait.ITextLayout.html#method.hit_test_text_range
+    pub fn hit_test_text_range_absolute(
+        &self,
+        absolute_position: u32,
+        length: u32,
+        origin_x: f32,
+        origin_y: f32,
+        metrics: &mut Vec<HitTestMetrics>,
+    ) -> Result<(), Error> {
+        let range = match self.window_range(absolute_position, length)? {
+            Some(range) => range,
+            None => {
+                metrics.clear();
+                return Ok(());
+            }
+        };
+
+        self.hit_test_text_range(range.start, range.length, origin_x, origin_y, metrics)?;
+        for m in metrics.iter_mut() {
+            m.text_position = self.offset_position(m.text_position);
+        }
+        Ok(())
+    }
+
+    /// Like [`selection_rects`][1], but takes a document range. The part of the range before
+    /// the layout's window is ignored, so a selection which starts on an earlier line is
+    /// highlighted from the start of this one. Fails with `E_INVALIDARG` if the range would end
+    /// past `u32::MAX`.
+    ///
+    /// [1]: trait.ITextLayout.html#method.selection_rects
+    pub fn selection_rects_absolute(
+        &self,
+        range: impl Into<TextRange>,
+        origin: Point2f,
+    ) -> Result<Vec<Rectf>, Error> {
+        let range = range.into();
+        match self.window_range(range.start, range.length)? {
+            Some(range) => self.selection_rects(range, origin),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Like [`next_caret_position`][1], but takes and returns document positions. Fails with
+    /// `E_INVALIDARG` if the position comes before the layout's window.
+    ///
+    /// [1]: trait.ITextLayout.html#method.next_caret_position
+    pub fn next_caret_position_absolute(&self, absolute_position: u32) -> Result<u32, Error> {
+        let position = self.relative_or_invalid(absolute_position)?;
+        Ok(self.offset_position(self.next_caret_position(position)))
+    }
+
+    /// Like [`previous_caret_position`][1], but takes and returns document positions. Fails
+    /// with `E_INVALIDARG` if the position comes before the layout's window.
+    ///
+    /// [1]: trait.ITextLayout.html#method.previous_caret_position
+    pub fn previous_caret_position_absolute(&self, absolute_position: u32) -> Result<u32, Error> {
+        let position = self.relative_or_invalid(absolute_position)?;
+        Ok(self.offset_position(self.previous_caret_position(position)))
+    }
+
+    /// Like [`caret_position_for_line_offset_with_goal`][1], but takes and returns document
+    /// positions. Fails with `E_INVALIDARG` if the position comes before the layout's window.
+    /// Moving past the first or last line of the window stops at its start or end; moving on
+    /// to the neighbouring layout is up to the caller.
+    ///
+    /// [1]: trait.ITextLayout.html#method.caret_position_for_line_offset_with_goal
+    pub fn caret_position_for_line_offset_absolute(
+        &self,
+        absolute_position: u32,
+        lines: i32,
+        goal_x: f32,
+    ) -> Result<u32, Error> {
+        let position = self.relative_or_invalid(absolute_position)?;
+        let moved = self.caret_position_for_line_offset_with_goal(position, lines, goal_x);
+        Ok(self.offset_position(moved))
+    }
+
+    /// Gets the effective line breaking conditions around the cluster containing `position`:
+    /// `preceding` for a break before the cluster and `following` for a break after it.
+    ///
+    /// The conditions come from DirectWrite's line breaking analysis of the layout's text, with
+    /// the layout's locales, combining the conditions reported on either side of each cluster
+    /// boundary. The first cluster has a `Neutral` preceding condition. When the cluster is an
+    /// inline object, or an inline object is right before or after it, the object's own
+    /// conditions are combined in with
+    /// [`BreakCondition::combine`](../enums/enum.BreakCondition.html#method.combine), which
+    /// recovers e.g. a `MustBreak` that the text only reports as a break opportunity.
+    ///
+    /// DirectWrite never hands the text back, so this fails with
+    /// `HRESULT_FROM_WIN32(ERROR_INVALID_STATE)` unless the layout was built with
+    /// [`with_retained_text`][1]. Fails with `E_INVALIDARG` if `position` is past the end of
+    /// the text.
+    ///
+    /// [1]: struct.TextLayoutBuilder.html#method.with_retained_text
+    pub fn break_conditions_at(&self, position: u32) -> Result<BreakConditions, Error> {
+        let breakpoints = match (&self.text, &self.factory) {
+            (Some(text), Some(factory)) => breaks::line_breakpoints(self, text, factory)?,
+            _ => return Err(HRESULT_FROM_WIN32(ERROR_INVALID_STATE).into()),
+        };
+
+        let clusters = self.cluster_metrics();
+        let mut start = 0;
+        let index = clusters
+            .iter()
+            .position(|cluster| {
+                let end = start + cluster.length as u32;
+                if position < end {
+                    return true;
+                }
+                start = end;
+                false
+            })
+            .ok_or_else(|| Error::from(E_INVALIDARG))?;
+        let end = start + clusters[index].length as u32;
+
+        // The condition between the code units before and at `at`
+        let between = |at: u32| match at as usize {
+            0 => UncheckedEnum::from(BreakCondition::Neutral as u32),
+            at if at >= breakpoints.len() => breakpoints[at - 1].break_condition_after,
+            at => BreakCondition::combine(
+                breakpoints[at - 1].break_condition_after,
+                breakpoints[at].break_condition_before,
+            ),
+        };
+        let mut preceding = between(start);
+        let mut following = between(end);
+
+        if let Some(object) = self.inline_object(start)?.value {
+            let own = object.break_conditions();
+            preceding = BreakCondition::combine(preceding, own.preceding);
+            following = BreakCondition::combine(following, own.following);
+        }
+        if index > 0 {
+            if let Some(object) = self.inline_object(start - 1)?.value {
+                let before = object.break_conditions().following;
+                preceding = BreakCondition::combine(preceding, before);
+            }
+        }
+        if index + 1 < clusters.len() {
+            if let Some(object) = self.inline_object(end)?.value {
+                let next = object.break_conditions().preceding;
+                following = BreakCondition::combine(following, next);
+            }
+        }
+
+        Ok(BreakConditions {
+            preceding,
+            following,
+        })
+    }
+
+    /// Quickly determines whether the text is plain left-to-right text which needs no special
+    /// handling, i.e. it contains no bidirectional text and no scripts which require complex
+    /// shaping (such as Arabic or Devanagari). Editors can use this to skip computing the visual
+    /// order of the text for caret movement and selection.
+    ///
+    /// The scripts are checked with a text analyzer from the factory the layout was built
+    /// with, so this fails with `HRESULT_FROM_WIN32(ERROR_INVALID_STATE)` for layouts wrapped
+    /// with `from_raw`.
+    pub fn is_simple_ltr(&self) -> Result<bool, Error> {
+        let factory = match &self.factory {
+            Some(factory) => factory,
+            None => return Err(HRESULT_FROM_WIN32(ERROR_INVALID_STATE).into()),
+        };
+
+        // A reordering depth of 1 means there is no bidirectional text in the layout.
+        if self.metrics().max_bidi_reordering_depth > 1 {
+            return Ok(false);
+        }
+
+        let runs = recording::record_glyph_runs(self, 1.0)?;
+        if runs.iter().any(|run| run.bidi_level % 2 == 1) {
+            return Ok(false);
+        }
+
+        let analyzer = factory.create_text_analyzer()?;
+        for run in &runs {
+            if !analyzer.is_text_simple(&run.string, &run.font_face)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+unsafe impl Send for TextLayout {}
+unsafe impl Sync for TextLayout {}
+
+impl ComWrapper for TextLayout {
+    type Interface = IDWriteTextLayout;
+
+    unsafe fn get_raw(&self) -> *mut IDWriteTextLayout {
+        self.ptr.as_raw()
+    }
+
+    unsafe fn into_raw(self) -> *mut IDWriteTextLayout {
+        self.ptr.into_raw()
+    }
+
+    unsafe fn from_raw(raw: *mut IDWriteTextLayout) -> Self {
+        Self::from_ptr(ComPtr::from_raw(raw))
+    }
+
+    unsafe fn from_ptr(ptr: ComPtr<IDWriteTextLayout>) -> Self {
+        TextLayout {
+            ptr,
+            absolute_offset: 0,
+            text_len: None,
+            text: None,
+            factory: None,
+        }
+    }
+
+    unsafe fn into_ptr(self) -> ComPtr<IDWriteTextLayout> {
+        self.ptr
+    }
+}
+
+impl std::fmt::Debug for TextLayout {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("TextLayout")
+            .field("ptr", &self.ptr.as_raw())
+            .field("absolute_offset", &self.absolute_offset)
+            .field("text_len", &self.text_len)
+            .field("text_retained", &self.text.is_some())
+            .finish()
+    }
 }
 
 pub unsafe trait ITextLayout: ITextFormat {
     /// Determines the minimum possible width the layout can be set to without emergency breaking
     /// between the characters of whole words occurring.
+    ///
+    /// Trailing whitespace doesn't count towards this width, so layouts which are empty or only
+    /// contain whitespace and newlines return exactly 0.0. Treat that as "fits anywhere", not as
+    /// an unknown width.
     fn determine_min_width(&self) -> f32 {
         unsafe {
             let mut value = 0.0;
@@ -105,17 +554,72 @@ pub unsafe trait ITextLayout: ITextFormat {
         context: &DrawContext,
     ) -> Result<(), Error> {
         unsafe {
-            let hr = self.raw_tl().Draw(
-                context.ptr(),
-                renderer.raw_tr() as *const _ as *mut _,
-                origin_x,
-                origin_y,
-            );
-            if SUCCEEDED(hr) {
-                Ok(())
-            } else {
-                Err(hr.into())
+            let start = match profiling::start() {
+                Some(start) => start,
+                None => {
+                    return draw_raw(
+                        self.raw_tl(),
+                        renderer.raw_tr(),
+                        origin_x,
+                        origin_y,
+                        context,
+                    )
+                }
+            };
+
+            let (result, runs) = profiling::count_runs(|| {
+                draw_raw(
+                    self.raw_tl(),
+                    renderer.raw_tr(),
+                    origin_x,
+                    origin_y,
+                    context,
+                )
+            });
+            result?;
+            profiling::report(start, |p, dur| p.layout_drawn(runs, dur));
+            Ok(())
+        }
+    }
+
+    /// Draws the layout onto a Direct2D render target with its top-left corner at `origin`,
+    /// filling the text with `color`. Text with a brush of its own set as the drawing effect
+    /// keeps that brush.
+    ///
+    /// Must be called between `BeginDraw` and `EndDraw` on the render target. Direct2D reports
+    /// errors from drawing when `EndDraw` is called, so only failing to create the brush is
+    /// returned here.
+    #[cfg(feature = "d2d")]
+    fn draw_colored(
+        &self,
+        render_target: &ID2D1RenderTarget,
+        origin: Point2f,
+        color: Color,
+    ) -> Result<(), Error> {
+        unsafe {
+            let color = D2D1_COLOR_F {
+                r: color.r,
+                g: color.g,
+                b: color.b,
+                a: color.a,
+            };
+            let mut brush = std::ptr::null_mut();
+            let hr = render_target.CreateSolidColorBrush(&color, std::ptr::null(), &mut brush);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
             }
+            let brush = ComPtr::from_raw(brush);
+
+            render_target.DrawTextLayout(
+                D2D1_POINT_2F {
+                    x: origin.x,
+                    y: origin.y,
+                },
+                self.raw_tl() as *const _ as *mut _,
+                brush.as_raw() as *mut ID2D1Brush,
+                D2D1_DRAW_TEXT_OPTIONS_NONE,
+            );
+            Ok(())
         }
     }
 
@@ -150,15 +654,92 @@ pub unsafe trait ITextLayout: ITextFormat {
         }
     }
 
-    /// Fill all of the Cluster metrics into a Vec.
+    /// Fill all of the Cluster metrics into a Vec. Empty layouts have no clusters and return an
+    /// empty Vec; a newline or space is a cluster of its own.
     fn cluster_metrics(&self) -> Vec<ClusterMetrics> {
         let count = self.cluster_metrics_count();
+        if count == 0 {
+            return Vec::new();
+        }
         let mut buf = Vec::with_capacity(count);
         unsafe { buf.set_len(count) };
         assert_eq!(self.cluster_metrics_slice(&mut buf), Ok(count));
         buf
     }
 
+    /// Pairs the starting text position of every cluster with the x offset of its trailing
+    /// edge, measured from the start of its line in logical order. The offsets are the running
+    /// sums of the cluster widths and start over at each line, so a cluster spans from the
+    /// previous entry's offset (or 0 at the start of a line) to its own. This maps x
+    /// coordinates to text positions without hit testing every position.
+    fn cluster_offsets(&self) -> Vec<(u32, f32)> {
+        let lines = self.line_metrics();
+        let mut lines = lines.iter();
+        let mut line_end = lines.next().map(|line| line.length).unwrap_or(0);
+
+        let clusters = self.cluster_metrics();
+        let mut offsets = Vec::with_capacity(clusters.len());
+        let mut position = 0;
+        let mut offset = 0.0;
+        for cluster in clusters {
+            while position >= line_end {
+                match lines.next() {
+                    Some(line) => line_end += line.length,
+                    None => break,
+                }
+                offset = 0.0;
+            }
+            offset += cluster.width;
+            offsets.push((position, offset));
+            position += cluster.length as u32;
+        }
+        offsets
+    }
+
+    /// Finds where lines may be broken, with one entry per UTF-16 code unit of the text. An entry
+    /// is true if a line can wrap directly after that code unit. Only the last code unit of a
+    /// cluster can be a break opportunity, so ligatures and surrogate pairs are never split.
+    /// Empty layouts return an empty Vec.
+    fn break_opportunities(&self) -> Vec<bool> {
+        let mut breaks = Vec::new();
+        for cluster in self.cluster_metrics() {
+            if cluster.length == 0 {
+                continue;
+            }
+            let inner = cluster.length as usize - 1;
+            breaks.extend(std::iter::repeat(false).take(inner));
+            breaks.push(cluster.can_wrap_line_after());
+        }
+        breaks
+    }
+
+    /// Gets the spacing added around each character at the specified text position. Requires
+    /// Windows 8; on older versions this fails with `E_NOINTERFACE`.
+    fn character_spacing(&self, position: u32) -> RangeResult<CharacterSpacing> {
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout1>(self.raw_tl())?;
+            let (mut leading, mut trailing, mut minimum_advance) = (0.0, 0.0, 0.0);
+            let mut range = MaybeUninit::uninit();
+            let hr = layout.GetCharacterSpacing(
+                position,
+                &mut leading,
+                &mut trailing,
+                &mut minimum_advance,
+                range.as_mut_ptr(),
+            );
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+
+            let spacing = CharacterSpacing {
+                leading,
+                trailing,
+                minimum_advance,
+            };
+            Ok((spacing, range.assume_init().into()).into())
+        }
+    }
+
     /// Get the drawing effect applied at the specified position
     fn drawing_effect(&self, position: u32) -> RangeResult<Option<ClientEffect>> {
         unsafe {
@@ -180,6 +761,39 @@ pub unsafe trait ITextLayout: ITextFormat {
         }
     }
 
+    /// Gets the variable font axis values applied at the specified text position, along with
+    /// the range of text which has the same values. Text without axis values set returns an
+    /// empty Vec.
+    ///
+    /// Requires Windows 10 April 2018 Update (version 1803); on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn font_axis_values(&self, position: u32) -> RangeResult<Vec<FontAxisValue>> {
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout4>(self.raw_tl())?;
+            let count = layout.GetFontAxisValueCount(position);
+            let mut values = vec![std::mem::zeroed::<DWRITE_FONT_AXIS_VALUE>(); count as usize];
+            let mut range = std::mem::zeroed();
+            let hr = layout.GetFontAxisValues(position, values.as_mut_ptr(), count, &mut range);
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+
+            let values = values.into_iter().map(FontAxisValue::from).collect();
+            Ok((values, range.into()).into())
+        }
+    }
+
+    /// Gets which font axes the layout derives automatically.
+    ///
+    /// Requires Windows 10 April 2018 Update (version 1803); on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn automatic_font_axes(&self) -> Result<UncheckedEnum<AutomaticFontAxes>, Error> {
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout4>(self.raw_tl())?;
+            Ok(layout.GetAutomaticFontAxes().into())
+        }
+    }
+
     /// Gets the font collection of the text at the specified position. Also returns the text range
     /// which has identical formatting to the current character.
     fn font_collection(&self, position: u32) -> RangeResult<FontCollection> {
@@ -192,6 +806,7 @@ pub unsafe trait ITextLayout: ITextFormat {
             if res < 0 {
                 return Err(res.into());
             }
+            origin::inherit(collection, self.raw_tl() as *const _);
             Ok((FontCollection::from_raw(collection), range.into()).into())
         }
     }
@@ -199,27 +814,29 @@ pub unsafe trait ITextLayout: ITextFormat {
     /// Get the font family name applied at the specified text position.
     fn font_family_name(&self, position: u32) -> RangeResult<String> {
         unsafe {
-            let mut len = 0;
             let mut range = std::mem::zeroed();
-            let hr = self
-                .raw_tl()
-                .GetFontFamilyNameLength(position, &mut len, &mut range);
-            if !SUCCEEDED(hr) {
-                return Err(hr.into());
-            }
-
-            let mut buf = vec![0u16; len as usize + 1];
-            let hr = self.raw_tl().GetFontFamilyName(
-                position,
-                buf.as_mut_ptr(),
-                buf.len() as u32,
-                &mut range,
-            );
-            if !SUCCEEDED(hr) {
-                return Err(hr.into());
-            }
-
-            Ok((String::from_utf16_lossy(&buf), range.into()).into())
+            let name = get_wide_string(
+                || {
+                    let (mut len, mut scratch) = (0, std::mem::zeroed());
+                    let hr =
+                        self.raw_tl()
+                            .GetFontFamilyNameLength(position, &mut len, &mut scratch);
+                    if SUCCEEDED(hr) {
+                        Ok(len)
+                    } else {
+                        Err(hr.into())
+                    }
+                },
+                |buf| {
+                    self.raw_tl().GetFontFamilyName(
+                        position,
+                        buf.as_mut_ptr(),
+                        buf.len() as u32,
+                        &mut range,
+                    )
+                },
+            )?;
+            Ok((name, range.into()).into())
         }
     }
 
@@ -346,38 +963,151 @@ pub unsafe trait ITextLayout: ITextFormat {
     }
 
     /// Retrieves the information about each individual text line of the text string.
+    ///
+    /// There is always at least one line, even for an empty layout, where it has a length of 0
+    /// and the height of the format's font. Text ending in a newline has an extra empty line
+    /// after it, so `"\n"` has two lines.
     fn line_metrics(&self) -> Vec<LineMetrics> {
         let count = self.line_metrics_count();
+        if count == 0 {
+            return Vec::new();
+        }
         let mut buf = Vec::with_capacity(count);
         unsafe { buf.set_len(count) };
         assert_eq!(self.line_metrics_slice(&mut buf), Ok(count));
         buf
     }
 
-    /// Gets the locale name applied to the text at the specified text position.
-    fn locale_name(&self, position: u32) -> RangeResult<String> {
+    /// Retrieves the metrics of each line including the leading above and below it, which is
+    /// needed to align the first baseline of a paragraph exactly.
+    ///
+    /// Requires Windows 10; on older versions this fails with `E_NOINTERFACE`.
+    fn line_metrics1(&self) -> Result<Vec<LineMetrics1>, Error> {
         unsafe {
-            let mut len = 0;
-            let mut range = std::mem::zeroed();
-            let hr = self
-                .raw_tl()
-                .GetLocaleNameLength(position, &mut len, &mut range);
-            if !SUCCEEDED(hr) {
-                return Err(hr.into());
+            let layout = query_interface::<IDWriteTextLayout3>(self.raw_tl())?;
+            let mut count = 0;
+            layout.GetLineMetrics(std::ptr::null_mut(), 0, &mut count);
+
+            // The line count can change between the two calls if the layout is invalidated,
+            // so keep growing the buffer until everything fits.
+            loop {
+                let mut buf = vec![std::mem::zeroed::<DWRITE_LINE_METRICS1>(); count as usize];
+                let hr = layout.GetLineMetrics(buf.as_mut_ptr(), count, &mut count);
+                if hr == HRESULT_FROM_WIN32(ERROR_INSUFFICIENT_BUFFER) {
+                    continue;
+                }
+                if !SUCCEEDED(hr) {
+                    return Err(hr.into());
+                }
+
+                buf.truncate(count as usize);
+                return Ok(buf.into_iter().map(LineMetrics1::from).collect());
             }
+        }
+    }
 
-            let mut buf = vec![0u16; len as usize + 1];
-            let hr = self.raw_tl().GetLocaleName(
-                position,
-                buf.as_mut_ptr(),
-                buf.len() as u32,
-                &mut range,
-            );
-            if !SUCCEEDED(hr) {
-                return Err(hr.into());
+    /// Whether the text ends in a newline, so that the caret after it sits on an empty final
+    /// line.
+    fn ends_with_newline(&self) -> bool {
+        self.line_metrics()
+            .iter()
+            .rev()
+            .find(|line| line.length > 0)
+            .map(|line| line.newline_length > 0)
+            .unwrap_or(false)
+    }
+
+    /// Splits the text into paragraphs at its hard line breaks. Each range includes the
+    /// newline ending it. Text ending in a newline has an empty final paragraph after it, and
+    /// empty text is a single empty paragraph.
+    fn paragraph_ranges(&self) -> Vec<TextRange> {
+        let mut paragraphs = Vec::new();
+        let mut start = 0;
+        let mut end = 0;
+        for line in self.line_metrics() {
+            end += line.length;
+            if line.newline_length > 0 {
+                paragraphs.push(TextRange {
+                    start,
+                    length: end - start,
+                });
+                start = end;
+            }
+        }
+        paragraphs.push(TextRange {
+            start,
+            length: end - start,
+        });
+        paragraphs
+    }
+
+    /// Describes where each line's text and box are. Text ending in a newline always gets an
+    /// empty final line after it, at the same place `hit_test_text_position` puts the end of
+    /// the text, which is where an editor shows the caret after the user presses Enter.
+    fn line_info(&self) -> Vec<LineInfo> {
+        let lines = self.line_metrics();
+        let mut top = self
+            .hit_test_text_position(0, false)
+            .map(|hit| hit.metrics.position.y)
+            .unwrap_or(0.0);
+
+        let mut info = Vec::with_capacity(lines.len() + 1);
+        let mut start = 0;
+        for line in &lines {
+            info.push(LineInfo {
+                range: TextRange {
+                    start,
+                    length: line.length,
+                },
+                newline_length: line.newline_length,
+                top,
+                height: line.height,
+                baseline: line.baseline,
+            });
+            start += line.length;
+            top += line.height;
+        }
+
+        // DirectWrite normally reports the empty line itself; make sure it is always there.
+        if let Some(&last) = info.last() {
+            if last.newline_length > 0 {
+                info.push(LineInfo {
+                    range: TextRange { start, length: 0 },
+                    newline_length: 0,
+                    top,
+                    ..last
+                });
             }
+        }
+        info
+    }
 
-            Ok((String::from_utf16_lossy(&buf), range.into()).into())
+    /// Gets the locale name applied to the text at the specified text position.
+    fn locale_name(&self, position: u32) -> RangeResult<String> {
+        unsafe {
+            let mut range = std::mem::zeroed();
+            let name = get_wide_string(
+                || {
+                    let (mut len, mut scratch) = (0, std::mem::zeroed());
+                    let hr = self
+                        .raw_tl()
+                        .GetLocaleNameLength(position, &mut len, &mut scratch);
+                    if SUCCEEDED(hr) {
+                        Ok(len)
+                    } else {
+                        Err(hr.into())
+                    }
+                },
+                |buf| {
+                    self.raw_tl().GetLocaleName(
+                        position,
+                        buf.as_mut_ptr(),
+                        buf.len() as u32,
+                        &mut range,
+                    )
+                },
+            )?;
+            Ok((name, range.into()).into())
         }
     }
 
@@ -392,6 +1122,10 @@ pub unsafe trait ITextLayout: ITextFormat {
     }
 
     /// Retrieves overall metrics for the formatted string.
+    ///
+    /// An empty layout still has one line, so its `height` is the line height of the format's
+    /// font and its `width` is 0. Whitespace is only counted by
+    /// `width_including_trailing_whitespace`, and a trailing newline adds a line.
     fn metrics(&self) -> TextMetrics {
         unsafe {
             let mut metrics = std::mem::zeroed();
@@ -400,6 +1134,68 @@ pub unsafe trait ITextLayout: ITextFormat {
         }
     }
 
+    /// Retrieves the overall metrics for the formatted string, including the height with
+    /// trailing whitespace. Requires Windows 8.1; on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn metrics1(&self) -> Result<TextMetrics1, Error> {
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout2>(self.raw_tl())?;
+            let mut metrics = std::mem::zeroed();
+            let hr = layout.GetMetrics(&mut metrics);
+            if SUCCEEDED(hr) {
+                Ok(metrics.into())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Computes where the text block starts inside the layout box when laid out with the given
+    /// alignments, using the same rules as DirectWrite: the widest line (ignoring trailing
+    /// whitespace) is aligned within the layout width, and the lines as a whole within the
+    /// layout height. For right-to-left paragraphs leading and justified text start from the
+    /// right edge. Only horizontal reading directions are supported.
+    ///
+    /// This is useful for drawing backgrounds or selections for alignments other than the
+    /// layout's current ones without building another layout.
+    fn visual_origin(&self, alignment: TextAlignment, paragraph: ParagraphAlignment) -> Point2f {
+        let metrics = self.metrics();
+        let rtl = self.reading_direction().value == ReadingDirection::RightToLeft as u32;
+        let free_x = metrics.layout_width - metrics.alignment_width(false);
+        let free_y = metrics.layout_height - metrics.height;
+
+        let x = match (alignment, rtl) {
+            (TextAlignment::Center, _) => free_x / 2.0,
+            (TextAlignment::Leading, false) | (TextAlignment::Justified, false) => 0.0,
+            (TextAlignment::Trailing, true) => 0.0,
+            (TextAlignment::Trailing, false) => free_x,
+            (TextAlignment::Leading, true) | (TextAlignment::Justified, true) => free_x,
+        };
+        let y = match paragraph {
+            ParagraphAlignment::Near => 0.0,
+            ParagraphAlignment::Far => free_y,
+            ParagraphAlignment::Center => free_y / 2.0,
+        };
+        Point2f::new(x, y)
+    }
+
+    /// Discards the computed layout, so that the next query or draw lays the text out again.
+    /// DirectWrite caches the metrics reported by inline objects, so call this after a custom
+    /// inline object changes its size for `metrics()` and friends to see the new size.
+    ///
+    /// Requires Windows 10; on older versions this fails with `E_NOINTERFACE`.
+    fn invalidate_layout(&mut self) -> Result<(), Error> {
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout3>(self.raw_tl())?;
+            let hr = layout.InvalidateLayout();
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
     /// Returns the overhangs (in DIPs) of the layout and all objects contained in it, including
     /// text glyphs and inline objects.
     fn overhang_metrics(&self) -> OverhangMetrics {
@@ -410,6 +1206,156 @@ pub unsafe trait ITextLayout: ITextFormat {
         }
     }
 
+    /// Gets whether legacy pair kerning is enabled at the specified text position. Requires
+    /// Windows 8; on older versions this fails with `E_NOINTERFACE`.
+    fn pair_kerning(&self, position: u32) -> RangeResult<bool> {
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout1>(self.raw_tl())?;
+            let mut kerning = 0;
+            let mut range = MaybeUninit::uninit();
+            let hr = layout.GetPairKerning(position, &mut kerning, range.as_mut_ptr());
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+
+            Ok((kerning != 0, range.assume_init().into()).into())
+        }
+    }
+
+    /// Returns the overhangs (in DIPs) of the glyphs on a single line, relative to that line's
+    /// box. The line box spans the full layout width horizontally and the line's height
+    /// vertically, so a positive `bottom` means descenders reach into the next line.
+    ///
+    /// This is computed from the ink bounds of the glyph runs on the line, so unlike
+    /// `overhang_metrics` it does not take inline objects into account. A line without any
+    /// visible glyphs reports the negated size of its box on every side.
+    fn line_overhang(&self, line_index: usize) -> Result<OverhangMetrics, Error> {
+        let lines = self.line_metrics();
+        if line_index >= lines.len() {
+            return Err(E_INVALIDARG.into());
+        }
+
+        let line_start: u32 = lines[..line_index].iter().map(|l| l.length).sum();
+        let line_end = line_start + lines[line_index].length;
+        let line_top = self.metrics().top
+            + lines[..line_index]
+                .iter()
+                .map(|l| l.height)
+                .sum::<f32>();
+        let line_bottom = line_top + lines[line_index].height;
+        let line_right = self.max_width();
+
+        let mut ink: Option<math2d::Rectf> = None;
+        for run in recording::record_glyph_runs(self, 1.0)? {
+            if run.text_position < line_start || run.text_position >= line_end {
+                continue;
+            }
+
+            if let Some(b) = run.glyph_run().bounds(run.baseline_origin)? {
+                ink = Some(match ink {
+                    Some(i) => math2d::Rectf {
+                        left: i.left.min(b.left),
+                        top: i.top.min(b.top),
+                        right: i.right.max(b.right),
+                        bottom: i.bottom.max(b.bottom),
+                    },
+                    None => b,
+                });
+            }
+        }
+
+        Ok(match ink {
+            Some(ink) => OverhangMetrics {
+                left: -ink.left,
+                top: line_top - ink.top,
+                right: ink.right - line_right,
+                bottom: ink.bottom - line_bottom,
+            },
+            None => OverhangMetrics {
+                left: -line_right,
+                top: line_top - line_bottom,
+                right: -line_right,
+                bottom: line_top - line_bottom,
+            },
+        })
+    }
+
+    /// Reports the rendering mode DirectWrite recommends for each glyph run in the layout when
+    /// drawn with the given rendering parameters. This is intended as a debugging aid for
+    /// figuring out why some text renders blurry or aliased.
+    ///
+    /// Each run's mode comes from `GetRecommendedRenderingMode` on its font face, so runs too
+    /// large to rasterize as glyphs are reported as `Outline` at whatever size DirectWrite
+    /// switches for the antialiasing `params` ask for. Where the font face supports
+    /// `IDWriteFontFace3` the extended recommendation is used, which also reports the grid
+    /// fitting mode; otherwise `grid_fit_mode` is `None`.
+    fn rendering_report(
+        &self,
+        params: &dyn IRenderingParams,
+        pixels_per_dip: f32,
+    ) -> Result<Vec<RunRenderingInfo>, Error> {
+        let runs = recording::record_glyph_runs(self, pixels_per_dip)?;
+        let mut report = Vec::with_capacity(runs.len());
+        for run in runs {
+            let (rendering_mode, grid_fit_mode) = unsafe {
+                font_face::recommended_rendering(
+                    run.font_face.raw_fontface(),
+                    run.font_em_size,
+                    pixels_per_dip,
+                    run.is_sideways,
+                    run.measuring_mode.value,
+                    params,
+                )?
+            };
+
+            let family_name = ITextLayout::font_family_name(self, run.text_position)?.value;
+            report.push(RunRenderingInfo {
+                range: TextRange {
+                    start: run.text_position,
+                    length: run.text_length,
+                },
+                family_name,
+                font_em_size: run.font_em_size,
+                measuring_mode: run.measuring_mode,
+                rendering_mode,
+                grid_fit_mode,
+                renders_as_outline: rendering_mode.value == RenderingMode1::Outline as u32,
+            });
+        }
+
+        Ok(report)
+    }
+
+    /// Chooses between ClearType and grayscale antialiasing for drawing the layout on a target
+    /// with the given rendering parameters.
+    ///
+    /// ClearType blends each color channel against the pixels already on the target, so it
+    /// only looks right on an opaque background; on a target with transparency (a layered
+    /// window, a texture composited later) it leaves colored fringes and this always returns
+    /// `Grayscale`. Otherwise ClearType is recommended unless the parameters turn it off:
+    /// a ClearType level of 0, flat pixel geometry, or an `Aliased` or `Outline` rendering
+    /// mode, none of which render per-channel coverage.
+    fn recommended_antialias_mode(
+        &self,
+        params: &dyn IRenderingParams,
+        is_target_transparent: bool,
+    ) -> TextAntialiasMode {
+        if is_target_transparent {
+            return TextAntialiasMode::Grayscale;
+        }
+
+        let mode = params.rendering_mode1().value;
+        let per_channel = mode != RenderingMode1::Aliased as u32
+            && mode != RenderingMode1::Outline as u32
+            && params.cleartype_level() > 0.0
+            && params.pixel_geometry().value != PixelGeometry::Flat as u32;
+        if per_channel {
+            TextAntialiasMode::ClearType
+        } else {
+            TextAntialiasMode::Grayscale
+        }
+    }
+
     /// Returns whether the text at the specified position has strikethrough applied.
     fn strikethrough(&self, position: u32) -> RangeResult<bool> {
         unsafe {
@@ -441,6 +1387,52 @@ pub unsafe trait ITextLayout: ITextFormat {
         }
     }
 
+    /// Iterates the runs of one formatting attribute over the whole text, with `get` looking
+    /// up the attribute at a position the way e.g. `font_weight` does. The text length is
+    /// taken from the line metrics.
+    ///
+    /// Every item starts where the previous one ended, and the iterator stops at the end of
+    /// the text even if a lookup reports an empty range, so it always terminates.
+    fn runs_of<T, F>(&self, get: F) -> AttributeRuns<Self, F>
+    where
+        F: FnMut(&Self, u32) -> RangeResult<T>,
+    {
+        let len = self.line_metrics().iter().map(|line| line.length).sum();
+        AttributeRuns::new(self, len, get)
+    }
+
+    /// Iterates the runs of font weight over the whole text.
+    fn font_weight_runs(&self) -> AttributeRuns<Self, fn(&Self, u32) -> RangeResult<FontWeight>> {
+        self.runs_of(<Self as ITextLayout>::font_weight as fn(&Self, u32) -> _)
+    }
+
+    /// Iterates the runs of font size over the whole text.
+    fn font_size_runs(&self) -> AttributeRuns<Self, fn(&Self, u32) -> RangeResult<f32>> {
+        self.runs_of(<Self as ITextLayout>::font_size as fn(&Self, u32) -> _)
+    }
+
+    /// Iterates the runs of font family name over the whole text.
+    fn font_family_name_runs(&self) -> AttributeRuns<Self, fn(&Self, u32) -> RangeResult<String>> {
+        self.runs_of(<Self as ITextLayout>::font_family_name as fn(&Self, u32) -> _)
+    }
+
+    /// Iterates the runs of font style over the whole text.
+    fn font_style_runs(
+        &self,
+    ) -> AttributeRuns<Self, fn(&Self, u32) -> RangeResult<UncheckedEnum<FontStyle>>> {
+        self.runs_of(<Self as ITextLayout>::font_style as fn(&Self, u32) -> _)
+    }
+
+    /// Iterates the runs of underline over the whole text.
+    fn underline_runs(&self) -> AttributeRuns<Self, fn(&Self, u32) -> RangeResult<bool>> {
+        self.runs_of(<Self as ITextLayout>::underline as fn(&Self, u32) -> _)
+    }
+
+    /// Iterates the runs of strikethrough over the whole text.
+    fn strikethrough_runs(&self) -> AttributeRuns<Self, fn(&Self, u32) -> RangeResult<bool>> {
+        self.runs_of(<Self as ITextLayout>::strikethrough as fn(&Self, u32) -> _)
+    }
+
     /// Gets the typography description applied to the text at the specified text position.
     fn typography(&self, position: u32) -> RangeResult<Typography> {
         unsafe {
@@ -454,10 +1446,22 @@ pub unsafe trait ITextLayout: ITextFormat {
         }
     }
 
+    /// Gets how glyphs are oriented when the reading direction is vertical. Requires
+    /// Windows 8.1; on older versions this fails with `E_NOINTERFACE`.
+    fn vertical_glyph_orientation(&self) -> Result<UncheckedEnum<VerticalGlyphOrientation>, Error> {
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout2>(self.raw_tl())?;
+            Ok(layout.GetVerticalGlyphOrientation().into())
+        }
+    }
+
     /// The application calls this function passing in a specific pixel location relative to the
     /// top-left location of the layout box and obtains the information about the correspondent
     /// hit-test metrics of the text string where the hit-test has occurred. Returns None if the
     /// specified pixel location is outside the string.
+    ///
+    /// On an empty layout every point misses: `is_inside` is false and the metrics describe
+    /// text position 0 with a length of 0.
     fn hit_test_point(&self, point_x: f32, point_y: f32) -> HitTestPoint {
         unsafe {
             let mut trail = 0;
@@ -479,24 +1483,149 @@ pub unsafe trait ITextLayout: ITextFormat {
         }
     }
 
+    /// Finds the text position that a caret should move to, or that dropped text should be
+    /// inserted at, for a point relative to the top-left of the layout box. A hit on the
+    /// trailing half of a cluster resolves to the position after the whole cluster. Always 0 on
+    /// an empty layout.
+    fn insertion_index_at(&self, point_x: f32, point_y: f32) -> u32 {
+        let hit = self.hit_test_point(point_x, point_y);
+        if hit.is_trailing_hit {
+            hit.metrics.text_position + hit.metrics.length
+        } else {
+            hit.metrics.text_position
+        }
+    }
+
+    /// Finds where the caret moves when the user presses the key for the next character in
+    /// logical order. Whole clusters are skipped, so the caret never stops inside a surrogate
+    /// pair or a ligature; a position inside one moves to its end. Stays put at the end of the
+    /// text, and is always 0 on an empty layout.
+    fn next_caret_position(&self, position: u32) -> u32 {
+        let mut end = 0;
+        for cluster in self.cluster_metrics() {
+            end += cluster.length as u32;
+            if end > position {
+                return end;
+            }
+        }
+        end
+    }
+
+    /// Finds where the caret moves when the user presses the key for the previous character in
+    /// logical order. Whole clusters are skipped, so a position inside a surrogate pair or a
+    /// ligature moves to its start. Stays put at 0.
+    fn previous_caret_position(&self, position: u32) -> u32 {
+        let mut start = 0;
+        let mut previous = 0;
+        for cluster in self.cluster_metrics() {
+            if start >= position {
+                break;
+            }
+            previous = start;
+            start += cluster.length as u32;
+        }
+        previous
+    }
+
+    /// Finds where the caret moves when the user presses the up (negative `lines`) or down
+    /// (positive `lines`) key, keeping the caret at the same x coordinate it has now. Moving
+    /// up from the first line goes to the start of the text, and moving down from the last line
+    /// goes to its end.
+    ///
+    /// Editors usually remember the x coordinate the caret had before the first of several
+    /// vertical moves, so that passing through a short line doesn't pull the caret to the left.
+    /// Use [`caret_position_for_line_offset_with_goal`][1] with that coordinate for this.
+    ///
+    /// [1]: #method.caret_position_for_line_offset_with_goal
+    fn caret_position_for_line_offset(&self, position: u32, lines: i32) -> u32 {
+        let goal_x = self
+            .hit_test_text_position(position, false)
+            .map(|hit| hit.point_x)
+            .unwrap_or(0.0);
+        self.caret_position_for_line_offset_with_goal(position, lines, goal_x)
+    }
+
+    /// Like [`caret_position_for_line_offset`][1], but places the caret as close as possible to
+    /// `goal_x` on the target line, which is relative to the left of the layout box like the
+    /// `point_x` returned by `hit_test_text_position`.
+    ///
+    /// [1]: #method.caret_position_for_line_offset
+    fn caret_position_for_line_offset_with_goal(
+        &self,
+        position: u32,
+        lines: i32,
+        goal_x: f32,
+    ) -> u32 {
+        let info = self.line_info();
+        let last = match info.last() {
+            Some(last) => last,
+            None => return 0,
+        };
+        let text_end = last.range.start + last.range.length;
+
+        let current = info
+            .iter()
+            .rposition(|line| line.range.start <= position)
+            .unwrap_or(0) as i64;
+        let target = current + lines as i64;
+        if target < 0 {
+            return 0;
+        }
+        if target >= info.len() as i64 {
+            return text_end;
+        }
+
+        let line = &info[target as usize];
+        let y = line.top + line.height / 2.0;
+        let hit = self.insertion_index_at(goal_x, y);
+
+        // Landing past the end of the line would put the caret after the newline, i.e. at the
+        // start of the next line.
+        let line_end = line.range.start + line.range.length - line.newline_length;
+        hit.max(line.range.start).min(line_end)
+    }
+
+    /// Finds the text selected by dragging from `start` to `end`, both relative to the top-left
+    /// of the layout box. Each point resolves to the insertion position nearest to it, and the
+    /// range covers the text between them regardless of which point comes first, so dragging
+    /// upwards or to the left selects the same text as the opposite drag.
+    fn range_between_points(&self, start: Point2f, end: Point2f) -> TextRange {
+        let a = self.insertion_index_at(start.x, start.y);
+        let b = self.insertion_index_at(end.x, end.y);
+        TextRange {
+            start: a.min(b),
+            length: a.max(b) - a.min(b),
+        }
+    }
+
     /// The application calls this function to get the pixel location relative to the top-left of
     /// the layout box given the text position and the logical side of the position. This function
     /// is normally used as part of caret positioning of text where the caret is drawn at the
     /// location corresponding to the current text editing position. It may also be used as a way
     /// to programmatically obtain the geometry of a particular text position in UI automation.
-    fn hit_test_text_position(&self, position: u32, trailing: bool) -> Option<HitTestTextPosition> {
-        let trailing = if trailing { 0 } else { 1 };
+    ///
+    /// Position 0 of an empty layout is valid, and reports the start of the empty line.
+    /// Positions past the end of the text fail with `E_INVALIDARG`.
+    fn hit_test_text_position(
+        &self,
+        position: u32,
+        trailing: bool,
+    ) -> Result<HitTestTextPosition, Error> {
         unsafe {
             let (mut x, mut y) = (0.0, 0.0);
             let mut metrics = std::mem::zeroed();
-            let res =
-                self.raw_tl()
-                    .HitTestTextPosition(position, trailing, &mut x, &mut y, &mut metrics);
-            if res != S_OK {
-                return None;
+            let hr = self.raw_tl().HitTestTextPosition(
+                position,
+                trailing as i32,
+                &mut x,
+                &mut y,
+                &mut metrics,
+            );
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
             }
 
-            Some(HitTestTextPosition {
+            Ok(HitTestTextPosition {
                 metrics: metrics.into(),
                 point_x: x,
                 point_y: y,
@@ -504,9 +1633,59 @@ pub unsafe trait ITextLayout: ITextFormat {
         }
     }
 
+    /// Gets the rectangle of a caret placed at the leading or trailing edge of a text position,
+    /// relative to the top-left of the layout box. The caret is `CARET_WIDTH` DIPs wide and as
+    /// tall as the text at that position.
+    ///
+    /// This works on empty layouts and on empty lines, where there is no text to measure: the
+    /// caret then takes the height of the line, or failing that the height of the format's
+    /// font, so that an editor always has somewhere to draw it.
+    fn caret_rect(&self, position: u32, trailing: bool) -> Result<Rectf, Error> {
+        let (x, y, mut height) = unsafe {
+            let (mut x, mut y) = (0.0, 0.0);
+            let mut metrics = std::mem::zeroed::<DWRITE_HIT_TEST_METRICS>();
+            let hr = self.raw_tl().HitTestTextPosition(
+                position,
+                trailing as i32,
+                &mut x,
+                &mut y,
+                &mut metrics,
+            );
+            if !SUCCEEDED(hr) {
+                return Err(hr.into());
+            }
+            (x, y, metrics.height)
+        };
+
+        if height <= 0.0 {
+            let mut top = 0.0;
+            for line in self.line_metrics() {
+                if top + line.height > y {
+                    height = line.height;
+                    break;
+                }
+                top += line.height;
+            }
+        }
+        if height <= 0.0 {
+            height = format_line_height(self);
+        }
+
+        Ok(Rectf {
+            left: x,
+            top: y,
+            right: x + CARET_WIDTH,
+            bottom: y + height,
+        })
+    }
+
     /// The application calls this function to get a set of hit-test metrics corresponding to a
     /// range of text positions. One of the main usages is to implement highlight selection of
     /// the text string. origin_x and origin_y are added to the hit-test metrics returned.
+    ///
+    /// Ranges over whitespace or newlines produce boxes like any other text, but a range with a
+    /// length of 0 (such as the whole of an empty layout) may produce a single zero-width box
+    /// or none at all. Use `caret_rect` to find where such a range is.
     fn hit_test_text_range(
         &self,
         position: u32,
@@ -518,40 +1697,170 @@ pub unsafe trait ITextLayout: ITextFormat {
         unsafe {
             metrics.clear();
 
-            // Calculate the total number of items we need
-            let mut actual_count = 0;
-            let hr = self.raw_tl().HitTestTextRange(
-                position,
-                length,
-                origin_x,
-                origin_y,
-                std::ptr::null_mut(),
-                0,
-                &mut actual_count,
-            );
-            match hr {
-                E_NOT_SUFFICIENT_BUFFER => (),
-                S_OK => return Ok(()),
-                hr => return Err(hr.into()),
+            // The count can change between calls if the layout is modified from another
+            // thread, so keep growing the buffer until everything fits.
+            loop {
+                let mut actual_count = 0;
+                let hr = self.raw_tl().HitTestTextRange(
+                    position,
+                    length,
+                    origin_x,
+                    origin_y,
+                    metrics.as_mut_ptr() as *mut _,
+                    metrics.capacity() as u32,
+                    &mut actual_count,
+                );
+                match hr {
+                    S_OK => {
+                        metrics.set_len(actual_count as usize);
+                        return Ok(());
+                    }
+                    E_NOT_SUFFICIENT_BUFFER => metrics.reserve(actual_count as usize),
+                    hr => return Err(hr.into()),
+                }
+            }
+        }
+    }
+
+    /// Measures the width of a range of the text, e.g. to size a highlight or place a popup
+    /// under a word, without hit-testing it.
+    ///
+    /// The widths of the clusters overlapping the range are summed line by line, and the
+    /// widest line is returned when the range wraps. A cluster which is only partly in the
+    /// range, such as a ligature, counts in full. The range is clamped to the end of the
+    /// text, and a range starting past the end fails with `E_INVALIDARG`.
+    fn range_width(&self, position: u32, length: u32) -> Result<f32, Error> {
+        let clusters = self.cluster_metrics();
+        let total: u32 = clusters.iter().map(|c| c.length as u32).sum();
+        if position > total {
+            return Err(E_INVALIDARG.into());
+        }
+        let end = position.saturating_add(length).min(total);
+
+        let lines = self.line_metrics();
+        let mut line_ends = lines.iter().scan(0, |end, line| {
+            *end += line.length;
+            Some(*end)
+        });
+        let mut line_end = line_ends.next().unwrap_or(total);
+
+        let (mut widest, mut current) = (0.0f32, 0.0);
+        let mut start = 0;
+        for cluster in &clusters {
+            while start >= line_end {
+                widest = widest.max(current);
+                current = 0.0;
+                line_end = line_ends.next().unwrap_or(std::u32::MAX);
+            }
+            let cluster_end = start + cluster.length as u32;
+            if cluster_end > position && start < end {
+                current += cluster.width;
             }
+            start = cluster_end;
+        }
+        Ok(widest.max(current))
+    }
 
-            metrics.reserve(actual_count as usize);
-            let hr = self.raw_tl().HitTestTextRange(
-                position,
-                length,
-                origin_x,
-                origin_y,
-                metrics.as_mut_ptr() as *mut _,
-                metrics.capacity() as u32,
-                &mut actual_count,
-            );
-            if hr != S_OK {
-                metrics.set_len(0);
-                return Err(hr.into());
+    /// Like [`hit_test_text_range`](#method.hit_test_text_range), but returns the metrics in
+    /// a new Vec. `origin` is added to every box. Use `HitTestMetrics::rect` to turn the boxes
+    /// into selection rectangles.
+    fn hit_test_text_range_vec(
+        &self,
+        position: u32,
+        length: u32,
+        origin: Point2f,
+    ) -> Result<Vec<HitTestMetrics>, Error> {
+        let mut metrics = Vec::new();
+        self.hit_test_text_range(position, length, origin.x, origin.y, &mut metrics)?;
+        Ok(metrics)
+    }
+
+    /// Finds the rectangles to highlight for a selection of `range`, offset by `origin`. Boxes
+    /// with no width are dropped and boxes which touch on the same line are merged, so a
+    /// selection within one line of text in a single direction is one rectangle. A selection
+    /// spanning text of both directions can be several disjoint rectangles on one line, as its
+    /// ends are in different places on screen.
+    ///
+    /// The rectangles are sorted from top to bottom, and from left to right within a line.
+    fn selection_rects(
+        &self,
+        range: impl Into<TextRange>,
+        origin: Point2f,
+    ) -> Result<Vec<Rectf>, Error> {
+        const EPSILON: f32 = 0.01;
+
+        let range = range.into();
+        let mut rects: Vec<Rectf> = self
+            .hit_test_text_range_vec(range.start, range.length, origin)?
+            .iter()
+            .map(|metrics| metrics.rect())
+            .filter(|rect| rect.right - rect.left > 0.0)
+            .collect();
+        rects.sort_by(|a, b| {
+            (a.top, a.left)
+                .partial_cmp(&(b.top, b.left))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mut merged: Vec<Rectf> = Vec::with_capacity(rects.len());
+        for rect in rects {
+            if let Some(last) = merged.last_mut() {
+                let same_line = (last.top - rect.top).abs() < EPSILON
+                    && (last.bottom - rect.bottom).abs() < EPSILON;
+                if same_line && rect.left <= last.right + EPSILON {
+                    last.right = last.right.max(rect.right);
+                    continue;
+                }
             }
+            merged.push(rect);
+        }
+        Ok(merged)
+    }
+
+    /// Sets the spacing added before (`leading`) and after (`trailing`) each character in a
+    /// range of text, in DIPs. Negative values tighten the text. `minimum_advance` keeps the
+    /// advance of each character from dropping below that width, and must not be negative.
+    /// Requires Windows 8; on older versions this fails with `E_NOINTERFACE`.
+    fn set_character_spacing(
+        &mut self,
+        leading: f32,
+        trailing: f32,
+        minimum_advance: f32,
+        range: impl Into<TextRange>,
+    ) -> Result<(), Error> {
+        let range = range.into().into();
 
-            metrics.set_len(actual_count as usize);
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout1>(self.raw_tl())?;
+            let hr = layout.SetCharacterSpacing(leading, trailing, minimum_advance, range);
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Applies many ranged attributes in order, e.g. the spans of a syntax highlighter.
+    /// Each span is applied with its `set_*` method as if called one by one, but a span which
+    /// fails doesn't stop the rest from being applied; every failure is reported together.
+    fn apply_attributes(&mut self, spans: &[AttributeSpan]) -> Result<(), Vec<SpanError>> {
+        let failures: Vec<SpanError> = spans
+            .iter()
+            .enumerate()
+            .filter_map(|(index, span)| {
+                span.apply(self).err().map(|error| SpanError {
+                    index,
+                    attribute: span.name(),
+                    range: span.range(),
+                    error,
+                })
+            })
+            .collect();
+        if failures.is_empty() {
             Ok(())
+        } else {
+            Err(failures)
         }
     }
 
@@ -579,6 +1888,48 @@ pub unsafe trait ITextLayout: ITextFormat {
         }
     }
 
+    /// Sets the variable font axis values for text within a text range, e.g. to pick a weight
+    /// between the named instances of a variable font. Axes the font doesn't have are ignored,
+    /// and an empty slice removes the values from the range.
+    ///
+    /// Requires Windows 10 April 2018 Update (version 1803); on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn set_font_axis_values(
+        &mut self,
+        values: &[FontAxisValue],
+        range: impl Into<TextRange>,
+    ) -> Result<(), Error> {
+        let range = range.into().into();
+        let values: Vec<DWRITE_FONT_AXIS_VALUE> = values.iter().map(|&v| v.into()).collect();
+
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout4>(self.raw_tl())?;
+            let hr = layout.SetFontAxisValues(values.as_ptr(), values.len() as u32, range);
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
+    /// Sets which font axes the layout derives automatically, e.g. to have the optical size
+    /// of variable fonts follow the font size without computing it for every range.
+    ///
+    /// Requires Windows 10 April 2018 Update (version 1803); on older versions this fails with
+    /// `E_NOINTERFACE`.
+    fn set_automatic_font_axes(&mut self, axes: AutomaticFontAxes) -> Result<(), Error> {
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout4>(self.raw_tl())?;
+            let hr = layout.SetAutomaticFontAxes(axes as u32);
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
     /// Sets the font collection for text within a text range.
     fn set_font_collection(
         &mut self,
@@ -766,6 +2117,26 @@ pub unsafe trait ITextLayout: ITextFormat {
         }
     }
 
+    /// Enables or disables legacy pair kerning for a range of text. Requires Windows 8; on older
+    /// versions this fails with `E_NOINTERFACE`.
+    fn set_pair_kerning(
+        &mut self,
+        enabled: bool,
+        range: impl Into<TextRange>,
+    ) -> Result<(), Error> {
+        let range = range.into().into();
+
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout1>(self.raw_tl())?;
+            let hr = layout.SetPairKerning(enabled as i32, range);
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
     /// Sets strikethrough for text within a specified text range.
     fn set_strikethrough(
         &mut self,
@@ -816,6 +2187,23 @@ pub unsafe trait ITextLayout: ITextFormat {
         }
     }
 
+    /// Sets how glyphs are oriented when the reading direction is vertical. Requires
+    /// Windows 8.1; on older versions this fails with `E_NOINTERFACE`.
+    fn set_vertical_glyph_orientation(
+        &mut self,
+        orientation: VerticalGlyphOrientation,
+    ) -> Result<(), Error> {
+        unsafe {
+            let layout = query_interface::<IDWriteTextLayout2>(self.raw_tl())?;
+            let hr = layout.SetVerticalGlyphOrientation(orientation as u32);
+            if SUCCEEDED(hr) {
+                Ok(())
+            } else {
+                Err(hr.into())
+            }
+        }
+    }
+
     unsafe fn raw_tl(&self) -> &IDWriteTextLayout;
 }
 
@@ -825,12 +2213,65 @@ unsafe impl ITextFormat for TextLayout {
     }
 }
 
+unsafe fn draw_raw(
+    layout: &IDWriteTextLayout,
+    renderer: &IDWriteTextRenderer,
+    origin_x: f32,
+    origin_y: f32,
+    context: &DrawContext,
+) -> Result<(), Error> {
+    let hr = layout.Draw(
+        context.ptr(),
+        renderer as *const _ as *mut _,
+        origin_x,
+        origin_y,
+    );
+    if SUCCEEDED(hr) {
+        Ok(())
+    } else {
+        Err(hr.into())
+    }
+}
+
 unsafe impl ITextLayout for TextLayout {
     unsafe fn raw_tl(&self) -> &IDWriteTextLayout {
         &self.ptr
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// The spacing around each character in a range of text, as returned by `character_spacing` on
+/// a TextLayout. All values are in DIPs.
+pub struct CharacterSpacing {
+    /// Space added before each character.
+    pub leading: f32,
+    /// Space added after each character.
+    pub trailing: f32,
+    /// The minimum advance width of each character.
+    pub minimum_advance: f32,
+}
+
+#[derive(Copy, Clone, Debug)]
+/// Where a line of text is, as returned by `line_info` on a TextLayout.
+pub struct LineInfo {
+    /// The text on the line, including trailing whitespace and the newline. Empty for the
+    /// line after text ending in a newline.
+    pub range: TextRange,
+
+    /// The number of code units in the newline sequence ending the line, or 0 if the line
+    /// was wrapped or ends the text.
+    pub newline_length: u32,
+
+    /// The distance from the top of the layout box to the top of the line.
+    pub top: f32,
+
+    /// The height of the line.
+    pub height: f32,
+
+    /// The distance from the top of the line to its baseline.
+    pub baseline: f32,
+}
+
 #[derive(Copy, Clone)]
 /// Results from calling `hit_test_point` on a TextLayout.
 pub struct HitTestPoint {
@@ -858,3 +2299,59 @@ pub struct HitTestTextPosition {
     /// The output geometry fully enclosing the specified text position.
     pub metrics: HitTestMetrics,
 }
+
+#[derive(Clone, Debug)]
+/// The rendering decisions DirectWrite made for a single glyph run, as returned by
+/// `rendering_report` on a TextLayout.
+pub struct RunRenderingInfo {
+    /// The range of text covered by the glyph run.
+    pub range: TextRange,
+
+    /// The font family applied to the text of the run.
+    pub family_name: String,
+
+    /// The logical size of the font in DIPs.
+    pub font_em_size: f32,
+
+    /// The measuring mode the layout used for the run.
+    pub measuring_mode: UncheckedEnum<MeasuringMode>,
+
+    /// The rendering mode recommended for the run. Faces which support the extended
+    /// recommendation can report modes only `RenderingMode1` has.
+    pub rendering_mode: UncheckedEnum<RenderingMode1>,
+
+    /// The grid fitting mode recommended for the run, if the font face could report it.
+    pub grid_fit_mode: Option<UncheckedEnum<GridFitMode>>,
+
+    /// Whether `rendering_mode` is `Outline`, i.e. the run will be filled as geometry rather
+    /// than rasterized as glyphs. This is usually because the run is too large for glyph
+    /// rendering, but rendering parameters which ask for outlines give it for any size.
+    pub renders_as_outline: bool,
+}
+
+/// The default line height of a format's font, for lines which have no text to measure.
+fn format_line_height<F>(format: &F) -> f32
+where
+    F: ITextFormat + ?Sized,
+{
+    let size = format.font_size();
+    let font = format.font_collection().and_then(|collection| {
+        let family = format.font_family_name()?;
+        let index = collection.find_family_by_name(&family)?;
+        // Style and stretch hardly change the vertical metrics, so the regular face will do
+        collection.family(index)?.first_matching_font(
+            format.font_weight(),
+            FontStretch::Normal,
+            FontStyle::Normal,
+        )
+    });
+
+    match font {
+        Some(font) => {
+            let m = font.metrics();
+            let units = m.ascent as f32 + m.descent as f32 + m.line_gap as f32;
+            units * size / m.design_units_per_em as f32
+        }
+        None => size,
+    }
+}
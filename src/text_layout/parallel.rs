@@ -0,0 +1,127 @@
+//! Building many layouts at once on a few worker threads.
+//!
+//! DirectWrite factories and text formats are free-threaded: once created they are immutable
+//! and can be used from any thread at the same time, which is why `Factory` and `TextFormat`
+//! are `Send + Sync`. Laying out text is also independent per layout, so creating layouts for
+//! e.g. every page of a book scales with the number of cores. A `TextLayout` itself is not
+//! synchronized internally, but each one is created and finished on a single worker before
+//! being handed back, so no two threads ever touch the same layout. None of this needs COM to
+//! be initialized on the worker threads, since DirectWrite doesn't use apartments.
+
+use crate::factory::Factory;
+use crate::text_format::TextFormat;
+use crate::text_layout::TextLayout;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use dcommon::Error;
+
+/// The most worker threads a `LayoutPool` uses by default.
+const DEFAULT_MAX_THREADS: usize = 8;
+
+#[derive(Copy, Clone, Debug)]
+/// Settings for building many layouts on a few scoped worker threads. The threads only live
+/// for the duration of each `layout_many` call.
+pub struct LayoutPool {
+    threads: usize,
+    sequential_threshold: usize,
+}
+
+impl LayoutPool {
+    /// Uses one thread per core, up to 8, and builds 16 or fewer texts on the calling thread.
+    pub fn new() -> LayoutPool {
+        let cores = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        LayoutPool {
+            threads: cores.min(DEFAULT_MAX_THREADS),
+            sequential_threshold: 16,
+        }
+    }
+
+    /// Sets the number of worker threads. 0 is treated as 1, which builds everything on the
+    /// calling thread.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Sets the number of texts up to which spawning threads isn't worth it, and the layouts
+    /// are built on the calling thread instead.
+    pub fn with_sequential_threshold(mut self, threshold: usize) -> Self {
+        self.sequential_threshold = threshold;
+        self
+    }
+
+    /// Builds a layout of `size` for each of `texts` with `format`. The results are in the
+    /// same order as the texts.
+    pub fn layout_many(
+        &self,
+        factory: &Factory,
+        format: &TextFormat,
+        texts: impl IntoIterator<Item = String>,
+        size: (f32, f32),
+    ) -> Vec<Result<TextLayout, Error>> {
+        let texts: Vec<String> = texts.into_iter().collect();
+        let build = |text: &str| {
+            TextLayout::create(factory)
+                .with_str(text)
+                .with_format(format)
+                .with_size(size.0, size.1)
+                .build()
+                .map_err(Error::from)
+        };
+
+        let threads = self.threads.min(texts.len());
+        if threads <= 1 || texts.len() <= self.sequential_threshold {
+            return texts.iter().map(|text| build(text)).collect();
+        }
+
+        // Workers take the next text as they finish, so a few long texts don't hold up the
+        // rest the way fixed chunks would.
+        let next = AtomicUsize::new(0);
+        let mut built: Vec<(usize, Result<TextLayout, Error>)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            match texts.get(i) {
+                                Some(text) => done.push((i, build(text))),
+                                None => return done,
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        });
+
+        built.sort_by_key(|&(i, _)| i);
+        built.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+impl Default for LayoutPool {
+    fn default() -> Self {
+        LayoutPool::new()
+    }
+}
+
+/// Builds a layout of `size` for each of `texts` with `format`, spreading the work over a few
+/// threads. The results are in the same order as the texts. See
+/// [`LayoutPool`](struct.LayoutPool.html) to control the number of threads.
+pub fn layout_many(
+    factory: &Factory,
+    format: &TextFormat,
+    texts: impl IntoIterator<Item = String>,
+    size: (f32, f32),
+) -> Vec<Result<TextLayout, Error>> {
+    LayoutPool::new().layout_many(factory, format, texts, size)
+}
@@ -0,0 +1,66 @@
+use crate::text_layout::{RangeResult, RangeValue};
+
+/// Walks the runs of one formatting attribute of a layout, as returned by `runs_of` and the
+/// `*_runs` methods of `ITextLayout`.
+///
+/// Each item covers the text from the end of the previous one up to where the attribute next
+/// changes, clipped to the end of the text, so together the items cover the text exactly
+/// once. A lookup which fails is yielded as an error and ends the iteration.
+pub struct AttributeRuns<'a, L: ?Sized, F> {
+    layout: &'a L,
+    get: F,
+    position: u32,
+    len: u32,
+}
+
+impl<'a, L: ?Sized, F> AttributeRuns<'a, L, F> {
+    pub(crate) fn new(layout: &'a L, len: u32, get: F) -> Self {
+        AttributeRuns {
+            layout,
+            get,
+            position: 0,
+            len,
+        }
+    }
+}
+
+impl<'a, L, T, F> Iterator for AttributeRuns<'a, L, F>
+where
+    L: ?Sized,
+    F: FnMut(&L, u32) -> RangeResult<T>,
+{
+    type Item = RangeResult<T>;
+
+    fn next(&mut self) -> Option<RangeResult<T>> {
+        if self.position >= self.len {
+            return None;
+        }
+
+        let run = match (self.get)(self.layout, self.position) {
+            Ok(run) => run,
+            Err(e) => {
+                self.position = self.len;
+                return Some(Err(e));
+            }
+        };
+
+        // The reported range can start before the position or reach past the end of the
+        // text, and an empty one would never make progress.
+        let start = self.position;
+        let end = run
+            .range
+            .start
+            .saturating_add(run.range.length)
+            .min(self.len)
+            .max(start + 1);
+        self.position = end;
+
+        let mut range = run.range;
+        range.start = start;
+        range.length = end - start;
+        Some(Ok(RangeValue {
+            range,
+            value: run.value,
+        }))
+    }
+}
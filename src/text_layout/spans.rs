@@ -0,0 +1,203 @@
+use crate::descriptions::TextRange;
+use crate::effects::ClientEffect;
+use crate::enums::{FontStretch, FontStyle, FontWeight};
+use crate::inline_object::InlineObject;
+use crate::text_layout::ITextLayout;
+use crate::typography::Typography;
+
+use dcommon::Error;
+
+#[derive(Copy, Clone)]
+/// One ranged formatting attribute, for applying many at once with
+/// [`ITextLayout::apply_attributes`](trait.ITextLayout.html#method.apply_attributes).
+pub enum AttributeSpan<'a> {
+    /// Sets the font weight.
+    Weight {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// The weight.
+        value: FontWeight,
+    },
+
+    /// Sets the font style.
+    Style {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// The style.
+        value: FontStyle,
+    },
+
+    /// Sets the font stretch.
+    Stretch {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// The stretch.
+        value: FontStretch,
+    },
+
+    /// Sets the font size in DIPs.
+    FontSize {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// The size.
+        value: f32,
+    },
+
+    /// Sets the font family.
+    FontFamily {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// The family name.
+        value: &'a str,
+    },
+
+    /// Sets whether the text is underlined.
+    Underline {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// Whether to underline.
+        value: bool,
+    },
+
+    /// Sets whether the text is struck through.
+    Strikethrough {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// Whether to strike through.
+        value: bool,
+    },
+
+    /// Sets whether pair kerning is enabled.
+    PairKerning {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// Whether to kern.
+        value: bool,
+    },
+
+    /// Sets the locale.
+    Locale {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// The locale name, e.g. `en-us`.
+        value: &'a str,
+    },
+
+    /// Sets the font features.
+    Typography {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// The features.
+        value: &'a Typography,
+    },
+
+    /// Replaces the text with an inline object.
+    InlineObject {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// The object.
+        value: &'a InlineObject,
+    },
+
+    /// Sets the drawing effect handed to the renderer.
+    DrawingEffect {
+        /// The text the attribute applies to.
+        range: TextRange,
+        /// The effect.
+        value: &'a ClientEffect,
+    },
+}
+
+impl<'a> AttributeSpan<'a> {
+    /// The text the attribute applies to.
+    pub fn range(&self) -> TextRange {
+        match *self {
+            AttributeSpan::Weight { range, .. }
+            | AttributeSpan::Style { range, .. }
+            | AttributeSpan::Stretch { range, .. }
+            | AttributeSpan::FontSize { range, .. }
+            | AttributeSpan::FontFamily { range, .. }
+            | AttributeSpan::Underline { range, .. }
+            | AttributeSpan::Strikethrough { range, .. }
+            | AttributeSpan::PairKerning { range, .. }
+            | AttributeSpan::Locale { range, .. }
+            | AttributeSpan::Typography { range, .. }
+            | AttributeSpan::InlineObject { range, .. }
+            | AttributeSpan::DrawingEffect { range, .. } => range,
+        }
+    }
+
+    /// A short description of the attribute, for error messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AttributeSpan::Weight { .. } => "font weight",
+            AttributeSpan::Style { .. } => "font style",
+            AttributeSpan::Stretch { .. } => "font stretch",
+            AttributeSpan::FontSize { .. } => "font size",
+            AttributeSpan::FontFamily { .. } => "font family",
+            AttributeSpan::Underline { .. } => "underline",
+            AttributeSpan::Strikethrough { .. } => "strikethrough",
+            AttributeSpan::PairKerning { .. } => "pair kerning",
+            AttributeSpan::Locale { .. } => "locale",
+            AttributeSpan::Typography { .. } => "typography",
+            AttributeSpan::InlineObject { .. } => "inline object",
+            AttributeSpan::DrawingEffect { .. } => "drawing effect",
+        }
+    }
+
+    /// Applies the attribute with the matching `set_*` method of the layout.
+    pub fn apply<L>(&self, layout: &mut L) -> Result<(), Error>
+    where
+        L: ITextLayout + ?Sized,
+    {
+        match *self {
+            AttributeSpan::Weight { range, value } => layout.set_font_weight(value, range),
+            AttributeSpan::Style { range, value } => layout.set_font_style(value, range),
+            AttributeSpan::Stretch { range, value } => layout.set_font_stretch(value, range),
+            AttributeSpan::FontSize { range, value } => layout.set_font_size(value, range),
+            AttributeSpan::FontFamily { range, value } => layout.set_font_family_name(value, range),
+            AttributeSpan::Underline { range, value } => layout.set_underline(value, range),
+            AttributeSpan::Strikethrough { range, value } => layout.set_strikethrough(value, range),
+            AttributeSpan::PairKerning { range, value } => layout.set_pair_kerning(value, range),
+            AttributeSpan::Locale { range, value } => layout.set_locale_name(value, range),
+            AttributeSpan::Typography { range, value } => layout.set_typography(value, range),
+            AttributeSpan::InlineObject { range, value } => layout.set_inline_object(value, range),
+            AttributeSpan::DrawingEffect { range, value } => {
+                layout.set_drawing_effect(value, range)
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+/// A span which [`ITextLayout::apply_attributes`](trait.ITextLayout.html#method.apply_attributes)
+/// could not apply.
+pub struct SpanError {
+    /// The index of the span in the slice that was passed in.
+    pub index: usize,
+
+    /// The kind of attribute, as given by [`AttributeSpan::name`](enum.AttributeSpan.html#method.name).
+    pub attribute: &'static str,
+
+    /// The range of the span.
+    pub range: TextRange,
+
+    /// The error DirectWrite reported.
+    pub error: Error,
+}
+
+impl std::fmt::Display for SpanError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "failed to apply {} (span {}) to text range {}..{}: {:?}",
+            self.attribute,
+            self.index,
+            self.range.start,
+            self.range.start + self.range.length,
+            self.error
+        )
+    }
+}
+
+impl std::error::Error for SpanError {}
@@ -1,5 +1,6 @@
 use crate::descriptions::{GlyphRun, GlyphRunDescription};
 use crate::descriptions::{Strikethrough, Underline};
+use crate::profiling;
 use crate::text_renderer::custom::CustomTextRenderer;
 use crate::text_renderer::custom::DrawGlyphRun;
 use crate::text_renderer::custom::DrawInlineObject;
@@ -98,6 +99,7 @@ unsafe impl<T: CustomTextRenderer> IDWriteTextRenderer for ComRenderer<T> {
         glyph_run_desc: *const DWRITE_GLYPH_RUN_DESCRIPTION,
         client_effect: *mut IUnknown,
     ) -> HRESULT {
+        profiling::count_run();
         let data = DrawGlyphRun {
             context: DrawContext::from_ptr(context),
             baseline_origin: (baseline_origin_x, baseline_origin_y).into(),
@@ -0,0 +1,314 @@
+use crate::descriptions::GlyphRunDescription;
+use crate::effects::ClientEffect;
+use crate::text_renderer::custom::{
+    CustomTextRenderer, DrawGlyphRun, DrawInlineObject, DrawStrikethrough, DrawUnderline,
+};
+use crate::text_renderer::DrawContext;
+
+use dcommon::helpers::WideStr;
+use dcommon::Error;
+use math2d::Matrix3x2f;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Decides what a [`TeeRenderer`][1] does when one of its renderers fails.
+///
+/// [1]: struct.TeeRenderer.html
+pub enum TeePolicy {
+    /// Stop as soon as the first renderer fails, without calling the second.
+    StopOnError,
+
+    /// Always call both renderers, returning the first error encountered.
+    ContinueOnError,
+}
+
+/// A renderer which forwards every draw call to two renderers, `first` and then `second`.
+///
+/// The pixel snapping, transform, and pixels per DIP queries are answered by `first`.
+pub struct TeeRenderer<A, B> {
+    /// The primary renderer, which is called first and answers the pixel snapping queries.
+    pub first: A,
+
+    /// The renderer which is called second.
+    pub second: B,
+
+    /// How errors from either renderer are combined.
+    pub policy: TeePolicy,
+}
+
+impl<A, B> TeeRenderer<A, B>
+where
+    A: CustomTextRenderer,
+    B: CustomTextRenderer,
+{
+    /// Combine two renderers, stopping on the first error.
+    pub fn new(first: A, second: B) -> Self {
+        TeeRenderer {
+            first,
+            second,
+            policy: TeePolicy::StopOnError,
+        }
+    }
+
+    /// Set the policy used for combining errors.
+    pub fn with_policy(mut self, policy: TeePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    fn combine(
+        &mut self,
+        mut call: impl FnMut(&mut dyn CustomTextRenderer) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let first = call(&mut self.first);
+        if first.is_err() && self.policy == TeePolicy::StopOnError {
+            return first;
+        }
+        let second = call(&mut self.second);
+        first.and(second)
+    }
+}
+
+impl<A, B> CustomTextRenderer for TeeRenderer<A, B>
+where
+    A: CustomTextRenderer,
+    B: CustomTextRenderer,
+{
+    fn pixel_snapping_disabled(&self, context: DrawContext) -> bool {
+        self.first.pixel_snapping_disabled(context)
+    }
+
+    fn current_transform(&self, context: DrawContext) -> Matrix3x2f {
+        self.first.current_transform(context)
+    }
+
+    fn pixels_per_dip(&self, context: DrawContext) -> f32 {
+        self.first.pixels_per_dip(context)
+    }
+
+    fn draw_glyph_run(&mut self, context: &DrawGlyphRun) -> Result<(), Error> {
+        self.combine(|r| r.draw_glyph_run(context))
+    }
+
+    fn draw_underline(&mut self, context: &DrawUnderline) -> Result<(), Error> {
+        self.combine(|r| r.draw_underline(context))
+    }
+
+    fn draw_strikethrough(&mut self, context: &DrawStrikethrough) -> Result<(), Error> {
+        self.combine(|r| r.draw_strikethrough(context))
+    }
+
+    fn draw_inline_object(&mut self, context: &DrawInlineObject) -> Result<(), Error> {
+        self.combine(|r| r.draw_inline_object(context))
+    }
+}
+
+/// A text decoration passed to the decoration handler of a [`FilterRenderer`][1].
+///
+/// [1]: struct.FilterRenderer.html
+pub enum Decoration<'a, 'b> {
+    /// A section of underline.
+    Underline(&'a DrawUnderline<'b>),
+
+    /// A section of strikethrough.
+    Strikethrough(&'a DrawStrikethrough<'b>),
+}
+
+type GlyphRunHandler = Box<dyn FnMut(&DrawGlyphRun) -> Result<(), Error> + Send>;
+type DecorationHandler = Box<dyn FnMut(Decoration) -> Result<(), Error> + Send>;
+type InlineObjectHandler = Box<dyn FnMut(&DrawInlineObject) -> Result<(), Error> + Send>;
+
+/// A renderer which routes glyph runs, decorations, and inline objects to separate handlers.
+/// Anything without a handler is forwarded to the primary renderer, which also answers the
+/// pixel snapping, transform, and pixels per DIP queries.
+pub struct FilterRenderer<P> {
+    primary: P,
+    glyph_runs: Option<GlyphRunHandler>,
+    decorations: Option<DecorationHandler>,
+    inline_objects: Option<InlineObjectHandler>,
+}
+
+impl<P> FilterRenderer<P>
+where
+    P: CustomTextRenderer,
+{
+    /// Create a filter which initially forwards everything to `primary`.
+    pub fn new(primary: P) -> Self {
+        FilterRenderer {
+            primary,
+            glyph_runs: None,
+            decorations: None,
+            inline_objects: None,
+        }
+    }
+
+    /// Route glyph runs to `handler` instead of the primary renderer.
+    pub fn with_glyph_runs(
+        mut self,
+        handler: impl FnMut(&DrawGlyphRun) -> Result<(), Error> + Send + 'static,
+    ) -> Self {
+        self.glyph_runs = Some(Box::new(handler));
+        self
+    }
+
+    /// Route underlines and strikethroughs to `handler` instead of the primary renderer.
+    pub fn with_decorations(
+        mut self,
+        handler: impl FnMut(Decoration) -> Result<(), Error> + Send + 'static,
+    ) -> Self {
+        self.decorations = Some(Box::new(handler));
+        self
+    }
+
+    /// Route inline objects to `handler` instead of the primary renderer.
+    pub fn with_inline_objects(
+        mut self,
+        handler: impl FnMut(&DrawInlineObject) -> Result<(), Error> + Send + 'static,
+    ) -> Self {
+        self.inline_objects = Some(Box::new(handler));
+        self
+    }
+
+    /// Get the primary renderer back out of the filter.
+    pub fn into_inner(self) -> P {
+        self.primary
+    }
+}
+
+impl<P> CustomTextRenderer for FilterRenderer<P>
+where
+    P: CustomTextRenderer,
+{
+    fn pixel_snapping_disabled(&self, context: DrawContext) -> bool {
+        self.primary.pixel_snapping_disabled(context)
+    }
+
+    fn current_transform(&self, context: DrawContext) -> Matrix3x2f {
+        self.primary.current_transform(context)
+    }
+
+    fn pixels_per_dip(&self, context: DrawContext) -> f32 {
+        self.primary.pixels_per_dip(context)
+    }
+
+    fn draw_glyph_run(&mut self, context: &DrawGlyphRun) -> Result<(), Error> {
+        match self.glyph_runs {
+            Some(ref mut handler) => handler(context),
+            None => self.primary.draw_glyph_run(context),
+        }
+    }
+
+    fn draw_underline(&mut self, context: &DrawUnderline) -> Result<(), Error> {
+        match self.decorations {
+            Some(ref mut handler) => handler(Decoration::Underline(context)),
+            None => self.primary.draw_underline(context),
+        }
+    }
+
+    fn draw_strikethrough(&mut self, context: &DrawStrikethrough) -> Result<(), Error> {
+        match self.decorations {
+            Some(ref mut handler) => handler(Decoration::Strikethrough(context)),
+            None => self.primary.draw_strikethrough(context),
+        }
+    }
+
+    fn draw_inline_object(&mut self, context: &DrawInlineObject) -> Result<(), Error> {
+        match self.inline_objects {
+            Some(ref mut handler) => handler(context),
+            None => self.primary.draw_inline_object(context),
+        }
+    }
+}
+
+/// A renderer which passes the client effect of every draw call through a mapping function
+/// before forwarding the call to the inner renderer. This can be used to substitute
+/// application effects, or to supply a default effect for text which has none.
+pub struct MapEffectRenderer<R, F> {
+    inner: R,
+    map: F,
+}
+
+impl<R, F> MapEffectRenderer<R, F>
+where
+    R: CustomTextRenderer,
+    F: FnMut(Option<&ClientEffect>) -> Option<ClientEffect> + Send + 'static,
+{
+    /// Wrap `inner`, mapping every client effect through `map`.
+    pub fn new(inner: R, map: F) -> Self {
+        MapEffectRenderer { inner, map }
+    }
+
+    /// Get the inner renderer back out of the wrapper.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R, F> CustomTextRenderer for MapEffectRenderer<R, F>
+where
+    R: CustomTextRenderer,
+    F: FnMut(Option<&ClientEffect>) -> Option<ClientEffect> + Send + 'static,
+{
+    fn pixel_snapping_disabled(&self, context: DrawContext) -> bool {
+        self.inner.pixel_snapping_disabled(context)
+    }
+
+    fn current_transform(&self, context: DrawContext) -> Matrix3x2f {
+        self.inner.current_transform(context)
+    }
+
+    fn pixels_per_dip(&self, context: DrawContext) -> f32 {
+        self.inner.pixels_per_dip(context)
+    }
+
+    fn draw_glyph_run(&mut self, context: &DrawGlyphRun) -> Result<(), Error> {
+        let effect = (self.map)(context.client_effect);
+        let desc = &context.glyph_run_desc;
+        self.inner.draw_glyph_run(&DrawGlyphRun {
+            context: context.context,
+            baseline_origin: context.baseline_origin,
+            measuring_mode: context.measuring_mode,
+            glyph_run: context.glyph_run,
+            glyph_run_desc: GlyphRunDescription {
+                locale_name: desc.locale_name,
+                string: WideStr {
+                    data: desc.string.data,
+                },
+                cluster_map: desc.cluster_map,
+                text_position: desc.text_position,
+            },
+            client_effect: effect.as_ref(),
+        })
+    }
+
+    fn draw_underline(&mut self, context: &DrawUnderline) -> Result<(), Error> {
+        let effect = (self.map)(context.client_effect);
+        self.inner.draw_underline(&DrawUnderline {
+            context: context.context,
+            baseline_origin: context.baseline_origin,
+            underline: context.underline,
+            client_effect: effect.as_ref(),
+        })
+    }
+
+    fn draw_strikethrough(&mut self, context: &DrawStrikethrough) -> Result<(), Error> {
+        let effect = (self.map)(context.client_effect);
+        self.inner.draw_strikethrough(&DrawStrikethrough {
+            context: context.context,
+            baseline_origin: context.baseline_origin,
+            strikethrough: context.strikethrough,
+            client_effect: effect.as_ref(),
+        })
+    }
+
+    fn draw_inline_object(&mut self, context: &DrawInlineObject) -> Result<(), Error> {
+        let effect = (self.map)(context.client_effect);
+        self.inner.draw_inline_object(&DrawInlineObject {
+            context: context.context,
+            origin: context.origin,
+            inline_object: context.inline_object,
+            is_sideways: context.is_sideways,
+            is_right_to_left: context.is_right_to_left,
+            client_effect: effect.as_ref(),
+        })
+    }
+}
@@ -16,9 +16,15 @@ use math2d::Point2f;
 
 #[doc(inline)]
 pub use crate::text_renderer::custom::com_renderer::ComRenderer;
+#[doc(inline)]
+pub use crate::text_renderer::custom::combinators::{
+    Decoration, FilterRenderer, MapEffectRenderer, TeePolicy, TeeRenderer,
+};
 
 #[doc(hidden)]
 pub mod com_renderer;
+#[doc(hidden)]
+pub mod combinators;
 
 /// An application-implemented TextRenderer that can be passed to DirectWrite to receive
 /// glyphs and inline objects from a TextLayout to perform customized rendering.
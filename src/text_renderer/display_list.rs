@@ -0,0 +1,342 @@
+//! An owned record of everything a TextLayout draws, which can be replayed, inspected, or (with
+//! the `serde` feature) saved as a snapshot for golden-image tests.
+
+use crate::font_face::{FontFace, IFontFace};
+use crate::font_file::FontFile;
+use crate::helpers::query_interface;
+use crate::localized_strings::LocalizedStrings;
+use crate::text_layout::ITextLayout;
+use crate::text_renderer::recording::{
+    DrawCommand, OwnedDecoration, OwnedGlyphRun, RecordingRenderer,
+};
+
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+use math2d::Point2f;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::dwrite_3::IDWriteFontFace3;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Everything a layout drew, in drawing order. All positions are relative to the origin the
+/// layout was recorded at, in DIPs.
+pub struct DisplayList {
+    /// The recorded drawing operations.
+    pub items: Vec<DisplayItem>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A single drawing operation recorded from a layout.
+pub enum DisplayItem {
+    /// A run of glyphs.
+    GlyphRun(GlyphRunItem),
+    /// A section of underline.
+    Underline(DecorationItem),
+    /// A section of strikethrough.
+    Strikethrough(DecorationItem),
+    /// The placeholder for an inline object.
+    InlineObject(InlineObjectItem),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Identifies a font face by what it is rather than where it lives in memory, so that two
+/// recordings made with the same fonts installed compare equal.
+pub struct FontIdentity {
+    /// The family name of the face, in the first locale the font provides.
+    pub family_name: String,
+    /// A 64-bit FNV-1a hash of the contents of every file making up the face.
+    pub file_hash: u64,
+    /// The index of the face within its font file.
+    pub index: u32,
+    /// The raw bits of the face's `FontSimulations`.
+    pub simulations: u32,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// An owned copy of a glyph run.
+pub struct GlyphRunItem {
+    /// The x coordinate of the baseline origin.
+    pub x: f32,
+    /// The y coordinate of the baseline origin.
+    pub y: f32,
+    /// The identity of the font face the run is drawn with.
+    pub font: FontIdentity,
+    /// The font face itself. This is only available on lists that were recorded in this process;
+    /// lists read back from a snapshot have `None` here.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub font_face: Option<FontFace>,
+    /// The em size of the font, in DIPs.
+    pub font_em_size: f32,
+    /// The glyph indices of the run.
+    pub glyph_indices: Vec<u16>,
+    /// The advance of each glyph.
+    pub glyph_advances: Vec<f32>,
+    /// The `(advance_offset, ascender_offset)` of each glyph.
+    pub glyph_offsets: Vec<(f32, f32)>,
+    /// Whether the glyphs are rotated 90 degrees to the left.
+    pub is_sideways: bool,
+    /// The implicit resolved bidi level of the run.
+    pub bidi_level: u32,
+    /// The position of the first character of the run in the layout's text.
+    pub text_position: u32,
+    /// The number of characters the run covers.
+    pub text_length: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The placement of an underline or strikethrough.
+pub struct DecorationItem {
+    /// The x coordinate of the baseline origin.
+    pub x: f32,
+    /// The y coordinate of the baseline origin.
+    pub y: f32,
+    /// Width of the decoration, measured parallel to the baseline.
+    pub width: f32,
+    /// Thickness of the decoration, measured perpendicular to the baseline.
+    pub thickness: f32,
+    /// Offset of the decoration from the baseline.
+    pub offset: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The space reserved for an inline object.
+pub struct InlineObjectItem {
+    /// The x coordinate of the top-left corner of the object.
+    pub x: f32,
+    /// The y coordinate of the top-left corner of the object.
+    pub y: f32,
+    /// The width the object reported.
+    pub width: f32,
+    /// The height the object reported.
+    pub height: f32,
+    /// Distance from the top of the object to its baseline.
+    pub baseline: f32,
+    /// Whether the object is rotated 90 degrees to the left.
+    pub is_sideways: bool,
+    /// Whether the object is in a right-to-left context.
+    pub is_right_to_left: bool,
+}
+
+impl DisplayList {
+    /// Draws the layout at the origin with pixel snapping disabled and records everything it
+    /// produced.
+    pub fn record<L>(layout: &L, pixels_per_dip: f32) -> Result<DisplayList, Error>
+    where
+        L: ITextLayout + ?Sized,
+    {
+        let recorder = RecordingRenderer::new().with_pixels_per_dip(pixels_per_dip);
+        recorder.record(layout, Point2f::new(0.0, 0.0))?;
+        let records = recorder.take_records();
+
+        // Each face is only identified once per recording
+        let mut identities: Vec<(FontFace, FontIdentity)> = Vec::new();
+        let mut items = Vec::with_capacity(records.len());
+        for record in records {
+            items.push(match record {
+                DrawCommand::GlyphRun(run) => {
+                    let known = identities
+                        .iter()
+                        .find(|(face, _)| unsafe { face.get_raw() == run.font_face.get_raw() });
+                    let font = match known {
+                        Some((_, id)) => id.clone(),
+                        None => {
+                            let id = FontIdentity::of(&run.font_face)?;
+                            identities.push((run.font_face.clone(), id.clone()));
+                            id
+                        }
+                    };
+                    DisplayItem::GlyphRun(GlyphRunItem::new(run, font))
+                }
+                DrawCommand::Underline(u) => DisplayItem::Underline(DecorationItem::from(&u)),
+                DrawCommand::Strikethrough(s) => {
+                    DisplayItem::Strikethrough(DecorationItem::from(&s))
+                }
+                DrawCommand::InlineObject {
+                    origin,
+                    inline_object,
+                    is_sideways,
+                    is_right_to_left,
+                } => {
+                    let metrics = inline_object.metrics();
+                    DisplayItem::InlineObject(InlineObjectItem {
+                        x: origin.x,
+                        y: origin.y,
+                        width: metrics.size.width,
+                        height: metrics.size.height,
+                        baseline: metrics.baseline,
+                        is_sideways,
+                        is_right_to_left,
+                    })
+                }
+            });
+        }
+
+        Ok(DisplayList { items })
+    }
+
+    /// Returns a copy of this list with every floating-point value rounded to `precision`
+    /// decimal places.
+    pub fn rounded(&self, precision: u32) -> DisplayList {
+        let scale = 10f32.powi(precision as i32);
+        // Adding 0.0 turns -0.0 into 0.0 so that it doesn't show up as noise in snapshots
+        let r = |v: f32| (v * scale).round() / scale + 0.0;
+
+        let items = self
+            .items
+            .iter()
+            .map(|item| match item {
+                DisplayItem::GlyphRun(run) => DisplayItem::GlyphRun(GlyphRunItem {
+                    x: r(run.x),
+                    y: r(run.y),
+                    font_em_size: r(run.font_em_size),
+                    glyph_advances: run.glyph_advances.iter().map(|&a| r(a)).collect(),
+                    glyph_offsets: run
+                        .glyph_offsets
+                        .iter()
+                        .map(|&(a, b)| (r(a), r(b)))
+                        .collect(),
+                    ..run.clone()
+                }),
+                DisplayItem::Underline(d) => DisplayItem::Underline(d.rounded(&r)),
+                DisplayItem::Strikethrough(d) => DisplayItem::Strikethrough(d.rounded(&r)),
+                DisplayItem::InlineObject(o) => DisplayItem::InlineObject(InlineObjectItem {
+                    x: r(o.x),
+                    y: r(o.y),
+                    width: r(o.width),
+                    height: r(o.height),
+                    baseline: r(o.baseline),
+                    ..*o
+                }),
+            })
+            .collect();
+
+        DisplayList { items }
+    }
+
+    #[cfg(feature = "serde")]
+    /// Serializes the list as pretty-printed JSON, with one value per line so that snapshots
+    /// produce readable diffs. Floating-point values are rounded to `precision` decimal places
+    /// first.
+    pub fn to_json(&self, precision: u32) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.rounded(precision))
+    }
+
+    #[cfg(feature = "serde")]
+    /// Reads back a list written by `to_json`. Glyph runs in the result have no `font_face`.
+    pub fn from_json(json: &str) -> Result<DisplayList, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+impl GlyphRunItem {
+    fn new(run: OwnedGlyphRun, font: FontIdentity) -> GlyphRunItem {
+        GlyphRunItem {
+            x: run.baseline_origin.x,
+            y: run.baseline_origin.y,
+            font,
+            font_em_size: run.font_em_size,
+            glyph_indices: run.glyph_indices,
+            glyph_advances: run.glyph_advances,
+            glyph_offsets: run
+                .glyph_offsets
+                .iter()
+                .map(|o| (o.advance_offset, o.ascender_offset))
+                .collect(),
+            is_sideways: run.is_sideways,
+            bidi_level: run.bidi_level,
+            text_position: run.text_position,
+            text_length: run.text_length,
+            font_face: Some(run.font_face),
+        }
+    }
+}
+
+impl DecorationItem {
+    fn rounded(&self, r: &impl Fn(f32) -> f32) -> DecorationItem {
+        DecorationItem {
+            x: r(self.x),
+            y: r(self.y),
+            width: r(self.width),
+            thickness: r(self.thickness),
+            offset: r(self.offset),
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedDecoration> for DecorationItem {
+    fn from(decoration: &'a OwnedDecoration) -> DecorationItem {
+        DecorationItem {
+            x: decoration.baseline_origin.x,
+            y: decoration.baseline_origin.y,
+            width: decoration.width,
+            thickness: decoration.thickness,
+            offset: decoration.offset,
+        }
+    }
+}
+
+impl FontIdentity {
+    /// Computes the identity of a font face. The files of the face are identified by their
+    /// contents, so the same font gives the same identity wherever it was loaded from, e.g. on
+    /// another machine or from memory. Every file is read in full the first time a face is
+    /// seen.
+    pub fn of(face: &dyn IFontFace) -> Result<FontIdentity, Error> {
+        let mut file_hash = FNV_OFFSET;
+        for file in face.files()? {
+            file_hash = hash_file(&file, file_hash)?;
+        }
+
+        Ok(FontIdentity {
+            family_name: family_name(face).unwrap_or_default(),
+            file_hash,
+            index: face.index(),
+            simulations: face.simulations().0,
+        })
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+fn fnv1a(mut hash: u64, data: &[u8]) -> u64 {
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+fn hash_file(file: &FontFile, mut hash: u64) -> Result<u64, Error> {
+    let stream = file.open_stream()?;
+    let mut buf = vec![0; 64 * 1024];
+    let mut offset = 0;
+    while offset < stream.len() {
+        let count = stream.read_at(offset, &mut buf)?;
+        hash = fnv1a(hash, &buf[..count]);
+        offset += count as u64;
+    }
+    Ok(hash)
+}
+
+fn family_name(face: &dyn IFontFace) -> Option<String> {
+    unsafe {
+        let face = face.raw_fontface();
+        let face3 = query_interface::<IDWriteFontFace3>(face).ok()?;
+
+        let mut names = std::ptr::null_mut();
+        let hr = face3.GetFamilyNames(&mut names);
+        if !SUCCEEDED(hr) {
+            return None;
+        }
+        let names = LocalizedStrings::from_raw(names);
+        names.get(0).map(|s| s.string())
+    }
+}
@@ -13,7 +13,17 @@ use winapi::shared::winerror::SUCCEEDED;
 use winapi::um::dwrite::{IDWritePixelSnapping, IDWriteTextRenderer};
 use wio::com::ComPtr;
 
+#[doc(inline)]
+pub use self::display_list::DisplayList;
+#[doc(inline)]
+pub use self::raster::{RasterOptions, RasterRenderer};
+#[doc(inline)]
+pub use self::recording::{DrawCommand, RecordingRenderer};
+
 pub mod custom;
+pub mod display_list;
+pub mod raster;
+pub mod recording;
 
 #[repr(transparent)]
 #[derive(ComWrapper)]
@@ -42,7 +52,7 @@ pub unsafe trait ITextRenderer: IPixelSnapping {
                 context.baseline_origin.x,
                 context.baseline_origin.y,
                 context.measuring_mode.value,
-                &context.glyph_run.into_raw(),
+                &context.glyph_run.into_raw()?,
                 &context.glyph_run_desc.into_raw(),
                 unwrap_opt_com(context.client_effect),
             );
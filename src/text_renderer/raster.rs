@@ -0,0 +1,468 @@
+//! A software text renderer which rasterizes layouts into pixel buffers on the CPU.
+
+use crate::descriptions::GlyphRun;
+use crate::enums::{MeasuringMode, RenderingMode, TextAntialiasMode, TextureType};
+use crate::factory::Factory;
+use crate::glyph_run_analysis::IGlyphRunAnalysis;
+use crate::pixel_snapping::snap_baseline;
+use crate::text_renderer::custom::{
+    CustomTextRenderer, DrawGlyphRun, DrawInlineObject, DrawStrikethrough, DrawUnderline,
+};
+use crate::text_renderer::DrawContext;
+
+use std::sync::{Arc, Mutex};
+
+use dcommon::Error;
+use math2d::{Matrix3x2f, Point2f, Recti};
+use winapi::shared::winerror::E_NOINTERFACE;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Controls how text is rasterized by [`RasterRenderer`][1] and [`TextLayout::rasterize`][2].
+///
+/// [1]: struct.RasterRenderer.html
+/// [2]: ../../struct.TextLayout.html#method.rasterize
+pub struct RasterOptions {
+    /// ClearType produces a `Bgra` image with per-channel coverage blended against the
+    /// background, while grayscale produces an `A8` coverage mask. Grayscale glyphs are
+    /// antialiased by DirectWrite itself on Windows 8.1 and later, and aliased before that.
+    pub antialias: TextAntialiasMode,
+
+    /// The color palette used for the layers of color glyphs.
+    pub palette_index: u32,
+
+    /// The straight (not premultiplied) RGBA color of the text. Color glyph layers which use
+    /// the foreground color are drawn with this as well.
+    pub text_color: [f32; 4],
+
+    /// The straight RGBA color the text is blended against. Transparent if `None`. Ignored
+    /// for grayscale output.
+    pub background: Option<[f32; 4]>,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        RasterOptions {
+            antialias: TextAntialiasMode::Grayscale,
+            palette_index: 0,
+            text_color: [0.0, 0.0, 0.0, 1.0],
+            background: None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The pixel format of a [`RasterImage`](struct.RasterImage.html).
+pub enum RasterFormat {
+    /// One byte of coverage per pixel.
+    A8,
+
+    /// Four bytes per pixel in blue, green, red, alpha order, with premultiplied alpha.
+    Bgra,
+}
+
+impl RasterFormat {
+    /// The number of bytes used for each pixel.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            RasterFormat::A8 => 1,
+            RasterFormat::Bgra => 4,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// The pixels produced by rasterizing text, covering only the area that was drawn to.
+pub struct RasterImage {
+    /// The device pixels covered by the image. Pixel `(x, y)` starts at byte
+    /// `((y - bounds.top) * width + (x - bounds.left)) * bytes_per_pixel` of `data`.
+    pub bounds: Recti,
+
+    /// The format of `data`.
+    pub format: RasterFormat,
+
+    /// The pixels, stored row by row without padding.
+    pub data: Vec<u8>,
+}
+
+impl RasterImage {
+    /// The width of the image in pixels.
+    pub fn width(&self) -> u32 {
+        (self.bounds.right - self.bounds.left).max(0) as u32
+    }
+
+    /// The height of the image in pixels.
+    pub fn height(&self) -> u32 {
+        (self.bounds.bottom - self.bounds.top).max(0) as u32
+    }
+}
+
+/// Coverage for one drawing operation, with either 1 or 3 (red, green, blue) channels.
+struct Layer {
+    bounds: Recti,
+    channels: usize,
+    coverage: Vec<u8>,
+    color: [f32; 4],
+}
+
+struct RasterState {
+    factory: Factory,
+    options: RasterOptions,
+    pixels_per_dip: f32,
+    layers: Vec<Layer>,
+}
+
+#[derive(Clone)]
+/// A text renderer which rasterizes everything it's given with GlyphRunAnalysis. Clones share
+/// the same canvas, so keep one around to call [`finish`](#method.finish) on after passing
+/// another to `TextRenderer::new` and drawing.
+///
+/// Baselines are snapped to device pixels with
+/// [`snap_baseline`](../pixel_snapping/fn.snap_baseline.html), and underlines and
+/// strikethroughs are filled with the text color. Sideways runs are turned into their vertical
+/// lines. Inline objects are not drawn.
+pub struct RasterRenderer {
+    state: Arc<Mutex<RasterState>>,
+}
+
+impl RasterRenderer {
+    /// Creates an empty renderer drawing at `pixels_per_dip` device pixels per DIP.
+    pub fn new(factory: &Factory, options: RasterOptions, pixels_per_dip: f32) -> RasterRenderer {
+        RasterRenderer {
+            state: Arc::new(Mutex::new(RasterState {
+                factory: factory.clone(),
+                options,
+                pixels_per_dip,
+                layers: Vec::new(),
+            })),
+        }
+    }
+
+    /// Composites everything drawn so far into an image and clears the canvas.
+    pub fn finish(&self) -> RasterImage {
+        let mut state = self.state.lock().unwrap();
+        let layers = std::mem::replace(&mut state.layers, Vec::new());
+        composite(&state.options, &layers)
+    }
+}
+
+impl CustomTextRenderer for RasterRenderer {
+    fn pixel_snapping_disabled(&self, _context: DrawContext) -> bool {
+        false
+    }
+
+    fn current_transform(&self, _context: DrawContext) -> Matrix3x2f {
+        Matrix3x2f::IDENTITY
+    }
+
+    fn pixels_per_dip(&self, _context: DrawContext) -> f32 {
+        self.state.lock().unwrap().pixels_per_dip
+    }
+
+    fn draw_glyph_run(&mut self, context: &DrawGlyphRun) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        let measuring_mode = match context.measuring_mode.value {
+            1 => MeasuringMode::GdiClassic,
+            2 => MeasuringMode::GdiNatural,
+            _ => MeasuringMode::Natural,
+        };
+
+        let color_layers = match state.factory.translate_color_glyph_run_with_palette(
+            context.baseline_origin,
+            &context.glyph_run,
+            Some(&context.glyph_run_desc),
+            measuring_mode,
+            state.options.palette_index,
+        ) {
+            Ok(layers) => layers,
+            Err(e) if e.0 == E_NOINTERFACE => None,
+            Err(e) => return Err(e),
+        };
+
+        let text_color = state.options.text_color;
+        match color_layers {
+            Some(color_layers) => {
+                for layer in color_layers {
+                    let layer = layer?;
+                    let color = match layer.palette_index {
+                        Some(_) => {
+                            let c = layer.run_color;
+                            [c.r, c.g, c.b, c.a]
+                        }
+                        None => text_color,
+                    };
+                    let layer = state.rasterize(
+                        &layer.glyph_run(),
+                        layer.baseline_origin,
+                        measuring_mode,
+                        color,
+                    )?;
+                    state.layers.push(layer);
+                }
+            }
+            None => {
+                let layer = state.rasterize(
+                    &context.glyph_run,
+                    context.baseline_origin,
+                    measuring_mode,
+                    text_color,
+                )?;
+                state.layers.push(layer);
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_underline(&mut self, context: &DrawUnderline) -> Result<(), Error> {
+        let line = &context.underline;
+        let mut state = self.state.lock().unwrap();
+        let origin = state.snap(context.baseline_origin, false);
+        let top = origin.y + line.offset;
+        state.fill_rect(origin.x, top, origin.x + line.width, top + line.thickness);
+        Ok(())
+    }
+
+    fn draw_strikethrough(&mut self, context: &DrawStrikethrough) -> Result<(), Error> {
+        let line = &context.strikethrough;
+        let mut state = self.state.lock().unwrap();
+        let origin = state.snap(context.baseline_origin, false);
+        let top = origin.y + line.offset;
+        state.fill_rect(origin.x, top, origin.x + line.width, top + line.thickness);
+        Ok(())
+    }
+
+    fn draw_inline_object(&mut self, _context: &DrawInlineObject) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl RasterState {
+    /// Snaps a baseline origin to the pixel grid, like DirectWrite expects from a renderer which
+    /// doesn't disable pixel snapping. The baseline of a sideways run is drawn vertically, so
+    /// its x coordinate is snapped instead.
+    fn snap(&self, origin: Point2f, is_sideways: bool) -> Point2f {
+        if is_sideways {
+            Point2f::new(snap_baseline(origin.x, self.pixels_per_dip), origin.y)
+        } else {
+            Point2f::new(origin.x, snap_baseline(origin.y, self.pixels_per_dip))
+        }
+    }
+
+    fn rasterize(
+        &self,
+        run: &GlyphRun,
+        origin: Point2f,
+        measuring_mode: MeasuringMode,
+        color: [f32; 4],
+    ) -> Result<Layer, Error> {
+        let rendering_mode = match measuring_mode {
+            MeasuringMode::Natural => RenderingMode::Natural,
+            MeasuringMode::GdiClassic => RenderingMode::GdiClassic,
+            MeasuringMode::GdiNatural => RenderingMode::GdiNatural,
+        };
+        let origin = self.snap(origin, run.is_sideways);
+        // Sideways glyphs are analyzed as a horizontal run, which is then turned 90 degrees
+        // clockwise about its origin into the vertical line
+        let rotation = if run.is_sideways {
+            Some(sideways_rotation(origin, self.pixels_per_dip))
+        } else {
+            None
+        };
+        let (channels, bounds, coverage) = match self.options.antialias {
+            TextAntialiasMode::ClearType => {
+                let analysis = self.factory.create_glyph_run_analysis(
+                    run,
+                    self.pixels_per_dip,
+                    rotation.as_ref(),
+                    rendering_mode,
+                    measuring_mode,
+                    origin.x,
+                    origin.y,
+                )?;
+                let bounds = analysis.alpha_texture_bounds(TextureType::ClearType3x1)?;
+                let texture = analysis.create_alpha_texture(TextureType::ClearType3x1, bounds)?;
+                (3, bounds, texture)
+            }
+            TextAntialiasMode::Grayscale => {
+                // Grayscale analyses need Windows 8.1; before that, aliased coverage is the
+                // only single-channel texture there is
+                let analysis = match self.factory.create_grayscale_glyph_run_analysis(
+                    run,
+                    self.pixels_per_dip,
+                    rotation.as_ref(),
+                    rendering_mode,
+                    measuring_mode,
+                    origin.x,
+                    origin.y,
+                ) {
+                    Err(e) if e.0 == E_NOINTERFACE => self.factory.create_glyph_run_analysis(
+                        run,
+                        self.pixels_per_dip,
+                        rotation.as_ref(),
+                        RenderingMode::Aliased,
+                        measuring_mode,
+                        origin.x,
+                        origin.y,
+                    )?,
+                    result => result?,
+                };
+                let bounds = analysis.alpha_texture_bounds(TextureType::Aliased1x1)?;
+                let texture = analysis.create_alpha_texture(TextureType::Aliased1x1, bounds)?;
+                (1, bounds, texture)
+            }
+        };
+        Ok(Layer {
+            bounds,
+            channels,
+            coverage,
+            color,
+        })
+    }
+
+    /// Fills a rectangle given in DIPs, with partially covered edge pixels antialiased.
+    fn fill_rect(&mut self, left: f32, top: f32, right: f32, bottom: f32) {
+        let scale = self.pixels_per_dip;
+        let (left, right) = (left.min(right) * scale, left.max(right) * scale);
+        let (top, bottom) = (top.min(bottom) * scale, top.max(bottom) * scale);
+        let bounds = Recti {
+            left: left.floor() as i32,
+            top: top.floor() as i32,
+            right: right.ceil() as i32,
+            bottom: bottom.ceil() as i32,
+        };
+
+        let mut coverage = Vec::new();
+        for y in bounds.top..bounds.bottom {
+            let cover_y = (bottom.min(y as f32 + 1.0) - top.max(y as f32)).max(0.0);
+            for x in bounds.left..bounds.right {
+                let cover_x = (right.min(x as f32 + 1.0) - left.max(x as f32)).max(0.0);
+                coverage.push((cover_x * cover_y * 255.0).round() as u8);
+            }
+        }
+
+        let color = self.options.text_color;
+        self.layers.push(Layer {
+            bounds,
+            channels: 1,
+            coverage,
+            color,
+        });
+    }
+}
+
+/// Turns a run 90 degrees clockwise about its baseline origin, in device pixels.
+fn sideways_rotation(origin: Point2f, pixels_per_dip: f32) -> Matrix3x2f {
+    let (x, y) = (origin.x * pixels_per_dip, origin.y * pixels_per_dip);
+    Matrix3x2f {
+        a: 0.0,
+        b: 1.0,
+        c: -1.0,
+        d: 0.0,
+        x: x + y,
+        y: y - x,
+    }
+}
+
+fn composite(options: &RasterOptions, layers: &[Layer]) -> RasterImage {
+    let format = match options.antialias {
+        TextAntialiasMode::ClearType => RasterFormat::Bgra,
+        TextAntialiasMode::Grayscale => RasterFormat::A8,
+    };
+
+    let mut bounds: Option<Recti> = None;
+    for layer in layers {
+        let b = layer.bounds;
+        if b.right <= b.left || b.bottom <= b.top {
+            continue;
+        }
+        bounds = Some(match bounds {
+            None => b,
+            Some(u) => Recti {
+                left: u.left.min(b.left),
+                top: u.top.min(b.top),
+                right: u.right.max(b.right),
+                bottom: u.bottom.max(b.bottom),
+            },
+        });
+    }
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => {
+            return RasterImage {
+                bounds: Recti {
+                    left: 0,
+                    top: 0,
+                    right: 0,
+                    bottom: 0,
+                },
+                format,
+                data: Vec::new(),
+            }
+        }
+    };
+
+    let width = (bounds.right - bounds.left) as usize;
+    let height = (bounds.bottom - bounds.top) as usize;
+
+    // Premultiplied RGBA. Each color channel is blended with its own coverage, which is what
+    // makes ClearType work; alpha uses the strongest of the three.
+    let clear = match (format, options.background) {
+        (RasterFormat::Bgra, Some(bg)) => [bg[0] * bg[3], bg[1] * bg[3], bg[2] * bg[3], bg[3]],
+        _ => [0.0; 4],
+    };
+    let mut canvas = vec![clear; width * height];
+
+    for layer in layers {
+        let layer_width = (layer.bounds.right - layer.bounds.left).max(0) as usize;
+        if layer_width == 0 {
+            continue;
+        }
+        let dx = (layer.bounds.left - bounds.left) as usize;
+        let dy = (layer.bounds.top - bounds.top) as usize;
+        let color = layer.color;
+
+        let rows = layer.coverage.chunks(layer_width * layer.channels);
+        for (row, src) in rows.enumerate() {
+            let start = (dy + row) * width + dx;
+            let dst_row = &mut canvas[start..start + layer_width];
+            for (dst, cov) in dst_row.iter_mut().zip(src.chunks(layer.channels)) {
+                let k = match layer.channels {
+                    3 => [cov[0], cov[1], cov[2]],
+                    _ => [cov[0]; 3],
+                };
+                let k = [
+                    k[0] as f32 / 255.0 * color[3],
+                    k[1] as f32 / 255.0 * color[3],
+                    k[2] as f32 / 255.0 * color[3],
+                ];
+                for c in 0..3 {
+                    dst[c] = color[c] * k[c] + dst[c] * (1.0 - k[c]);
+                }
+                let k_max = k[0].max(k[1]).max(k[2]);
+                dst[3] = k_max + dst[3] * (1.0 - k_max);
+            }
+        }
+    }
+
+    let to_byte = |v: f32| (v.max(0.0).min(1.0) * 255.0).round() as u8;
+    let data = match format {
+        RasterFormat::A8 => canvas.iter().map(|px| to_byte(px[3])).collect(),
+        RasterFormat::Bgra => canvas
+            .iter()
+            .flat_map(|px| {
+                let bgra = [
+                    to_byte(px[2]),
+                    to_byte(px[1]),
+                    to_byte(px[0]),
+                    to_byte(px[3]),
+                ];
+                bgra.to_vec()
+            })
+            .collect(),
+    };
+
+    RasterImage {
+        bounds,
+        format,
+        data,
+    }
+}
@@ -0,0 +1,486 @@
+//! A headless text renderer which keeps copies of everything a layout draws.
+
+use crate::descriptions::{GlyphOffset, GlyphRun, TextRange};
+use crate::enums::{FlowDirection, MeasuringMode, ReadingDirection};
+use crate::font_face::FontFace;
+use crate::inline_object::InlineObject;
+use crate::text_layout::ITextLayout;
+use crate::text_renderer::custom::{
+    CustomTextRenderer, DrawGlyphRun, DrawInlineObject, DrawStrikethrough, DrawUnderline,
+};
+use crate::text_renderer::{DrawContext, TextRenderer};
+
+use crate::effects::ClientEffect;
+#[cfg(feature = "d2d")]
+use crate::helpers::query_interface;
+
+use std::sync::{Arc, Mutex};
+
+use checked_enum::UncheckedEnum;
+#[cfg(feature = "d2d")]
+use com_wrapper::ComWrapper;
+use dcommon::Error;
+#[cfg(feature = "d2d")]
+use math2d::Color;
+use math2d::{Matrix3x2f, Point2f};
+#[cfg(feature = "d2d")]
+use winapi::um::d2d1::ID2D1SolidColorBrush;
+
+/// An owned copy of a glyph run that was drawn by a TextLayout, along with the
+/// information needed to locate it in the layout again.
+#[derive(Clone)]
+pub struct OwnedGlyphRun {
+    /// The origin of the run's baseline, relative to the point the layout was drawn at.
+    pub baseline_origin: Point2f,
+
+    /// The measuring mode the run was laid out with.
+    pub measuring_mode: UncheckedEnum<MeasuringMode>,
+
+    /// The font face of the glyphs.
+    pub font_face: FontFace,
+
+    /// The font size in DIPs.
+    pub font_em_size: f32,
+
+    /// The glyphs to draw.
+    pub glyph_indices: Vec<u16>,
+
+    /// The advance of each glyph.
+    pub glyph_advances: Vec<f32>,
+
+    /// The offset of each glyph from its default position.
+    pub glyph_offsets: Vec<GlyphOffset>,
+
+    /// Whether the glyphs are rotated for vertical text.
+    pub is_sideways: bool,
+
+    /// The bidirectional level of the run; odd levels are right-to-left.
+    pub bidi_level: u32,
+
+    /// The position in the layout's text of the first character in the run.
+    pub text_position: u32,
+
+    /// The number of UTF-16 code units in the run.
+    pub text_length: u32,
+
+    /// The text of the run.
+    pub string: Vec<u16>,
+
+    /// The drawing effect applied to the run, if any.
+    pub client_effect: Option<ClientEffect>,
+
+    /// The color of the run's drawing effect, if it is a Direct2D solid color brush.
+    #[cfg(feature = "d2d")]
+    pub color: Option<Color>,
+}
+
+impl OwnedGlyphRun {
+    /// Borrow this run in the same form it was handed to the renderer.
+    pub fn glyph_run(&self) -> GlyphRun {
+        GlyphRun {
+            font_face: &self.font_face,
+            font_em_size: self.font_em_size,
+            glyph_indices: &self.glyph_indices,
+            glyph_advances: &self.glyph_advances,
+            glyph_offsets: &self.glyph_offsets,
+            is_sideways: self.is_sideways,
+            bidi_level: self.bidi_level,
+        }
+    }
+
+    /// The range of the layout's text the run was shaped from.
+    pub fn text_range(&self) -> TextRange {
+        TextRange {
+            start: self.text_position,
+            length: self.text_length,
+        }
+    }
+
+    /// The origin of each glyph, in the same coordinates as `baseline_origin`.
+    ///
+    /// Glyphs advance to the right from the baseline origin, or to the left when the run is
+    /// right-to-left, and are moved by their offsets. The origin of a right-to-left glyph is
+    /// still its left edge. Sideways runs are rotated 90 degrees clockwise, so they advance
+    /// downwards.
+    pub fn glyph_origins(&self) -> Vec<Point2f> {
+        let rtl = self.bidi_level % 2 == 1;
+        let mut pen = 0.0;
+        let mut origins = Vec::with_capacity(self.glyph_advances.len());
+        for (i, &advance) in self.glyph_advances.iter().enumerate() {
+            let (advance_offset, up) = match self.glyph_offsets.get(i) {
+                Some(offset) => (offset.advance_offset, offset.ascender_offset),
+                None => (0.0, 0.0),
+            };
+            let along = if rtl {
+                pen -= advance;
+                pen - advance_offset
+            } else {
+                pen += advance;
+                pen - advance + advance_offset
+            };
+
+            let (dx, dy) = if self.is_sideways {
+                (up, along)
+            } else {
+                (along, -up)
+            };
+            origins.push(Point2f::new(
+                self.baseline_origin.x + dx,
+                self.baseline_origin.y + dy,
+            ));
+        }
+        origins
+    }
+}
+
+#[derive(Clone)]
+/// An owned copy of an underline or strikethrough that was drawn by a TextLayout.
+pub struct OwnedDecoration {
+    /// The origin of the baseline the line is drawn relative to.
+    pub baseline_origin: Point2f,
+
+    /// The width of the line, measured parallel to the baseline.
+    pub width: f32,
+
+    /// The thickness of the line, measured perpendicular to the baseline.
+    pub thickness: f32,
+
+    /// The offset of the line from the baseline. A positive offset is below the baseline and
+    /// a negative one is above it.
+    pub offset: f32,
+
+    /// The height of the tallest run the underline is drawn under. Always 0 for
+    /// strikethroughs.
+    pub run_height: f32,
+
+    /// The reading direction of the text the line is drawn for.
+    pub reading_direction: UncheckedEnum<ReadingDirection>,
+
+    /// The flow direction of the text the line is drawn for.
+    pub flow_direction: UncheckedEnum<FlowDirection>,
+
+    /// The locale of the text the line is drawn for.
+    pub locale_name: String,
+
+    /// The measuring mode the text was laid out with.
+    pub measuring_mode: UncheckedEnum<MeasuringMode>,
+}
+
+#[derive(Clone)]
+/// One call a TextLayout made to draw itself, as kept by a
+/// [`RecordingRenderer`](struct.RecordingRenderer.html).
+pub enum DrawCommand {
+    /// A run of glyphs.
+    GlyphRun(OwnedGlyphRun),
+
+    /// A section of underline.
+    Underline(OwnedDecoration),
+
+    /// A section of strikethrough.
+    Strikethrough(OwnedDecoration),
+
+    /// An inline object.
+    InlineObject {
+        /// The top-left corner of the object.
+        origin: Point2f,
+
+        /// The object that was drawn.
+        inline_object: InlineObject,
+
+        /// Whether the object is rotated for vertical text.
+        is_sideways: bool,
+
+        /// Whether the text around the object is right-to-left.
+        is_right_to_left: bool,
+    },
+}
+
+struct RecordingState {
+    records: Vec<DrawCommand>,
+    pixels_per_dip: f32,
+}
+
+#[derive(Clone)]
+/// A renderer which doesn't draw anything, but keeps a copy of everything it is asked to
+/// draw, in order. The borrowed glyph arrays and strings DirectWrite hands to a renderer only
+/// live for the duration of each callback, so the recorder owns copies of all of them.
+///
+/// Pixel snapping is disabled so that the recorded positions are exactly what the layout
+/// computed. Layouts can be drawn into the recorder with [`record`](#method.record), or it can
+/// be passed to `TextRenderer::new` or a combinator like any other renderer. Clones share the
+/// same records, so keep one around to read them from after handing another one off.
+pub struct RecordingRenderer {
+    state: Arc<Mutex<RecordingState>>,
+}
+
+impl Default for RecordingRenderer {
+    fn default() -> Self {
+        RecordingRenderer::new()
+    }
+}
+
+impl RecordingRenderer {
+    /// Creates an empty recorder which reports 1 pixel per DIP.
+    pub fn new() -> RecordingRenderer {
+        RecordingRenderer {
+            state: Arc::new(Mutex::new(RecordingState {
+                records: Vec::new(),
+                pixels_per_dip: 1.0,
+            })),
+        }
+    }
+
+    /// Sets the pixels per DIP reported to the layout, which affects glyph positions when
+    /// the layout uses a GDI-compatible measuring mode. This applies to every clone.
+    pub fn with_pixels_per_dip(self, pixels_per_dip: f32) -> Self {
+        self.state.lock().unwrap().pixels_per_dip = pixels_per_dip;
+        self
+    }
+
+    /// Draws `layout` with its top-left corner at `origin`, appending what it draws to the
+    /// records.
+    pub fn record<L>(&self, layout: &L, origin: Point2f) -> Result<(), Error>
+    where
+        L: ITextLayout + ?Sized,
+    {
+        let mut renderer = TextRenderer::new(self.clone());
+        // SAFETY: the renderer is a RecordingRenderer, which never reads the context
+        unsafe {
+            layout.draw(
+                &mut renderer,
+                origin.x,
+                origin.y,
+                &DrawContext::from_usize(0),
+            )
+        }
+    }
+
+    /// A copy of everything drawn so far, in drawing order.
+    pub fn records(&self) -> Vec<DrawCommand> {
+        self.state.lock().unwrap().records.clone()
+    }
+
+    /// A copy of the glyph runs drawn so far, in drawing order.
+    pub fn glyph_runs(&self) -> Vec<OwnedGlyphRun> {
+        let state = self.state.lock().unwrap();
+        state
+            .records
+            .iter()
+            .filter_map(|record| match record {
+                DrawCommand::GlyphRun(run) => Some(run.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Takes everything drawn so far, leaving the records empty.
+    pub fn take_records(&self) -> Vec<DrawCommand> {
+        let mut state = self.state.lock().unwrap();
+        std::mem::replace(&mut state.records, Vec::new())
+    }
+
+    /// Forgets everything drawn so far.
+    pub fn clear(&self) {
+        self.state.lock().unwrap().records.clear();
+    }
+
+    fn push(&self, command: DrawCommand) {
+        self.state.lock().unwrap().records.push(command);
+    }
+}
+
+impl CustomTextRenderer for RecordingRenderer {
+    fn pixel_snapping_disabled(&self, _context: DrawContext) -> bool {
+        true
+    }
+
+    fn current_transform(&self, _context: DrawContext) -> Matrix3x2f {
+        Matrix3x2f::IDENTITY
+    }
+
+    fn pixels_per_dip(&self, _context: DrawContext) -> f32 {
+        self.state.lock().unwrap().pixels_per_dip
+    }
+
+    fn draw_glyph_run(&mut self, context: &DrawGlyphRun) -> Result<(), Error> {
+        let run = &context.glyph_run;
+        let desc = &context.glyph_run_desc;
+        self.push(DrawCommand::GlyphRun(OwnedGlyphRun {
+            baseline_origin: context.baseline_origin,
+            measuring_mode: context.measuring_mode,
+            font_face: run.font_face.clone(),
+            font_em_size: run.font_em_size,
+            glyph_indices: run.glyph_indices.to_vec(),
+            glyph_advances: run.glyph_advances.to_vec(),
+            glyph_offsets: run.glyph_offsets.to_vec(),
+            is_sideways: run.is_sideways,
+            bidi_level: run.bidi_level,
+            text_position: desc.text_position,
+            text_length: desc.string.data.len() as u32,
+            string: desc.string.data.to_vec(),
+            client_effect: context.client_effect.cloned(),
+            #[cfg(feature = "d2d")]
+            color: context.client_effect.and_then(solid_brush_color),
+        }));
+        Ok(())
+    }
+
+    fn draw_underline(&mut self, context: &DrawUnderline) -> Result<(), Error> {
+        let underline = &context.underline;
+        self.push(DrawCommand::Underline(OwnedDecoration {
+            baseline_origin: context.baseline_origin,
+            width: underline.width,
+            thickness: underline.thickness,
+            offset: underline.offset,
+            run_height: underline.run_height,
+            reading_direction: underline.reading_direction,
+            flow_direction: underline.flow_direction,
+            locale_name: unsafe { wide_cstr_to_string(underline.locale_name.as_ptr()) },
+            measuring_mode: underline.measuring_mode,
+        }));
+        Ok(())
+    }
+
+    fn draw_strikethrough(&mut self, context: &DrawStrikethrough) -> Result<(), Error> {
+        let strikethrough = &context.strikethrough;
+        self.push(DrawCommand::Strikethrough(OwnedDecoration {
+            baseline_origin: context.baseline_origin,
+            width: strikethrough.width,
+            thickness: strikethrough.thickness,
+            offset: strikethrough.offset,
+            run_height: 0.0,
+            reading_direction: strikethrough.reading_direction,
+            flow_direction: strikethrough.flow_direction,
+            locale_name: unsafe { wide_cstr_to_string(strikethrough.locale_name.as_ptr()) },
+            measuring_mode: strikethrough.measuring_mode,
+        }));
+        Ok(())
+    }
+
+    fn draw_inline_object(&mut self, context: &DrawInlineObject) -> Result<(), Error> {
+        self.push(DrawCommand::InlineObject {
+            origin: context.origin,
+            inline_object: context.inline_object.clone(),
+            is_sideways: context.is_sideways,
+            is_right_to_left: context.is_right_to_left,
+        });
+        Ok(())
+    }
+}
+
+unsafe fn wide_cstr_to_string(ptr: *const u16) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    let mut len = 0;
+    while *ptr.add(len) != 0 {
+        len += 1;
+    }
+    String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len))
+}
+
+/// Reads the color of a drawing effect which is an `ID2D1SolidColorBrush`, with the brush
+/// opacity folded into alpha.
+#[cfg(feature = "d2d")]
+fn solid_brush_color(effect: &ClientEffect) -> Option<Color> {
+    unsafe {
+        let brush = query_interface::<ID2D1SolidColorBrush>(&*effect.get_raw()).ok()?;
+        let color = brush.GetColor();
+        let opacity = brush.GetOpacity();
+        Some(Color::new(color.r, color.g, color.b, color.a * opacity))
+    }
+}
+
+/// Draws the layout at the origin and returns every glyph run it produced, in drawing order.
+pub(crate) fn record_glyph_runs<L>(
+    layout: &L,
+    pixels_per_dip: f32,
+) -> Result<Vec<OwnedGlyphRun>, Error>
+where
+    L: ITextLayout + ?Sized,
+{
+    let recorder = RecordingRenderer::new().with_pixels_per_dip(pixels_per_dip);
+    recorder.record(layout, Point2f::new(0.0, 0.0))?;
+    Ok(recorder.glyph_runs())
+}
+
+#[cfg(all(test, feature = "d2d"))]
+mod tests {
+    use super::*;
+    use crate::text_layout::ITextLayout;
+    use crate::{Factory, TextFormat, TextLayout};
+
+    use winapi::shared::dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM;
+    use winapi::shared::winerror::SUCCEEDED;
+    use winapi::um::d2d1::*;
+    use winapi::um::dcommon::{D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_PIXEL_FORMAT};
+    use winapi::Interface;
+    use wio::com::ComPtr;
+
+    fn solid_brush(color: D2D1_COLOR_F) -> ClientEffect {
+        unsafe {
+            let mut factory: *mut ID2D1Factory = std::ptr::null_mut();
+            let hr = D2D1CreateFactory(
+                D2D1_FACTORY_TYPE_SINGLE_THREADED,
+                &ID2D1Factory::uuidof(),
+                std::ptr::null(),
+                &mut factory as *mut _ as *mut _,
+            );
+            assert!(SUCCEEDED(hr));
+            let factory = ComPtr::from_raw(factory);
+
+            // A DC render target can create resources without being bound to a DC
+            let props = D2D1_RENDER_TARGET_PROPERTIES {
+                _type: D2D1_RENDER_TARGET_TYPE_DEFAULT,
+                pixelFormat: D2D1_PIXEL_FORMAT {
+                    format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                    alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+                },
+                dpiX: 0.0,
+                dpiY: 0.0,
+                usage: D2D1_RENDER_TARGET_USAGE_NONE,
+                minLevel: D2D1_FEATURE_LEVEL_DEFAULT,
+            };
+            let mut target = std::ptr::null_mut();
+            assert!(SUCCEEDED(factory.CreateDCRenderTarget(&props, &mut target)));
+            let target = ComPtr::from_raw(target);
+
+            let mut brush = std::ptr::null_mut();
+            let hr = target.CreateSolidColorBrush(&color, std::ptr::null(), &mut brush);
+            assert!(SUCCEEDED(hr));
+            ClientEffect::from_raw(brush as *mut _)
+        }
+    }
+
+    #[test]
+    fn solid_brush_run_color() {
+        let factory = Factory::new().unwrap();
+        let format = TextFormat::create(&factory)
+            .with_family("Segoe UI")
+            .with_size(16.0)
+            .build()
+            .unwrap();
+        let mut layout = TextLayout::create(&factory)
+            .with_str("plain red")
+            .with_format(&format)
+            .with_size(300.0, 100.0)
+            .build()
+            .unwrap();
+
+        let red = solid_brush(D2D1_COLOR_F {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        });
+        layout.set_drawing_effect(&red, 6u32..9).unwrap();
+
+        let runs = record_glyph_runs(&layout, 1.0).unwrap();
+        let plain = runs.iter().find(|r| r.text_position == 0).unwrap();
+        assert!(plain.color.is_none());
+
+        let colored = runs.iter().find(|r| r.text_position == 6).unwrap();
+        let color = colored.color.unwrap();
+        assert_eq!((color.r, color.g, color.b, color.a), (1.0, 0.0, 0.0, 1.0));
+    }
+}
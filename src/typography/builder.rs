@@ -9,7 +9,7 @@ use winapi::shared::winerror::SUCCEEDED;
 
 /// Builds a Typography object with the listed font features.
 pub struct TypographyBuilder<'a> {
-    factory: &'a Factory,
+    factory: Factory,
     features: FeatureList<'a>,
 }
 
@@ -21,9 +21,9 @@ enum FeatureList<'a> {
 }
 
 impl<'a> TypographyBuilder<'a> {
-    pub(crate) fn new(factory: &'a Factory) -> Self {
+    pub(crate) fn new(factory: &Factory) -> Self {
         TypographyBuilder {
-            factory,
+            factory: factory.clone(),
             features: FeatureList::Empty,
         }
     }
@@ -4,6 +4,7 @@ use crate::descriptions::FontFeature;
 use crate::factory::Factory;
 
 use com_wrapper::ComWrapper;
+use dcommon::Error;
 use winapi::shared::winerror::SUCCEEDED;
 use winapi::um::dwrite::IDWriteTypography;
 use wio::com::ComPtr;
@@ -11,7 +12,8 @@ use wio::com::ComPtr;
 #[doc(inline)]
 pub use self::builder::TypographyBuilder;
 
-mod builder;
+#[doc(hidden)]
+pub mod builder;
 
 #[repr(transparent)]
 #[derive(ComWrapper)]
@@ -23,7 +25,7 @@ pub struct Typography {
 
 impl Typography {
     /// Create a new Typography object
-    pub fn create(factory: &Factory) -> TypographyBuilder {
+    pub fn create<'a>(factory: &Factory) -> TypographyBuilder<'a> {
         TypographyBuilder::new(factory)
     }
 
@@ -31,6 +33,14 @@ impl Typography {
     pub fn all_features<'a>(&'a self) -> impl Iterator<Item = FontFeature> + 'a {
         (0..self.feature_count()).filter_map(move |i| self.feature(i))
     }
+
+    /// Get an iterator over every feature index paired with the result of fetching that
+    /// feature. Unlike `all_features`, failures are reported rather than left out.
+    pub fn enumerate_features<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (u32, Result<FontFeature, Error>)> + 'a {
+        (0..self.feature_count()).map(move |i| (i, self.try_feature(i)))
+    }
 }
 
 pub unsafe trait ITypography {
@@ -41,13 +51,19 @@ pub unsafe trait ITypography {
 
     /// Get the font feature at the specified index.
     fn feature(&self, index: u32) -> Option<FontFeature> {
+        self.try_feature(index).ok()
+    }
+
+    /// Get the font feature at the specified index, returning the error if it can't be
+    /// fetched.
+    fn try_feature(&self, index: u32) -> Result<FontFeature, Error> {
         unsafe {
             let mut feature = std::mem::zeroed();
             let hr = self.raw_typography().GetFontFeature(index, &mut feature);
             if SUCCEEDED(hr) {
-                Some(feature.into())
+                Ok(feature.into())
             } else {
-                None
+                Err(hr.into())
             }
         }
     }
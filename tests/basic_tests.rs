@@ -11,6 +11,31 @@ fn create_factory() {
     Factory::new().unwrap();
 }
 
+#[test]
+fn isolated_factories() {
+    use com_wrapper::ComWrapper;
+    use winapi::um::dwrite::{DWriteCreateFactory, IDWriteFactory, DWRITE_FACTORY_TYPE_ISOLATED};
+    use winapi::Interface;
+
+    let isolated = Factory::new_isolated().unwrap();
+    assert!(isolated.is_isolated());
+    assert!(isolated.clone().is_isolated());
+    assert!(!Factory::new().unwrap().is_isolated());
+
+    // Only factories created through this crate are known to be isolated
+    let foreign = unsafe {
+        let mut ptr: *mut IDWriteFactory = std::ptr::null_mut();
+        let hr = DWriteCreateFactory(
+            DWRITE_FACTORY_TYPE_ISOLATED,
+            &IDWriteFactory::uuidof(),
+            &mut ptr as *mut _ as *mut _,
+        );
+        assert!(winapi::shared::winerror::SUCCEEDED(hr));
+        Factory::from_raw(ptr)
+    };
+    assert!(!foreign.is_isolated());
+}
+
 #[test]
 fn create_format() {
     let factory = Factory::new().unwrap();
@@ -121,3 +146,2232 @@ fn query_fonts() {
     assert_eq!(gmetrics[0].advance_width, 1229);
     assert_eq!(gmetrics[1].advance_width, 1171);
 }
+
+#[test]
+fn line_metrics1_matches_line_metrics() {
+    use directwrite::text_format::ITextFormat;
+    use directwrite::text_layout::ITextLayout;
+    use winapi::shared::winerror::E_NOINTERFACE;
+
+    let factory = Factory::new().unwrap();
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(18.0)
+        .build()
+        .unwrap();
+    let mut layout = TextLayout::create(&factory)
+        .with_str("First line\nSecond line\nThird")
+        .with_format(&font)
+        .with_width(400.0)
+        .with_height(300.0)
+        .build()
+        .unwrap();
+
+    let lines1 = match layout.line_metrics1() {
+        Ok(lines) => lines,
+        // Older than Windows 10
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    };
+    let lines = layout.line_metrics();
+    assert_eq!(lines1.len(), 3);
+    assert_eq!(lines1.len(), lines.len());
+    for (line1, line) in lines1.iter().zip(&lines) {
+        assert_eq!(line1.length, line.length);
+        assert_eq!(line1.height, line.height);
+        assert_eq!(line1.baseline, line.baseline);
+        assert!(line1.leading_before >= 0.0 && line1.leading_after >= 0.0);
+        assert!(line1.leading_before + line1.leading_after <= line1.height);
+    }
+
+    // Changing the spacing is picked up once the layout is invalidated
+    layout
+        .set_line_spacing(LineSpacingMethod::Uniform, 40.0, 30.0)
+        .unwrap();
+    layout.invalidate_layout().unwrap();
+    let spaced = layout.line_metrics1().unwrap();
+    assert_eq!(spaced.len(), 3);
+    for line in &spaced {
+        assert_eq!(line.height, 40.0);
+        assert_eq!(line.baseline, 30.0);
+    }
+}
+
+#[test]
+fn hit_test_text_position_edges() {
+    use directwrite::text_layout::ITextLayout;
+    use winapi::shared::winerror::E_INVALIDARG;
+
+    let factory = Factory::new().unwrap();
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(20.0)
+        .build()
+        .unwrap();
+    let layout = TextLayout::create(&factory)
+        .with_str("abcd")
+        .with_format(&font)
+        .with_size(300.0, 100.0)
+        .build()
+        .unwrap();
+
+    for i in 0..4 {
+        let leading = layout.hit_test_text_position(i, false).unwrap();
+        let trailing = layout.hit_test_text_position(i, true).unwrap();
+        assert_eq!(leading.metrics.text_position, i);
+
+        // In left-to-right text the trailing edge of a character is to the right of its leading
+        // edge, and both bound the character's box.
+        let metrics = leading.metrics;
+        assert!(trailing.point_x > leading.point_x);
+        assert!((leading.point_x - metrics.position.x).abs() < 0.01);
+        assert!((trailing.point_x - (metrics.position.x + metrics.size.width)).abs() < 0.01);
+
+        if i > 0 {
+            let previous = layout.hit_test_text_position(i - 1, true).unwrap();
+            assert!((previous.point_x - leading.point_x).abs() < 0.01);
+        }
+    }
+
+    let err = layout.hit_test_text_position(10, false).unwrap_err();
+    assert_eq!(err.0, E_INVALIDARG);
+}
+
+#[test]
+fn line_overhang() {
+    use directwrite::text_format::ITextFormat;
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(32.0)
+        .build()
+        .unwrap();
+
+    let mut layout = TextLayout::create(&factory)
+        .with_str("gjpqy")
+        .with_format(&font)
+        .with_width(300.0)
+        .with_height(200.0)
+        .build()
+        .unwrap();
+
+    // Squeeze the line so the descenders can't fit inside of it
+    layout
+        .set_line_spacing(LineSpacingMethod::Uniform, 20.0, 16.0)
+        .unwrap();
+
+    let overhang = layout.line_overhang(0).unwrap();
+    assert!(overhang.bottom > 0.0);
+
+    assert!(layout.line_overhang(1).is_err());
+}
+
+#[test]
+fn rendering_report() {
+    use directwrite::text_layout::ITextLayout;
+    use directwrite::RenderingParams;
+
+    let factory = Factory::new().unwrap();
+
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let mut layout = TextLayout::create(&factory)
+        .with_str("small HUGE")
+        .with_format(&font)
+        .with_width(5000.0)
+        .with_height(1000.0)
+        .build()
+        .unwrap();
+
+    layout.set_font_size(8.0, 0..6).unwrap();
+    layout.set_font_size(400.0, 6..10).unwrap();
+
+    let params = RenderingParams::create_default(&factory).unwrap();
+    let report = layout.rendering_report(&params, 1.0).unwrap();
+
+    let small = report.iter().find(|r| r.range.start == 0).unwrap();
+    let huge = report.iter().find(|r| r.range.start == 6).unwrap();
+    assert_eq!(small.font_em_size, 8.0);
+    assert_eq!(huge.font_em_size, 400.0);
+    assert_ne!(small.rendering_mode.value, huge.rendering_mode.value);
+    assert!(!small.renders_as_outline);
+    assert!(huge.renders_as_outline);
+
+    // The recommendation comes from DirectWrite, so parameters asking for outlines get them
+    // at any size
+    let outlines = RenderingParams::create(&factory)
+        .with_rendering_mode(RenderingMode::Outline)
+        .build()
+        .unwrap();
+    let report = layout.rendering_report(&outlines, 1.0).unwrap();
+    assert!(report.iter().all(|r| r.renders_as_outline));
+}
+
+#[test]
+fn system_font_set() {
+    use directwrite::font_set::IFontSet;
+
+    let factory = Factory::new().unwrap();
+
+    let set = factory.system_font_set().unwrap();
+    assert!(set.font_count() > 0);
+
+    let names = set
+        .property_values(0, FontPropertyId::PostscriptName)
+        .unwrap()
+        .unwrap();
+    assert!(names.count() > 0);
+
+    let matches = set
+        .matching_fonts(
+            "Segoe UI",
+            FontWeight::NORMAL,
+            FontStretch::Normal,
+            FontStyle::Normal,
+        )
+        .unwrap();
+    assert!(matches.font_count() > 0);
+    assert!(matches.font_count() <= set.font_count());
+}
+
+#[test]
+fn compare_measuring_modes() {
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(13.0)
+        .build()
+        .unwrap();
+
+    let text = "The quick brown fox jumps over the lazy dog";
+    let comparison = font.compare_measuring_modes(&factory, text, 200.0).unwrap();
+
+    let entries = comparison.entries();
+    assert!(match entries[0].measuring_mode {
+        MeasuringMode::Natural => true,
+        _ => false,
+    });
+    assert!(match entries[1].measuring_mode {
+        MeasuringMode::GdiClassic => true,
+        _ => false,
+    });
+    assert!(match entries[2].measuring_mode {
+        MeasuringMode::GdiNatural => true,
+        _ => false,
+    });
+    for entry in &entries {
+        assert!(entry.width > 0.0);
+        assert!(entry.line_count >= 1);
+
+        // Each entry measures a layout of the text in its own mode
+        let layout = TextLayout::create(&factory)
+            .with_str(text)
+            .with_format(&font)
+            .with_width(200.0)
+            .with_height(std::f32::MAX)
+            .with_measuring_mode(entry.measuring_mode)
+            .build()
+            .unwrap();
+        let metrics = layout.metrics();
+        assert_eq!(entry.width, metrics.width);
+        assert_eq!(entry.height, metrics.height);
+        assert_eq!(entry.line_count, metrics.line_count);
+    }
+
+    let differs = entries[1..].iter().any(|entry| {
+        entry.line_count != comparison.natural.line_count
+            || entry.width_including_trailing_whitespace
+                != comparison.natural.width_including_trailing_whitespace
+    });
+    assert_eq!(comparison.gdi_differs(), differs);
+
+    // GDI advances are rounded to whole pixels, so the widths are allowed to differ from the
+    // ideal layout, but never by more than a pixel per character.
+    let slack = text.len() as f32;
+    for entry in &entries[1..] {
+        let delta = entry.width_including_trailing_whitespace
+            - comparison.natural.width_including_trailing_whitespace;
+        assert!(entry.line_count != comparison.natural.line_count || delta.abs() <= slack);
+    }
+}
+
+#[test]
+fn translate_color_glyph_run() {
+    use directwrite::descriptions::{GlyphOffset, GlyphRun};
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_face::IFontFace;
+    use directwrite::font_family::IFontFamily;
+    use math2d::Point2f;
+
+    let factory = Factory::new().unwrap();
+
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    let index = collection.find_family_by_name("Segoe UI Emoji").unwrap();
+    let face = collection
+        .family(index)
+        .unwrap()
+        .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap()
+        .create_face()
+        .unwrap();
+
+    let translate = |c: char| {
+        let indices = face.glyph_indices(&[c as u32]).unwrap();
+        let metrics = face.design_glyph_metrics(&indices, false).unwrap();
+        let advances = [metrics[0].advance_width as f32 / 64.0];
+        let offsets = [GlyphOffset {
+            advance_offset: 0.0,
+            ascender_offset: 0.0,
+        }];
+        let run = GlyphRun {
+            font_face: &face,
+            font_em_size: 32.0,
+            glyph_indices: &indices,
+            glyph_advances: &advances,
+            glyph_offsets: &offsets,
+            is_sideways: false,
+            bidi_level: 0,
+        };
+        factory
+            .translate_color_glyph_run(Point2f::new(0.0, 32.0), &run, None, MeasuringMode::Natural)
+            .unwrap()
+    };
+
+    let layers = translate('\u{1F600}')
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(layers.len() > 1);
+    assert!(layers.iter().any(|l| l.palette_index.is_some()));
+
+    // Each layer can be handed back to DirectWrite as it is, whether or not the advances and
+    // offsets came back null.
+    for layer in &layers {
+        factory
+            .create_glyph_run_analysis(
+                &layer.glyph_run(),
+                1.0,
+                None,
+                RenderingMode::Aliased,
+                MeasuringMode::Natural,
+                layer.baseline_origin.x,
+                layer.baseline_origin.y,
+            )
+            .unwrap();
+    }
+
+    // Plain ASCII in the emoji font has no color layers.
+    assert!(translate('A').is_none());
+}
+
+#[test]
+fn color_glyph_run_flattened() {
+    use directwrite::descriptions::{GlyphOffset, GlyphRun};
+    use directwrite::enums::RenderingMode;
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_face::IFontFace;
+    use directwrite::font_family::IFontFamily;
+    use math2d::Point2f;
+
+    let factory = Factory::new().unwrap();
+
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    let index = match collection.find_family_by_name("Segoe UI Emoji") {
+        Some(index) => index,
+        // No color font to test with
+        None => return,
+    };
+    let face = collection
+        .family(index)
+        .unwrap()
+        .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap()
+        .create_face()
+        .unwrap();
+
+    let flatten = |c: char| {
+        let indices = face.glyph_indices(&[c as u32]).unwrap();
+        let advances = [32.0];
+        let offsets = [GlyphOffset {
+            advance_offset: 0.0,
+            ascender_offset: 0.0,
+        }];
+        let run = GlyphRun {
+            font_face: &face,
+            font_em_size: 32.0,
+            glyph_indices: &indices,
+            glyph_advances: &advances,
+            glyph_offsets: &offsets,
+            is_sideways: false,
+            bidi_level: 0,
+        };
+        factory
+            .color_glyph_run_flattened(
+                Point2f::new(0.0, 32.0),
+                &run,
+                1.0,
+                RenderingMode::Aliased,
+                MeasuringMode::Natural,
+            )
+            .unwrap()
+    };
+
+    let emoji = flatten('\u{1F600}');
+    assert!(emoji.width() > 0 && emoji.height() > 0);
+    assert_eq!(emoji.data.len(), (emoji.width() * emoji.height()) as usize);
+    assert!(!emoji.is_empty());
+
+    // Runs without color layers still produce their plain coverage
+    let plain = flatten('A');
+    assert!(!plain.is_empty());
+}
+
+#[test]
+fn is_simple_ltr() {
+    use winapi::shared::winerror::{ERROR_INVALID_STATE, HRESULT_FROM_WIN32};
+
+    let factory = Factory::new().unwrap();
+
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let layout = |text: &str| {
+        TextLayout::create(&factory)
+            .with_str(text)
+            .with_format(&font)
+            .with_width(300.0)
+            .with_height(200.0)
+            .build()
+            .unwrap()
+    };
+
+    assert!(layout("Plain English text").is_simple_ltr().unwrap());
+    assert!(!layout("English and עברית mixed").is_simple_ltr().unwrap());
+
+    // Layouts which only reached the crate as raw pointers have no factory to analyze with
+    let raw = layout("Plain English text");
+    let wrapped = unsafe { TextLayout::from_raw_addref(raw.as_raw_ptr()) };
+    assert_eq!(
+        wrapped.is_simple_ltr().unwrap_err().0,
+        HRESULT_FROM_WIN32(ERROR_INVALID_STATE)
+    );
+}
+
+#[test]
+fn glyph_run_analysis() {
+    use directwrite::descriptions::{GlyphOffset, GlyphRun};
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_face::IFontFace;
+    use directwrite::font_family::IFontFamily;
+    use directwrite::glyph_run_analysis::IGlyphRunAnalysis;
+    use directwrite::RenderingParams;
+    use winapi::shared::winerror::E_INVALIDARG;
+
+    let factory = Factory::new().unwrap();
+
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    let index = collection.find_family_by_name("Segoe UI").unwrap();
+    let face = collection
+        .family(index)
+        .unwrap()
+        .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap()
+        .create_face()
+        .unwrap();
+
+    let indices = face.glyph_indices(&['W' as u32]).unwrap();
+    let advances = [24.0];
+    let offsets = [GlyphOffset {
+        advance_offset: 0.0,
+        ascender_offset: 0.0,
+    }];
+    let run = GlyphRun {
+        font_face: &face,
+        font_em_size: 24.0,
+        glyph_indices: &indices,
+        glyph_advances: &advances,
+        glyph_offsets: &offsets,
+        is_sideways: false,
+        bidi_level: 0,
+    };
+
+    let analysis = factory
+        .create_glyph_run_analysis(
+            &run,
+            1.0,
+            None,
+            RenderingMode::Aliased,
+            MeasuringMode::Natural,
+            10.0,
+            30.0,
+        )
+        .unwrap();
+
+    let bounds = analysis
+        .alpha_texture_bounds(TextureType::Aliased1x1)
+        .unwrap();
+    assert!(bounds.right > bounds.left && bounds.bottom > bounds.top);
+    assert!(bounds.bottom <= 31);
+
+    let texture = analysis
+        .create_alpha_texture(TextureType::Aliased1x1, bounds)
+        .unwrap();
+    let width = (bounds.right - bounds.left) as usize;
+    let height = (bounds.bottom - bounds.top) as usize;
+    assert_eq!(texture.len(), width * height);
+    assert!(texture.iter().any(|&a| a == 255));
+
+    // An aliased analysis has no ClearType coverage.
+    let cleartype = analysis
+        .alpha_texture_bounds(TextureType::ClearType3x1)
+        .unwrap();
+    assert!(cleartype.right <= cleartype.left);
+
+    let params = RenderingParams::create_default(&factory).unwrap();
+    let (gamma, _, _) = analysis.alpha_blend_params(&params).unwrap();
+    assert!(gamma > 0.0);
+
+    // Advances and offsets must be empty or match the glyph count.
+    let indices = face.glyph_indices(&['W' as u32, 'W' as u32]).unwrap();
+    let analyze = |advances: &[f32], offsets: &[GlyphOffset]| {
+        let run = GlyphRun {
+            glyph_indices: &indices,
+            glyph_advances: advances,
+            glyph_offsets: offsets,
+            ..run
+        };
+        factory.create_glyph_run_analysis(
+            &run,
+            1.0,
+            None,
+            RenderingMode::Aliased,
+            MeasuringMode::Natural,
+            10.0,
+            30.0,
+        )
+    };
+    assert!(analyze(&[], &[]).is_ok());
+    assert!(analyze(&[24.0, 24.0], &[]).is_ok());
+    assert_eq!(analyze(&advances, &[]).err().unwrap().0, E_INVALIDARG);
+    assert_eq!(analyze(&[], &offsets).err().unwrap().0, E_INVALIDARG);
+}
+
+#[test]
+fn insertion_index_at() {
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let layout = TextLayout::create(&factory)
+        .with_str("Hello")
+        .with_format(&font)
+        .with_width(300.0)
+        .with_height(200.0)
+        .build()
+        .unwrap();
+
+    let clusters = layout.cluster_metrics();
+    let left: f32 = clusters[..1].iter().map(|c| c.width).sum();
+    let width = clusters[1].width;
+    let y = layout.metrics().height / 2.0;
+
+    // Dropping on the right half of the 'e' inserts after it, the left half before it.
+    assert_eq!(layout.insertion_index_at(left + width * 0.75, y), 2);
+    assert_eq!(layout.insertion_index_at(left + width * 0.25, y), 1);
+
+    // Past the end of the line inserts at the end of the text.
+    assert_eq!(layout.insertion_index_at(290.0, y), 5);
+}
+
+#[test]
+fn range_between_points() {
+    use directwrite::text_layout::ITextLayout;
+    use math2d::Point2f;
+
+    let factory = Factory::new().unwrap();
+
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let layout = TextLayout::create(&factory)
+        .with_str("First line\nSecond line")
+        .with_format(&font)
+        .with_width(300.0)
+        .with_height(200.0)
+        .build()
+        .unwrap();
+
+    // Just inside the leading half of "s" in "First" and of "o" in "Second"
+    let point_at = |position: u32| {
+        let caret = layout.caret_rect(position, false).unwrap();
+        Point2f {
+            x: caret.left + 0.5,
+            y: (caret.top + caret.bottom) / 2.0,
+        }
+    };
+    let start = point_at(3);
+    let end = point_at(14);
+    assert!(end.y > start.y);
+
+    let range = layout.range_between_points(start, end);
+    assert_eq!(range.start, 3);
+    assert_eq!(range.length, 11);
+
+    // Dragging backwards selects the same text
+    let reversed = layout.range_between_points(end, start);
+    assert_eq!(reversed.start, range.start);
+    assert_eq!(reversed.length, range.length);
+
+    // A click without a drag is an empty range
+    assert_eq!(layout.range_between_points(start, start).length, 0);
+}
+
+#[test]
+fn custom_rendering_params() {
+    use directwrite::rendering_params::IRenderingParams;
+    use directwrite::RenderingParams;
+    use winapi::shared::winerror::E_INVALIDARG;
+
+    let factory = Factory::new().unwrap();
+
+    let params = RenderingParams::create(&factory)
+        .with_gamma(2.2)
+        .with_enhanced_contrast(0.0)
+        .with_cleartype_level(0.0)
+        .with_pixel_geometry(PixelGeometry::Flat)
+        .with_rendering_mode(RenderingMode::NaturalSymmetric)
+        .build()
+        .unwrap();
+
+    assert_eq!(params.gamma(), 2.2);
+    assert_eq!(params.enhanced_contrast(), 0.0);
+    assert_eq!(params.cleartype_level(), 0.0);
+    assert_eq!(params.pixel_geometry().value, PixelGeometry::Flat as u32);
+    assert_eq!(
+        params.rendering_mode().value,
+        RenderingMode::NaturalSymmetric as u32
+    );
+
+    let invalid = [(0.0, 0.5, 1.0), (300.0, 0.5, 1.0), (1.8, -1.0, 1.0), (1.8, 0.5, 1.5)];
+    for &(gamma, contrast, cleartype) in &invalid {
+        let err = RenderingParams::create(&factory)
+            .with_gamma(gamma)
+            .with_enhanced_contrast(contrast)
+            .with_cleartype_level(cleartype)
+            .build()
+            .err()
+            .unwrap();
+        assert_eq!(err.0, E_INVALIDARG);
+    }
+}
+
+#[test]
+fn filtered_font_set() {
+    use directwrite::font_set::{FontProperty, FontSet, IFontSet};
+    use std::collections::HashSet;
+    use winapi::shared::winerror::E_NOINTERFACE;
+
+    let factory = Factory::new().unwrap();
+    let set = factory.system_font_set().unwrap();
+
+    let filters = [
+        FontProperty {
+            id: FontPropertyId::Weight,
+            value: "700",
+            locale: None,
+        },
+        FontProperty {
+            id: FontPropertyId::Stretch,
+            value: "5",
+            locale: None,
+        },
+    ];
+    let bold = match set.filtered(&filters, false) {
+        Ok(bold) => bold,
+        // Older than Windows 10 April 2018 Update
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert!(bold.font_count() > 0);
+    assert!(bold.font_count() < set.font_count());
+
+    let property = |set: &FontSet, i, id| {
+        set.property_values(i, id)
+            .unwrap()
+            .and_then(|v| v.get(0).map(|s| s.string()))
+    };
+
+    let all: HashSet<_> = (0..set.font_count())
+        .filter_map(|i| property(&set, i, FontPropertyId::PostscriptName))
+        .collect();
+    for i in 0..bold.font_count() {
+        assert_eq!(property(&bold, i, FontPropertyId::Weight).unwrap(), "700");
+        assert_eq!(property(&bold, i, FontPropertyId::Stretch).unwrap(), "5");
+        if let Some(name) = property(&bold, i, FontPropertyId::PostscriptName) {
+            assert!(all.contains(&name));
+        }
+    }
+
+    // Matching any of the filters gives at least as many fonts as matching all of them.
+    let either = set.filtered(&filters, true).unwrap();
+    assert!(either.font_count() >= bold.font_count());
+}
+
+#[test]
+fn font_download_queue() {
+    use directwrite::font_download::FontDownloadQueue;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let factory = Factory::new().unwrap();
+    let queue = factory.font_download_queue().unwrap();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let listener_calls = calls.clone();
+    let token = queue
+        .add_listener(move |_: &FontDownloadQueue, _| {
+            listener_calls.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+
+    // Nothing was requested, so there's nothing to download.
+    if queue.is_empty() {
+        let generation = queue.generation_count();
+        queue.begin_download().unwrap();
+        assert_eq!(queue.generation_count(), generation);
+    }
+
+    queue.remove_listener(token).unwrap();
+    queue.cancel_download().unwrap();
+}
+
+#[test]
+fn rendering_params_grid_fit_mode() {
+    use directwrite::rendering_params::IRenderingParams;
+    use directwrite::RenderingParams;
+
+    let factory = Factory::new().unwrap();
+
+    let params = RenderingParams::create(&factory)
+        .with_enhanced_contrast(1.0)
+        .with_enhanced_contrast_grayscale(0.25)
+        .with_grid_fit_mode(GridFitMode::Disabled)
+        .build()
+        .unwrap();
+    assert_eq!(params.grid_fit_mode().value, GridFitMode::Disabled as u32);
+    assert_eq!(params.enhanced_contrast(), 1.0);
+    assert_eq!(params.enhanced_contrast_grayscale(), 0.25);
+
+    let plain = RenderingParams::create(&factory).build().unwrap();
+    assert_eq!(plain.grid_fit_mode().value, GridFitMode::Default as u32);
+
+    assert!(RenderingParams::create(&factory)
+        .with_enhanced_contrast_grayscale(-1.0)
+        .build()
+        .is_err());
+}
+
+#[test]
+fn gdi_interop() {
+    use directwrite::font::IFont;
+    use directwrite::font_face::IFontFace;
+    use directwrite::font_family::IFontFamily;
+    use winapi::um::wingdi::{
+        CreateCompatibleDC, CreateFontIndirectW, DeleteDC, DeleteObject, SelectObject, LOGFONTW,
+    };
+
+    let factory = Factory::new().unwrap();
+    let interop = factory.gdi_interop().unwrap();
+
+    let mut logfont: LOGFONTW = unsafe { std::mem::zeroed() };
+    logfont.lfHeight = -24;
+    logfont.lfWeight = 700;
+    for (dst, src) in logfont.lfFaceName.iter_mut().zip("Segoe UI".encode_utf16()) {
+        *dst = src;
+    }
+
+    let font = interop.create_font_from_logfont(&logfont).unwrap();
+    assert_eq!(font.weight(), FontWeight::BOLD);
+    let family_name = font
+        .font_family()
+        .and_then(|f| f.family_name())
+        .and_then(|n| n.get_by_name("en-US"))
+        .map(|s| s.string())
+        .unwrap();
+    assert_eq!(family_name, "Segoe UI");
+
+    unsafe {
+        let dc = CreateCompatibleDC(std::ptr::null_mut());
+        assert!(!dc.is_null());
+        let hfont = CreateFontIndirectW(&logfont);
+        let old = SelectObject(dc, hfont as *mut _);
+
+        let face = interop.create_font_face_from_hdc(dc).unwrap();
+        let indices = face.glyph_indices(&['A' as u32]).unwrap();
+        assert_ne!(indices[0], 0);
+
+        SelectObject(dc, old);
+        DeleteObject(hfont as *mut _);
+        DeleteDC(dc);
+    }
+
+    assert!(interop
+        .create_font_face_from_hdc(std::ptr::null_mut())
+        .is_err());
+}
+
+#[test]
+fn pair_kerning() {
+    let factory = Factory::new().unwrap();
+
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let mut layout = TextLayout::create(&factory)
+        .with_str("AVAVA WAVE")
+        .with_format(&font)
+        .with_width(300.0)
+        .with_height(200.0)
+        .build()
+        .unwrap();
+
+    let (is_kerned, _) = layout.pair_kerning(0).unwrap().into();
+    assert!(!is_kerned);
+
+    layout.set_pair_kerning(true, 1..5).unwrap();
+
+    let (is_kerned, range) = layout.pair_kerning(2).unwrap().into();
+    assert!(is_kerned);
+    assert_eq!(range.start, 1);
+    assert_eq!(range.length, 4);
+
+    let (is_kerned, range) = layout.pair_kerning(0).unwrap().into();
+    assert!(!is_kerned);
+    assert_eq!(range.start, 0);
+    assert_eq!(range.length, 1);
+}
+
+#[test]
+fn windowed_layout() {
+    use directwrite::descriptions::TextRange;
+    use directwrite::text_layout::ITextLayout;
+    use math2d::Point2f;
+
+    let factory = Factory::new().unwrap();
+
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let document = "h\u{e9}llo \u{1F600} w\u{f6}rld\nsecond line of text\nthird";
+    let start = document.find("second").unwrap();
+    let end = document[start..].find('\n').unwrap() + start;
+    let offset = document[..start].encode_utf16().count() as u32;
+
+    let layout = TextLayout::create(&factory)
+        .with_str_window(document, start..end, offset)
+        .with_format(&font)
+        .with_size(300.0, 200.0)
+        .build()
+        .unwrap();
+    assert_eq!(layout.absolute_offset(), offset);
+    assert_eq!(layout.to_relative(offset + 3), Some(3));
+    assert_eq!(layout.to_relative(offset - 1), None);
+    assert!(layout
+        .hit_test_text_position_absolute(offset - 1, false)
+        .is_err());
+
+    // Hit testing the 'o' of "second" reports its position in the whole document
+    let hit = layout
+        .hit_test_text_position_absolute(offset + 3, false)
+        .unwrap();
+    assert_eq!(hit.metrics.text_position, offset + 3);
+    let x = hit.metrics.position.x + hit.metrics.size.width * 0.25;
+    let y = hit.metrics.position.y + hit.metrics.size.height * 0.5;
+
+    let point = layout.hit_test_point_absolute(x, y);
+    assert!(point.is_inside);
+    assert_eq!(point.metrics.text_position, offset + 3);
+    assert_eq!(layout.insertion_index_at_absolute(x, y), offset + 3);
+    assert_eq!(layout.hit_test_point(x, y).metrics.text_position, 3);
+
+    let mut metrics = Vec::new();
+    layout
+        .hit_test_text_range_absolute(offset - 4, 10, 0.0, 0.0, &mut metrics)
+        .unwrap();
+    assert_eq!(metrics.len(), 1);
+    assert_eq!(metrics[0].text_position, offset);
+    assert_eq!(metrics[0].length, 6);
+
+    // Ranges ending past u32::MAX are rejected rather than wrapping around
+    assert!(layout
+        .hit_test_text_range_absolute(offset, u32::MAX, 0.0, 0.0, &mut metrics)
+        .is_err());
+    assert!(layout
+        .selection_rects_absolute(
+            TextRange {
+                start: offset,
+                length: u32::MAX
+            },
+            Point2f::new(0.0, 0.0)
+        )
+        .is_err());
+    assert_eq!(layout.to_absolute(u32::MAX), None);
+    assert_eq!(
+        layout.to_absolute_range(TextRange {
+            start: 3,
+            length: 3
+        }),
+        Some(TextRange {
+            start: offset + 3,
+            length: 3
+        })
+    );
+
+    // A selection starting on the line before is highlighted from the start of this one
+    let rects = layout
+        .selection_rects_absolute(
+            TextRange {
+                start: offset - 4,
+                length: 10,
+            },
+            Point2f::new(0.0, 0.0),
+        )
+        .unwrap();
+    let relative = layout
+        .selection_rects(
+            TextRange {
+                start: 0,
+                length: 6,
+            },
+            Point2f::new(0.0, 0.0),
+        )
+        .unwrap();
+    assert_eq!(rects.len(), 1);
+    assert_eq!(
+        (rects[0].left, rects[0].right),
+        (relative[0].left, relative[0].right)
+    );
+
+    assert_eq!(
+        layout.next_caret_position_absolute(offset + 3).unwrap(),
+        offset + 4
+    );
+    assert_eq!(
+        layout.previous_caret_position_absolute(offset + 3).unwrap(),
+        offset + 2
+    );
+    assert!(layout.next_caret_position_absolute(offset - 1).is_err());
+    // There is only one line, so moving down goes to its end
+    assert_eq!(
+        layout
+            .caret_position_for_line_offset_absolute(offset + 3, 1, x)
+            .unwrap(),
+        offset + "second line of text".len() as u32
+    );
+
+    // The UTF-16 window gives the same positions
+    let wide: Vec<u16> = document.encode_utf16().collect();
+    let len = "second line of text".len();
+    let wide_layout = TextLayout::create(&factory)
+        .with_text_window(&wide, offset as usize..offset as usize + len, offset)
+        .with_format(&font)
+        .with_size(300.0, 200.0)
+        .build()
+        .unwrap();
+    assert_eq!(wide_layout.insertion_index_at_absolute(x, y), offset + 3);
+
+    // A window which would end past u32::MAX has positions with no document position
+    assert!(TextLayout::create(&factory)
+        .with_str_window(document, start..end, u32::MAX - 3)
+        .with_format(&font)
+        .with_size(300.0, 200.0)
+        .build()
+        .is_err());
+}
+
+#[test]
+fn break_opportunities() {
+    let factory = Factory::new().unwrap();
+
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let layout = TextLayout::create(&factory)
+        .with_str("hello world")
+        .with_format(&font)
+        .with_size(300.0, 200.0)
+        .build()
+        .unwrap();
+
+    let breaks = layout.break_opportunities();
+    assert_eq!(breaks.len(), 11);
+
+    // A line can wrap after the space, so "world" starts the next line
+    assert!(breaks[5]);
+    for &mid_word in &[0, 1, 2, 3, 6, 7, 8, 9] {
+        assert!(!breaks[mid_word], "break inside a word at {}", mid_word);
+    }
+}
+
+#[test]
+fn bitmap_render_target() {
+    use directwrite::descriptions::{GlyphOffset, GlyphRun};
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_face::IFontFace;
+    use directwrite::font_family::IFontFamily;
+    use directwrite::RenderingParams;
+    use math2d::Color;
+
+    let factory = Factory::new().unwrap();
+    let interop = factory.gdi_interop().unwrap();
+    let mut target = interop.create_bitmap_render_target(64, 48).unwrap();
+
+    let size = target.size().unwrap();
+    assert_eq!((size.width, size.height), (64, 48));
+    assert!(!target.memory_dc().is_null());
+    assert!(target.bitmap_bits().unwrap().iter().all(|&b| b == 0));
+
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    let index = collection.find_family_by_name("Segoe UI").unwrap();
+    let face = collection
+        .family(index)
+        .unwrap()
+        .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap()
+        .create_face()
+        .unwrap();
+
+    let indices = face.glyph_indices(&['W' as u32]).unwrap();
+    let run = GlyphRun {
+        font_face: &face,
+        font_em_size: 32.0,
+        glyph_indices: &indices,
+        glyph_advances: &[32.0],
+        glyph_offsets: &[GlyphOffset {
+            advance_offset: 0.0,
+            ascender_offset: 0.0,
+        }],
+        is_sideways: false,
+        bidi_level: 0,
+    };
+
+    let params = RenderingParams::create_default(&factory).unwrap();
+    let white = Color::new(1.0, 1.0, 1.0, 1.0);
+    let bounds = target
+        .draw_glyph_run(8.0, 36.0, MeasuringMode::Natural, &run, &params, white)
+        .unwrap();
+    assert!(bounds.right > bounds.left && bounds.bottom > bounds.top);
+
+    let bits = target.bitmap_bits().unwrap();
+    assert_eq!(bits.len(), 64 * 48 * 4);
+    assert!(bits.iter().any(|&b| b != 0));
+}
+
+#[test]
+fn character_spacing() {
+    let factory = Factory::new().unwrap();
+
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let text = "Tracking test";
+    let mut layout = TextLayout::create(&factory)
+        .with_str(text)
+        .with_format(&font)
+        .with_size(1000.0, 200.0)
+        .build()
+        .unwrap();
+
+    let (spacing, _) = layout.character_spacing(0).unwrap().into();
+    assert_eq!(spacing.leading, 0.0);
+    assert_eq!(spacing.trailing, 0.0);
+
+    let mut last_width = layout.metrics().width;
+    for &tracking in &[0.5, 1.0, 2.0] {
+        layout
+            .set_character_spacing(tracking, tracking, 0.0, ..text.len() as u32)
+            .unwrap();
+        let width = layout.metrics().width;
+        assert!(width > last_width);
+        last_width = width;
+    }
+
+    let (spacing, range) = layout.character_spacing(3).unwrap().into();
+    assert_eq!(spacing.leading, 2.0);
+    assert_eq!(spacing.trailing, 2.0);
+    assert_eq!(spacing.minimum_advance, 0.0);
+    assert_eq!(range.start, 0);
+    assert_eq!(range.length as usize, text.len());
+}
+
+#[test]
+fn text_metrics1() {
+    let factory = Factory::new().unwrap();
+
+    let font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let layout = TextLayout::create(&factory)
+        .with_str("Trailing spaces   ")
+        .with_format(&font)
+        .with_size(300.0, 200.0)
+        .build()
+        .unwrap();
+
+    let metrics = layout.metrics();
+    let metrics1 = layout.metrics1().unwrap();
+
+    // The common fields are the same, and reachable through Deref
+    assert_eq!(metrics1.width, metrics.width);
+    assert_eq!(
+        metrics1.width_including_trailing_whitespace,
+        metrics.width_including_trailing_whitespace
+    );
+    assert_eq!(metrics1.height, metrics.height);
+    assert_eq!(metrics1.line_count, 1);
+    assert!(metrics1.height_including_trailing_whitespace >= metrics1.height);
+}
+
+#[test]
+fn last_line_wrapping() {
+    use directwrite::descriptions::Trimming;
+    use directwrite::text_format::ITextFormat;
+    use directwrite::text_layout::ITextLayout;
+    use winapi::shared::winerror::E_NOINTERFACE;
+
+    let factory = Factory::new().unwrap();
+
+    let mut font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    font.set_trimming(
+        &Trimming {
+            granularity: (TrimmingGranularity::Character as u32).into(),
+            delimiter: 0,
+            delimiter_count: 0,
+        },
+        None,
+    )
+    .unwrap();
+
+    match font.last_line_wrapping() {
+        Ok(wrapping) => assert!(wrapping),
+        // Older than Windows 8.1
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    }
+
+    let mut layout = TextLayout::create(&factory)
+        .with_str("Overflowing labels")
+        .with_format(&font)
+        .with_size(60.0, 200.0)
+        .build()
+        .unwrap();
+    assert!(layout.last_line_wrapping().unwrap());
+    let wrapped = layout.metrics().line_count;
+
+    layout.set_last_line_wrapping(false).unwrap();
+    assert!(!layout.last_line_wrapping().unwrap());
+    let overflowing = layout.metrics().line_count;
+
+    assert_eq!(overflowing, 1);
+    assert_ne!(wrapped, overflowing);
+
+    // Layouts created from the format pick up its setting
+    font.set_last_line_wrapping(false).unwrap();
+    assert!(!font.last_line_wrapping().unwrap());
+    let layout = TextLayout::create(&factory)
+        .with_str("Overflowing labels")
+        .with_format(&font)
+        .with_size(60.0, 200.0)
+        .build()
+        .unwrap();
+    assert!(!layout.last_line_wrapping().unwrap());
+    assert_eq!(layout.metrics().line_count, 1);
+}
+
+#[test]
+fn optical_alignment() {
+    use directwrite::text_format::ITextFormat;
+    use winapi::shared::winerror::E_NOINTERFACE;
+
+    let factory = Factory::new().unwrap();
+
+    let mut font = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    match font.optical_alignment() {
+        Ok(alignment) => assert_eq!(alignment.value, OpticalAlignment::None as u32),
+        // Older than Windows 8.1
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    }
+
+    font.set_optical_alignment(OpticalAlignment::NoSideBearings)
+        .unwrap();
+    assert_eq!(
+        font.optical_alignment().unwrap().value,
+        OpticalAlignment::NoSideBearings as u32
+    );
+
+    let mut layout = TextLayout::create(&factory)
+        .with_str("\u{201C}Hanging\u{201D} punctuation")
+        .with_format(&font)
+        .with_size(300.0, 200.0)
+        .with_optical_alignment(OpticalAlignment::None)
+        .build()
+        .unwrap();
+    assert_eq!(
+        layout.optical_alignment().unwrap().value,
+        OpticalAlignment::None as u32
+    );
+
+    layout
+        .set_optical_alignment(OpticalAlignment::NoSideBearings)
+        .unwrap();
+    assert_eq!(
+        layout.optical_alignment().unwrap().value,
+        OpticalAlignment::NoSideBearings as u32
+    );
+}
+
+#[test]
+fn has_embedded_bitmap() {
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_face::IFontFace;
+    use directwrite::font_family::IFontFamily;
+
+    let factory = Factory::new().unwrap();
+
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    let index = collection.find_family_by_name("Segoe UI").unwrap();
+    let face = collection
+        .family(index)
+        .unwrap()
+        .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap()
+        .create_face()
+        .unwrap();
+
+    // Segoe UI is outline-only at every size
+    assert!(!face.has_embedded_bitmap(12.0, 1.0));
+    assert!(!face.has_embedded_bitmap(16.0, 1.5));
+}
+
+#[test]
+fn line_spacing2() {
+    use directwrite::descriptions::LineSpacing2;
+    use directwrite::text_format::ITextFormat;
+    use winapi::shared::winerror::{E_INVALIDARG, E_NOINTERFACE};
+
+    let factory = Factory::new().unwrap();
+    let mut format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    // Older than Windows 10
+    match format.line_spacing2() {
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        result => result.unwrap(),
+    };
+
+    let mut proportional = LineSpacing2 {
+        method: LineSpacingMethod::Proportional.into(),
+        height: 1.5,
+        baseline: 1.0,
+        leading_before: 0.5,
+        font_line_gap_usage: FontLineGapUsage::Enabled.into(),
+    };
+    format.set_line_spacing2(&proportional).unwrap();
+    let spacing = format.line_spacing2().unwrap();
+    assert_eq!(spacing.method.value, LineSpacingMethod::Proportional as u32);
+    assert_eq!(spacing.height, 1.5);
+    assert_eq!(spacing.leading_before, 0.5);
+    assert_eq!(
+        spacing.font_line_gap_usage.value,
+        FontLineGapUsage::Enabled as u32
+    );
+
+    proportional.height = -1.0;
+    let err = format.set_line_spacing2(&proportional).unwrap_err();
+    assert_eq!(err.0, E_INVALIDARG);
+
+    // Layouts accept the same options, and the old API converts over
+    let mut layout = TextLayout::create(&factory)
+        .with_str("one\ntwo")
+        .with_format(&format)
+        .with_size(300.0, 200.0)
+        .build()
+        .unwrap();
+    format
+        .set_line_spacing(LineSpacingMethod::Uniform, 20.0, 16.0)
+        .unwrap();
+    let uniform: LineSpacing2 = format.line_spacing().unwrap().into();
+    layout.set_line_spacing2(&uniform).unwrap();
+    let spacing = layout.line_spacing2().unwrap();
+    assert_eq!(spacing.method.value, LineSpacingMethod::Uniform as u32);
+    assert_eq!((spacing.height, spacing.baseline), (20.0, 16.0));
+}
+
+#[test]
+fn family_and_locale_names_have_no_terminator() {
+    use directwrite::text_format::ITextFormat;
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .with_locale("en-gb")
+        .build()
+        .unwrap();
+    assert_eq!(format.font_family_name().unwrap(), "Segoe UI");
+    assert_eq!(format.locale_name().unwrap(), "en-gb");
+
+    let layout = TextLayout::create(&factory)
+        .with_str("Hello")
+        .with_format(&format)
+        .with_size(200.0, 50.0)
+        .build()
+        .unwrap();
+    let family = ITextLayout::font_family_name(&layout, 1).unwrap();
+    assert_eq!(*family, "Segoe UI");
+    assert_eq!(family.range.length, 5);
+    let locale = ITextLayout::locale_name(&layout, 1).unwrap();
+    assert_eq!(*locale, "en-gb");
+}
+
+#[test]
+fn visual_origin_matches_hit_testing() {
+    use directwrite::text_format::ITextFormat;
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let alignments = [
+        TextAlignment::Leading,
+        TextAlignment::Trailing,
+        TextAlignment::Center,
+        TextAlignment::Justified,
+    ];
+    let paragraphs = [
+        ParagraphAlignment::Near,
+        ParagraphAlignment::Far,
+        ParagraphAlignment::Center,
+    ];
+
+    for &rtl in &[false, true] {
+        for &alignment in &alignments {
+            for &paragraph in &paragraphs {
+                // Hebrew so that the first character is also the right-most one in RTL
+                let text = if rtl {
+                    "\u{5e9}\u{5dc}\u{5d5}\u{5dd} "
+                } else {
+                    "Hello "
+                };
+                let mut layout = TextLayout::create(&factory)
+                    .with_str(text)
+                    .with_format(&format)
+                    .with_size(300.0, 100.0)
+                    .build()
+                    .unwrap();
+                if rtl {
+                    layout
+                        .set_reading_direction(ReadingDirection::RightToLeft)
+                        .unwrap();
+                }
+                layout.set_text_alignment(alignment).unwrap();
+                layout.set_paragraph_alignment(paragraph).unwrap();
+
+                let origin = layout.visual_origin(alignment, paragraph);
+                let metrics = layout.metrics();
+                assert!((origin.x - metrics.left).abs() < 0.01);
+                assert!((origin.y - metrics.top).abs() < 0.01);
+
+                let first = layout.hit_test_text_position(0, false).unwrap().metrics;
+                assert!((origin.y - first.position.y).abs() < 0.01);
+                let edge = if rtl {
+                    first.position.x + first.size.width - metrics.width
+                } else {
+                    first.position.x
+                };
+                assert!((origin.x - edge).abs() < 0.01);
+            }
+        }
+    }
+}
+
+#[test]
+fn script_aware_line_spacing() {
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let latin = format
+        .script_aware_line_spacing(&factory, "The quick brown fox")
+        .unwrap();
+    let cjk = format
+        .script_aware_line_spacing(&factory, "漢字とひらがなとカタカナ")
+        .unwrap();
+
+    assert_eq!(latin.method.value, LineSpacingMethod::Uniform as u32);
+    assert_eq!(cjk.method.value, LineSpacingMethod::Uniform as u32);
+    assert!(cjk.spacing > latin.spacing);
+    assert!(cjk.spacing >= 16.0 * 1.5);
+    assert!(latin.baseline > 0.0 && latin.baseline < latin.spacing);
+
+    // Punctuation and spaces between ideographs don't count against them
+    let spaced_cjk = format
+        .script_aware_line_spacing(&factory, "漢字 、 ひらがな 。 カタカナ ! ? ...")
+        .unwrap();
+    assert!(spaced_cjk.spacing >= 16.0 * 1.5);
+}
+
+#[test]
+fn layout_keeps_utf16_text() {
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    // An interior NUL and a surrogate pair survive untouched
+    let text = [0x61, 0x00, 0x62, 0xD83D, 0xDE00];
+    let layout = TextLayout::create(&factory)
+        .with_text(&text)
+        .with_format(&format)
+        .with_size(300.0, 100.0)
+        .with_retained_text(true)
+        .build()
+        .unwrap();
+    assert_eq!(layout.text_utf16(), Some(&text[..]));
+    assert_eq!(layout.text_len(), 5);
+    let lines: u32 = layout.line_metrics().iter().map(|l| l.length).sum();
+    assert_eq!(lines, 5);
+
+    let layout = TextLayout::create(&factory)
+        .with_str("héllo")
+        .with_format(&format)
+        .with_size(300.0, 100.0)
+        .with_retained_text(true)
+        .build()
+        .unwrap();
+    assert_eq!(layout.text_len(), 5);
+    assert_eq!(
+        String::from_utf16(layout.text_utf16().unwrap()).unwrap(),
+        "héllo"
+    );
+
+    // Without opting in only the length is kept, and Debug doesn't query the layout
+    let layout = TextLayout::create(&factory)
+        .with_str("héllo\n")
+        .with_format(&format)
+        .with_size(300.0, 100.0)
+        .build()
+        .unwrap();
+    assert_eq!(layout.text_utf16(), None);
+    assert_eq!(layout.text_len(), 6);
+    assert!(format!("{:?}", layout).contains("text_len: Some(6)"));
+}
+
+#[test]
+fn cluster_offsets() {
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let layout = TextLayout::create(&factory)
+        .with_str("Drag to select")
+        .with_format(&format)
+        .with_size(1000.0, 100.0)
+        .build()
+        .unwrap();
+
+    let offsets = layout.cluster_offsets();
+    assert_eq!(offsets.len(), 14);
+    assert_eq!(offsets[0].0, 0);
+    for pair in offsets.windows(2) {
+        assert!(pair[1].0 > pair[0].0);
+        assert!(pair[1].1 > pair[0].1);
+    }
+
+    let width = layout.metrics().width_including_trailing_whitespace;
+    let last = offsets.last().unwrap().1;
+    assert!((last - width).abs() < 0.01);
+
+    // Offsets start over on each line
+    let layout = TextLayout::create(&factory)
+        .with_str("ab\ncd")
+        .with_format(&format)
+        .with_size(1000.0, 100.0)
+        .build()
+        .unwrap();
+    let offsets = layout.cluster_offsets();
+    let c = offsets.iter().find(|&&(pos, _)| pos == 3).unwrap();
+    let a = offsets.iter().find(|&&(pos, _)| pos == 0).unwrap();
+    assert!(c.1 < a.1 * 2.0);
+}
+
+#[test]
+fn ranged_builder_formatting() {
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let layout = TextLayout::create(&factory)
+        .with_str("plain bold italic")
+        .with_format(&format)
+        .with_size(300.0, 100.0)
+        .with_range_weight(FontWeight::BOLD, 6u32..10)
+        .with_range_style(FontStyle::Italic, 11u32..17)
+        .with_range_font_size(24.0, 11u32..17)
+        .with_range_underline(true, 0u32..5)
+        .build()
+        .unwrap();
+
+    assert_eq!(layout.font_weight(7).unwrap().value, FontWeight::BOLD);
+    assert_eq!(layout.font_weight(0).unwrap().value, FontWeight::NORMAL);
+    assert_eq!(
+        layout.font_style(12).unwrap().value.value,
+        FontStyle::Italic as u32
+    );
+    assert_eq!(layout.font_size(12).unwrap().value, 24.0);
+    assert!(layout.underline(2).unwrap().value);
+    assert!(!layout.underline(7).unwrap().value);
+
+    // A bad attribute fails the whole build and says what went wrong
+    let err = TextLayout::create(&factory)
+        .with_str("plain")
+        .with_format(&format)
+        .with_size(300.0, 100.0)
+        .with_range_weight(FontWeight::BOLD, 0u32..2)
+        .with_range_font_size(-1.0, 1u32..3)
+        .build()
+        .unwrap_err();
+    assert_eq!(err.attribute, Some("font size"));
+    assert_eq!(err.range.map(|r| (r.start, r.length)), Some((1, 2)));
+}
+
+#[test]
+fn builder_locale_ranges() {
+    use directwrite::descriptions::TextRange;
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .with_locale("en-US")
+        .build()
+        .unwrap();
+
+    // "Hello " and " colour" surround a Japanese phrase
+    let layout = TextLayout::create(&factory)
+        .with_str("Hello \u{65e5}\u{672c}\u{8a9e}\u{3067}\u{3059} colour")
+        .with_format(&format)
+        .with_size(300.0, 100.0)
+        .with_locale_ranges(&[
+            (TextRange::from(6u32..11), "ja-JP"),
+            (TextRange::from(12u32..18), "en-GB"),
+        ])
+        .build()
+        .unwrap();
+
+    let japanese = layout.locale_name(7).unwrap();
+    assert_eq!(japanese.value, "ja-JP");
+    assert_eq!((japanese.range.start, japanese.range.length), (6, 5));
+
+    let british = layout.locale_name(12).unwrap();
+    assert_eq!(british.value, "en-GB");
+    assert_eq!((british.range.start, british.range.length), (12, 6));
+
+    assert_eq!(layout.locale_name(0).unwrap().value, "en-US");
+    assert_eq!(layout.locale_name(11).unwrap().value, "en-US");
+}
+
+#[test]
+fn glyph_image_data() {
+    use directwrite::enums::GlyphImageFormats;
+    use directwrite::font_face::IFontFace;
+    use winapi::shared::winerror::{E_INVALIDARG, E_NOINTERFACE};
+
+    let factory = Factory::new().unwrap();
+    let face = |path| {
+        let file = FontFile::create(&factory)
+            .with_file_path(path)
+            .build()
+            .unwrap();
+        FontFace::create(&factory)
+            .with_files(&[file])
+            .with_font_face_type(FontFaceType::TrueType)
+            .with_face_index(0)
+            .build()
+            .unwrap()
+    };
+    let opensans = face("tests/test_fonts/OpenSans-Regular.ttf");
+
+    // Plain outline fonts have no images to hand out
+    let formats = match opensans.glyph_image_formats() {
+        Ok(formats) => formats,
+        // Older than Windows 10 Anniversary Update
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert_eq!(formats.0, GlyphImageFormats::TRUETYPE.0);
+    let glyph = opensans.glyph_indices(&['a' as u32]).unwrap()[0];
+    assert_eq!(
+        opensans.glyph_formats(glyph, 1, 512).unwrap().0,
+        GlyphImageFormats::TRUETYPE.0
+    );
+    let err = opensans
+        .glyph_image_data(glyph, 32, GlyphImageFormats::PNG)
+        .unwrap_err();
+    assert_eq!(err.0, E_INVALIDARG);
+
+    // Open Sans with an SVG document for "R" and a 16 ppem PNG strike holding "e"
+    let images = face("tests/test_fonts/OpenSans-Images.ttf");
+    let formats = images.glyph_image_formats().unwrap();
+    assert_eq!(
+        formats.0,
+        GlyphImageFormats::TRUETYPE.0 | GlyphImageFormats::SVG.0 | GlyphImageFormats::PNG.0
+    );
+    let glyphs = images.glyph_indices(&['R' as u32, 'e' as u32]).unwrap();
+    let (svg_glyph, png_glyph) = (glyphs[0], glyphs[1]);
+
+    let svg = images
+        .glyph_image_data(svg_glyph, 64, GlyphImageFormats::SVG)
+        .unwrap();
+    assert!(svg.data().starts_with(b"<svg"));
+    let id = format!("id=\"glyph{}\"", svg_glyph);
+    assert!(String::from_utf8_lossy(svg.data()).contains(&id));
+    assert_eq!((svg.pixel_size.width, svg.pixel_size.height), (0, 0));
+
+    // The only strike is used whatever size is asked for
+    let png = images
+        .glyph_image_data(png_glyph, 64, GlyphImageFormats::PNG)
+        .unwrap();
+    assert!(png.data().starts_with(b"\x89PNG\r\n\x1a\n"));
+    assert_eq!(png.pixels_per_em, 16);
+    assert_eq!((png.pixel_size.width, png.pixel_size.height), (16, 16));
+
+    // Asking for a format the glyph doesn't have, more than one format, or one without image
+    // data is an error
+    for &(glyph, format) in &[
+        (png_glyph, GlyphImageFormats::SVG),
+        (svg_glyph, GlyphImageFormats::PNG),
+        (svg_glyph, GlyphImageFormats::TRUETYPE),
+        (
+            svg_glyph,
+            GlyphImageFormats(GlyphImageFormats::SVG.0 | GlyphImageFormats::PNG.0),
+        ),
+    ] {
+        let err = images.glyph_image_data(glyph, 16, format).unwrap_err();
+        assert_eq!(err.0, E_INVALIDARG);
+    }
+}
+
+#[test]
+fn stylistic_set_names() {
+    use directwrite::font_face::IFontFace;
+
+    let factory = Factory::new().unwrap();
+    let face = |path| {
+        let file = FontFile::create(&factory)
+            .with_file_path(path)
+            .build()
+            .unwrap();
+        FontFace::create(&factory)
+            .with_files(&[file])
+            .with_font_face_type(FontFaceType::TrueType)
+            .with_face_index(0)
+            .build()
+            .unwrap()
+    };
+
+    // Open Sans has no stylistic sets at all
+    let opensans = face("tests/test_fonts/OpenSans-Regular.ttf");
+    assert!(opensans.stylistic_set_names().unwrap().is_empty());
+
+    // Fira Code has several, each listed once
+    let firacode = face("tests/test_fonts/FiraCode-Regular.ttf");
+    let sets = firacode.stylistic_set_names().unwrap();
+    assert!(!sets.is_empty());
+    for (i, (tag, name)) in sets.iter().enumerate() {
+        assert!(tag.as_bytes().starts_with(b"ss"));
+        assert!(sets[..i].iter().all(|(other, _)| other != tag));
+        if let Some(name) = name {
+            assert!(!name.is_empty());
+        }
+    }
+}
+
+#[test]
+fn proportional_line_spacing_follows_font_size() {
+    use directwrite::descriptions::LineSpacing2;
+    use directwrite::text_format::ITextFormat;
+    use directwrite::text_layout::ITextLayout;
+    use winapi::shared::winerror::E_NOINTERFACE;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let mut layout = TextLayout::create(&factory)
+        .with_str("small\nlarge")
+        .with_format(&format)
+        .with_size(300.0, 200.0)
+        .build()
+        .unwrap();
+
+    let spacing = LineSpacing2 {
+        method: LineSpacingMethod::Proportional.into(),
+        height: 1.5,
+        baseline: 1.0,
+        leading_before: 0.5,
+        font_line_gap_usage: FontLineGapUsage::Default.into(),
+    };
+    match layout.set_line_spacing2(&spacing) {
+        // Older than Windows 10
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        result => result.unwrap(),
+    }
+    layout.set_font_size(32.0, 6u32..11).unwrap();
+
+    let read = layout.line_spacing2().unwrap();
+    assert_eq!(read.method.value, LineSpacingMethod::Proportional as u32);
+    assert_eq!(read.height, 1.5);
+
+    // The line in the larger font gets proportionally taller
+    let lines = layout.line_metrics();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].height > lines[0].height * 1.8);
+}
+
+#[test]
+fn selection_rects() {
+    use directwrite::text_layout::ITextLayout;
+    use math2d::Point2f;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(20.0)
+        .build()
+        .unwrap();
+
+    // The Hebrew word is displayed right to left, so its first two letters are on the right
+    let layout = TextLayout::create(&factory)
+        .with_str("abc \u{5d0}\u{5d1}\u{5d2} def")
+        .with_format(&format)
+        .with_size(400.0, 100.0)
+        .build()
+        .unwrap();
+    let origin = Point2f::new(10.0, 20.0);
+
+    // "bc " is one contiguous piece of left-to-right text
+    let rects = layout.selection_rects(1u32..4, origin).unwrap();
+    assert_eq!(rects.len(), 1);
+    let boxes = layout.hit_test_text_range_vec(1, 3, origin).unwrap();
+    let left = boxes.iter().map(|b| b.position.x).fold(f32::MAX, f32::min);
+    assert!((rects[0].left - left).abs() < 0.01);
+
+    // "c " followed by the first two Hebrew letters leaves a gap where the third one is
+    let rects = layout.selection_rects(2u32..6, origin).unwrap();
+    assert!(rects.len() > 1);
+    for pair in rects.windows(2) {
+        assert_eq!(pair[0].top, pair[1].top);
+        assert!(pair[0].right < pair[1].left);
+    }
+    let gimel = layout
+        .hit_test_text_position(6, false)
+        .unwrap()
+        .metrics
+        .rect();
+    assert!(rects.iter().all(
+        |r| r.right <= gimel.left + origin.x + 0.01 || r.left >= gimel.right + origin.x - 0.01
+    ));
+
+    assert!(layout.selection_rects(2u32..2, origin).unwrap().is_empty());
+}
+
+#[test]
+fn hit_test_text_range_vec() {
+    use directwrite::text_layout::ITextLayout;
+    use math2d::Point2f;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let layout = TextLayout::create(&factory)
+        .with_str("first line\nsecond line")
+        .with_format(&format)
+        .with_size(300.0, 100.0)
+        .build()
+        .unwrap();
+
+    let origin = Point2f::new(10.0, 20.0);
+    let metrics = layout.hit_test_text_range_vec(6, 12, origin).unwrap();
+    assert!(metrics.len() >= 2);
+
+    // Matches the buffer-reusing variant, even with a buffer that starts out too small
+    let mut reused = Vec::with_capacity(1);
+    layout
+        .hit_test_text_range(6, 12, origin.x, origin.y, &mut reused)
+        .unwrap();
+    assert_eq!(reused.len(), metrics.len());
+
+    let rects: Vec<_> = metrics.iter().map(|m| m.rect()).collect();
+    assert!(rects.iter().all(|r| r.left >= 10.0 && r.top >= 20.0));
+    assert!(rects.iter().all(|r| r.right >= r.left && r.bottom > r.top));
+    assert!(rects.last().unwrap().top > rects[0].top);
+}
+
+#[test]
+fn run_extents() {
+    use directwrite::font_face::IFontFace;
+
+    let factory = Factory::new().unwrap();
+    let file = FontFile::create(&factory)
+        .with_file_path("tests/test_fonts/OpenSans-Regular.ttf")
+        .build()
+        .unwrap();
+    let face = FontFace::create(&factory)
+        .with_files(&[file])
+        .with_font_face_type(FontFaceType::TrueType)
+        .with_face_index(0)
+        .build()
+        .unwrap();
+
+    let em_size = 32.0;
+    let text: Vec<u32> = "Type".chars().map(|c| c as u32).collect();
+    let glyphs = face.glyph_indices(&text).unwrap();
+    let units = face.metrics().design_units_per_em as f32;
+    let advances: Vec<f32> = face
+        .design_glyph_metrics(&glyphs, false)
+        .unwrap()
+        .iter()
+        .map(|m| m.advance_width as f32 * em_size / units)
+        .collect();
+
+    for &rtl in &[false, true] {
+        let extents = face.run_extents(em_size, &glyphs, &advances, rtl).unwrap();
+        let sum: f32 = advances.iter().sum();
+        assert!((extents.advance - sum).abs() < 0.001);
+        assert!(extents.left_overhang.is_finite());
+        assert!(extents.right_overhang.is_finite());
+        // The ink of a word fits within a glyph's width of its advance box
+        assert!(extents.left_overhang.abs() < em_size);
+        assert!(extents.right_overhang.abs() < em_size);
+    }
+
+    assert!(face
+        .run_extents(em_size, &glyphs, &advances[1..], false)
+        .is_err());
+}
+
+#[test]
+fn attribute_runs() {
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let layout = TextLayout::create(&factory)
+        .with_str("Hello, World!")
+        .with_format(&format)
+        .with_size(400.0, 100.0)
+        .with_range_weight(FontWeight::BOLD, 0u32..5)
+        .with_range_underline(true, 7u32..12)
+        .with_range_font_size(30.0, 7u32..13)
+        .build()
+        .unwrap();
+
+    let weights: Vec<_> = layout
+        .font_weight_runs()
+        .map(|run| run.unwrap())
+        .map(|run| (run.range.start, run.range.length, run.value))
+        .collect();
+    assert_eq!(
+        weights,
+        [(0, 5, FontWeight::BOLD), (5, 8, FontWeight::NORMAL)]
+    );
+
+    let underlines: Vec<_> = layout
+        .underline_runs()
+        .map(|run| run.unwrap())
+        .map(|run| (run.range.start, run.range.length, run.value))
+        .collect();
+    assert_eq!(underlines, [(0, 7, false), (7, 5, true), (12, 1, false)]);
+
+    // The last run is clipped to the text even though the formatting extends past it
+    let sizes: Vec<_> = layout
+        .font_size_runs()
+        .map(|run| run.unwrap())
+        .map(|run| (run.range.start, run.range.length, run.value))
+        .collect();
+    assert_eq!(sizes, [(0, 7, 16.0), (7, 6, 30.0)]);
+
+    // A custom lookup which always reports an empty range still covers the text once
+    let runs: Vec<_> = layout
+        .runs_of(|layout, position| {
+            let mut run = layout.font_size(position)?;
+            run.range.length = 0;
+            Ok(run)
+        })
+        .map(|run| run.unwrap().range)
+        .map(|range| (range.start, range.length))
+        .collect();
+    assert_eq!(runs.len(), 13);
+    assert!(runs.iter().enumerate().all(|(i, &r)| r == (i as u32, 1)));
+
+    let empty = TextLayout::create(&factory)
+        .with_str("")
+        .with_format(&format)
+        .with_size(400.0, 100.0)
+        .build()
+        .unwrap();
+    assert_eq!(empty.font_weight_runs().count(), 0);
+}
+
+#[test]
+fn font_panose() {
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_family::IFontFamily;
+
+    let factory = Factory::new().unwrap();
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    let font = |name: &str| {
+        let index = collection.find_family_by_name(name).unwrap();
+        collection
+            .family(index)
+            .unwrap()
+            .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+            .unwrap()
+    };
+
+    let consolas = font("Consolas").panose();
+    let segoe = font("Segoe UI").panose();
+
+    assert_eq!(
+        consolas.family_kind().value,
+        PanoseFamily::TextDisplay as u32
+    );
+    assert_eq!(consolas.bytes()[0], 2);
+    // Consolas is monospaced, Segoe UI isn't
+    assert_eq!(consolas.proportion(), 9);
+    assert_ne!(segoe.proportion(), 9);
+    // Both are sans serif faces of regular weight
+    assert_eq!(consolas.serif_style(), segoe.serif_style());
+    assert!((5..=6).contains(&segoe.weight()));
+}
+
+#[test]
+fn font_unicode_ranges_and_metrics1() {
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_family::IFontFamily;
+
+    let factory = Factory::new().unwrap();
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    let index = collection.find_family_by_name("Segoe UI").unwrap();
+    let font = collection
+        .family(index)
+        .unwrap()
+        .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap();
+
+    let ranges = font.unicode_ranges().unwrap();
+    assert!(!ranges.is_empty());
+    assert!(ranges.windows(2).all(|w| w[0].last < w[1].first));
+    for &c in &['A', 'é', 'Ω'] {
+        assert!(ranges.iter().any(|r| r.contains(c)));
+        assert!(font.has_character(c));
+    }
+    assert!(!ranges.iter().any(|r| r.contains('日')));
+
+    let metrics = font.metrics1();
+    assert_eq!(
+        metrics.design_units_per_em,
+        font.metrics().design_units_per_em
+    );
+    assert!(metrics.glyph_box_right > metrics.glyph_box_left);
+    assert!(metrics.glyph_box_top > metrics.glyph_box_bottom);
+    // Subscripts sit below the baseline and superscripts above it, both smaller than the font
+    assert!(metrics.subscript_position_y > 0);
+    assert!(metrics.superscript_position_y > 0);
+    assert!((metrics.subscript_size_y as u16) < metrics.design_units_per_em);
+    assert!((metrics.superscript_size_y as u16) < metrics.design_units_per_em);
+}
+
+#[test]
+fn apply_attribute_spans() {
+    use directwrite::descriptions::TextRange;
+    use directwrite::text_layout::{AttributeSpan, ITextLayout};
+    use winapi::shared::winerror::E_INVALIDARG;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let mut layout = TextLayout::create(&factory)
+        .with_str("let x = 1; // done")
+        .with_format(&format)
+        .with_size(500.0, 100.0)
+        .build()
+        .unwrap();
+
+    let range = |start, length| TextRange { start, length };
+    let spans = [
+        AttributeSpan::Weight {
+            range: range(0, 3),
+            value: FontWeight::BOLD,
+        },
+        // A negative font size is rejected, but doesn't stop the spans after it
+        AttributeSpan::FontSize {
+            range: range(4, 1),
+            value: -1.0,
+        },
+        AttributeSpan::FontFamily {
+            range: range(11, 7),
+            value: "Consolas",
+        },
+        AttributeSpan::Style {
+            range: range(11, 7),
+            value: FontStyle::Italic,
+        },
+        AttributeSpan::Underline {
+            range: range(8, 1),
+            value: true,
+        },
+    ];
+
+    let failures = layout.apply_attributes(&spans).unwrap_err();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].index, 1);
+    assert_eq!(failures[0].attribute, "font size");
+    assert_eq!(failures[0].range, range(4, 1));
+    assert_eq!(failures[0].error.0, E_INVALIDARG);
+
+    assert_eq!(layout.font_weight(1).unwrap().value, FontWeight::BOLD);
+    assert_eq!(layout.font_size(4).unwrap().value, 16.0);
+    assert_eq!(layout.font_family_name(12).unwrap().value, "Consolas");
+    assert_eq!(
+        layout.font_style(12).unwrap().value.value,
+        FontStyle::Italic as u32
+    );
+    assert!(layout.underline(8).unwrap().value);
+
+    // All of them apply cleanly without the bad span
+    let spans: Vec<_> = spans
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != 1)
+        .map(|(_, span)| *span)
+        .collect();
+    layout.apply_attributes(&spans).unwrap();
+}
+
+#[test]
+fn font_locality_and_color() {
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_family::IFontFamily;
+
+    let factory = Factory::new().unwrap();
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    let font = |name: &str| {
+        let index = collection.find_family_by_name(name).unwrap();
+        collection
+            .family(index)
+            .unwrap()
+            .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+            .unwrap()
+    };
+
+    // Installed fonts are always fully available
+    let segoe = font("Segoe UI");
+    assert_eq!(segoe.locality().value, FontLocality::Local as u32);
+    assert!(!segoe.is_color_font());
+
+    // Windows 8.1 and later ship a color emoji font
+    if let Some(emoji) = collection.find_family_by_name("Segoe UI Emoji") {
+        let emoji = collection
+            .family(emoji)
+            .unwrap()
+            .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+            .unwrap();
+        assert!(emoji.is_color_font());
+    }
+}
+
+#[test]
+fn range_width() {
+    use directwrite::text_layout::ITextLayout;
+    use winapi::shared::winerror::E_INVALIDARG;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let layout = TextLayout::create(&factory)
+        .with_str("measure this word please")
+        .with_format(&format)
+        .with_size(1000.0, 100.0)
+        .build()
+        .unwrap();
+
+    let full = layout.metrics().width_including_trailing_whitespace;
+    let word = layout.range_width(13, 4).unwrap();
+    assert!(word > 0.0 && word < full);
+
+    // The same as the distance between the carets at either end
+    let left = layout.hit_test_text_position(13, false).unwrap().point_x;
+    let right = layout.hit_test_text_position(17, false).unwrap().point_x;
+    assert!((word - (right - left)).abs() < 0.01);
+
+    assert!((layout.range_width(0, 24).unwrap() - full).abs() < 0.01);
+    assert_eq!(layout.range_width(5, 0).unwrap(), 0.0);
+    assert_eq!(layout.range_width(25, 1).unwrap_err().0, E_INVALIDARG);
+
+    // A range which wraps is as wide as its widest line
+    let narrow = TextLayout::create(&factory)
+        .with_str("measure this word please")
+        .with_format(&format)
+        .with_size(90.0, 100.0)
+        .build()
+        .unwrap();
+    assert!(narrow.line_metrics().len() > 1);
+    let wrapped = narrow.range_width(0, 24).unwrap();
+    assert!(wrapped > 0.0 && wrapped < full);
+}
+
+#[test]
+fn analyze_mixed_string() {
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let text = "Hello 😀 世界";
+    let runs = factory.analyze_string(text, &format).unwrap();
+    assert!(runs.len() >= 3);
+
+    // The runs cover the text in order
+    let mut position = 0;
+    for run in &runs {
+        assert_eq!(run.range.start, position);
+        assert!(run.width > 0.0);
+        position += run.range.length;
+    }
+    assert_eq!(position as usize, text.encode_utf16().count());
+
+    let run_at = |pos: u32| {
+        runs.iter()
+            .find(|r| r.range.start <= pos && pos < r.range.start + r.range.length)
+            .unwrap()
+    };
+    assert_eq!(run_at(0).family, "Segoe UI");
+    // The emoji and the CJK characters come from fallback fonts
+    assert!(run_at(6).family.contains("Emoji"));
+    let cjk = run_at(9);
+    assert!(!cjk.family.is_empty());
+    assert_ne!(cjk.family, "Segoe UI");
+    assert!(!cjk.family.contains("Emoji"));
+
+    let layout = TextLayout::create(&factory)
+        .with_str(text)
+        .with_format(&format)
+        .with_size(1000.0, 100.0)
+        .build()
+        .unwrap();
+    let total: f32 = runs.iter().map(|r| r.width).sum();
+    let width = layout.metrics().width_including_trailing_whitespace;
+    assert!((total - width).abs() < 0.01);
+}
@@ -0,0 +1,125 @@
+extern crate directwrite;
+
+use directwrite::enums::{FontFaceType, FontWeight};
+use directwrite::font_face::{FontFaceBuildError, IFontFace};
+use directwrite::font_file::FontFile;
+use directwrite::rendering_params::RenderingParamsBuilder;
+use directwrite::text_format::TextFormatBuilder;
+use directwrite::text_layout::TextLayoutBuilder;
+use directwrite::typography::TypographyBuilder;
+use directwrite::{Factory, FontFace, RenderingParams, TextFormat, TextLayout, Typography};
+
+// Builders keep their own reference to the factory, so they can outlive the borrow they
+// were created from.
+fn heading_format() -> TextFormatBuilder<'static> {
+    let factory = Factory::new().unwrap();
+    TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_weight(FontWeight::BOLD)
+        .with_size(24.0)
+}
+
+fn crisp_params(factory: &Factory) -> RenderingParamsBuilder {
+    RenderingParams::create(factory).with_gamma(1.8)
+}
+
+fn ligatures_off<'a>(factory: &Factory) -> TypographyBuilder<'a> {
+    Typography::create(factory).with_feature(*b"liga", 0)
+}
+
+struct Label<'a> {
+    builder: TextLayoutBuilder<'a>,
+}
+
+#[test]
+fn builders_can_be_returned_and_stored() {
+    let format = heading_format().build().unwrap();
+
+    let label = {
+        let factory = Factory::new().unwrap();
+        Label {
+            builder: TextLayout::create(&factory)
+                .with_str("Returned from a scope")
+                .with_size(400.0, 100.0),
+        }
+    };
+    let layout = label.builder.with_format(&format).build().unwrap();
+    assert_eq!(layout.text_len(), 21);
+
+    let factory = Factory::new().unwrap();
+    crisp_params(&factory).build().unwrap();
+    ligatures_off(&factory).build().unwrap();
+}
+
+fn open_sans(factory: &Factory) -> FontFile {
+    FontFile::create(factory)
+        .with_file_path("tests/test_fonts/OpenSans-Regular.ttf")
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn font_face_infers_face_type() {
+    let factory = Factory::new().unwrap();
+    let files = [open_sans(&factory)];
+
+    let face = FontFace::create(&factory)
+        .with_files(&files)
+        .with_face_index(0)
+        .with_validation(true)
+        .infer_face_type()
+        .build()
+        .unwrap();
+    assert_eq!(face.font_type().value, FontFaceType::TrueType.to_u32());
+}
+
+#[test]
+fn font_face_validation_errors() {
+    let factory = Factory::new().unwrap();
+    let files = [open_sans(&factory)];
+
+    let err = FontFace::create(&factory)
+        .with_files(&files)
+        .with_font_face_type(FontFaceType::TrueType)
+        .with_face_index(3)
+        .with_validation(true)
+        .build_detailed()
+        .unwrap_err();
+    match &err {
+        FontFaceBuildError::FaceIndexOutOfRange { requested, faces } => {
+            assert_eq!((*requested, *faces), (3, 1));
+        }
+        other => panic!("unexpected error: {}", other),
+    }
+    assert_eq!(
+        err.to_string(),
+        "face index 3 requested but the files have 1 face"
+    );
+
+    let err = FontFace::create(&factory)
+        .with_files(&files)
+        .with_font_face_type(FontFaceType::CFF)
+        .with_face_index(0)
+        .with_validation(true)
+        .build_detailed()
+        .unwrap_err();
+    match &err {
+        FontFaceBuildError::FaceTypeMismatch { requested, found } => {
+            assert_eq!(requested.to_u32(), FontFaceType::CFF.to_u32());
+            assert_eq!(found.value, FontFaceType::TrueType.to_u32());
+        }
+        other => panic!("unexpected error: {}", other),
+    }
+    assert_eq!(
+        err.to_string(),
+        "file is TrueType but CFF face type requested"
+    );
+
+    // Without validation the mismatch still fails, just without the explanation
+    assert!(FontFace::create(&factory)
+        .with_files(&files)
+        .with_font_face_type(FontFaceType::CFF)
+        .with_face_index(0)
+        .build()
+        .is_err());
+}
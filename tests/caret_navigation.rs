@@ -0,0 +1,93 @@
+//! Moving a caret through a layout the way the arrow keys of a text editor do.
+
+extern crate directwrite;
+
+use directwrite::text_layout::ITextLayout;
+use directwrite::{Factory, TextFormat, TextLayout};
+
+// A monospaced font lines the columns of different lines up, so vertical moves are predictable
+fn layout(factory: &Factory, text: &str) -> TextLayout {
+    let format = TextFormat::create(factory)
+        .with_family("Consolas")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    TextLayout::create(factory)
+        .with_str(text)
+        .with_format(&format)
+        .with_size(400.0, 300.0)
+        .build()
+        .unwrap()
+}
+
+// Line starts: 0, 11, 14 and 25. The emoji takes positions 25 and 26.
+const TEXT: &str = "abcdefghij\nxy\nabcdefghij\n\u{1F600}!";
+
+#[test]
+fn horizontal_moves_skip_clusters() {
+    let factory = Factory::new().unwrap();
+    let layout = layout(&factory, TEXT);
+
+    assert_eq!(layout.next_caret_position(0), 1);
+    assert_eq!(layout.next_caret_position(10), 11);
+    assert_eq!(layout.next_caret_position(25), 27);
+    assert_eq!(layout.next_caret_position(26), 27);
+    assert_eq!(layout.next_caret_position(27), 28);
+    assert_eq!(layout.next_caret_position(28), 28);
+
+    assert_eq!(layout.previous_caret_position(28), 27);
+    assert_eq!(layout.previous_caret_position(27), 25);
+    assert_eq!(layout.previous_caret_position(26), 25);
+    assert_eq!(layout.previous_caret_position(1), 0);
+    assert_eq!(layout.previous_caret_position(0), 0);
+}
+
+#[test]
+fn vertical_moves_keep_the_column() {
+    let factory = Factory::new().unwrap();
+    let layout = layout(&factory, TEXT);
+
+    // Straight down between lines of the same length
+    assert_eq!(layout.caret_position_for_line_offset(3, 2), 17);
+    assert_eq!(layout.caret_position_for_line_offset(17, -2), 3);
+
+    // A short line clamps the caret to its end, before the newline
+    assert_eq!(layout.caret_position_for_line_offset(19, -1), 13);
+
+    // Without a goal the column is lost on the way up...
+    assert_eq!(layout.caret_position_for_line_offset(13, -1), 2);
+
+    // ...but moving on from the goal of the original position restores it
+    let goal_x = layout.hit_test_text_position(19, false).unwrap().point_x;
+    assert_eq!(
+        layout.caret_position_for_line_offset_with_goal(13, -1, goal_x),
+        5
+    );
+    assert_eq!(
+        layout.caret_position_for_line_offset_with_goal(19, -2, goal_x),
+        5
+    );
+
+    // Moving past the first or last line goes to the start or end of the text
+    assert_eq!(layout.caret_position_for_line_offset(3, -1), 0);
+    assert_eq!(layout.caret_position_for_line_offset(27, 1), 28);
+    assert_eq!(layout.caret_position_for_line_offset(3, 10), 28);
+}
+
+#[test]
+fn empty_and_trailing_lines() {
+    let factory = Factory::new().unwrap();
+
+    let empty = layout(&factory, "");
+    assert_eq!(empty.next_caret_position(0), 0);
+    assert_eq!(empty.previous_caret_position(0), 0);
+    assert_eq!(empty.caret_position_for_line_offset(0, 1), 0);
+    assert_eq!(empty.caret_position_for_line_offset(0, -1), 0);
+
+    // The empty line after a trailing newline can be moved onto and back from
+    let trailing = layout(&factory, "ab\n");
+    assert_eq!(trailing.next_caret_position(2), 3);
+    assert_eq!(trailing.caret_position_for_line_offset(1, 1), 3);
+    assert_eq!(trailing.caret_position_for_line_offset(3, -1), 0);
+}
@@ -4,6 +4,7 @@ extern crate winapi;
 use dcommon::Error;
 use directwrite::font_collection::loader::FontCollectionLoader;
 use directwrite::font_file::loader::{FileLoaderHandle, FontFileLoader, StaticDataStream};
+use directwrite::test_support::{self, FaultyStream, MockLoader, RoundtripStep};
 use directwrite::{Factory, FontCollection, FontFile, TextFormat, TextLayout};
 use winapi::shared::winerror::{ERROR_NOT_FOUND, HRESULT_FROM_WIN32};
 
@@ -78,7 +79,9 @@ impl FontCollectionLoader for DataCollectionLoader {
 #[test]
 fn load_custom_font() {
     let factory = Factory::new().unwrap();
-    let file_loader = DataFileLoader.register(&factory).unwrap();
+    let file_loader = MockLoader::new(DataFileLoader);
+    let log = file_loader.log();
+    let file_loader = file_loader.register(&factory).unwrap();
     let collection_loader = DataCollectionLoader(file_loader)
         .register(&factory)
         .unwrap();
@@ -130,4 +133,688 @@ fn load_custom_font() {
 
     test_layout(&factory, &opensans, "Lay this out in Open Sans ;3");
     test_layout(&factory, &firacode, "Lay this out in Fira Code >>=");
+
+    // DirectWrite may keep fragments of cached faces, but must never misuse them
+    assert!(!log.reads().is_empty());
+    assert_eq!(log.take_problems(), Vec::<String>::new());
+}
+
+#[test]
+fn loader_roundtrip() {
+    let report = test_support::assert_loader_roundtrip(DataFileLoader, "FiraCode-Bold");
+    assert!(report.analysis.supported);
+    assert_eq!(report.analysis.num_faces, 1);
+    assert!(report.glyph_count > 0);
+
+    let log = {
+        let loader = MockLoader::new(DataFileLoader);
+        let log = loader.log();
+        test_support::assert_loader_roundtrip(loader, "OpenSans-Regular");
+        log
+    };
+    // The isolated factory is gone, so every fragment must have been released
+    assert!(!log.reads().is_empty());
+    log.assert_balanced();
+
+    let err = test_support::check_loader_roundtrip(DataFileLoader, "Missing").unwrap_err();
+    assert_eq!(err.error.0, HRESULT_FROM_WIN32(ERROR_NOT_FOUND));
+    assert!(err.step == RoundtripStep::BuildFile || err.step == RoundtripStep::Analyze);
+}
+
+#[test]
+fn loader_roundtrip_faulty_stream() {
+    struct TruncatedLoader;
+    impl FontFileLoader for TruncatedLoader {
+        type Key = str;
+        type Stream = FaultyStream<StaticDataStream>;
+
+        fn create_stream(&self, _key: &str) -> Result<Self::Stream, Error> {
+            // Past the table directory, so the file looks like a font until its tables are read
+            Ok(FaultyStream::new(OPENSANS_REGULAR, 1024))
+        }
+    }
+
+    let err = test_support::check_loader_roundtrip(TruncatedLoader, "OpenSans-Regular")
+        .err()
+        .expect("reading the whole file can't succeed");
+    assert!(err.step == RoundtripStep::Analyze || err.step == RoundtripStep::CreateFace);
+    assert!(err.to_string().starts_with("loader roundtrip failed"));
+}
+
+#[test]
+fn rank_by_coverage() {
+    use directwrite::font_family::{FontFamily, IFontFamily};
+    use directwrite::SkipOrFail;
+
+    fn name(family: &FontFamily) -> String {
+        family
+            .family_name()
+            .and_then(|n| n.get_by_name("en-US"))
+            .map(|s| s.string())
+            .unwrap()
+    }
+
+    let factory = Factory::new().unwrap();
+    let file_loader = DataFileLoader.register(&factory).unwrap();
+    let collection_loader = DataCollectionLoader(file_loader)
+        .register(&factory)
+        .unwrap();
+    let collection = FontCollection::create(&factory)
+        .with_loader(&collection_loader)
+        .with_key(&())
+        .build()
+        .unwrap();
+
+    let latin = collection
+        .rank_by_coverage(
+            "The quick brown fox jumps over the lazy dog",
+            5,
+            SkipOrFail::Fail,
+        )
+        .unwrap();
+    assert_eq!(latin.len(), 2);
+    assert!(latin.iter().all(|&(_, score)| score == 1.0));
+    assert_eq!(name(&latin[0].0), "Open Sans");
+
+    // Once enough families cover everything, the rest aren't looked at
+    let first = collection
+        .rank_by_coverage("Hello", 1, SkipOrFail::Fail)
+        .unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(name(&first[0].0), "Open Sans");
+
+    // Fira Code has box drawing characters, Open Sans doesn't
+    let boxes = collection
+        .rank_by_coverage("ab\u{2500}\u{2502}", 5, SkipOrFail::Fail)
+        .unwrap();
+    assert_eq!(boxes.len(), 2);
+    assert_eq!(name(&boxes[0].0), "Fira Code");
+    assert_eq!(boxes[0].1, 1.0);
+    assert_eq!(name(&boxes[1].0), "Open Sans");
+    assert_eq!(boxes[1].1, 0.5);
+
+    assert!(collection
+        .rank_by_coverage("abc", 0, SkipOrFail::Fail)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn isolated_factory_loaders() {
+    let isolated = Factory::new_isolated().unwrap();
+    let shared = Factory::new().unwrap();
+
+    let file_loader = DataFileLoader.register(&isolated).unwrap();
+    let collection_loader = DataCollectionLoader(file_loader)
+        .register(&isolated)
+        .unwrap();
+
+    let collection = FontCollection::create(&isolated)
+        .with_loader(&collection_loader)
+        .with_key(&())
+        .build()
+        .unwrap();
+    assert_eq!(collection.find_family_by_name("Open Sans"), Some(0));
+
+    // The shared factory never saw the loader being registered
+    let result = FontCollection::create(&shared)
+        .with_loader(&collection_loader)
+        .with_key(&())
+        .build();
+    assert!(result.is_err());
+}
+
+#[test]
+fn in_memory_loader() {
+    use directwrite::font_file::loader::InMemoryLoader;
+    use directwrite::font_file::IFontFile;
+    use winapi::shared::winerror::E_NOINTERFACE;
+
+    let factory = Factory::new().unwrap();
+    let loader = match InMemoryLoader::register(&factory) {
+        Ok(loader) => loader,
+        // Older than Windows 10 Creators Update
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    };
+
+    let data = OPENSANS_REGULAR.data.to_vec();
+    let file = loader.create_file(data).unwrap();
+    assert_eq!(loader.file_count(), 1);
+
+    let analysis = file.analyze().unwrap();
+    assert!(analysis.supported);
+    assert_eq!(analysis.num_faces, 1);
+
+    loader.unregister();
+}
+
+#[test]
+fn font_identity_ignores_the_loader() {
+    use directwrite::font_file::loader::InMemoryLoader;
+    use directwrite::text_renderer::display_list::FontIdentity;
+    use directwrite::FontFace;
+
+    let factory = Factory::new().unwrap();
+    let identity = |file: FontFile| {
+        let face = FontFace::create(&factory)
+            .with_files(&[file])
+            .with_face_index(0)
+            .infer_face_type()
+            .build()
+            .unwrap();
+        FontIdentity::of(&face).unwrap()
+    };
+
+    let file_loader = DataFileLoader.register(&factory).unwrap();
+    let from_loader = FontFile::create(&factory)
+        .with_loader(&file_loader)
+        .with_key("OpenSans-Regular")
+        .build()
+        .unwrap();
+    let from_path = FontFile::create(&factory)
+        .with_file_path("tests/test_fonts/OpenSans-Regular.ttf")
+        .build()
+        .unwrap();
+
+    let expected = identity(from_loader);
+    assert_eq!(identity(from_path), expected);
+
+    // Older than Windows 10 Creators Update
+    if let Ok(memory_loader) = InMemoryLoader::register(&factory) {
+        let from_memory = memory_loader
+            .create_file(OPENSANS_REGULAR.data.to_vec())
+            .unwrap();
+        assert_eq!(identity(from_memory), expected);
+    }
+
+    let other = FontFile::create(&factory)
+        .with_loader(&file_loader)
+        .with_key("FiraCode-Regular")
+        .build()
+        .unwrap();
+    assert_ne!(identity(other).file_hash, expected.file_hash);
+}
+
+#[test]
+fn informational_string_truncated() {
+    use directwrite::enums::{FontStretch, FontStyle, FontWeight, InformationalStringId};
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_family::IFontFamily;
+
+    let factory = Factory::new().unwrap();
+    let file_loader = DataFileLoader.register(&factory).unwrap();
+    let collection_loader = DataCollectionLoader(file_loader)
+        .register(&factory)
+        .unwrap();
+    let collection = FontCollection::create(&factory)
+        .with_loader(&collection_loader)
+        .with_key(&())
+        .build()
+        .unwrap();
+
+    let font = collection
+        .family(collection.find_family_by_name("Open Sans").unwrap())
+        .unwrap()
+        .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap();
+
+    let full = font
+        .informational_strings(InformationalStringId::LicenseDescription)
+        .unwrap()
+        .get_by_name("en-us")
+        .unwrap()
+        .string();
+    assert!(full.chars().count() > 20);
+
+    let short = font
+        .informational_string_truncated(InformationalStringId::LicenseDescription, "en-us", 20)
+        .unwrap()
+        .unwrap();
+    assert_eq!(short.chars().count(), 20);
+    assert!(full.starts_with(&short));
+    assert!(short.capacity() < full.len());
+
+    // Unknown locales fall back to the English value.
+    let fallback = font
+        .informational_string_truncated(InformationalStringId::LicenseDescription, "xx-xx", 10)
+        .unwrap()
+        .unwrap();
+    assert_eq!(fallback, full.chars().take(10).collect::<String>());
+
+    // Asking for more than there is returns the whole string.
+    let all = font
+        .informational_string_truncated(InformationalStringId::LicenseDescription, "en-us", 1 << 20)
+        .unwrap()
+        .unwrap();
+    assert_eq!(all, full);
+
+    // Names are cut the same way, whether or not they fit the bounded buffer
+    let names = font.face_name().unwrap();
+    let name = names.get_by_name("en-us").unwrap();
+    assert_eq!(name.string(), "Regular");
+    assert_eq!(name.string_truncated(3).unwrap(), "Reg");
+    assert_eq!(name.string_truncated(7).unwrap(), "Regular");
+    assert_eq!(name.string_truncated(100).unwrap(), "Regular");
+    assert_eq!(name.string_truncated(0).unwrap(), "");
+}
+
+#[test]
+fn unpack_woff2() {
+    use directwrite::enums::ContainerType;
+    use directwrite::font_file::loader::InMemoryLoader;
+    use directwrite::font_file::IFontFile;
+    use winapi::shared::winerror::E_NOINTERFACE;
+
+    // Open Sans with every glyph but those of "Regular" emptied, to keep the fixture small
+    const PACKED: &[u8] = include_bytes!("test_fonts/OpenSans-Regular.woff2");
+
+    let factory = Factory::new().unwrap();
+    let container = match factory.analyze_container(PACKED) {
+        Ok(container) => container,
+        // Older than Windows 10 Creators Update
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert_eq!(container.value, ContainerType::Woff2 as u32);
+
+    let plain = factory.analyze_container(OPENSANS_REGULAR.data).unwrap();
+    assert_eq!(plain.value, ContainerType::Unknown as u32);
+
+    let unpacked = factory.unpack_font_data(PACKED).unwrap();
+    assert_eq!(&unpacked[..4], &[0, 1, 0, 0]);
+
+    let loader = InMemoryLoader::register(&factory).unwrap();
+    let file = loader.create_file_from_packed(PACKED).unwrap();
+    assert!(file.analyze().unwrap().supported);
+    loader.unregister();
+}
+
+#[test]
+fn loader_teardown_order() {
+    use winapi::shared::winerror::ERROR_BUSY;
+
+    let file_loader = {
+        // The handle keeps the factory alive on its own
+        let factory = Factory::new().unwrap();
+        DataFileLoader.register(&factory).unwrap()
+    };
+    let factory = file_loader.factory().unwrap().clone();
+    let collection_loader = DataCollectionLoader(file_loader.clone())
+        .register(&factory)
+        .unwrap();
+
+    let file = FontFile::create(&factory)
+        .with_loader(&file_loader)
+        .with_key("OpenSans-Regular")
+        .build()
+        .unwrap();
+    let collection = FontCollection::create(&factory)
+        .with_loader(&collection_loader)
+        .with_key(&())
+        .build()
+        .unwrap();
+    let collection_copy = collection.clone();
+
+    // Both loaders still have objects depending on them
+    let busy = HRESULT_FROM_WIN32(ERROR_BUSY);
+    assert_eq!(collection_loader.unregister().unwrap_err().0, busy);
+    assert_eq!(file_loader.unregister().unwrap_err().0, busy);
+
+    // Refusing to unregister left the loaders usable
+    assert_eq!(collection.find_family_by_name("Open Sans"), Some(0));
+    FontFile::create(&factory)
+        .with_loader(&file_loader)
+        .with_key("FiraCode-Bold")
+        .build()
+        .unwrap();
+
+    drop(collection);
+    assert_eq!(collection_loader.unregister().unwrap_err().0, busy);
+    drop(collection_copy);
+    collection_loader.unregister().unwrap();
+
+    assert_eq!(file_loader.unregister().unwrap_err().0, busy);
+    drop(file);
+    file_loader.unregister().unwrap();
+
+    // Once unregistered, the factory no longer knows the loader
+    assert!(file_loader.unregister().is_err());
+}
+
+#[test]
+fn unregister_all_loaders() {
+    use winapi::shared::winerror::ERROR_BUSY;
+
+    // Tests share the process-wide factory, so use an isolated one to know exactly which
+    // loaders are registered on it
+    let factory = Factory::new_isolated().unwrap();
+    assert!(factory.is_isolated());
+    assert!(!Factory::new().unwrap().is_isolated());
+
+    let file_loader = DataFileLoader.register(&factory).unwrap();
+    let collection_loader = DataCollectionLoader(file_loader.clone())
+        .register(&factory)
+        .unwrap();
+
+    // A live file keeps its loader registered, but everything else is still unregistered
+    let file = FontFile::create(&factory)
+        .with_loader(&file_loader)
+        .with_key("OpenSans-Regular")
+        .build()
+        .unwrap();
+    let busy = HRESULT_FROM_WIN32(ERROR_BUSY);
+    assert_eq!(factory.unregister_all_loaders().unwrap_err().0, busy);
+    assert!(collection_loader.unregister().is_err());
+
+    drop(file);
+    assert_eq!(factory.unregister_all_loaders(), Ok(1));
+    assert!(file_loader.unregister().is_err());
+    assert_eq!(factory.unregister_all_loaders(), Ok(0));
+
+    // The factory accepts new loaders afterwards
+    let file_loader = DataFileLoader.register(&factory).unwrap();
+    let collection_loader = DataCollectionLoader(file_loader.clone())
+        .register(&factory)
+        .unwrap();
+    let collection = FontCollection::create(&factory)
+        .with_loader(&collection_loader)
+        .with_key(&())
+        .build()
+        .unwrap();
+    assert_eq!(collection.find_family_by_name("Open Sans"), Some(0));
+    drop(collection);
+    assert_eq!(factory.unregister_all_loaders(), Ok(2));
+
+    // Dropping every handle doesn't unregister a loader, so it is still found afterwards
+    drop(DataFileLoader.register(&factory).unwrap());
+    assert_eq!(factory.unregister_all_loaders(), Ok(1));
+}
+
+#[test]
+fn collectors_skip_or_fail() {
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::SkipOrFail;
+    use winapi::shared::winerror::E_FAIL;
+
+    let factory = Factory::new().unwrap();
+    let file_loader = DataFileLoader.register(&factory).unwrap();
+    let collection_loader = DataCollectionLoader(file_loader)
+        .register(&factory)
+        .unwrap();
+    let collection = FontCollection::create(&factory)
+        .with_loader(&collection_loader)
+        .with_key(&())
+        .build()
+        .unwrap();
+
+    let all = collection.family_names("en-us", SkipOrFail::Fail).unwrap();
+    assert_eq!(all, vec!["Open Sans".to_string(), "Fira Code".to_string()]);
+
+    let failing = test_support::failing_collection(&collection, 0, E_FAIL.into());
+    assert_eq!(failing.family_count(), 2);
+
+    // Failing aborts at the broken index
+    let err = failing.family_names("en-us", SkipOrFail::Fail).unwrap_err();
+    assert_eq!(err.0, E_FAIL);
+    let err = failing
+        .rank_by_coverage("abc", 5, SkipOrFail::Fail)
+        .unwrap_err();
+    assert_eq!(err.0, E_FAIL);
+
+    // Skipping leaves the family out, but records where and why
+    let mut skipped = Vec::new();
+    let names = failing
+        .family_names("en-us", SkipOrFail::Skip(&mut skipped))
+        .unwrap();
+    assert_eq!(names, vec!["Fira Code".to_string()]);
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0].0, 0);
+    assert_eq!((skipped[0].1).0, E_FAIL);
+
+    let mut skipped = Vec::new();
+    let ranked = failing
+        .rank_by_coverage("abc", 5, SkipOrFail::Skip(&mut skipped))
+        .unwrap();
+    assert_eq!(ranked.len(), 1);
+    assert_eq!(skipped.iter().map(|s| s.0).collect::<Vec<_>>(), vec![0]);
+
+    // The plain accessors still hide the error
+    assert!(failing.family(0).is_none());
+    assert!(failing.family(1).is_some());
+    assert_eq!(failing.all_families().count(), 1);
+}
+
+#[test]
+fn enumerate_families() {
+    use directwrite::font_collection::IFontCollection;
+    use winapi::shared::winerror::E_FAIL;
+
+    let factory = Factory::new().unwrap();
+    let file_loader = DataFileLoader.register(&factory).unwrap();
+    let collection_loader = DataCollectionLoader(file_loader)
+        .register(&factory)
+        .unwrap();
+    let collection = FontCollection::create(&factory)
+        .with_loader(&collection_loader)
+        .with_key(&())
+        .build()
+        .unwrap();
+
+    let indices: Vec<u32> = collection
+        .enumerate_families()
+        .map(|(index, family)| {
+            assert!(family.is_ok());
+            index
+        })
+        .collect();
+    assert_eq!(indices, vec![0, 1]);
+
+    // The failure is reported at its own index instead of being left out
+    let failing = test_support::failing_collection(&collection, 1, E_FAIL.into());
+    let results: Vec<_> = failing.enumerate_families().collect();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].0, 0);
+    assert!(results[0].1.is_ok());
+    assert_eq!(results[1].0, 1);
+    assert_eq!(results[1].1.as_ref().map_err(|e| e.0).err(), Some(E_FAIL));
+}
+
+#[test]
+fn enumerate_fonts() {
+    use directwrite::enums::{FontStretch, FontStyle, FontWeight};
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_family::IFontFamily;
+    use directwrite::font_list::IFontList;
+
+    let factory = Factory::new().unwrap();
+    let file_loader = DataFileLoader.register(&factory).unwrap();
+    let collection_loader = DataCollectionLoader(file_loader)
+        .register(&factory)
+        .unwrap();
+    let collection = FontCollection::create(&factory)
+        .with_loader(&collection_loader)
+        .with_key(&())
+        .build()
+        .unwrap();
+
+    let index = collection.find_family_by_name("Fira Code").unwrap();
+    let fonts = collection
+        .family(index)
+        .unwrap()
+        .matching_fonts(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap();
+    assert_eq!(fonts.count(), 4);
+
+    let weights: Vec<(u32, u32)> = fonts
+        .enumerate_fonts()
+        .map(|(index, font)| (index, font.unwrap().weight().0))
+        .collect();
+    let expected: Vec<(u32, u32)> = fonts
+        .all_fonts()
+        .enumerate()
+        .map(|(index, font)| (index as u32, font.weight().0))
+        .collect();
+    assert_eq!(weights, expected);
+    assert_eq!(weights[0].1, FontWeight::NORMAL.0);
+}
+
+#[test]
+fn enumerate_features() {
+    use directwrite::descriptions::FontFeature;
+    use directwrite::enums::font_feature_tag::FontFeatureTag;
+    use directwrite::Typography;
+
+    let factory = Factory::new().unwrap();
+    let features = [
+        FontFeature {
+            name_tag: FontFeatureTag::KERNING,
+            parameter: 0,
+        },
+        FontFeature {
+            name_tag: FontFeatureTag::STANDARD_LIGATURES,
+            parameter: 1,
+        },
+    ];
+    let typography = Typography::create(&factory)
+        .with_features(&features)
+        .build()
+        .unwrap();
+
+    let enumerated: Vec<_> = typography
+        .enumerate_features()
+        .map(|(index, feature)| (index, feature.unwrap()))
+        .collect();
+    assert_eq!(enumerated, vec![(0, features[0]), (1, features[1])]);
+    assert_eq!(
+        typography.all_features().collect::<Vec<_>>(),
+        features.to_vec()
+    );
+}
+
+#[test]
+fn per_layout_font_fallback() {
+    use com_wrapper::ComWrapper;
+    use directwrite::text_format::ITextFormat;
+    use directwrite::text_layout::ITextLayout;
+    use directwrite::text_renderer::display_list::DisplayItem;
+    use directwrite::text_renderer::DisplayList;
+    use directwrite::FontFallback;
+    use winapi::shared::winerror::E_NOINTERFACE;
+
+    let factory = Factory::new().unwrap();
+    let file_loader = DataFileLoader.register(&factory).unwrap();
+    let collection_loader = DataCollectionLoader(file_loader)
+        .register(&factory)
+        .unwrap();
+    let collection = FontCollection::create(&factory)
+        .with_loader(&collection_loader)
+        .with_key(&())
+        .build()
+        .unwrap();
+
+    // Box drawing characters are in Fira Code but not in Open Sans
+    let fallback = match FontFallback::create(&factory)
+        .with_mapping(&[0x2500..=0x257F], &["Fira Code"], Some(&collection))
+        .build()
+    {
+        Ok(fallback) => fallback,
+        // Older than Windows 8.1
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    };
+
+    let format = TextFormat::create(&factory)
+        .with_collection(&collection)
+        .with_family("Open Sans")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let mut layout = TextLayout::create(&factory)
+        .with_str("a\u{2500}b")
+        .with_format(&format)
+        .with_size(300.0, 100.0)
+        .build()
+        .unwrap();
+
+    assert!(layout.font_fallback().unwrap().is_none());
+    layout.set_font_fallback(&fallback).unwrap();
+    let current = layout.font_fallback().unwrap().unwrap();
+    assert_eq!(unsafe { current.get_raw() }, unsafe { fallback.get_raw() });
+    // The format the layout was created from is untouched
+    assert!(format.font_fallback().unwrap().is_none());
+
+    // The requested family is unchanged; the fallback only shows in the fonts drawn with
+    assert_eq!(
+        ITextLayout::font_family_name(&layout, 1).unwrap().value,
+        "Open Sans"
+    );
+    let list = DisplayList::record(&layout, 1.0).unwrap();
+    let family_at = |pos: u32| {
+        list.items.iter().find_map(|item| match item {
+            DisplayItem::GlyphRun(run)
+                if run.text_position <= pos && pos < run.text_position + run.text_length =>
+            {
+                Some(run.font.family_name.clone())
+            }
+            _ => None,
+        })
+    };
+    assert_eq!(family_at(0).as_ref().map(|s| &s[..]), Some("Open Sans"));
+    assert_eq!(family_at(1).as_ref().map(|s| &s[..]), Some("Fira Code"));
+}
+
+#[test]
+fn read_font_file_streams() {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let factory = Factory::new().unwrap();
+    let original = include_bytes!("test_fonts/OpenSans-Regular.ttf");
+
+    // Through the local file loader
+    let local = FontFile::create(&factory)
+        .with_file_path("tests/test_fonts/OpenSans-Regular.ttf")
+        .build()
+        .unwrap();
+    let reader = local.open_stream().unwrap();
+    assert_eq!(reader.len(), original.len() as u64);
+    assert!(reader.read_all().unwrap() == &original[..]);
+
+    // Through a custom loader, in small chunks
+    let file_loader = DataFileLoader.register(&factory).unwrap();
+    let custom = FontFile::create(&factory)
+        .with_loader(&file_loader)
+        .with_key("OpenSans-Regular")
+        .build()
+        .unwrap();
+    let mut reader = custom.open_stream().unwrap();
+    let mut data = Vec::new();
+    let mut chunk = [0; 1000];
+    loop {
+        let count = reader.read(&mut chunk).unwrap();
+        if count == 0 {
+            break;
+        }
+        data.extend_from_slice(&chunk[..count]);
+    }
+    assert!(data == &original[..]);
+
+    // Seeking and positional reads agree with the original bytes
+    let mut tail = [0; 16];
+    let pos = reader.seek(SeekFrom::End(-16)).unwrap();
+    assert_eq!(pos, original.len() as u64 - 16);
+    reader.read_exact(&mut tail).unwrap();
+    assert_eq!(&tail[..], &original[original.len() - 16..]);
+
+    let mut past_end = [0; 32];
+    let count = reader
+        .read_at(original.len() as u64 - 8, &mut past_end)
+        .unwrap();
+    assert_eq!(count, 8);
+    assert_eq!(&past_end[..8], &original[original.len() - 8..]);
+    assert!(reader
+        .seek(SeekFrom::Current(-(original.len() as i64) - 1))
+        .is_err());
 }
@@ -0,0 +1,72 @@
+#![cfg(feature = "serde")]
+
+extern crate directwrite;
+
+use directwrite::text_layout::ITextLayout;
+use directwrite::text_renderer::display_list::DisplayItem;
+use directwrite::text_renderer::DisplayList;
+use directwrite::{Factory, TextFormat, TextLayout};
+
+fn snapshot(factory: &Factory, size: f32) -> String {
+    let format = TextFormat::create(factory)
+        .with_family("Segoe UI")
+        .with_size(size)
+        .build()
+        .unwrap();
+
+    let mut layout = TextLayout::create(factory)
+        .with_str("Golden snapshot")
+        .with_format(&format)
+        .with_width(1000.0)
+        .with_height(200.0)
+        .build()
+        .unwrap();
+    layout.set_underline(true, 0u32..6).unwrap();
+
+    DisplayList::record(&layout, 1.0)
+        .unwrap()
+        .to_json(3)
+        .unwrap()
+}
+
+#[test]
+fn snapshots_are_stable() {
+    let factory = Factory::new().unwrap();
+
+    let first = snapshot(&factory, 16.0);
+    let second = snapshot(&factory, 16.0);
+    assert_eq!(first, second);
+
+    let list = DisplayList::from_json(&first).unwrap();
+    assert!(list.items.iter().any(|item| match item {
+        DisplayItem::GlyphRun(run) => run.font_face.is_none() && !run.font.family_name.is_empty(),
+        _ => false,
+    }));
+    assert!(list.items.iter().any(|item| match item {
+        DisplayItem::Underline(_) => true,
+        _ => false,
+    }));
+    assert_eq!(list.to_json(3).unwrap(), first);
+}
+
+#[test]
+fn font_size_change_is_a_focused_diff() {
+    let factory = Factory::new().unwrap();
+
+    let small = snapshot(&factory, 16.0);
+    let large = snapshot(&factory, 20.0);
+
+    let small: Vec<&str> = small.lines().collect();
+    let large: Vec<&str> = large.lines().collect();
+    // Same glyphs, same fonts, same structure; only the measurements move
+    assert_eq!(small.len(), large.len());
+
+    let changed: Vec<_> = small.iter().zip(&large).filter(|(a, b)| a != b).collect();
+    assert!(!changed.is_empty());
+    assert!(changed.len() < small.len() / 2);
+    for (a, b) in changed {
+        for key in &["family_name", "file_hash", "index", "simulations"] {
+            assert!(!a.contains(key) && !b.contains(key), "{} -> {}", a, b);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Drawing a layout onto a Direct2D render target. Needs the `d2d` feature.
+#![cfg(feature = "d2d")]
+
+extern crate directwrite;
+extern crate winapi;
+
+use directwrite::text_layout::ITextLayout;
+use directwrite::{Factory, TextFormat, TextLayout};
+
+use math2d::{Color, Point2f};
+use winapi::shared::dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM;
+use winapi::shared::windef::RECT;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::d2d1::*;
+use winapi::um::dcommon::{D2D1_ALPHA_MODE_IGNORE, D2D1_PIXEL_FORMAT};
+use winapi::Interface;
+use wio::com::ComPtr;
+
+#[test]
+fn draw_colored_on_dc_target() {
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(24.0)
+        .build()
+        .unwrap();
+    let layout = TextLayout::create(&factory)
+        .with_str("Hello")
+        .with_format(&format)
+        .with_size(100.0, 40.0)
+        .build()
+        .unwrap();
+
+    // A GDI bitmap the Direct2D target draws into, so the pixels can be read back
+    let interop = factory.gdi_interop().unwrap();
+    let target = interop.create_bitmap_render_target(100, 40).unwrap();
+
+    unsafe {
+        let mut d2d = std::ptr::null_mut();
+        let hr = D2D1CreateFactory(
+            D2D1_FACTORY_TYPE_SINGLE_THREADED,
+            &ID2D1Factory::uuidof(),
+            std::ptr::null(),
+            &mut d2d,
+        );
+        assert!(SUCCEEDED(hr));
+        let d2d = ComPtr::from_raw(d2d as *mut ID2D1Factory);
+
+        let props = D2D1_RENDER_TARGET_PROPERTIES {
+            _type: D2D1_RENDER_TARGET_TYPE_DEFAULT,
+            pixelFormat: D2D1_PIXEL_FORMAT {
+                format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                alphaMode: D2D1_ALPHA_MODE_IGNORE,
+            },
+            dpiX: 0.0,
+            dpiY: 0.0,
+            usage: D2D1_RENDER_TARGET_USAGE_DEFAULT,
+            minLevel: D2D1_FEATURE_LEVEL_DEFAULT,
+        };
+        let mut dc_target = std::ptr::null_mut();
+        assert!(SUCCEEDED(d2d.CreateDCRenderTarget(&props, &mut dc_target)));
+        let dc_target = ComPtr::from_raw(dc_target);
+        let rect = RECT {
+            left: 0,
+            top: 0,
+            right: 100,
+            bottom: 40,
+        };
+        assert!(SUCCEEDED(dc_target.BindDC(target.memory_dc(), &rect)));
+        // ClearType would tint the edges of the glyphs differently per channel
+        dc_target.SetTextAntialiasMode(D2D1_TEXT_ANTIALIAS_MODE_GRAYSCALE);
+
+        dc_target.BeginDraw();
+        dc_target.Clear(&D2D1_COLOR_F {
+            r: 1.0,
+            g: 1.0,
+            b: 1.0,
+            a: 1.0,
+        });
+        layout
+            .draw_colored(
+                &dc_target,
+                Point2f::new(0.0, 0.0),
+                Color::new(1.0, 0.0, 0.0, 1.0),
+            )
+            .unwrap();
+        let hr = dc_target.EndDraw(std::ptr::null_mut(), std::ptr::null_mut());
+        assert!(SUCCEEDED(hr));
+    }
+
+    let bits = target.bitmap_bits().unwrap();
+    let pixels: Vec<_> = bits.chunks(4).map(|p| (p[2], p[1], p[0])).collect();
+    let inked = pixels.iter().filter(|&&p| p != (255, 255, 255)).count();
+    assert!(inked > 50);
+    assert!(pixels.iter().any(|&(r, g, b)| r > 200 && g < 60 && b < 60));
+    // Red blended over white in grayscale only ever lowers green and blue, by the same amount
+    assert!(pixels
+        .iter()
+        .all(|&(r, g, b)| r >= g && g.max(b) - g.min(b) < 8));
+}
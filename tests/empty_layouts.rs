@@ -0,0 +1,167 @@
+//! How every metrics, hit-testing and caret helper behaves on layouts with no visible text.
+
+extern crate directwrite;
+
+use directwrite::text_layout::{ITextLayout, CARET_WIDTH};
+use directwrite::{Factory, TextFormat, TextLayout};
+
+fn layout(factory: &Factory, text: &str) -> TextLayout {
+    let format = TextFormat::create(factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    TextLayout::create(factory)
+        .with_str(text)
+        .with_format(&format)
+        .with_size(300.0, 200.0)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn empty() {
+    let factory = Factory::new().unwrap();
+    let layout = layout(&factory, "");
+
+    assert_eq!(layout.determine_min_width(), 0.0);
+    assert!(layout.cluster_metrics().is_empty());
+    assert!(layout.break_opportunities().is_empty());
+
+    let lines = layout.line_metrics();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].length, 0);
+    assert!(lines[0].height > 0.0);
+
+    let metrics = layout.metrics();
+    assert_eq!(metrics.line_count, 1);
+    assert_eq!(metrics.width, 0.0);
+    assert_eq!(metrics.height, lines[0].height);
+
+    let hit = layout.hit_test_point(50.0, 5.0);
+    assert!(!hit.is_inside);
+    assert_eq!(hit.metrics.text_position, 0);
+    assert_eq!(layout.insertion_index_at(50.0, 5.0), 0);
+    assert!(layout.hit_test_text_position(0, false).is_ok());
+
+    let mut boxes = Vec::new();
+    layout
+        .hit_test_text_range(0, 0, 0.0, 0.0, &mut boxes)
+        .unwrap();
+    assert!(boxes.iter().all(|b| b.size.width == 0.0));
+
+    let caret = layout.caret_rect(0, false).unwrap();
+    assert_eq!(caret.left, 0.0);
+    assert_eq!(caret.right - caret.left, CARET_WIDTH);
+    assert_eq!(caret.bottom - caret.top, lines[0].height);
+}
+
+#[test]
+fn newline() {
+    let factory = Factory::new().unwrap();
+    let layout = layout(&factory, "\n");
+
+    assert_eq!(layout.determine_min_width(), 0.0);
+
+    let clusters = layout.cluster_metrics();
+    assert_eq!(clusters.len(), 1);
+    assert!(clusters[0].is_newline());
+    assert_eq!(layout.break_opportunities().len(), 1);
+
+    let lines = layout.line_metrics();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].newline_length, 1);
+    assert_eq!(lines[1].length, 0);
+    assert_eq!(layout.metrics().line_count, 2);
+
+    // The caret after the newline sits on the empty second line
+    let first = layout.caret_rect(0, false).unwrap();
+    let second = layout.caret_rect(1, false).unwrap();
+    assert!(first.bottom > first.top);
+    assert!(second.bottom > second.top);
+    assert!(second.top >= first.bottom - 0.01);
+
+    let hit = layout.hit_test_point(5.0, second.top + 1.0);
+    assert_eq!(hit.metrics.text_position, 1);
+    assert_eq!(layout.insertion_index_at(5.0, second.top + 1.0), 1);
+}
+
+#[test]
+fn space() {
+    let factory = Factory::new().unwrap();
+    let layout = layout(&factory, " ");
+
+    assert_eq!(layout.determine_min_width(), 0.0);
+
+    let clusters = layout.cluster_metrics();
+    assert_eq!(clusters.len(), 1);
+    assert!(clusters[0].is_whitespace());
+    assert_eq!(layout.break_opportunities(), vec![true]);
+
+    let lines = layout.line_metrics();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].trailing_whitespace_length, 1);
+
+    let metrics = layout.metrics();
+    assert_eq!(metrics.line_count, 1);
+    assert_eq!(metrics.width, 0.0);
+    assert!(metrics.width_including_trailing_whitespace > 0.0);
+
+    let mut boxes = Vec::new();
+    layout
+        .hit_test_text_range(0, 1, 0.0, 0.0, &mut boxes)
+        .unwrap();
+    assert_eq!(boxes.len(), 1);
+    assert!(boxes[0].size.width > 0.0);
+
+    let before = layout.caret_rect(0, false).unwrap();
+    let after = layout.caret_rect(0, true).unwrap();
+    assert!(after.left > before.left);
+    assert_eq!(after.bottom - after.top, lines[0].height);
+}
+
+#[test]
+fn trailing_newlines_and_paragraphs() {
+    let factory = Factory::new().unwrap();
+    let ranges = |layout: &TextLayout| -> Vec<(u32, u32)> {
+        layout
+            .paragraph_ranges()
+            .iter()
+            .map(|r| (r.start, r.length))
+            .collect()
+    };
+
+    let empty = layout(&factory, "");
+    assert!(!empty.ends_with_newline());
+    assert_eq!(ranges(&empty), vec![(0, 0)]);
+    let lines = empty.line_info();
+    assert_eq!(lines.len(), 1);
+    assert_eq!((lines[0].range.start, lines[0].range.length), (0, 0));
+    assert!(lines[0].height > 0.0);
+
+    let one = layout(&factory, "abc\n");
+    assert!(one.ends_with_newline());
+    assert_eq!(ranges(&one), vec![(0, 4), (4, 0)]);
+
+    let two = layout(&factory, "abc\n\n");
+    assert!(two.ends_with_newline());
+    assert_eq!(ranges(&two), vec![(0, 4), (4, 1), (5, 0)]);
+
+    let plain = layout(&factory, "abc");
+    assert!(!plain.ends_with_newline());
+    assert_eq!(ranges(&plain), vec![(0, 3)]);
+
+    for (layout, len, count) in vec![(&one, 4, 2), (&two, 5, 3)] {
+        let lines = layout.line_info();
+        assert_eq!(lines.len(), count);
+
+        // The phantom line is empty, below the others, and where the caret goes
+        let last = lines.last().unwrap();
+        assert_eq!((last.range.start, last.range.length), (len, 0));
+        assert!(last.height > 0.0);
+        let hit = layout.hit_test_text_position(len, false).unwrap();
+        assert!((hit.metrics.position.y - last.top).abs() < 0.01);
+        assert!(last.top > lines[0].top);
+    }
+}
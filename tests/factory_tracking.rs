@@ -0,0 +1,211 @@
+//! Only built with the `factory-tracking` feature. Debug test runs check that objects stay
+//! with their factory, while `cargo test --release` runs the same tests with the checks
+//! compiled out.
+
+extern crate directwrite;
+extern crate winapi;
+
+use dcommon::Error;
+use directwrite::enums::FontFaceType;
+use directwrite::factory::FACTORY_TRACKING;
+use directwrite::font_collection::FontCollection;
+use directwrite::font_file::loader::{FontFileLoader, StaticDataStream};
+use directwrite::{Factory, FontFace, FontFile, TextFormat, TextLayout};
+use winapi::shared::winerror::E_INVALIDARG;
+
+const OPENSANS_PATH: &str = "tests/test_fonts/OpenSans-Regular.ttf";
+
+struct OpenSansLoader;
+impl FontFileLoader for OpenSansLoader {
+    type Key = ();
+    type Stream = StaticDataStream;
+
+    fn create_stream(&self, _key: &()) -> Result<StaticDataStream, Error> {
+        Ok(StaticDataStream {
+            last_write: 0,
+            data: include_bytes!("test_fonts/OpenSans-Regular.ttf"),
+        })
+    }
+}
+
+/// With tracking, mixing factories fails up front. Without it, whatever DirectWrite makes of
+/// it is returned.
+fn assert_mismatch<T>(result: Result<T, Error>) {
+    if FACTORY_TRACKING {
+        match result {
+            Err(e) => assert_eq!(e.0, E_INVALIDARG),
+            Ok(_) => panic!("an object from another factory was accepted"),
+        }
+    }
+}
+
+#[test]
+fn tracking_only_in_debug_builds() {
+    assert_eq!(FACTORY_TRACKING, cfg!(debug_assertions));
+
+    // Origins are kept outside the wrappers, so tracking doesn't change their layout
+    assert_eq!(
+        std::mem::size_of::<TextFormat>(),
+        std::mem::size_of::<usize>()
+    );
+}
+
+#[test]
+fn mismatch_hook() {
+    use directwrite::factory::{clear_mismatch_hook, set_mismatch_hook, FactoryMismatch};
+    use std::sync::{Arc, Mutex};
+
+    let isolated = Factory::new_isolated().unwrap();
+    let shared = Factory::new().unwrap();
+    let collection = FontCollection::system_font_collection(&isolated, false).unwrap();
+
+    let reported = Arc::new(Mutex::new(Vec::<FactoryMismatch>::new()));
+    let sink = reported.clone();
+    set_mismatch_hook(move |mismatch| sink.lock().unwrap().push(*mismatch));
+    let result = TextFormat::create(&shared)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .with_collection(&collection)
+        .build();
+    clear_mismatch_hook();
+
+    assert_mismatch(result);
+    // Other tests may report their own mismatches while the hook is installed
+    let reported = reported.lock().unwrap();
+    if FACTORY_TRACKING {
+        let expected = unsafe {
+            use com_wrapper::ComWrapper;
+            FactoryMismatch {
+                what: "font collection",
+                origin: isolated.get_raw() as usize,
+                factory: shared.get_raw() as usize,
+            }
+        };
+        assert!(reported.contains(&expected));
+        assert!(expected.to_string().contains("font collection"));
+    } else {
+        assert!(reported.is_empty());
+    }
+}
+
+#[test]
+fn collection_from_another_factory() {
+    let isolated = Factory::new_isolated().unwrap();
+    let shared = Factory::new().unwrap();
+    let collection = FontCollection::system_font_collection(&isolated, false).unwrap();
+
+    assert_mismatch(
+        TextFormat::create(&shared)
+            .with_family("Segoe UI")
+            .with_size(16.0)
+            .with_collection(&collection)
+            .build(),
+    );
+}
+
+#[test]
+fn format_from_another_factory() {
+    let isolated = Factory::new_isolated().unwrap();
+    let shared = Factory::new().unwrap();
+    let format = TextFormat::create(&isolated)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    assert_mismatch(
+        TextLayout::create(&shared)
+            .with_str("mixed")
+            .with_format(&format)
+            .with_size(100.0, 100.0)
+            .build()
+            .map_err(Error::from),
+    );
+}
+
+#[test]
+fn font_file_from_another_factory() {
+    let isolated = Factory::new_isolated().unwrap();
+    let shared = Factory::new().unwrap();
+    let file = FontFile::create(&isolated)
+        .with_file_path(OPENSANS_PATH)
+        .build()
+        .unwrap();
+
+    assert_mismatch(
+        FontFace::create(&shared)
+            .with_files(&[file])
+            .with_face_index(0)
+            .with_font_face_type(FontFaceType::TrueType)
+            .build(),
+    );
+}
+
+#[test]
+fn loader_from_another_factory() {
+    let isolated = Factory::new_isolated().unwrap();
+    let shared = Factory::new().unwrap();
+    let loader = OpenSansLoader.register(&isolated).unwrap();
+
+    assert_mismatch(
+        FontFile::create(&shared)
+            .with_loader(&loader)
+            .with_key(&())
+            .build(),
+    );
+}
+
+#[test]
+fn objects_from_the_same_factory() {
+    use directwrite::text_format::ITextFormat;
+
+    let isolated = Factory::new_isolated().unwrap();
+    let collection = FontCollection::system_font_collection(&isolated, false).unwrap();
+    let format = TextFormat::create(&isolated)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .with_collection(&collection)
+        .build()
+        .unwrap();
+    TextLayout::create(&isolated)
+        .with_str("same factory")
+        .with_format(&format)
+        .with_size(100.0, 100.0)
+        .build()
+        .unwrap();
+
+    // Objects handed out by getters come from the factory of the object they came from
+    let reported = format.font_collection().unwrap();
+    TextFormat::create(&isolated)
+        .with_family("Segoe UI")
+        .with_size(12.0)
+        .with_collection(&reported)
+        .build()
+        .unwrap();
+
+    let loader = OpenSansLoader.register(&isolated).unwrap();
+    let file = FontFile::create(&isolated)
+        .with_loader(&loader)
+        .with_key(&())
+        .build()
+        .unwrap();
+    FontFace::create(&isolated)
+        .with_files(&[file])
+        .with_face_index(0)
+        .with_font_face_type(FontFaceType::TrueType)
+        .build()
+        .unwrap();
+
+    // Every call to `Factory::new` returns the same shared factory
+    let format = TextFormat::create(&Factory::new().unwrap())
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    TextLayout::create(&Factory::new().unwrap())
+        .with_str("shared factory")
+        .with_format(&format)
+        .with_size(100.0, 100.0)
+        .build()
+        .unwrap();
+}
@@ -0,0 +1,123 @@
+extern crate directwrite;
+
+use directwrite::enums::{FontStretch, FontStyle, FontWeight};
+use directwrite::font::IFont;
+use directwrite::font_collection::IFontCollection;
+use directwrite::font_face::IFontFace;
+use directwrite::font_family::IFontFamily;
+use directwrite::geometry_sink::{CollectingSink, FlatteningSink, PathElement, SvgPathSink};
+use directwrite::GeometrySink;
+use directwrite::{Factory, FontCollection, FontFace};
+
+fn segoe_ui(factory: &Factory) -> FontFace {
+    let collection = FontCollection::system_font_collection(factory, false).unwrap();
+    let index = collection.find_family_by_name("Segoe UI").unwrap();
+    collection
+        .family(index)
+        .unwrap()
+        .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap()
+        .create_face()
+        .unwrap()
+}
+
+#[test]
+fn collect_outline_of_i() {
+    let factory = Factory::new().unwrap();
+    let face = segoe_ui(&factory);
+    let glyphs = face.glyph_indices(&['I' as u32]).unwrap();
+
+    let mut sink = CollectingSink::new();
+    face.glyph_run_outline(64.0, &glyphs, None, None, false, false, &mut sink)
+        .unwrap();
+    let elements = sink.into_elements();
+
+    let begins = elements
+        .iter()
+        .filter(|e| match e {
+            PathElement::BeginFigure { .. } => true,
+            _ => false,
+        })
+        .count();
+    let ends = elements
+        .iter()
+        .filter(|e| match e {
+            PathElement::EndFigure(_) => true,
+            _ => false,
+        })
+        .count();
+    assert!(begins >= 1);
+    assert_eq!(begins, ends);
+
+    // The stem of an 'I' is drawn with straight lines
+    assert!(elements.iter().any(|e| match e {
+        PathElement::Lines(points) => !points.is_empty(),
+        _ => false,
+    }));
+}
+
+#[test]
+fn svg_path_commands() {
+    use math2d::Point2f;
+
+    let mut sink = SvgPathSink::new(1);
+    sink.begin_figure(Point2f::new(0.0, 0.0), 0);
+    sink.add_lines(&[Point2f::new(10.0, 0.0), Point2f::new(10.26, 5.0)]);
+    sink.end_figure(1);
+    sink.begin_figure(Point2f::new(1.0, 1.0), 0);
+    sink.end_figure(0);
+    assert_eq!(
+        sink.into_svg(),
+        "M 0.0,0.0 L 10.0,0.0 L 10.3,5.0 Z M 1.0,1.0"
+    );
+}
+
+#[test]
+fn svg_outline_of_i() {
+    let factory = Factory::new().unwrap();
+    let face = segoe_ui(&factory);
+    let glyphs = face.glyph_indices(&['I' as u32]).unwrap();
+
+    let mut sink = SvgPathSink::new(2);
+    face.glyph_run_outline(64.0, &glyphs, None, None, false, false, &mut sink)
+        .unwrap();
+    let svg = sink.into_svg();
+
+    assert!(svg.starts_with("M "));
+    assert!(svg.ends_with('Z'));
+    assert!(svg.contains(" L "));
+}
+
+#[test]
+fn flatten_outline_of_o() {
+    let factory = Factory::new().unwrap();
+    let face = segoe_ui(&factory);
+    let glyphs = face.glyph_indices(&['O' as u32]).unwrap();
+
+    // The unflattened outline is made of curves
+    let mut curved = CollectingSink::new();
+    face.glyph_run_outline(64.0, &glyphs, None, None, false, false, &mut curved)
+        .unwrap();
+    assert!(curved.elements().iter().any(|e| match e {
+        PathElement::Beziers(_) => true,
+        _ => false,
+    }));
+
+    let mut sink = FlatteningSink::new(CollectingSink::new(), 0.1);
+    face.glyph_run_outline(64.0, &glyphs, None, None, false, false, &mut sink)
+        .unwrap();
+    let elements = sink.into_inner().into_elements();
+
+    assert!(!elements.iter().any(|e| match e {
+        PathElement::Beziers(_) => true,
+        _ => false,
+    }));
+    let lines: usize = elements
+        .iter()
+        .map(|e| match e {
+            PathElement::Lines(points) => points.len(),
+            _ => 0,
+        })
+        .sum();
+    assert!(lines > 16);
+}
@@ -0,0 +1,95 @@
+extern crate directwrite;
+
+use directwrite::enums::*;
+use directwrite::font::IFont;
+use directwrite::font_collection::IFontCollection;
+use directwrite::font_face::IFontFace;
+use directwrite::font_family::IFontFamily;
+use directwrite::glyph_atlas::{rasterize_glyphs, AtlasAllocator, GlyphAtlasEntry, ShelfAllocator};
+use directwrite::{Factory, FontCollection};
+
+fn overlaps(a: &GlyphAtlasEntry, b: &GlyphAtlasEntry) -> bool {
+    a.atlas_x < b.atlas_x + b.width
+        && b.atlas_x < a.atlas_x + a.width
+        && a.atlas_y < b.atlas_y + b.height
+        && b.atlas_y < a.atlas_y + a.height
+}
+
+#[test]
+fn shelf_allocator_bounds() {
+    let mut shelf = ShelfAllocator::new(32, 16).with_padding(1);
+    assert_eq!(shelf.allocate(10, 8), Some((0, 0)));
+    assert_eq!(shelf.allocate(10, 4), Some((11, 0)));
+    assert_eq!(shelf.allocate(12, 4), Some((0, 9)));
+    assert_eq!(shelf.allocate(33, 1), None);
+    assert_eq!(shelf.allocate(4, 8), None);
+}
+
+#[test]
+fn pack_glyphs() {
+    let factory = Factory::new().unwrap();
+
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    let index = collection.find_family_by_name("Segoe UI").unwrap();
+    let face = collection
+        .family(index)
+        .unwrap()
+        .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap()
+        .create_face()
+        .unwrap();
+
+    let chars: Vec<u32> = "AWgj@ .".chars().map(|c| c as u32).collect();
+    let indices = face.glyph_indices(&chars).unwrap();
+
+    let mut atlas = ShelfAllocator::new(64, 128).with_padding(1);
+    let entries = rasterize_glyphs(
+        &factory,
+        &face,
+        24.0,
+        1.0,
+        RenderingMode::ClearTypeNaturalSymmetric,
+        &indices,
+        &mut atlas,
+    )
+    .unwrap();
+    assert_eq!(entries.len(), indices.len());
+
+    for (i, (entry, coverage)) in entries.iter().enumerate() {
+        assert_eq!(entry.glyph_index, indices[i]);
+        assert_eq!(coverage.len(), (entry.width * entry.height) as usize);
+        assert!(entry.atlas_x + entry.width <= atlas.width());
+        assert!(entry.atlas_y + entry.height <= atlas.height());
+    }
+
+    // The space has no ink and takes no room; everything else is drawn above the baseline
+    let space = &entries[5].0;
+    assert_eq!((space.width, space.height), (0, 0));
+    assert!(entries[0].0.top < 0);
+    assert!(entries[0].1.iter().any(|&a| a > 128));
+
+    let placed: Vec<_> = entries
+        .iter()
+        .map(|e| e.0)
+        .filter(|e| e.width > 0)
+        .collect();
+    assert_eq!(placed.len(), indices.len() - 1);
+    for (i, a) in placed.iter().enumerate() {
+        for b in &placed[i + 1..] {
+            assert!(!overlaps(a, b), "{:?} overlaps {:?}", a, b);
+        }
+    }
+
+    // Running out of room is an error rather than a silently dropped glyph
+    let mut tiny = ShelfAllocator::new(8, 8);
+    assert!(rasterize_glyphs(
+        &factory,
+        &face,
+        24.0,
+        1.0,
+        RenderingMode::Aliased,
+        &indices,
+        &mut tiny,
+    )
+    .is_err());
+}
@@ -0,0 +1,311 @@
+extern crate directwrite;
+
+use directwrite::enums::BreakCondition;
+use directwrite::inline_object::custom::CustomInlineObject;
+use directwrite::inline_object::{BreakConditions, DrawingContext};
+use directwrite::metrics::{InlineObjectMetrics, OverhangMetrics};
+use directwrite::text_renderer::custom::*;
+use directwrite::text_renderer::DrawContext;
+use directwrite::{InlineObject, TextRenderer};
+
+use std::sync::{Arc, Mutex};
+
+use dcommon::Error;
+use math2d::{Matrix3x2f, Sizef};
+
+const SCALED: Matrix3x2f = Matrix3x2f {
+    a: 2.0,
+    b: 0.0,
+    c: 0.0,
+    d: 2.0,
+    x: 10.0,
+    y: 20.0,
+};
+
+struct ScaledRenderer;
+
+impl CustomTextRenderer for ScaledRenderer {
+    fn pixel_snapping_disabled(&self, _: DrawContext) -> bool {
+        false
+    }
+
+    fn current_transform(&self, _: DrawContext) -> Matrix3x2f {
+        SCALED
+    }
+
+    fn pixels_per_dip(&self, _: DrawContext) -> f32 {
+        1.5
+    }
+
+    fn draw_glyph_run(&mut self, _: &DrawGlyphRun) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn draw_underline(&mut self, _: &DrawUnderline) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn draw_strikethrough(&mut self, _: &DrawStrikethrough) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn draw_inline_object(&mut self, _: &DrawInlineObject) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+struct Swatch(Arc<Mutex<Option<(Matrix3x2f, f32)>>>);
+
+impl CustomInlineObject for Swatch {
+    fn metrics(&self) -> InlineObjectMetrics {
+        InlineObjectMetrics {
+            size: Sizef {
+                width: 12.0,
+                height: 12.0,
+            },
+            baseline: 12.0,
+            supports_sideways: false.into(),
+        }
+    }
+
+    fn overhang_metrics(&self) -> OverhangMetrics {
+        OverhangMetrics {
+            left: 0.0,
+            top: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+        }
+    }
+
+    fn break_conditions(&self) -> BreakConditions {
+        BreakConditions {
+            preceding: (BreakCondition::Neutral as u32).into(),
+            following: (BreakCondition::Neutral as u32).into(),
+        }
+    }
+
+    fn draw(&self, context: &DrawingContext) -> Result<(), Error> {
+        let transform = context.current_transform()?;
+        let pixels_per_dip = context.pixels_per_dip()?;
+        *self.0.lock().unwrap() = Some((transform, pixels_per_dip));
+        Ok(())
+    }
+}
+
+#[test]
+fn inline_object_reads_transform() {
+    let seen = Arc::new(Mutex::new(None));
+    let object = InlineObject::create_custom(Swatch(seen.clone()));
+    let mut renderer = TextRenderer::new(ScaledRenderer);
+
+    object
+        .draw(&DrawingContext {
+            client_context: unsafe { DrawContext::from_usize(0) },
+            renderer: &mut renderer,
+            origin: (4.0, 8.0).into(),
+            is_sideways: false,
+            is_right_to_left: false,
+            client_effect: None,
+        })
+        .unwrap();
+
+    let (transform, pixels_per_dip) = seen.lock().unwrap().unwrap();
+    assert_eq!(pixels_per_dip, 1.5);
+    assert_eq!((transform.a, transform.d), (2.0, 2.0));
+    assert_eq!((transform.b, transform.c), (0.0, 0.0));
+    assert_eq!((transform.x, transform.y), (10.0, 20.0));
+}
+
+struct Resizable(Arc<Mutex<f32>>);
+
+impl CustomInlineObject for Resizable {
+    fn metrics(&self) -> InlineObjectMetrics {
+        let width = *self.0.lock().unwrap();
+        InlineObjectMetrics {
+            size: Sizef {
+                width,
+                height: 12.0,
+            },
+            baseline: 12.0,
+            supports_sideways: false.into(),
+        }
+    }
+
+    fn overhang_metrics(&self) -> OverhangMetrics {
+        OverhangMetrics {
+            left: 0.0,
+            top: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+        }
+    }
+
+    fn break_conditions(&self) -> BreakConditions {
+        BreakConditions {
+            preceding: (BreakCondition::Neutral as u32).into(),
+            following: (BreakCondition::Neutral as u32).into(),
+        }
+    }
+
+    fn draw(&self, _context: &DrawingContext) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn invalidate_layout_remeasures_inline_objects() {
+    use directwrite::text_layout::ITextLayout;
+    use directwrite::{Factory, TextFormat, TextLayout};
+    use winapi::shared::winerror::E_NOINTERFACE;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let mut layout = TextLayout::create(&factory)
+        .with_str("a\u{FFFC}b")
+        .with_format(&format)
+        .with_size(500.0, 100.0)
+        .build()
+        .unwrap();
+
+    let width = Arc::new(Mutex::new(20.0));
+    let object = InlineObject::create_custom(Resizable(width.clone()));
+    layout.set_inline_object(&object, 1u32..2).unwrap();
+    let before = layout.metrics().width;
+
+    // The layout keeps the size it measured before
+    *width.lock().unwrap() = 120.0;
+    assert_eq!(layout.metrics().width, before);
+
+    match layout.invalidate_layout() {
+        Ok(()) => {}
+        // Older than Windows 10
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    }
+    let after = layout.metrics().width;
+    assert!((after - before - 100.0).abs() < 0.5);
+}
+
+/// A fixed width gap which forces a line break after it.
+struct Spacer;
+
+impl CustomInlineObject for Spacer {
+    fn metrics(&self) -> InlineObjectMetrics {
+        InlineObjectMetrics {
+            size: Sizef {
+                width: 30.0,
+                height: 12.0,
+            },
+            baseline: 12.0,
+            supports_sideways: false.into(),
+        }
+    }
+
+    fn overhang_metrics(&self) -> OverhangMetrics {
+        OverhangMetrics {
+            left: 0.0,
+            top: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+        }
+    }
+
+    fn break_conditions(&self) -> BreakConditions {
+        BreakConditions {
+            preceding: (BreakCondition::Neutral as u32).into(),
+            following: (BreakCondition::MustBreak as u32).into(),
+        }
+    }
+
+    fn draw(&self, _context: &DrawingContext) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn break_conditions_around_inline_object() {
+    use directwrite::text_layout::ITextLayout;
+    use directwrite::{Factory, TextFormat, TextLayout};
+    use winapi::shared::winerror::E_INVALIDARG;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let mut layout = TextLayout::create(&factory)
+        .with_str("ab\u{FFFC}cd ef")
+        .with_format(&format)
+        .with_size(500.0, 100.0)
+        .with_retained_text(true)
+        .build()
+        .unwrap();
+    layout
+        .set_inline_object(&InlineObject::create_custom(Spacer), 2u32..3)
+        .unwrap();
+
+    // Inside a word nothing may break
+    let word = layout.break_conditions_at(0).unwrap();
+    assert_eq!(word.following.value, BreakCondition::MayNotBreak as u32);
+    assert_eq!(word.preceding.value, BreakCondition::Neutral as u32);
+
+    // The spacer's own condition wins over the text around it
+    let spacer = layout.break_conditions_at(2).unwrap();
+    assert_eq!(spacer.following.value, BreakCondition::MustBreak as u32);
+    let next = layout.break_conditions_at(3).unwrap();
+    assert_eq!(next.preceding.value, BreakCondition::MustBreak as u32);
+
+    // So the text after it starts a new line, even though everything fits on one
+    assert_eq!(layout.line_metrics().len(), 2);
+
+    // After the space is an ordinary break opportunity
+    let space = layout.break_conditions_at(5).unwrap();
+    assert_eq!(space.following.value, BreakCondition::CanBreak as u32);
+
+    let err = layout.break_conditions_at(8).err().unwrap();
+    assert_eq!(err.0, E_INVALIDARG);
+}
+
+#[test]
+fn break_conditions_without_text() {
+    use directwrite::{Factory, TextFormat, TextLayout};
+    use winapi::shared::winerror::{ERROR_INVALID_STATE, HRESULT_FROM_WIN32};
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let build = |retain_text: bool| {
+        TextLayout::create(&factory)
+            .with_str("one two\nthree")
+            .with_format(&format)
+            .with_size(500.0, 100.0)
+            .with_retained_text(retain_text)
+            .build()
+            .unwrap()
+    };
+    let layout = build(true);
+
+    // Without the text there is nothing to analyze, whichever way the layout was made
+    let invalid_state = HRESULT_FROM_WIN32(ERROR_INVALID_STATE);
+    let err = build(false).break_conditions_at(0).err().unwrap();
+    assert_eq!(err.0, invalid_state);
+    let wrapped = unsafe { TextLayout::from_raw_addref(layout.as_raw_ptr()) };
+    assert_eq!(wrapped.text_utf16(), None);
+    let err = wrapped.break_conditions_at(0).err().unwrap();
+    assert_eq!(err.0, invalid_state);
+
+    let space = layout.break_conditions_at(3).unwrap();
+    assert_eq!(space.following.value, BreakCondition::CanBreak as u32);
+    let newline = layout.break_conditions_at(7).unwrap();
+    assert_eq!(newline.following.value, BreakCondition::MustBreak as u32);
+    let word = layout.break_conditions_at(9).unwrap();
+    assert_eq!(word.preceding.value, BreakCondition::MayNotBreak as u32);
+}
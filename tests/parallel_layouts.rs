@@ -0,0 +1,64 @@
+extern crate directwrite;
+
+use directwrite::text_layout::parallel::{layout_many, LayoutPool};
+use directwrite::text_layout::ITextLayout;
+use directwrite::{Factory, TextFormat};
+
+fn pages() -> Vec<String> {
+    (0..300)
+        .map(|i| {
+            let words = ["lorem", "ipsum", "dolor", "sit", "amet", "consectetur"];
+            (0..i % 40 + 1)
+                .map(|j| words[(i + j) % words.len()])
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+#[test]
+fn parallel_matches_sequential() {
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(14.0)
+        .build()
+        .unwrap();
+
+    let sequential =
+        LayoutPool::new()
+            .with_threads(1)
+            .layout_many(&factory, &format, pages(), (200.0, 400.0));
+    let parallel = LayoutPool::new()
+        .with_threads(4)
+        .with_sequential_threshold(0)
+        .layout_many(&factory, &format, pages(), (200.0, 400.0));
+    assert_eq!(sequential.len(), 300);
+    assert_eq!(parallel.len(), 300);
+
+    for ((text, a), b) in pages().iter().zip(&sequential).zip(&parallel) {
+        let (a, b) = (a.as_ref().unwrap(), b.as_ref().unwrap());
+        assert_eq!(b.text_len(), text.encode_utf16().count() as u32);
+
+        let (a, b) = (a.metrics(), b.metrics());
+        assert_eq!(a.width, b.width);
+        assert_eq!(a.height, b.height);
+        assert_eq!(a.line_count, b.line_count);
+    }
+
+    // Tiny batches and the default pool give the same results
+    let few = layout_many(
+        &factory,
+        &format,
+        pages().into_iter().take(3),
+        (200.0, 400.0),
+    );
+    assert_eq!(few.len(), 3);
+    for (a, b) in few.iter().zip(&sequential) {
+        assert_eq!(
+            a.as_ref().unwrap().metrics().height,
+            b.as_ref().unwrap().metrics().height
+        );
+    }
+    assert!(layout_many(&factory, &format, Vec::new(), (200.0, 400.0)).is_empty());
+}
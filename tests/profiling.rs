@@ -0,0 +1,139 @@
+extern crate directwrite;
+extern crate winapi;
+
+use dcommon::Error;
+use directwrite::font_collection::loader::FontCollectionLoader;
+use directwrite::font_collection::IFontCollection;
+use directwrite::profiling::{self, SummaryProfiler};
+use directwrite::text_layout::ITextLayout;
+use directwrite::text_renderer::custom::{
+    CustomTextRenderer, DrawGlyphRun, DrawInlineObject, DrawStrikethrough, DrawUnderline,
+};
+use directwrite::text_renderer::DrawContext;
+use directwrite::text_renderer::{RasterOptions, RecordingRenderer};
+use directwrite::{Factory, FontCollection, FontFile, TextFormat, TextLayout, TextRenderer};
+use math2d::{Matrix3x2f, Point2f};
+use winapi::shared::winerror::E_ABORT;
+
+struct FiraCodeLoader;
+impl FontCollectionLoader for FiraCodeLoader {
+    type Key = ();
+    type Iter = std::vec::IntoIter<Result<FontFile, Error>>;
+
+    fn get_iterator(&self, factory: &Factory, _key: &()) -> Result<Self::Iter, Error> {
+        let files = ["Regular", "Bold"]
+            .iter()
+            .map(|weight| {
+                FontFile::create(factory)
+                    .with_file_path(&format!("tests/test_fonts/FiraCode-{}.ttf", weight))
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        Ok(files.into_iter())
+    }
+}
+
+/// Fails every glyph run, to check the failure reaches the caller while a profiler is installed.
+struct FailingRenderer;
+impl CustomTextRenderer for FailingRenderer {
+    fn pixel_snapping_disabled(&self, _context: DrawContext) -> bool {
+        false
+    }
+
+    fn current_transform(&self, _context: DrawContext) -> Matrix3x2f {
+        Matrix3x2f::IDENTITY
+    }
+
+    fn pixels_per_dip(&self, _context: DrawContext) -> f32 {
+        1.0
+    }
+
+    fn draw_glyph_run(&mut self, _context: &DrawGlyphRun) -> Result<(), Error> {
+        Err(E_ABORT.into())
+    }
+
+    fn draw_underline(&mut self, _context: &DrawUnderline) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn draw_strikethrough(&mut self, _context: &DrawStrikethrough) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn draw_inline_object(&mut self, _context: &DrawInlineObject) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn summary_profiler_counts() {
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let summary = SummaryProfiler::new();
+    profiling::set_profiler(summary.clone());
+
+    let layout = TextLayout::create(&factory)
+        .with_str("Hello")
+        .with_format(&format)
+        .with_size(200.0, 50.0)
+        .build()
+        .unwrap();
+    let created = summary.summary().layouts_created;
+    assert_eq!(created.count, 1);
+    assert_eq!(created.items, 5);
+
+    layout
+        .rasterize(&factory, &RasterOptions::default(), 1.0)
+        .unwrap();
+    let drawn = summary.summary().layouts_drawn;
+    assert_eq!(drawn.count, 1);
+    assert!(drawn.items >= 1);
+    assert!(drawn.max >= drawn.p50);
+    let first_items = drawn.items;
+
+    // A renderer's error comes back unchanged, and the failed draw isn't recorded
+    let mut failing = TextRenderer::new(FailingRenderer);
+    let err = layout
+        .draw(&mut failing, 0.0, 0.0, &unsafe {
+            DrawContext::from_usize(0)
+        })
+        .unwrap_err();
+    assert_eq!(err.0, E_ABORT);
+    assert_eq!(summary.summary().layouts_drawn.count, 1);
+
+    // Runs are counted once each as they reach the renderer, which is drawn into directly
+    let recorder = RecordingRenderer::new();
+    recorder.record(&layout, Point2f::new(0.0, 0.0)).unwrap();
+    let drawn = summary.summary().layouts_drawn;
+    assert_eq!(drawn.count, 2);
+    assert_eq!(drawn.items, first_items + recorder.glyph_runs().len());
+
+    // Both FiraCode files are one family of two fonts
+    let loader = FiraCodeLoader.register(&factory).unwrap();
+    let collection = FontCollection::create(&factory)
+        .with_loader(&loader)
+        .with_key(&())
+        .build()
+        .unwrap();
+    assert_eq!(collection.family_count(), 1);
+    let built = summary.summary().collections_built;
+    assert_eq!(built.count, 1);
+    assert_eq!(built.items, 2);
+
+    // Nothing is recorded once the profiler is removed
+    profiling::clear_profiler();
+    let renderer =
+        directwrite::text_renderer::RasterRenderer::new(&factory, RasterOptions::default(), 1.0);
+    let mut renderer = directwrite::TextRenderer::new(renderer);
+    layout
+        .draw(&mut renderer, 0.0, 0.0, &unsafe {
+            DrawContext::from_usize(0)
+        })
+        .unwrap();
+    assert_eq!(summary.summary().layouts_drawn.count, 1);
+}
@@ -0,0 +1,266 @@
+//! Rasterization tests check exact pixels only where the output is fully determined, such as
+//! the solid interior of a block glyph, premultiplication and the background. Hashes of whole
+//! images are only compared between runs on the same machine: antialiased edges change with
+//! the Windows version's rasterizer and the versions of the system fonts, so stored golden
+//! hashes would only ever match on the machine that produced them.
+
+extern crate directwrite;
+
+use directwrite::enums::TextAntialiasMode;
+use directwrite::text_layout::ITextLayout;
+use directwrite::text_renderer::raster::{RasterFormat, RasterImage, RasterOptions};
+use directwrite::{Factory, TextFormat, TextLayout};
+
+fn layout(factory: &Factory) -> TextLayout {
+    let format = TextFormat::create(factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    TextLayout::create(factory)
+        .with_str("Hello")
+        .with_format(&format)
+        .with_size(200.0, 50.0)
+        .build()
+        .unwrap()
+}
+
+fn pixel(image: &RasterImage, x: i32, y: i32) -> &[u8] {
+    let bpp = image.format.bytes_per_pixel();
+    let i = ((y - image.bounds.top) as usize * image.width() as usize
+        + (x - image.bounds.left) as usize)
+        * bpp;
+    &image.data[i..i + bpp]
+}
+
+/// A full block in a monospace font, which covers its whole cell.
+fn block(factory: &Factory, size: f32) -> TextLayout {
+    let format = TextFormat::create(factory)
+        .with_family("Consolas")
+        .with_size(size)
+        .build()
+        .unwrap();
+
+    TextLayout::create(factory)
+        .with_str("\u{2588}")
+        .with_format(&format)
+        .with_size(200.0, 200.0)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn grayscale_is_a8() {
+    let factory = Factory::new().unwrap();
+    let layout = layout(&factory);
+    let options = RasterOptions {
+        antialias: TextAntialiasMode::Grayscale,
+        ..Default::default()
+    };
+
+    let image = layout.rasterize(&factory, &options, 1.0).unwrap();
+    assert_eq!(image.format, RasterFormat::A8);
+    assert!(image.width() > 0 && image.height() > 0);
+    assert_eq!(image.data.len(), (image.width() * image.height()) as usize);
+    assert!(image.data.iter().any(|&a| a == 255));
+    // "Hello" has gaps between its letters
+    assert!(image.data.iter().any(|&a| a == 0));
+
+    let again = layout.rasterize(&factory, &options, 1.0).unwrap();
+    assert_eq!(
+        (image.width(), image.height()),
+        (again.width(), again.height())
+    );
+    assert_eq!(image.data, again.data);
+}
+
+/// FNV-1a over the image bounds and pixels.
+fn image_hash(image: &RasterImage) -> u64 {
+    let b = image.bounds;
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    let bounds = [b.left, b.top, b.right, b.bottom];
+    let header = bounds.iter().flat_map(|v| v.to_le_bytes().to_vec());
+    for byte in header.chain(image.data.iter().cloned()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+#[test]
+fn hashes_match_across_factories() {
+    let shared = Factory::new().unwrap();
+    let isolated = Factory::new_isolated().unwrap();
+
+    let grayscale = RasterOptions {
+        antialias: TextAntialiasMode::Grayscale,
+        ..Default::default()
+    };
+    let cleartype = RasterOptions {
+        antialias: TextAntialiasMode::ClearType,
+        palette_index: 0,
+        text_color: [0.0, 0.0, 0.0, 1.0],
+        background: Some([1.0, 1.0, 1.0, 1.0]),
+    };
+
+    // Both modes produce the same image for the same text whichever factory lays it out
+    // and draws it, and the two modes never produce the same image
+    let mut hashes = Vec::new();
+    for options in &[grayscale, cleartype] {
+        let a = layout(&shared).rasterize(&shared, options, 1.0).unwrap();
+        let b = layout(&isolated)
+            .rasterize(&isolated, options, 1.0)
+            .unwrap();
+        assert_eq!(image_hash(&a), image_hash(&b), "{:?}", options.antialias);
+        hashes.push(image_hash(&a));
+    }
+    assert_ne!(hashes[0], hashes[1]);
+}
+
+#[test]
+fn block_pixels() {
+    let factory = Factory::new().unwrap();
+    let layout = block(&factory, 32.0);
+    let advance = layout.metrics().width;
+
+    let grayscale = RasterOptions {
+        antialias: TextAntialiasMode::Grayscale,
+        ..Default::default()
+    };
+    let image = layout.rasterize(&factory, &grayscale, 1.0).unwrap();
+    let b = image.bounds;
+    let (cx, cy) = ((b.left + b.right) / 2, (b.top + b.bottom) / 2);
+    assert_eq!(pixel(&image, cx, cy), [255]);
+
+    // The middle row is solid for the width of the cell, give or take the antialiased edges
+    let solid = (b.left..b.right)
+        .filter(|&x| pixel(&image, x, cy) == [255])
+        .count() as f32;
+    assert!((solid - advance).abs() <= 2.0, "{} vs {}", solid, advance);
+
+    // Twice the DIPs per pixel gives twice the pixels
+    let doubled = layout.rasterize(&factory, &grayscale, 2.0).unwrap();
+    let ratio = doubled.width() as f32 / image.width() as f32;
+    assert!((ratio - 2.0).abs() < 0.2, "{}", ratio);
+
+    // Black on white ClearType is black where the block is solid, and opaque everywhere
+    let cleartype = RasterOptions {
+        antialias: TextAntialiasMode::ClearType,
+        palette_index: 0,
+        text_color: [0.0, 0.0, 0.0, 1.0],
+        background: Some([1.0, 1.0, 1.0, 1.0]),
+    };
+    let image = layout.rasterize(&factory, &cleartype, 1.0).unwrap();
+    let b = image.bounds;
+    let (cx, cy) = ((b.left + b.right) / 2, (b.top + b.bottom) / 2);
+    assert_eq!(pixel(&image, cx, cy), [0, 0, 0, 255]);
+
+    // A half transparent red block is half transparent red, premultiplied
+    let red = RasterOptions {
+        antialias: TextAntialiasMode::ClearType,
+        palette_index: 0,
+        text_color: [1.0, 0.0, 0.0, 0.5],
+        background: None,
+    };
+    let image = layout.rasterize(&factory, &red, 1.0).unwrap();
+    assert_eq!(pixel(&image, cx, cy), [0, 0, 128, 128]);
+}
+
+#[test]
+fn cleartype_is_bgra_over_background() {
+    let factory = Factory::new().unwrap();
+    let layout = layout(&factory);
+    let options = RasterOptions {
+        antialias: TextAntialiasMode::ClearType,
+        palette_index: 0,
+        text_color: [0.0, 0.0, 0.0, 1.0],
+        background: Some([1.0, 1.0, 1.0, 1.0]),
+    };
+
+    let image = layout.rasterize(&factory, &options, 1.0).unwrap();
+    assert_eq!(image.format, RasterFormat::Bgra);
+    assert_eq!(
+        image.data.len(),
+        (image.width() * image.height()) as usize * 4
+    );
+
+    // Everything is opaque, and pixels the text doesn't touch keep the background
+    assert!(image.data.chunks(4).all(|px| px[3] == 255));
+    assert!(image.data.chunks(4).any(|px| px == [255, 255, 255, 255]));
+
+    // Subpixel coverage gives some pixels unequal channels
+    assert!(image
+        .data
+        .chunks(4)
+        .any(|px| px[0] != px[1] || px[1] != px[2]));
+
+    let again = layout.rasterize(&factory, &options, 1.0).unwrap();
+    assert_eq!(image.data, again.data);
+}
+
+#[test]
+fn color_glyphs_use_the_palette() {
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI Emoji")
+        .with_size(48.0)
+        .build()
+        .unwrap();
+    let layout = TextLayout::create(&factory)
+        .with_str("\u{1F600}")
+        .with_format(&format)
+        .with_size(200.0, 200.0)
+        .build()
+        .unwrap();
+
+    let options = RasterOptions {
+        antialias: TextAntialiasMode::ClearType,
+        palette_index: 0,
+        text_color: [0.0, 0.0, 0.0, 1.0],
+        background: Some([1.0, 1.0, 1.0, 1.0]),
+    };
+    let image = match layout.rasterize(&factory, &options, 1.0) {
+        Ok(image) => image,
+        // Older than Windows 8.1, where there are no color glyphs to draw
+        Err(e) if e.0 == winapi::shared::winerror::E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    };
+
+    // The face is yellow, which black text on white never is beyond a few fringe pixels
+    let yellow = image
+        .data
+        .chunks(4)
+        .filter(|px| px[2] > 200 && px[1] > 150 && px[0] < 100)
+        .count();
+    assert!(yellow > 100, "{} yellow pixels", yellow);
+
+    // Palette indices past the font's palettes are rejected rather than ignored
+    let options = RasterOptions {
+        palette_index: std::u32::MAX,
+        ..options
+    };
+    assert!(layout.rasterize(&factory, &options, 1.0).is_err());
+}
+
+#[test]
+fn snap_baseline_rounds_to_device_pixels() {
+    use directwrite::pixel_snapping::snap_baseline;
+
+    assert_eq!(snap_baseline(10.4, 1.0), 10.0);
+    assert_eq!(snap_baseline(10.6, 1.0), 11.0);
+
+    // At 150% a device pixel is 2/3 of a DIP
+    let snapped = snap_baseline(10.2, 1.5);
+    assert_eq!((snapped * 1.5).round(), 15.0);
+    assert!((snapped * 1.5 - 15.0).abs() < 1e-4);
+
+    // At 200% half-DIP positions are already on the grid
+    assert_eq!(snap_baseline(7.5, 2.0), 7.5);
+    assert_eq!(snap_baseline(7.3, 2.0), 7.5);
+    assert_eq!(snap_baseline(-3.3, 2.0), -3.5);
+
+    // Nonsensical scales leave the baseline alone
+    assert_eq!(snap_baseline(10.4, 0.0), 10.4);
+    assert_eq!(snap_baseline(10.4, std::f32::NAN), 10.4);
+}
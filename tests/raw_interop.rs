@@ -0,0 +1,126 @@
+extern crate directwrite;
+extern crate winapi;
+
+use directwrite::enums::*;
+use directwrite::font::IFont;
+use directwrite::font_collection::IFontCollection;
+use directwrite::font_family::IFontFamily;
+use directwrite::{Factory, FontCollection, RenderingParams, TextFormat, TextLayout};
+use winapi::um::dwrite::{IDWriteTextFormat, IDWriteTextLayout};
+use winapi::um::unknwnbase::IUnknown;
+
+unsafe fn refcount<T>(ptr: *mut T) -> u32 {
+    let unknown = &*(ptr as *mut IUnknown);
+    unknown.AddRef();
+    unknown.Release()
+}
+
+/// Stands in for a binding like `ID2D1RenderTarget::DrawTextLayout`, which borrows the layout
+/// for the duration of the call.
+unsafe fn draw_text_layout(layout: *mut IDWriteTextLayout) -> f32 {
+    let mut metrics = std::mem::zeroed();
+    assert!((*layout).GetMetrics(&mut metrics) >= 0);
+    metrics.width
+}
+
+/// Stands in for a binding which hands out a new reference, like a `CreateTextFormat` call.
+unsafe fn create_text_format(source: *mut IDWriteTextFormat) -> *mut IDWriteTextFormat {
+    (*source).AddRef();
+    source
+}
+
+#[test]
+fn borrowed_pointers() {
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let layout = TextLayout::create(&factory)
+        .with_str("Interop")
+        .with_format(&format)
+        .with_size(300.0, 200.0)
+        .build()
+        .unwrap();
+
+    unsafe {
+        let raw = layout.as_raw_ptr();
+        let before = refcount(raw);
+        assert!(draw_text_layout(raw) > 0.0);
+        assert_eq!(refcount(raw), before);
+
+        // A wrapper around a borrowed pointer holds its own reference
+        let borrowed = TextLayout::from_raw_addref(raw);
+        assert_eq!(refcount(raw), before + 1);
+        drop(borrowed);
+        assert_eq!(refcount(raw), before);
+    }
+}
+
+#[test]
+fn owned_pointers() {
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    unsafe {
+        let raw = format.as_raw_ptr();
+        let before = refcount(raw);
+
+        let owned = format.to_raw_addref();
+        assert_eq!(owned, raw);
+        assert_eq!(refcount(raw), before + 1);
+        (*owned).Release();
+        assert_eq!(refcount(raw), before);
+
+        // Taking over a reference handed out by another API doesn't add one
+        let created = TextFormat::from_raw_owned(create_text_format(raw));
+        assert_eq!(refcount(raw), before + 1);
+        assert_eq!(created.as_raw_ptr(), raw);
+        drop(created);
+        assert_eq!(refcount(raw), before);
+    }
+}
+
+#[test]
+fn other_wrappers() {
+    let factory = Factory::new().unwrap();
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    let params = RenderingParams::create_default(&factory).unwrap();
+
+    let index = collection.find_family_by_name("Segoe UI").unwrap();
+    let face = collection
+        .family(index)
+        .unwrap()
+        .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap()
+        .create_face()
+        .unwrap();
+
+    unsafe {
+        let raw = collection.as_raw_ptr();
+        let before = refcount(raw);
+        let copy = FontCollection::from_raw_owned(collection.to_raw_addref());
+        assert_eq!(refcount(raw), before + 1);
+        drop(copy);
+        assert_eq!(refcount(raw), before);
+
+        let raw = params.as_raw_ptr();
+        let before = refcount(raw);
+        let copy = RenderingParams::from_raw_addref(raw);
+        assert_eq!(refcount(raw), before + 1);
+        drop(copy);
+        assert_eq!(refcount(raw), before);
+
+        let raw = face.as_raw_ptr();
+        let before = refcount(raw);
+        let copy = directwrite::FontFace::from_raw_owned(face.to_raw_addref());
+        assert_eq!(refcount(raw), before + 1);
+        drop(copy);
+        assert_eq!(refcount(raw), before);
+    }
+}
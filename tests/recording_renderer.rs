@@ -0,0 +1,161 @@
+extern crate directwrite;
+
+use directwrite::text_layout::ITextLayout;
+use directwrite::text_renderer::{DrawCommand, RecordingRenderer};
+use directwrite::{Factory, TextFormat, TextLayout};
+use math2d::Point2f;
+
+#[test]
+fn records_runs_and_decorations() {
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .with_locale("en-us")
+        .build()
+        .unwrap();
+    let mut layout = TextLayout::create(&factory)
+        .with_str("underlined struck")
+        .with_format(&format)
+        .with_size(1000.0, 100.0)
+        .build()
+        .unwrap();
+    layout.set_underline(true, 0u32..10).unwrap();
+    layout.set_strikethrough(true, 11u32..17).unwrap();
+
+    let recorder = RecordingRenderer::new();
+    recorder.record(&layout, Point2f::new(10.0, 20.0)).unwrap();
+
+    let mut glyphs = 0;
+    let mut underlines = 0;
+    let mut strikethroughs = 0;
+    for record in recorder.records() {
+        match record {
+            DrawCommand::GlyphRun(run) => {
+                assert_eq!(run.glyph_indices.len(), run.glyph_advances.len());
+                assert!(run.baseline_origin.x >= 10.0);
+                assert!(run.baseline_origin.y > 20.0);
+                glyphs += run.glyph_indices.len();
+            }
+            DrawCommand::Underline(line) => {
+                assert!(line.width > 0.0 && line.thickness > 0.0);
+                assert_eq!(line.locale_name, "en-us");
+                underlines += 1;
+            }
+            DrawCommand::Strikethrough(line) => {
+                assert!(line.width > 0.0);
+                strikethroughs += 1;
+            }
+            DrawCommand::InlineObject { .. } => panic!("no inline objects were set"),
+        }
+    }
+    assert_eq!(glyphs, 17);
+    assert!(underlines >= 1);
+    assert!(strikethroughs >= 1);
+
+    // Recording again appends, and the copies outlive the layout
+    let count = recorder.records().len();
+    recorder.record(&layout, Point2f::new(0.0, 0.0)).unwrap();
+    drop(layout);
+    let records = recorder.take_records();
+    assert_eq!(records.len(), count * 2);
+    assert!(recorder.records().is_empty());
+}
+
+#[test]
+fn typed_effects_round_trip() {
+    use directwrite::effects::{DrawingEffect, TypedEffect};
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let mut layout = TextLayout::create(&factory)
+        .with_str("plain orange plain")
+        .with_format(&format)
+        .with_size(1000.0, 100.0)
+        .build()
+        .unwrap();
+
+    let orange = TypedEffect::new(0xFF8800u32);
+    layout.set_drawing_effect(&orange, 6u32..12).unwrap();
+
+    let effect = layout.drawing_effect(8).unwrap();
+    assert_eq!((effect.range.start, effect.range.length), (6, 6));
+    let effect = effect.value.as_ref().unwrap();
+    assert_eq!(effect.downcast_ref::<u32>(), Some(&0xFF8800));
+    // The type has to match exactly
+    assert_eq!(effect.downcast_ref::<i32>(), None);
+    assert!(!effect.is::<u64>());
+    assert!(layout.drawing_effect(0).unwrap().value.is_none());
+
+    let recorder = RecordingRenderer::new();
+    recorder.record(&layout, Point2f::new(0.0, 0.0)).unwrap();
+    drop(layout);
+
+    let mut colored = 0;
+    for record in recorder.records() {
+        if let DrawCommand::GlyphRun(run) = record {
+            let color = run
+                .client_effect
+                .as_ref()
+                .and_then(|effect| effect.downcast_ref::<u32>());
+            let in_range = run.text_position >= 6 && run.text_position < 12;
+            assert_eq!(color, if in_range { Some(&0xFF8800) } else { None });
+            if in_range {
+                colored += run.text_length;
+            }
+        }
+    }
+    assert_eq!(colored, 6);
+
+    // The renderer's copy keeps the value alive after the layout and the original are gone
+    drop(orange);
+    let run = recorder
+        .records()
+        .into_iter()
+        .filter_map(|record| match record {
+            DrawCommand::GlyphRun(run) => run.client_effect,
+            _ => None,
+        })
+        .next()
+        .unwrap();
+    let typed = TypedEffect::<u32>::from_client_effect(&run).unwrap();
+    assert_eq!(*typed, 0xFF8800);
+    assert!(TypedEffect::<String>::from_client_effect(&run).is_none());
+}
+
+#[test]
+fn glyph_origins_match_hit_testing() {
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let layout = TextLayout::create(&factory)
+        .with_str("Origins")
+        .with_format(&format)
+        .with_size(1000.0, 100.0)
+        .build()
+        .unwrap();
+
+    let recorder = RecordingRenderer::new();
+    recorder.record(&layout, Point2f::new(5.0, 7.0)).unwrap();
+    let runs = recorder.glyph_runs();
+    assert_eq!(runs.len(), 1);
+    let run = &runs[0];
+    assert_eq!(run.text_range().length, 7);
+
+    // Every character is a single glyph, so each glyph starts where its caret would be
+    let origins = run.glyph_origins();
+    assert_eq!(origins.len(), 7);
+    assert_eq!(origins[0].x, run.baseline_origin.x);
+    for (i, origin) in origins.iter().enumerate() {
+        let hit = layout.hit_test_text_position(i as u32, false).unwrap();
+        assert!((origin.x - (hit.point_x + 5.0)).abs() < 0.01);
+        assert_eq!(origin.y, run.baseline_origin.y);
+    }
+}
@@ -0,0 +1,194 @@
+extern crate directwrite;
+
+use directwrite::text_layout::ITextLayout;
+use directwrite::text_renderer::custom::*;
+use directwrite::text_renderer::{DrawContext, RecordingRenderer};
+use directwrite::{Factory, TextFormat, TextLayout, TextRenderer};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use dcommon::Error;
+use math2d::{Matrix3x2f, Point2f};
+
+struct GlyphRecorder(Arc<Mutex<Vec<Vec<u16>>>>);
+
+impl CustomTextRenderer for GlyphRecorder {
+    fn pixel_snapping_disabled(&self, _: DrawContext) -> bool {
+        true
+    }
+
+    fn current_transform(&self, _: DrawContext) -> Matrix3x2f {
+        Matrix3x2f::IDENTITY
+    }
+
+    fn pixels_per_dip(&self, _: DrawContext) -> f32 {
+        1.0
+    }
+
+    fn draw_glyph_run(&mut self, context: &DrawGlyphRun) -> Result<(), Error> {
+        let indices = context.glyph_run.glyph_indices.to_vec();
+        self.0.lock().unwrap().push(indices);
+        Ok(())
+    }
+
+    fn draw_underline(&mut self, _: &DrawUnderline) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn draw_strikethrough(&mut self, _: &DrawStrikethrough) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn draw_inline_object(&mut self, _: &DrawInlineObject) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+struct Counter(Arc<AtomicUsize>);
+
+impl CustomTextRenderer for Counter {
+    fn pixel_snapping_disabled(&self, _: DrawContext) -> bool {
+        false
+    }
+
+    fn current_transform(&self, _: DrawContext) -> Matrix3x2f {
+        Matrix3x2f::IDENTITY
+    }
+
+    fn pixels_per_dip(&self, _: DrawContext) -> f32 {
+        2.0
+    }
+
+    fn draw_glyph_run(&mut self, _: &DrawGlyphRun) -> Result<(), Error> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn draw_underline(&mut self, _: &DrawUnderline) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn draw_strikethrough(&mut self, _: &DrawStrikethrough) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn draw_inline_object(&mut self, _: &DrawInlineObject) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+fn mixed_layout(factory: &Factory) -> TextLayout {
+    let format = TextFormat::create(factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+
+    let mut layout = TextLayout::create(factory)
+        .with_str("Plain bold plain")
+        .with_format(&format)
+        .with_size(400.0, 100.0)
+        .build()
+        .unwrap();
+    layout
+        .set_font_weight(directwrite::enums::FontWeight::Bold, 6u32..10)
+        .unwrap();
+    layout
+}
+
+#[test]
+fn tee_observes_same_runs() {
+    let factory = Factory::new().unwrap();
+    let layout = mixed_layout(&factory);
+
+    let recorded = Arc::new(Mutex::new(Vec::new()));
+    let count = Arc::new(AtomicUsize::new(0));
+    let tee = TeeRenderer::new(GlyphRecorder(recorded.clone()), Counter(count.clone()))
+        .with_policy(TeePolicy::ContinueOnError);
+    let mut renderer = TextRenderer::new(tee);
+    unsafe {
+        layout
+            .draw(&mut renderer, 0.0, 0.0, &DrawContext::from_usize(0))
+            .unwrap();
+    }
+
+    let recorded = recorded.lock().unwrap();
+    assert!(recorded.len() >= 3);
+    assert_eq!(recorded.len(), count.load(Ordering::SeqCst));
+}
+
+#[test]
+fn tee_drives_recording_renderer() {
+    let factory = Factory::new().unwrap();
+    let layout = mixed_layout(&factory);
+
+    // The clone handed to the tee shares its records with the one kept here
+    let recorder = RecordingRenderer::new();
+    let count = Arc::new(AtomicUsize::new(0));
+    let tee = TeeRenderer::new(recorder.clone(), Counter(count.clone()));
+    let mut renderer = TextRenderer::new(tee);
+    unsafe {
+        layout
+            .draw(&mut renderer, 0.0, 0.0, &DrawContext::from_usize(0))
+            .unwrap();
+    }
+
+    let teed = recorder.glyph_runs();
+    assert!(teed.len() >= 3);
+    assert_eq!(teed.len(), count.load(Ordering::SeqCst));
+
+    // The tee takes its pixel settings from the recorder, so the runs match a direct recording
+    recorder.clear();
+    recorder.record(&layout, Point2f::new(0.0, 0.0)).unwrap();
+    let direct = recorder.glyph_runs();
+    assert_eq!(teed.len(), direct.len());
+    for (a, b) in teed.iter().zip(&direct) {
+        assert_eq!(a.glyph_indices, b.glyph_indices);
+        assert_eq!(a.glyph_advances, b.glyph_advances);
+        assert_eq!(a.baseline_origin.x, b.baseline_origin.x);
+        assert_eq!(a.baseline_origin.y, b.baseline_origin.y);
+    }
+}
+
+#[test]
+fn filter_routes_glyph_runs() {
+    let factory = Factory::new().unwrap();
+    let layout = mixed_layout(&factory);
+
+    let primary = Arc::new(AtomicUsize::new(0));
+    let routed = Arc::new(AtomicUsize::new(0));
+    let routed2 = routed.clone();
+    let filter = FilterRenderer::new(Counter(primary.clone())).with_glyph_runs(move |_| {
+        routed2.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    });
+    let mut renderer = TextRenderer::new(filter);
+    unsafe {
+        layout
+            .draw(&mut renderer, 0.0, 0.0, &DrawContext::from_usize(0))
+            .unwrap();
+    }
+
+    assert_eq!(primary.load(Ordering::SeqCst), 0);
+    assert!(routed.load(Ordering::SeqCst) >= 3);
+}
+
+#[test]
+fn map_effect_forwards_runs() {
+    let factory = Factory::new().unwrap();
+    let layout = mixed_layout(&factory);
+
+    let recorded = Arc::new(Mutex::new(Vec::new()));
+    let mapped = MapEffectRenderer::new(GlyphRecorder(recorded.clone()), |effect| {
+        effect.cloned()
+    });
+    let mut renderer = TextRenderer::new(mapped);
+    unsafe {
+        layout
+            .draw(&mut renderer, 0.0, 0.0, &DrawContext::from_usize(0))
+            .unwrap();
+    }
+
+    assert!(recorded.lock().unwrap().len() >= 3);
+}
@@ -0,0 +1,170 @@
+extern crate directwrite;
+extern crate winapi;
+
+use directwrite::enums::{RenderingMode, RenderingMode1, UnsupportedRenderingMode};
+use directwrite::rendering_params::IRenderingParams;
+use directwrite::{Factory, RenderingParams};
+
+use std::convert::TryFrom;
+
+use dcommon::Error;
+
+use winapi::shared::winerror::{E_INVALIDARG, E_NOINTERFACE};
+use winapi::um::dwrite::*;
+use winapi::um::dwrite_3::*;
+
+#[test]
+fn discriminants_match_winapi() {
+    let modes = [
+        (RenderingMode::Default, DWRITE_RENDERING_MODE_DEFAULT),
+        (RenderingMode::Aliased, DWRITE_RENDERING_MODE_ALIASED),
+        (RenderingMode::GdiClassic, DWRITE_RENDERING_MODE_GDI_CLASSIC),
+        (RenderingMode::GdiNatural, DWRITE_RENDERING_MODE_GDI_NATURAL),
+        (RenderingMode::Natural, DWRITE_RENDERING_MODE_NATURAL),
+        (
+            RenderingMode::NaturalSymmetric,
+            DWRITE_RENDERING_MODE_NATURAL_SYMMETRIC,
+        ),
+        (RenderingMode::Outline, DWRITE_RENDERING_MODE_OUTLINE),
+    ];
+    for &(mode, raw) in &modes {
+        assert_eq!(mode as u32, raw);
+    }
+
+    let modes1 = [
+        (RenderingMode1::Default, DWRITE_RENDERING_MODE1_DEFAULT),
+        (RenderingMode1::Aliased, DWRITE_RENDERING_MODE1_ALIASED),
+        (
+            RenderingMode1::GdiClassic,
+            DWRITE_RENDERING_MODE1_GDI_CLASSIC,
+        ),
+        (
+            RenderingMode1::GdiNatural,
+            DWRITE_RENDERING_MODE1_GDI_NATURAL,
+        ),
+        (RenderingMode1::Natural, DWRITE_RENDERING_MODE1_NATURAL),
+        (
+            RenderingMode1::NaturalSymmetric,
+            DWRITE_RENDERING_MODE1_NATURAL_SYMMETRIC,
+        ),
+        (RenderingMode1::Outline, DWRITE_RENDERING_MODE1_OUTLINE),
+        (
+            RenderingMode1::NaturalSymmetricDownsampled,
+            DWRITE_RENDERING_MODE1_NATURAL_SYMMETRIC_DOWNSAMPLED,
+        ),
+    ];
+    for &(mode, raw) in &modes1 {
+        assert_eq!(mode as u32, raw);
+    }
+}
+
+#[test]
+fn conversions() {
+    let all = [
+        RenderingMode::Default,
+        RenderingMode::Aliased,
+        RenderingMode::GdiClassic,
+        RenderingMode::GdiNatural,
+        RenderingMode::Natural,
+        RenderingMode::NaturalSymmetric,
+        RenderingMode::Outline,
+    ];
+    for &mode in &all {
+        let mode1 = RenderingMode1::from(mode);
+        assert_eq!(mode1 as u32, mode as u32);
+        assert_eq!(RenderingMode::try_from(mode1), Ok(mode));
+    }
+
+    let downsampled = RenderingMode1::NaturalSymmetricDownsampled;
+    let err = RenderingMode::try_from(downsampled).unwrap_err();
+    assert_eq!(err, UnsupportedRenderingMode(downsampled));
+    assert_eq!(Error::from(err).0, E_INVALIDARG);
+}
+
+#[test]
+fn custom_params_with_rendering_mode1() {
+    let factory = Factory::new().unwrap();
+
+    // Modes both enums have still go through the original factory method
+    let params = RenderingParams::create(&factory)
+        .with_rendering_mode(RenderingMode::Outline)
+        .build()
+        .unwrap();
+    assert_eq!(params.rendering_mode().value, RenderingMode::Outline as u32);
+    assert_eq!(
+        params.rendering_mode1().value,
+        RenderingMode1::Outline as u32
+    );
+
+    let params = match RenderingParams::create(&factory)
+        .with_rendering_mode(RenderingMode1::NaturalSymmetricDownsampled)
+        .build()
+    {
+        Ok(params) => params,
+        // Requires Windows 10
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert_eq!(
+        params.rendering_mode1().value,
+        RenderingMode1::NaturalSymmetricDownsampled as u32
+    );
+}
+
+#[test]
+fn recommended_antialias_mode() {
+    use directwrite::enums::{PixelGeometry, TextAntialiasMode};
+    use directwrite::text_layout::ITextLayout;
+    use directwrite::{TextFormat, TextLayout};
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let layout = TextLayout::create(&factory)
+        .with_str("antialiased")
+        .with_format(&format)
+        .with_size(300.0, 100.0)
+        .build()
+        .unwrap();
+
+    let cleartype = RenderingParams::create(&factory)
+        .with_cleartype_level(1.0)
+        .with_pixel_geometry(PixelGeometry::Rgb)
+        .with_rendering_mode(RenderingMode::Natural)
+        .build()
+        .unwrap();
+    assert_eq!(
+        layout.recommended_antialias_mode(&cleartype, false),
+        TextAntialiasMode::ClearType
+    );
+    // ClearType can't be composited onto a transparent target
+    assert_eq!(
+        layout.recommended_antialias_mode(&cleartype, true),
+        TextAntialiasMode::Grayscale
+    );
+
+    let aliased = RenderingParams::create(&factory)
+        .with_cleartype_level(1.0)
+        .with_pixel_geometry(PixelGeometry::Rgb)
+        .with_rendering_mode(RenderingMode::Aliased)
+        .build()
+        .unwrap();
+    assert_eq!(
+        layout.recommended_antialias_mode(&aliased, false),
+        TextAntialiasMode::Grayscale
+    );
+
+    let no_cleartype = RenderingParams::create(&factory)
+        .with_cleartype_level(0.0)
+        .with_pixel_geometry(PixelGeometry::Rgb)
+        .with_rendering_mode(RenderingMode::Natural)
+        .build()
+        .unwrap();
+    assert_eq!(
+        layout.recommended_antialias_mode(&no_cleartype, false),
+        TextAntialiasMode::Grayscale
+    );
+}
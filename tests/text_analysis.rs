@@ -0,0 +1,548 @@
+extern crate directwrite;
+
+use directwrite::descriptions::TextRange;
+use directwrite::enums::{NumberSubstitutionMethod, ReadingDirection};
+use directwrite::number_substitution::NumberSubstitution;
+use directwrite::text_analysis::source::{TextAnalysisProvider, TextAnalysisSource};
+use directwrite::Factory;
+
+struct Text {
+    text: Vec<u16>,
+    locale: Vec<u16>,
+    substitution: NumberSubstitution,
+}
+
+impl Text {
+    fn source(text: &str, substitution: &NumberSubstitution) -> (TextAnalysisSource, u32) {
+        Text::source_with_locale(text, "en-us", substitution)
+    }
+
+    fn source_with_locale(
+        text: &str,
+        locale: &str,
+        substitution: &NumberSubstitution,
+    ) -> (TextAnalysisSource, u32) {
+        let text: Vec<u16> = text.encode_utf16().collect();
+        let len = text.len() as u32;
+        let source = TextAnalysisSource::new(Text {
+            text,
+            locale: locale.encode_utf16().chain(Some(0)).collect(),
+            substitution: substitution.clone(),
+        });
+        (source, len)
+    }
+}
+
+impl TextAnalysisProvider for Text {
+    fn locale_name(&self, position: u32) -> (&[u16], u32) {
+        (&self.locale, self.text.len() as u32 - position)
+    }
+
+    fn number_substitution(&self, position: u32) -> (NumberSubstitution, u32) {
+        (self.substitution.clone(), self.text.len() as u32 - position)
+    }
+
+    fn paragraph_reading_direction(&self) -> ReadingDirection {
+        ReadingDirection::LeftToRight
+    }
+
+    fn text_at(&self, position: u32) -> Option<&[u16]> {
+        self.text.get(position as usize..).filter(|t| !t.is_empty())
+    }
+
+    fn text_before(&self, position: u32) -> Option<&[u16]> {
+        self.text.get(..position as usize).filter(|t| !t.is_empty())
+    }
+}
+
+fn utf16_len(s: &str) -> u32 {
+    s.encode_utf16().count() as u32
+}
+
+#[test]
+fn itemization_edit() {
+    let factory = Factory::new().unwrap();
+    let analyzer = factory.create_text_analyzer().unwrap();
+    let substitution = NumberSubstitution::create(&factory)
+        .with_method(NumberSubstitutionMethod::None)
+        .build()
+        .unwrap();
+
+    let before = "Hello שלום there world مرحبا again";
+    let after = "Hello שלום there мир мир مرحبا again";
+    let position = utf16_len("Hello שלום there ");
+
+    let (source, len) = Text::source(before, &substitution);
+    let full = TextRange {
+        start: 0,
+        length: len,
+    };
+    let original = analyzer.itemize(&source, full).unwrap();
+    let runs = original.runs();
+    assert!(runs.len() >= 4);
+    assert_eq!(runs[0].range.start, 0);
+    let covered: u32 = runs.iter().map(|r| r.range.length).sum();
+    assert_eq!(covered, len);
+    for pair in runs.windows(2) {
+        assert!(!pair[0].same_properties(&pair[1]));
+    }
+
+    let removed = utf16_len("world");
+    let inserted = utf16_len("мир мир");
+    let mut edited = original.clone();
+    let dirty = edited.adjust_for_edit(position, removed, inserted);
+    assert_eq!(edited.dirty(), Some(dirty));
+    assert!(dirty.start <= position);
+    assert!(dirty.start + dirty.length >= position + inserted);
+
+    // Only the runs around the edit were invalidated, and the rest kept their properties
+    let delta = inserted as i64 - removed as i64;
+    assert!(!edited.runs().is_empty());
+    assert!(edited.runs().len() < runs.len());
+    for run in edited.runs() {
+        let moved = run.range.start >= dirty.start + dirty.length;
+        let old_start = if moved {
+            (run.range.start as i64 - delta) as u32
+        } else {
+            run.range.start
+        };
+        let old = runs.iter().find(|r| r.range.start == old_start).unwrap();
+        assert_eq!(old.range.length, run.range.length);
+        assert!(old.same_properties(run));
+    }
+    assert_eq!(edited.runs()[0], runs[0]);
+
+    let (source, len) = Text::source(after, &substitution);
+    let fresh = analyzer.itemize(&source, dirty).unwrap();
+    edited.splice(fresh).unwrap();
+    assert_eq!(edited.dirty(), None);
+
+    let scratch = analyzer
+        .itemize(
+            &source,
+            TextRange {
+                start: 0,
+                length: len,
+            },
+        )
+        .unwrap();
+    assert_eq!(edited, scratch);
+
+    // There is nothing to splice into without a dirty window
+    let mut again = scratch.clone();
+    assert!(again.splice(scratch).is_err());
+}
+
+#[test]
+fn analyze_script() {
+    let factory = Factory::new().unwrap();
+    let analyzer = factory.create_text_analyzer().unwrap();
+    let substitution = NumberSubstitution::create(&factory)
+        .with_method(NumberSubstitutionMethod::None)
+        .build()
+        .unwrap();
+
+    let (source, len) = Text::source("Latin \u{5e9}\u{5dc}\u{5d5}\u{5dd} Latin", &substitution);
+    let runs = analyzer.analyze_script(&source, 0, len).unwrap();
+    assert!(runs.len() >= 3);
+
+    // The runs are contiguous and cover all of the text
+    assert_eq!(runs[0].start, 0);
+    for pair in runs.windows(2) {
+        assert_eq!(pair[0].start + pair[0].length, pair[1].start);
+    }
+    let last = runs.last().unwrap();
+    assert_eq!(last.start + last.length, len);
+
+    let latin = runs[0].script_analysis;
+    let hebrew = runs
+        .iter()
+        .find(|r| r.start <= 6 && 6 < r.start + r.length)
+        .unwrap();
+    assert_ne!(hebrew.script_analysis, latin);
+    assert_eq!(last.script_analysis, latin);
+
+    // Analyzing part of the text only reports runs within that part
+    let partial = analyzer.analyze_script(&source, 6, 4).unwrap();
+    assert_eq!(partial.len(), 1);
+    assert_eq!((partial[0].start, partial[0].length), (6, 4));
+    assert_eq!(partial[0].script_analysis, hebrew.script_analysis);
+
+    // The scripts map to their ISO 15924 numbers
+    assert_eq!(analyzer.iso_script_number(latin).unwrap(), 215);
+    assert_eq!(
+        analyzer.iso_script_number(hebrew.script_analysis).unwrap(),
+        125
+    );
+}
+
+#[test]
+fn analyze_bidi() {
+    let factory = Factory::new().unwrap();
+    let analyzer = factory.create_text_analyzer().unwrap();
+    let substitution = NumberSubstitution::create(&factory)
+        .with_method(NumberSubstitutionMethod::None)
+        .build()
+        .unwrap();
+
+    let (source, len) = Text::source(
+        "Hello \u{645}\u{631}\u{62d}\u{628}\u{627} world",
+        &substitution,
+    );
+    let runs = analyzer.analyze_bidi(&source, 0, len).unwrap();
+    assert!(!runs.is_empty());
+
+    let arabic = runs
+        .iter()
+        .find(|r| r.start <= 6 && 6 < r.start + r.length)
+        .unwrap();
+    assert_eq!(arabic.resolved_level % 2, 1);
+    assert!(runs.iter().any(|r| r.resolved_level % 2 == 0));
+    assert!(runs.iter().all(|r| r.explicit_level == 0));
+}
+
+#[test]
+fn analyze_line_breaks() {
+    use directwrite::enums::BreakCondition;
+
+    let factory = Factory::new().unwrap();
+    let analyzer = factory.create_text_analyzer().unwrap();
+    let substitution = NumberSubstitution::create(&factory)
+        .with_method(NumberSubstitutionMethod::None)
+        .build()
+        .unwrap();
+
+    let (source, len) = Text::source("one two\u{AD}three", &substitution);
+    let breaks = analyzer.analyze_line_breaks(&source, 0, len).unwrap();
+    assert_eq!(breaks.len(), len as usize);
+
+    // The space is whitespace and allows a break after it
+    assert!(breaks[3].is_whitespace);
+    assert_eq!(
+        breaks[3].break_condition_after.value,
+        BreakCondition::CanBreak as u32
+    );
+    // No break in the middle of a word
+    assert_eq!(
+        breaks[1].break_condition_after.value,
+        BreakCondition::MayNotBreak as u32
+    );
+    assert!(breaks[7].is_soft_hyphen);
+    assert!(!breaks[0].is_whitespace && !breaks[0].is_soft_hyphen);
+
+    // A partial range is indexed from its start
+    let partial = analyzer.analyze_line_breaks(&source, 4, 3).unwrap();
+    assert_eq!(partial.len(), 3);
+    assert!(!partial[0].is_whitespace);
+}
+
+fn segoe_ui(factory: &Factory) -> directwrite::FontFace {
+    use directwrite::enums::{FontStretch, FontStyle, FontWeight};
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_family::IFontFamily;
+    use directwrite::FontCollection;
+
+    let collection = FontCollection::system_font_collection(factory, false).unwrap();
+    let index = collection.find_family_by_name("Segoe UI").unwrap();
+    collection
+        .family(index)
+        .unwrap()
+        .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+        .unwrap()
+        .create_face()
+        .unwrap()
+}
+
+#[test]
+fn get_glyphs() {
+    use directwrite::descriptions::FontFeature;
+    use directwrite::enums::font_feature_tag::FontFeatureTag;
+    use directwrite::font_face::IFontFace;
+    use directwrite::text_analysis::{FeatureRange, ShapingInput};
+
+    let factory = Factory::new().unwrap();
+    let analyzer = factory.create_text_analyzer().unwrap();
+    let substitution = NumberSubstitution::create(&factory)
+        .with_method(NumberSubstitutionMethod::None)
+        .build()
+        .unwrap();
+    let face = segoe_ui(&factory);
+
+    let text = "Hello";
+    let (source, len) = Text::source(text, &substitution);
+    let script = analyzer.analyze_script(&source, 0, len).unwrap()[0].script_analysis;
+    let wide: Vec<u16> = text.encode_utf16().collect();
+
+    let mut input = ShapingInput {
+        text: &wide,
+        font_face: &face,
+        is_sideways: false,
+        is_right_to_left: false,
+        script_analysis: script,
+        locale: Some("en-us"),
+        number_substitution: None,
+        features: &[],
+    };
+    let shaped = analyzer.get_glyphs(&input).unwrap();
+
+    // Plain Latin text maps one character to one glyph
+    let chars: Vec<u32> = text.chars().map(|c| c as u32).collect();
+    assert_eq!(shaped.glyph_indices, face.glyph_indices(&chars).unwrap());
+    assert_eq!(shaped.cluster_map, vec![0, 1, 2, 3, 4]);
+    assert_eq!(shaped.text_props.len(), wide.len());
+    assert!(shaped.glyph_props.iter().all(|p| p.is_cluster_start));
+
+    // Explicit feature ranges are accepted as long as they cover the text
+    let kerning = [FontFeature {
+        name_tag: FontFeatureTag::KERNING,
+        parameter: 0,
+    }];
+    let ranges = [FeatureRange {
+        features: &kerning,
+        length: len,
+    }];
+    input.features = &ranges;
+    let with_features = analyzer.get_glyphs(&input).unwrap();
+    assert_eq!(with_features.glyph_indices, shaped.glyph_indices);
+}
+
+#[test]
+fn get_glyph_placements() {
+    use directwrite::descriptions::{FontFeature, GlyphRun};
+    use directwrite::enums::font_feature_tag::FontFeatureTag;
+    use directwrite::text_analysis::{FeatureRange, ShapingInput};
+    use math2d::Point2f;
+
+    let factory = Factory::new().unwrap();
+    let analyzer = factory.create_text_analyzer().unwrap();
+    let substitution = NumberSubstitution::create(&factory)
+        .with_method(NumberSubstitutionMethod::None)
+        .build()
+        .unwrap();
+    let face = segoe_ui(&factory);
+
+    let text = "AVAV";
+    let (source, len) = Text::source(text, &substitution);
+    let script = analyzer.analyze_script(&source, 0, len).unwrap()[0].script_analysis;
+    let wide: Vec<u16> = text.encode_utf16().collect();
+
+    let mut input = ShapingInput {
+        text: &wide,
+        font_face: &face,
+        is_sideways: false,
+        is_right_to_left: false,
+        script_analysis: script,
+        locale: Some("en-us"),
+        number_substitution: None,
+        features: &[],
+    };
+    let shaped = analyzer.get_glyphs(&input).unwrap();
+    let kerned = analyzer
+        .get_glyph_placements(&input, &shaped, 32.0)
+        .unwrap();
+    assert_eq!(kerned.advances.len(), shaped.glyph_indices.len());
+    assert_eq!(kerned.offsets.len(), shaped.glyph_indices.len());
+    assert!(kerned.advances.iter().all(|&a| a > 0.0));
+
+    // Turning kerning off widens the AV pairs
+    let no_kerning = [FontFeature {
+        name_tag: FontFeatureTag::KERNING,
+        parameter: 0,
+    }];
+    let ranges = [FeatureRange {
+        features: &no_kerning,
+        length: len,
+    }];
+    input.features = &ranges;
+    let unkerned = analyzer
+        .get_glyph_placements(&input, &shaped, 32.0)
+        .unwrap();
+    let width = |a: &[f32]| a.iter().sum::<f32>();
+    assert!(width(&kerned.advances) < width(&unkerned.advances));
+
+    // The placements drop straight into a GlyphRun
+    let run = GlyphRun {
+        font_face: &face,
+        font_em_size: 32.0,
+        glyph_indices: &shaped.glyph_indices,
+        glyph_advances: &kerned.advances,
+        glyph_offsets: &kerned.offsets,
+        is_sideways: false,
+        bidi_level: 0,
+    };
+    let bounds = run.bounds(Point2f { x: 0.0, y: 0.0 }).unwrap().unwrap();
+    assert!(bounds.right - bounds.left > 0.0);
+
+    // Mismatched inputs are rejected rather than read out of bounds
+    let mut truncated = shaped.clone();
+    truncated.glyph_props.pop();
+    assert!(analyzer
+        .get_glyph_placements(&input, &truncated, 32.0)
+        .is_err());
+
+    // So is a cluster map pointing past the last glyph, even when all the lengths agree
+    let mut dangling = shaped.clone();
+    let last = dangling.cluster_map.len() - 1;
+    dangling.cluster_map[last] = dangling.glyph_indices.len() as u16;
+    let err = analyzer
+        .get_glyph_placements(&input, &dangling, 32.0)
+        .unwrap_err();
+    assert_eq!(err.0, winapi::shared::winerror::E_INVALIDARG);
+}
+
+#[test]
+fn national_digit_substitution() {
+    use directwrite::font_face::IFontFace;
+    use directwrite::text_analysis::ShapingInput;
+
+    let factory = Factory::new().unwrap();
+    let analyzer = factory.create_text_analyzer().unwrap();
+    let substitution = NumberSubstitution::create(&factory)
+        .with_method(NumberSubstitutionMethod::National)
+        .with_locale("ar-EG")
+        .with_ignore_user_override(true)
+        .build()
+        .unwrap();
+    let face = segoe_ui(&factory);
+
+    let text = "123";
+    let (source, len) = Text::source_with_locale(text, "ar-EG", &substitution);
+    let full = TextRange {
+        start: 0,
+        length: len,
+    };
+    let itemization = analyzer.itemize(&source, full).unwrap();
+    let run = &itemization.runs()[0];
+    assert_eq!(run.range.length, len);
+    let run_substitution = run.number_substitution.as_ref().unwrap();
+
+    let wide: Vec<u16> = text.encode_utf16().collect();
+    let shaped = analyzer
+        .get_glyphs(&ShapingInput {
+            text: &wide,
+            font_face: &face,
+            is_sideways: false,
+            is_right_to_left: false,
+            script_analysis: run.script_analysis,
+            locale: Some("ar-EG"),
+            number_substitution: Some(run_substitution),
+            features: &[],
+        })
+        .unwrap();
+
+    // The European digits are shaped as ARABIC-INDIC DIGIT ONE, TWO and THREE
+    let eastern = face.glyph_indices(&[0x661, 0x662, 0x663]).unwrap();
+    let european = face.glyph_indices(&[0x31, 0x32, 0x33]).unwrap();
+    assert_ne!(eastern, european);
+    assert_eq!(shaped.glyph_indices, eastern);
+}
+
+/// A xorshift generator, so the random strings are the same on every run.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 32) as u32
+    }
+
+    /// A mix of ASCII, 2- and 3-byte BMP chars and astral chars needing surrogate pairs.
+    fn string(&mut self, chars: usize) -> String {
+        let ranges = [
+            (0x20, 0x7f),
+            (0x400, 0x500),
+            (0x4e00, 0x9fff),
+            (0x1f300, 0x1f64f),
+        ];
+        (0..chars)
+            .map(|_| {
+                let (lo, hi) = ranges[self.next() as usize % ranges.len()];
+                std::char::from_u32(lo + self.next() % (hi - lo)).unwrap()
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn utf16_index_matches_naive_conversion() {
+    use directwrite::text_analysis::Utf16Index;
+
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for round in 0..50 {
+        let text = rng.string(round * 7);
+        let index = Utf16Index::new(&text);
+        let utf16: Vec<u16> = text.encode_utf16().collect();
+        assert_eq!(index.utf16(), &utf16[..]);
+        assert_eq!(index.len_utf16() as usize, utf16.len());
+
+        for byte in 0..=text.len() + 1 {
+            let boundary = (0..=byte.min(text.len()))
+                .rev()
+                .find(|&b| text.is_char_boundary(b))
+                .unwrap();
+            let naive = text[..boundary].encode_utf16().count() as u32;
+            assert_eq!(index.utf16_from_byte(byte), naive, "{:?} @ {}", text, byte);
+        }
+
+        let mut starts = vec![0; utf16.len() + 2];
+        let mut pos = 0;
+        for (byte, c) in text.char_indices() {
+            for p in pos..pos + c.len_utf16() {
+                starts[p] = byte;
+            }
+            pos += c.len_utf16();
+        }
+        starts[utf16.len()] = text.len();
+        starts[utf16.len() + 1] = text.len();
+        for (pos, &byte) in starts.iter().enumerate() {
+            assert_eq!(
+                index.byte_from_utf16(pos as u32),
+                byte,
+                "{:?} @ {}",
+                text,
+                pos
+            );
+        }
+
+        for (byte, c) in text.char_indices() {
+            let range = index.utf16_range(byte..byte + c.len_utf8());
+            let mut buf = [0; 2];
+            assert_eq!(index.slice_utf16(range.clone()), c.encode_utf16(&mut buf));
+            assert_eq!(index.byte_range(range), byte..byte + c.len_utf8());
+        }
+    }
+}
+
+#[test]
+fn string_analysis_source() {
+    use directwrite::text_analysis::source::StringAnalysisSource;
+
+    let factory = Factory::new().unwrap();
+    let analyzer = factory.create_text_analyzer().unwrap();
+    let substitution = NumberSubstitution::create(&factory)
+        .with_method(NumberSubstitutionMethod::None)
+        .build()
+        .unwrap();
+
+    let text = "\u{1F600} Latin \u{5e9}\u{5dc}\u{5d5}\u{5dd} Latin";
+    let provider = StringAnalysisSource::new(text, "en-us", substitution);
+    let index = provider.index().clone();
+    let source = TextAnalysisSource::new(provider);
+
+    let runs = analyzer
+        .analyze_script(&source, 0, index.len_utf16())
+        .unwrap();
+    let hebrew = text.find('\u{5e9}').unwrap();
+    let run = runs
+        .iter()
+        .find(|r| {
+            r.start <= index.utf16_from_byte(hebrew)
+                && index.utf16_from_byte(hebrew) < r.start + r.length
+        })
+        .unwrap();
+    let bytes = index.byte_range(run.start..run.start + run.length);
+    assert!(text[bytes].contains("\u{5e9}\u{5dc}\u{5d5}\u{5dd}"));
+}
@@ -0,0 +1,229 @@
+extern crate directwrite;
+
+use directwrite::text_renderer::RecordingRenderer;
+use directwrite::{Factory, FontFace, TextFormat, TextLayout};
+
+use com_wrapper::ComWrapper;
+use math2d::Point2f;
+use winapi::shared::winerror::{E_NOINTERFACE, SUCCEEDED};
+use winapi::um::dwrite_3::{
+    IDWriteFontFace5, DWRITE_FONT_AXIS_TAG_OPTICAL_SIZE, DWRITE_FONT_AXIS_VALUE,
+};
+use winapi::Interface;
+
+fn drawn_faces(layout: &TextLayout) -> Vec<FontFace> {
+    let recorder = RecordingRenderer::new();
+    recorder.record(layout, Point2f::new(0.0, 0.0)).unwrap();
+    recorder
+        .glyph_runs()
+        .into_iter()
+        .map(|run| run.font_face)
+        .collect()
+}
+
+/// Reads the value of the `opsz` axis a face was instanced with, if it has one.
+fn optical_size(face: &FontFace) -> Option<f32> {
+    unsafe {
+        let mut ptr = std::ptr::null_mut();
+        let hr = (*face.get_raw()).QueryInterface(&IDWriteFontFace5::uuidof(), &mut ptr);
+        if !SUCCEEDED(hr) {
+            return None;
+        }
+        let face5 = wio::com::ComPtr::from_raw(ptr as *mut IDWriteFontFace5);
+
+        let count = face5.GetFontAxisValueCount();
+        let mut values = vec![std::mem::zeroed::<DWRITE_FONT_AXIS_VALUE>(); count as usize];
+        let hr = face5.GetFontAxisValues(values.as_mut_ptr(), count);
+        assert!(SUCCEEDED(hr));
+        values
+            .iter()
+            .find(|v| v.axisTag == DWRITE_FONT_AXIS_TAG_OPTICAL_SIZE)
+            .map(|v| v.value)
+    }
+}
+
+#[test]
+fn auto_optical_size() {
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::text_layout::ITextLayout;
+    use directwrite::FontCollection;
+
+    let factory = Factory::new().unwrap();
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    if collection
+        .find_family_by_name("Segoe UI Variable Text")
+        .is_none()
+    {
+        // No variable font with an optical size axis to test with
+        return;
+    }
+
+    // Both sizes are within the font's optical size range
+    for &size in &[12.0, 36.0] {
+        let format = TextFormat::create(&factory)
+            .with_family("Segoe UI Variable Text")
+            .with_size(size)
+            .build()
+            .unwrap();
+
+        let layout = TextLayout::create(&factory)
+            .with_str("Optical")
+            .with_format(&format)
+            .with_size(400.0, 200.0)
+            .with_auto_optical_size(true)
+            .build();
+        let layout = match layout {
+            Ok(layout) => layout,
+            // Older than Windows 10 April 2018 Update
+            Err(e) if e.0 == E_NOINTERFACE => return,
+            Err(e) => panic!("{:?}", e),
+        };
+
+        let faces = drawn_faces(&layout);
+        assert!(!faces.is_empty());
+        for face in &faces {
+            assert_eq!(optical_size(face), Some(size));
+        }
+    }
+}
+
+#[test]
+fn font_axis_values() {
+    use directwrite::descriptions::FontAxisValue;
+    use directwrite::enums::FontAxisTag;
+    use directwrite::text_layout::ITextLayout;
+
+    let factory = Factory::new().unwrap();
+    let format = TextFormat::create(&factory)
+        .with_family("Segoe UI")
+        .with_size(16.0)
+        .build()
+        .unwrap();
+    let mut layout = TextLayout::create(&factory)
+        .with_str("Light and Heavy")
+        .with_format(&format)
+        .with_size(400.0, 200.0)
+        .build()
+        .unwrap();
+
+    let empty = match layout.font_axis_values(0) {
+        Ok(values) => values,
+        // Older than Windows 10 April 2018 Update
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    };
+    assert!(empty.value.is_empty());
+    assert_eq!(empty.range.length, 15);
+
+    let heavy = [
+        FontAxisValue::new(*b"wght", 900.0),
+        FontAxisValue::new(*b"wdth", 75.0),
+    ];
+    layout.set_font_axis_values(&heavy, 10u32..15).unwrap();
+
+    let values = layout.font_axis_values(12).unwrap();
+    assert_eq!((values.range.start, values.range.length), (10, 5));
+    assert_eq!(values.value, heavy);
+    assert_eq!(values.value[0].axis_tag, FontAxisTag::from(*b"wght"));
+
+    let before = layout.font_axis_values(0).unwrap();
+    assert!(before.value.is_empty());
+    assert_eq!((before.range.start, before.range.length), (0, 10));
+
+    // Axis values apply to any font, variable or not, without breaking layout
+    assert!(layout.metrics().width > 0.0);
+}
+
+#[test]
+fn automatic_font_axes() {
+    use directwrite::enums::AutomaticFontAxes;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::text_layout::ITextLayout;
+    use directwrite::FontCollection;
+
+    let factory = Factory::new().unwrap();
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+    let family = match collection.find_family_by_name("Segoe UI Variable Text") {
+        Some(_) => "Segoe UI Variable Text",
+        None => "Segoe UI",
+    };
+
+    let format = TextFormat::create(&factory)
+        .with_family(family)
+        .with_size(10.0)
+        .build()
+        .unwrap();
+    let mut layout = TextLayout::create(&factory)
+        .with_str("Optical")
+        .with_format(&format)
+        .with_size(400.0, 200.0)
+        .build()
+        .unwrap();
+
+    match layout.automatic_font_axes() {
+        Ok(axes) => assert_eq!(axes.value, AutomaticFontAxes::None as u32),
+        // Older than Windows 10 April 2018 Update
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    }
+
+    layout
+        .set_automatic_font_axes(AutomaticFontAxes::OpticalSize)
+        .unwrap();
+    assert_eq!(
+        layout.automatic_font_axes().unwrap().value,
+        AutomaticFontAxes::OpticalSize as u32
+    );
+
+    // Fonts with an `opsz` axis get instanced at the font size
+    let faces = drawn_faces(&layout);
+    assert!(!faces.is_empty());
+    for face in &faces {
+        if let Some(size) = optical_size(face) {
+            assert_eq!(size, 10.0);
+        }
+    }
+}
+
+#[test]
+fn font_axis_ranges() {
+    use directwrite::enums::{FontStretch, FontStyle, FontWeight};
+    use directwrite::font::IFont;
+    use directwrite::font_collection::IFontCollection;
+    use directwrite::font_family::IFontFamily;
+    use directwrite::FontCollection;
+
+    let factory = Factory::new().unwrap();
+    let collection = FontCollection::system_font_collection(&factory, false).unwrap();
+
+    // Bahnschrift is a variable font shipped since Windows 10 1709; Segoe UI is static
+    for &name in &["Bahnschrift", "Segoe UI"] {
+        let index = match collection.find_family_by_name(name) {
+            Some(index) => index,
+            None => continue,
+        };
+        let font = collection
+            .family(index)
+            .unwrap()
+            .first_matching_font(FontWeight::NORMAL, FontStretch::Normal, FontStyle::Normal)
+            .unwrap();
+
+        let ranges = match font.axis_ranges() {
+            Ok(ranges) => ranges,
+            Err(e) if e.0 == E_NOINTERFACE => return,
+            Err(e) => panic!("{:?}", e),
+        };
+        for range in &ranges {
+            assert!(range.min <= range.default && range.default <= range.max);
+            assert_eq!(range.clamp(range.max + 1.0), range.max);
+        }
+
+        let variable = font.has_variations().unwrap();
+        assert_eq!(variable, ranges.iter().any(|range| range.is_variable()));
+        if name == "Bahnschrift" {
+            assert!(variable);
+            let weight = ranges.iter().find(|range| range.tag == *"wght").unwrap();
+            assert!(weight.min < 400.0 && weight.max > 400.0);
+        }
+    }
+}
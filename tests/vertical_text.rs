@@ -0,0 +1,172 @@
+extern crate directwrite;
+
+use directwrite::enums::*;
+use directwrite::metrics::VerticalRunMetrics;
+use directwrite::text_format::ITextFormat;
+use directwrite::text_layout::ITextLayout;
+use directwrite::text_renderer::RecordingRenderer;
+use directwrite::{Factory, TextFormat, TextLayout};
+
+use math2d::{Point2f, Rectf};
+
+#[derive(Default)]
+struct Recorded {
+    bounds: Vec<Rectf>,
+    vertical: Vec<VerticalRunMetrics>,
+    all_sideways: bool,
+}
+
+/// Draws the layout and measures every glyph run it produced.
+fn record_bounds(layout: &TextLayout) -> Recorded {
+    let recorder = RecordingRenderer::new();
+    recorder.record(layout, Point2f::new(0.0, 0.0)).unwrap();
+
+    let mut recorded = Recorded {
+        all_sideways: true,
+        ..Default::default()
+    };
+    for run in recorder.glyph_runs() {
+        let glyph_run = run.glyph_run();
+        recorded.all_sideways &= run.is_sideways;
+        if let Some(bounds) = glyph_run.bounds(run.baseline_origin).unwrap() {
+            recorded.bounds.push(bounds);
+        }
+        recorded
+            .vertical
+            .push(VerticalRunMetrics::from_glyph_run(&glyph_run).unwrap());
+    }
+    recorded
+}
+
+#[test]
+fn sideways_bounds_stack_vertically() {
+    let factory = Factory::new().unwrap();
+
+    let mut format = TextFormat::create(&factory)
+        .with_family("MS Gothic")
+        .with_size(24.0)
+        .with_locale("ja-JP")
+        .build()
+        .unwrap();
+    format
+        .set_reading_direction(ReadingDirection::TopToBottom)
+        .unwrap();
+    format.set_flow_direction(FlowDirection::RightToLeft).unwrap();
+
+    let layout = TextLayout::create(&factory)
+        .with_str("日本語の縦書き")
+        .with_format(&format)
+        .with_size(400.0, 400.0)
+        .build()
+        .unwrap();
+
+    let recorded = record_bounds(&layout);
+    assert!(recorded.all_sideways);
+    assert!(!recorded.bounds.is_empty());
+
+    let top = recorded.bounds.iter().map(|b| b.top).fold(std::f32::MAX, f32::min);
+    let bottom = recorded.bounds.iter().map(|b| b.bottom).fold(std::f32::MIN, f32::max);
+    let left = recorded.bounds.iter().map(|b| b.left).fold(std::f32::MAX, f32::min);
+    let right = recorded.bounds.iter().map(|b| b.right).fold(std::f32::MIN, f32::max);
+
+    // Seven full-width glyphs in a single vertical line
+    assert!(bottom - top > 3.0 * (right - left));
+
+    let length: f32 = recorded.vertical.iter().map(|v| v.length).sum();
+    assert!(length >= bottom - top);
+    for v in &recorded.vertical {
+        assert!(v.left > 0.0 && v.right > 0.0);
+    }
+}
+
+#[test]
+fn sideways_runs_rasterize_vertically() {
+    use directwrite::text_renderer::raster::RasterOptions;
+
+    let factory = Factory::new().unwrap();
+
+    let mut format = TextFormat::create(&factory)
+        .with_family("MS Gothic")
+        .with_size(24.0)
+        .with_locale("ja-JP")
+        .build()
+        .unwrap();
+    format
+        .set_reading_direction(ReadingDirection::TopToBottom)
+        .unwrap();
+    format.set_flow_direction(FlowDirection::RightToLeft).unwrap();
+
+    let layout = TextLayout::create(&factory)
+        .with_str("日本語の縦書き")
+        .with_format(&format)
+        .with_size(400.0, 400.0)
+        .build()
+        .unwrap();
+
+    let image = layout
+        .rasterize(&factory, &RasterOptions::default(), 1.0)
+        .unwrap();
+    assert!(image.data.iter().any(|&a| a > 0));
+
+    // The glyphs are drawn down a single column, like their bounds
+    assert!(image.height() > 3 * image.width());
+    let bounds = record_bounds(&layout).bounds;
+    let left = bounds.iter().map(|b| b.left).fold(std::f32::MAX, f32::min);
+    let right = bounds.iter().map(|b| b.right).fold(std::f32::MIN, f32::max);
+    assert!(image.bounds.left as f32 >= left.floor() - 2.0);
+    assert!(image.bounds.right as f32 <= right.ceil() + 2.0);
+}
+
+#[test]
+fn vertical_glyph_orientation() {
+    use winapi::shared::winerror::E_NOINTERFACE;
+
+    let factory = Factory::new().unwrap();
+
+    let mut format = TextFormat::create(&factory)
+        .with_family("MS Gothic")
+        .with_size(24.0)
+        .with_locale("ja-JP")
+        .build()
+        .unwrap();
+    format
+        .set_reading_direction(ReadingDirection::TopToBottom)
+        .unwrap();
+    format
+        .set_flow_direction(FlowDirection::RightToLeft)
+        .unwrap();
+
+    let mut layout = TextLayout::create(&factory)
+        .with_str("縦書きLatin")
+        .with_format(&format)
+        .with_size(400.0, 400.0)
+        .build()
+        .unwrap();
+
+    match layout.vertical_glyph_orientation() {
+        Ok(orientation) => assert_eq!(orientation.value, VerticalGlyphOrientation::Default as u32),
+        // Older than Windows 8.1
+        Err(e) if e.0 == E_NOINTERFACE => return,
+        Err(e) => panic!("{:?}", e),
+    }
+
+    layout
+        .set_vertical_glyph_orientation(VerticalGlyphOrientation::Stacked)
+        .unwrap();
+    assert_eq!(
+        layout.vertical_glyph_orientation().unwrap().value,
+        VerticalGlyphOrientation::Stacked as u32
+    );
+
+    let stacked = TextLayout::create(&factory)
+        .with_str("縦書きLatin")
+        .with_format(&format)
+        .with_size(400.0, 400.0)
+        .with_vertical_glyph_orientation(VerticalGlyphOrientation::Stacked)
+        .build()
+        .unwrap();
+    assert_eq!(
+        stacked.vertical_glyph_orientation().unwrap().value,
+        VerticalGlyphOrientation::Stacked as u32
+    );
+}